@@ -0,0 +1,326 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use std::collections::HashMap;
+
+use crate::components::invoice::{Client, ClientDefaults, ClientFields, FieldCategory, FieldItem};
+use crate::shared::hooks::use_clients::{save_client, upsert_client, use_clients};
+use crate::shared::hooks::use_user_templates::use_user_templates;
+use crate::shared::storage::{Catalog, Database, StorageError};
+use crate::shared::templates::all_templates;
+
+async fn load_fields() -> Result<Vec<FieldItem>, StorageError> {
+    Database::open().await?.load_catalog(Catalog::Fields).await
+}
+
+/// Empty input means no default
+fn optional(value: String) -> Option<String> {
+    let value = value.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Create, edit and archive the clients offered on the invoice Client card
+#[component]
+pub fn ClientDirectory() -> impl IntoView {
+    let clients = use_clients();
+    let user_templates = use_user_templates();
+    let templates = Signal::derive(move || all_templates(&user_templates.get()));
+
+    let fields = RwSignal::new(Vec::<FieldItem>::new());
+    spawn_local(async move {
+        match load_fields().await {
+            Ok(loaded) => fields.set(loaded),
+            Err(e) => log::error!("Failed to load fields: {e}"),
+        }
+    });
+
+    let draft = RwSignal::new(Client::new(HashMap::new()));
+    let values = RwSignal::new(HashMap::new());
+    let draft_id = Memo::new(move |_| draft.with(|draft| draft.id.clone()));
+    let query = RwSignal::new(String::new());
+    let show_archived = RwSignal::new(false);
+    let error = RwSignal::new(None::<String>);
+
+    let open = move |client: Client| {
+        values.set(client.values.clone());
+        draft.set(client);
+        error.set(None);
+    };
+
+    let listed = Memo::new(move |_| {
+        let query = query.get().to_lowercase();
+        clients
+            .get()
+            .into_iter()
+            .filter(|client| client.archived == show_archived.get())
+            .filter(|client| {
+                query.is_empty()
+                    || client.name().to_lowercase().contains(&query)
+                    || client
+                        .details()
+                        .iter()
+                        .any(|detail| detail.to_lowercase().contains(&query))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let has_client_fields = Memo::new(move |_| {
+        fields.with(|fields| {
+            fields
+                .iter()
+                .any(|field| field.category == FieldCategory::Client)
+        })
+    });
+
+    let store = move |client: Client| {
+        spawn_local(async move {
+            match save_client(client.clone()).await {
+                Ok(()) => {
+                    upsert_client(clients, client.clone());
+                    draft.set(client);
+                }
+                Err(e) => error.set(Some(format!("Failed to save client: {e}"))),
+            }
+        });
+    };
+
+    let on_save = move |_| {
+        let mut client = draft.get_untracked();
+        client.values = values.get_untracked();
+        store(client);
+    };
+
+    let on_archive = move |_| {
+        let mut client = draft.get_untracked();
+        client.values = values.get_untracked();
+        client.archived = !client.archived;
+        store(client);
+    };
+
+    let update_defaults = move |update: fn(&mut ClientDefaults, String), value: String| {
+        draft.update(|client| update(&mut client.defaults, value));
+    };
+
+    view! {
+        <div class="grid grid-cols-1 lg:grid-cols-[18rem,1fr] gap-6 p-4">
+            <aside class="bg-white shadow rounded-lg p-4 space-y-3 h-fit">
+                <input
+                    class="form-input"
+                    type="search"
+                    placeholder="Search clients"
+                    prop:value=query
+                    on:input=move |ev| query.set(event_target_value(&ev))
+                />
+                <label class="flex items-center gap-2 text-sm text-gray-700">
+                    <input
+                        type="checkbox"
+                        prop:checked=show_archived
+                        on:change=move |ev| show_archived.set(event_target_checked(&ev))
+                    />
+                    "Show archived"
+                </label>
+                <ul class="space-y-1">
+                    <For
+                        each=move || listed.get()
+                        key=|client| (client.id.clone(), client.name())
+                        children=move |client| {
+                            let id = client.id.clone();
+                            let is_current = move || draft.with(|draft| draft.id == id);
+                            let name = client.name();
+                            view! {
+                                <li>
+                                    <button
+                                        type="button"
+                                        class=move || {
+                                            if is_current() {
+                                                "w-full text-left px-2 py-1 rounded bg-indigo-50 text-indigo-700 text-sm"
+                                            } else {
+                                                "w-full text-left px-2 py-1 rounded hover:bg-gray-50 text-sm"
+                                            }
+                                        }
+                                        on:click=move |_| open(client.clone())
+                                    >
+                                        {name}
+                                    </button>
+                                </li>
+                            }
+                        }
+                    />
+                </ul>
+                <button
+                    type="button"
+                    class="w-full rounded-md border border-gray-300 px-3 py-1.5 text-sm hover:bg-gray-50"
+                    on:click=move |_| open(Client::new(HashMap::new()))
+                >
+                    "New client"
+                </button>
+            </aside>
+
+            <section class="space-y-4">
+                {move || {
+                    error
+                        .get()
+                        .map(|message| {
+                            view! {
+                                <div class="rounded-md bg-red-50 p-3 text-sm text-red-700">
+                                    {message}
+                                </div>
+                            }
+                        })
+                }}
+                <Show when=move || !has_client_fields.get()>
+                    <p class="text-sm text-gray-500">
+                        "Define client fields in the Data Fields tab of the builder first."
+                    </p>
+                </Show>
+                // Remount the fields for every client so their defaults apply anew
+                {move || {
+                    draft_id.track();
+                    view! { <ClientFields fields=fields.read_only() form_values=values /> }
+                }}
+
+                <div class="bg-white shadow rounded-lg p-4 space-y-4">
+                    <h3 class="text-sm font-semibold text-gray-900">"Invoice defaults"</h3>
+                    <div class="grid grid-cols-1 md:grid-cols-2 gap-4">
+                        <div>
+                            <label class="form-label" for="client-currency">
+                                "Currency"
+                            </label>
+                            <input
+                                class="form-input"
+                                id="client-currency"
+                                placeholder="Invoice currency"
+                                prop:value=move || {
+                                    draft.with(|draft| draft.defaults.currency.clone().unwrap_or_default())
+                                }
+                                on:input=move |ev| {
+                                    update_defaults(
+                                        |defaults, value| defaults.currency = optional(value),
+                                        event_target_value(&ev),
+                                    )
+                                }
+                            />
+                        </div>
+                        <div>
+                            <label class="form-label" for="client-payment-terms">
+                                "Payment terms (days)"
+                            </label>
+                            <input
+                                class="form-input"
+                                id="client-payment-terms"
+                                type="number"
+                                min="0"
+                                prop:value=move || {
+                                    draft
+                                        .with(|draft| draft.defaults.payment_terms_days)
+                                        .map(|days| days.to_string())
+                                        .unwrap_or_default()
+                                }
+                                on:input=move |ev| {
+                                    update_defaults(
+                                        |defaults, value| {
+                                            defaults.payment_terms_days = value.trim().parse().ok()
+                                        },
+                                        event_target_value(&ev),
+                                    )
+                                }
+                            />
+                        </div>
+                        <div>
+                            <label class="form-label" for="client-template">
+                                "Preferred template"
+                            </label>
+                            <select
+                                class="form-select"
+                                id="client-template"
+                                on:change=move |ev| {
+                                    update_defaults(
+                                        |defaults, value| defaults.template_id = optional(value),
+                                        event_target_value(&ev),
+                                    )
+                                }
+                            >
+                                <option
+                                    value=""
+                                    selected=move || {
+                                        draft.with(|draft| draft.defaults.template_id.is_none())
+                                    }
+                                >
+                                    "No preference"
+                                </option>
+                                {move || {
+                                    templates
+                                        .get()
+                                        .into_iter()
+                                        .map(|template| {
+                                            let id = template.id.clone();
+                                            view! {
+                                                <option
+                                                    value=template.id
+                                                    selected=move || {
+                                                        draft
+                                                            .with(|draft| {
+                                                                draft.defaults.template_id.as_ref() == Some(&id)
+                                                            })
+                                                    }
+                                                >
+                                                    {template.name}
+                                                </option>
+                                            }
+                                        })
+                                        .collect_view()
+                                }}
+                            </select>
+                        </div>
+                        <div>
+                            <label class="flex items-center gap-2 text-sm text-gray-700 mt-6">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=move || draft.with(|draft| draft.defaults.tax_exempt)
+                                    on:change=move |ev| {
+                                        let exempt = event_target_checked(&ev);
+                                        draft.update(|draft| draft.defaults.tax_exempt = exempt)
+                                    }
+                                />
+                                "Tax exempt"
+                            </label>
+                            <Show when=move || draft.with(|draft| draft.defaults.tax_exempt)>
+                                <input
+                                    class="form-input mt-2"
+                                    placeholder="Exemption reason or certificate"
+                                    prop:value=move || {
+                                        draft.with(|draft| draft.defaults.tax_exemption_note.clone())
+                                    }
+                                    on:input=move |ev| {
+                                        update_defaults(
+                                            |defaults, value| defaults.tax_exemption_note = value,
+                                            event_target_value(&ev),
+                                        )
+                                    }
+                                />
+                            </Show>
+                        </div>
+                    </div>
+
+                    <div class="flex flex-wrap gap-2">
+                        <button
+                            type="button"
+                            class="rounded-md bg-indigo-600 px-3 py-1.5 text-sm text-white hover:bg-indigo-700"
+                            on:click=on_save
+                        >
+                            "Save"
+                        </button>
+                        <button
+                            type="button"
+                            class="rounded-md border border-gray-300 px-3 py-1.5 text-sm hover:bg-gray-50"
+                            on:click=on_archive
+                        >
+                            {move || {
+                                if draft.with(|draft| draft.archived) { "Restore" } else { "Archive" }
+                            }}
+                        </button>
+                    </div>
+                </div>
+            </section>
+        </div>
+    }
+}
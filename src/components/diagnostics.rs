@@ -0,0 +1,78 @@
+use leptos::prelude::*;
+
+use crate::shared::typst::{Diagnostic, DiagnosticLevel};
+
+/// Compiler errors and warnings with the offending source line
+#[component]
+pub fn DiagnosticsList(#[prop(into)] diagnostics: Signal<Vec<Diagnostic>>) -> impl IntoView {
+    view! {
+        <ul class="space-y-2">
+            {move || {
+                diagnostics
+                    .get()
+                    .into_iter()
+                    .map(|diagnostic| view! { <DiagnosticItem diagnostic=diagnostic /> })
+                    .collect_view()
+            }}
+        </ul>
+    }
+}
+
+#[component]
+fn DiagnosticItem(diagnostic: Diagnostic) -> impl IntoView {
+    let (container_class, label) = match diagnostic.level {
+        DiagnosticLevel::Error => ("border-red-200 bg-red-50 text-red-800", "Error"),
+        DiagnosticLevel::Warning => ("border-yellow-200 bg-yellow-50 text-yellow-800", "Warning"),
+    };
+
+    let source_line = diagnostic.line.zip(diagnostic.source_line.clone()).map(|(line, text)| {
+        // Caret under the start of the span, columns are 1-based
+        let caret = format!("{}^", " ".repeat(diagnostic.column.unwrap_or(1) - 1));
+        view! {
+            <pre class="mt-2 overflow-x-auto rounded bg-white/70 p-2 font-mono text-xs text-gray-800">
+                <span class="select-none text-gray-400">{format!("{line:>4} | ")}</span>
+                {text}
+                "\n"
+                <span class="select-none text-gray-400">"     | "</span>
+                <span class="text-red-600">{caret}</span>
+            </pre>
+        }
+    });
+
+    view! {
+        <li class=format!("rounded-md border p-3 text-sm {container_class}")>
+            <div class="flex flex-wrap items-baseline gap-2">
+                <span class="font-semibold">{label}</span>
+                {diagnostic
+                    .location()
+                    .map(|location| {
+                        view! { <span class="font-mono text-xs">{location}</span> }
+                    })}
+                <span>{diagnostic.message.clone()}</span>
+            </div>
+            {source_line}
+            {diagnostic
+                .hints
+                .iter()
+                .map(|hint| view! { <p class="mt-1 text-xs">"Hint: " {hint.clone()}</p> })
+                .collect_view()}
+        </li>
+    }
+}
+
+/// Font families a template asked for that no loaded or uploaded font provides
+#[component]
+pub fn MissingFonts(#[prop(into)] families: Signal<Vec<String>>) -> impl IntoView {
+    move || {
+        let families = families.get();
+        (!families.is_empty()).then(|| {
+            view! {
+                <p class="rounded-md border border-gray-200 bg-gray-50 p-3 text-sm text-gray-700">
+                    "Fonts not available, the template falls back to other fonts: "
+                    <span class="font-medium">{families.join(", ")}</span>
+                    ". Upload them in the template editor to use them."
+                </p>
+            }
+        })
+    }
+}
@@ -0,0 +1,108 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use std::collections::BTreeSet;
+
+use crate::shared::dialog::confirm;
+use crate::shared::files::{read_file, selected_files};
+use crate::shared::fonts::{FALLBACK_FAMILY, FONT_ASSETS, use_font_registry};
+
+/// Bundled font families and the user's uploaded brand fonts
+#[component]
+pub fn FontManager() -> impl IntoView {
+    let registry = use_font_registry();
+    let error = RwSignal::new(None::<String>);
+
+    let bundled_families = FONT_ASSETS
+        .iter()
+        .map(|asset| asset.family)
+        .chain([FALLBACK_FAMILY])
+        .collect::<BTreeSet<_>>();
+
+    let on_upload = move |ev: web_sys::Event| {
+        error.set(None);
+        for file in selected_files(&ev) {
+            spawn_local(async move {
+                let result = match read_file(&file).await {
+                    Ok(data) => registry
+                        .add_user_font(file.name(), data)
+                        .await
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    error.set(Some(e));
+                }
+            });
+        }
+    };
+
+    view! {
+        <div class="bg-white shadow rounded-lg p-4 space-y-2">
+            <h3 class="text-sm font-semibold text-gray-900">"Fonts"</h3>
+            <ul class="space-y-1 text-sm">
+                {bundled_families
+                    .into_iter()
+                    .map(|family| {
+                        view! {
+                            <li class="flex items-center justify-between">
+                                <span>{family}</span>
+                                <span class="text-xs text-gray-400">
+                                    {move || {
+                                        if registry.is_loaded(family) { "loaded" } else { "on demand" }
+                                    }}
+                                </span>
+                            </li>
+                        }
+                    })
+                    .collect_view()}
+                {move || {
+                    registry
+                        .user_fonts()
+                        .get()
+                        .into_iter()
+                        .map(|font| {
+                            let id = font.id.clone();
+                            let label = format!("Remove {}?", font.file_name);
+                            view! {
+                                <li class="flex items-center justify-between">
+                                    <span title=font.file_name>{font.family}</span>
+                                    <button
+                                        type="button"
+                                        class="text-xs text-red-600 hover:text-red-800"
+                                        on:click=move |_| {
+                                            if !confirm(&label) {
+                                                return;
+                                            }
+                                            let id = id.clone();
+                                            spawn_local(async move {
+                                                if let Err(e) = registry.remove_user_font(id).await {
+                                                    error.set(Some(e.to_string()));
+                                                }
+                                            });
+                                        }
+                                    >
+                                        "Remove"
+                                    </button>
+                                </li>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </ul>
+            <label class="block text-sm text-gray-700" for="font-upload">
+                "Upload TTF/OTF font"
+            </label>
+            <input
+                class="block w-full text-sm text-gray-700"
+                type="file"
+                id="font-upload"
+                multiple
+                accept=".ttf,.otf,.ttc,.otc,font/ttf,font/otf"
+                on:change=on_upload
+            />
+            {move || {
+                error.get().map(|message| view! { <p class="text-xs text-red-600">{message}</p> })
+            }}
+        </div>
+    }
+}
@@ -0,0 +1,148 @@
+use base64::Engine;
+use base64::engine::general_purpose;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use serde::{Deserialize, Serialize};
+use typst::foundations::Bytes;
+
+use crate::shared::files::{base64_data, read_file, selected_files};
+use crate::shared::typst::VirtualFile;
+
+/// Images a template can place on the invoice
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AssetKind {
+    Logo,
+    Signature,
+}
+
+impl AssetKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AssetKind::Logo => "Logo",
+            AssetKind::Signature => "Signature",
+        }
+    }
+
+    fn file_stem(&self) -> &'static str {
+        match self {
+            AssetKind::Logo => "logo",
+            AssetKind::Signature => "signature",
+        }
+    }
+}
+
+/// An uploaded image, stored with the invoice
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct ImageAsset {
+    pub file_name: String,
+    #[serde(with = "base64_data")]
+    pub data: Vec<u8>,
+}
+
+impl ImageAsset {
+    /// Lowercase extension of the uploaded file, Typst picks the decoder from it
+    fn extension(&self) -> String {
+        self.file_name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_lowercase())
+            .unwrap_or_else(|| "png".to_string())
+    }
+
+    fn mime_type(&self) -> &'static str {
+        match self.extension().as_str() {
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "svg" => "image/svg+xml",
+            "webp" => "image/webp",
+            _ => "image/png",
+        }
+    }
+
+    /// Where templates find the image, e.g. `/assets/logo.png`
+    pub fn path(&self, kind: AssetKind) -> String {
+        format!("/assets/{}.{}", kind.file_stem(), self.extension())
+    }
+
+    pub fn virtual_file(&self, kind: AssetKind) -> VirtualFile {
+        VirtualFile::new(self.path(kind), Bytes::new(self.data.clone()))
+    }
+
+    pub fn data_url(&self) -> String {
+        format!(
+            "data:{};base64,{}",
+            self.mime_type(),
+            general_purpose::STANDARD.encode(&self.data)
+        )
+    }
+}
+
+#[component]
+pub fn AssetsCard(
+    logo: RwSignal<Option<ImageAsset>>,
+    signature: RwSignal<Option<ImageAsset>>,
+) -> impl IntoView {
+    view! {
+        <div class="bg-white shadow rounded-lg p-4 space-y-4">
+            <h3 class="text-sm font-semibold text-gray-900">"Images"</h3>
+            <ImageUpload kind=AssetKind::Logo asset=logo />
+            <ImageUpload kind=AssetKind::Signature asset=signature />
+        </div>
+    }
+}
+
+#[component]
+fn ImageUpload(kind: AssetKind, asset: RwSignal<Option<ImageAsset>>) -> impl IntoView {
+    let input_id = format!("asset-{}", kind.file_stem());
+
+    let on_change = move |ev: web_sys::Event| {
+        let Some(file) = selected_files(&ev).into_iter().next() else {
+            return;
+        };
+        spawn_local(async move {
+            match read_file(&file).await {
+                Ok(data) => asset.set(Some(ImageAsset {
+                    file_name: file.name(),
+                    data,
+                })),
+                Err(e) => log::error!("{e}"),
+            }
+        });
+    };
+
+    view! {
+        <div>
+            <label class="form-label" for=input_id.clone()>
+                {kind.label()}
+            </label>
+            {move || {
+                asset
+                    .get()
+                    .map(|image| {
+                        view! {
+                            <div class="flex items-center gap-3 mb-2">
+                                <img
+                                    class="h-12 max-w-[8rem] object-contain border border-gray-200 rounded"
+                                    src=image.data_url()
+                                    alt=kind.label()
+                                />
+                                <button
+                                    type="button"
+                                    class="text-sm text-red-600 hover:text-red-800"
+                                    on:click=move |_| asset.set(None)
+                                >
+                                    "Remove"
+                                </button>
+                            </div>
+                        }
+                    })
+            }}
+            <input
+                class="block w-full text-sm text-gray-700"
+                type="file"
+                id=input_id
+                accept="image/png,image/jpeg,image/gif,image/svg+xml,image/webp"
+                on:change=on_change
+            />
+        </div>
+    }
+}
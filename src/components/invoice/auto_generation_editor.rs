@@ -0,0 +1,513 @@
+use leptos::prelude::*;
+
+use super::*;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Editable copy of the settings of `AutoGenerationRule::Counter`
+#[derive(Clone, PartialEq, Debug)]
+struct CounterSettings {
+    prefix: String,
+    suffix: String,
+    start_value: i64,
+    increment: i64,
+    padding: usize,
+    reset_period: ResetPeriod,
+    fiscal_year_start: u8,
+}
+
+impl Default for CounterSettings {
+    fn default() -> Self {
+        Self {
+            prefix: "INV-{YYYY}-".to_string(),
+            suffix: String::new(),
+            start_value: 1,
+            increment: 1,
+            padding: 4,
+            reset_period: ResetPeriod::Yearly,
+            fiscal_year_start: 1,
+        }
+    }
+}
+
+impl CounterSettings {
+    fn from_rule(rule: &AutoGenerationRule) -> Option<Self> {
+        let AutoGenerationRule::Counter {
+            prefix,
+            suffix,
+            start_value,
+            increment,
+            reset_period,
+            padding,
+            fiscal_year_start,
+        } = rule
+        else {
+            return None;
+        };
+
+        Some(Self {
+            prefix: prefix.clone().unwrap_or_default(),
+            suffix: suffix.clone().unwrap_or_default(),
+            start_value: *start_value,
+            increment: *increment,
+            padding: padding.unwrap_or(0),
+            reset_period: reset_period.clone().unwrap_or(ResetPeriod::Never),
+            fiscal_year_start: fiscal_year_start.unwrap_or(1),
+        })
+    }
+
+    fn to_rule(&self) -> AutoGenerationRule {
+        let optional = |value: &str| (!value.is_empty()).then(|| value.to_string());
+        AutoGenerationRule::Counter {
+            prefix: optional(&self.prefix),
+            suffix: optional(&self.suffix),
+            start_value: self.start_value,
+            increment: self.increment.max(1),
+            reset_period: Some(self.reset_period.clone()),
+            padding: (self.padding > 0).then_some(self.padding),
+            fiscal_year_start: (self.fiscal_year_start != 1).then_some(self.fiscal_year_start),
+        }
+    }
+}
+
+fn reset_period_value(period: &ResetPeriod) -> &'static str {
+    match period {
+        ResetPeriod::Daily => "daily",
+        ResetPeriod::Monthly => "monthly",
+        ResetPeriod::Yearly => "yearly",
+        ResetPeriod::Never => "never",
+    }
+}
+
+/// The rule of a formula field, depending on the fields the expression reads
+fn formula_rule(expression: String, fields: &[FieldItem]) -> AutoGenerationRule {
+    let dependencies = Formula::parse(&expression)
+        .ok()
+        .and_then(|formula| formula_dependencies(&formula, fields).ok())
+        .unwrap_or_default();
+    AutoGenerationRule::Formula {
+        expression,
+        dependencies,
+    }
+}
+
+/// Which kind of rule the editor shows
+fn rule_kind(rule: &Option<AutoGenerationRule>) -> &'static str {
+    match rule {
+        Some(AutoGenerationRule::Formula { .. }) => "formula",
+        Some(AutoGenerationRule::Custom { .. }) => "custom",
+        _ => "counter",
+    }
+}
+
+/// Settings of an auto-generated field's rule
+#[component]
+pub fn AutoGenerationEditor(
+    rule: ReadSignal<Option<AutoGenerationRule>>,
+    set_rule: WriteSignal<Option<AutoGenerationRule>>,
+    /// Fields a formula can refer to
+    #[prop(into)]
+    fields: Signal<Vec<FieldItem>>,
+) -> impl IntoView {
+    let counter = RwSignal::new(CounterSettings::default());
+    let expression = RwSignal::new(String::new());
+
+    // Follow the rule of the field being edited, new fields start as a counter
+    Effect::new(move |_| match rule.get() {
+        Some(AutoGenerationRule::Formula {
+            expression: current,
+            ..
+        }) => expression.set(current),
+        Some(rule) => {
+            if let Some(settings) = CounterSettings::from_rule(&rule) {
+                counter.set(settings);
+            }
+        }
+        None => set_rule.set(Some(counter.get_untracked().to_rule())),
+    });
+
+    // Memos so editing a rule does not rebuild the inputs being typed in
+    let kind = Memo::new(move |_| rule.with(rule_kind));
+    let registry = DefaultGeneratorRegistry;
+
+    let update = move |change: &dyn Fn(&mut CounterSettings)| {
+        counter.update(|settings| change(settings));
+        set_rule.set(Some(counter.get_untracked().to_rule()));
+    };
+
+    let set_expression = move |value: String| {
+        expression.set(value.clone());
+        set_rule.set(Some(
+            fields.with_untracked(|fields| formula_rule(value, fields)),
+        ));
+    };
+
+    let preview = move || {
+        let context = GenerationContext {
+            current_values: Default::default(),
+            counter_states: Default::default(),
+            current_date: time::OffsetDateTime::now_utc(),
+        };
+        counter
+            .get()
+            .to_rule()
+            .generate(&context, "")
+            .unwrap_or_default()
+    };
+
+    let generator_id = Memo::new(move |_| {
+        rule.with(|rule| match rule {
+            Some(AutoGenerationRule::Custom { generator_id, .. }) => generator_id.clone(),
+            _ => String::new(),
+        })
+    });
+
+    let parameter_value = move |key: &str| {
+        rule.with(|rule| match rule {
+            Some(AutoGenerationRule::Custom { parameters, .. }) => {
+                parameters.get(key).cloned().unwrap_or_default()
+            }
+            _ => String::new(),
+        })
+    };
+
+    // Empty parameters fall back to the generator's default
+    let set_parameter = move |key: &str, value: String| {
+        if let Some(AutoGenerationRule::Custom {
+            generator_id,
+            mut parameters,
+        }) = rule.get_untracked()
+        {
+            if value.is_empty() {
+                parameters.remove(key);
+            } else {
+                parameters.insert(key.to_string(), value);
+            }
+            set_rule.set(Some(AutoGenerationRule::Custom {
+                generator_id,
+                parameters,
+            }));
+        }
+    };
+
+    let set_generator = move |generator_id: String| {
+        set_rule.set(Some(AutoGenerationRule::Custom {
+            generator_id,
+            parameters: Default::default(),
+        }));
+    };
+
+    let sample = move || {
+        let context = GenerationContext {
+            current_values: Default::default(),
+            counter_states: Default::default(),
+            current_date: time::OffsetDateTime::now_utc(),
+        };
+        rule.get()
+            .map(|rule| rule.generate(&context, "").unwrap_or_else(|error| error))
+            .unwrap_or_default()
+    };
+
+    // Names of the fields the formula reads, or why it cannot be used
+    let formula_status = move || -> Result<Vec<String>, String> {
+        let formula = Formula::parse(&expression.get()).map_err(|error| error.to_string())?;
+        fields.with(|fields| {
+            let names = formula_dependencies(&formula, fields)
+                .map_err(|error| error.to_string())?
+                .iter()
+                .filter_map(|id| fields.iter().find(|field| &field.id == id))
+                .map(|field| field.name.clone())
+                .collect::<Vec<_>>();
+            Ok(names)
+        })
+    };
+
+    let counter_view = move || {
+        view! {
+            <div>
+                <label class="form-label" for="counter-prefix">
+                    "Prefix"
+                </label>
+                <input
+                    class="form-input"
+                    id="counter-prefix"
+                    type="text"
+                    prop:value=move || counter.with(|c| c.prefix.clone())
+                    on:input=move |ev| {
+                        let value = event_target_value(&ev);
+                        update(&|c| c.prefix = value.clone());
+                    }
+                />
+            </div>
+            <div>
+                <label class="form-label" for="counter-suffix">
+                    "Suffix"
+                </label>
+                <input
+                    class="form-input"
+                    id="counter-suffix"
+                    type="text"
+                    prop:value=move || counter.with(|c| c.suffix.clone())
+                    on:input=move |ev| {
+                        let value = event_target_value(&ev);
+                        update(&|c| c.suffix = value.clone());
+                    }
+                />
+            </div>
+            <div>
+                <label class="form-label">"Next number looks like"</label>
+                <p class="py-2 font-mono text-sm text-gray-700">{preview}</p>
+            </div>
+            <div>
+                <label class="form-label" for="counter-start">
+                    "Start at"
+                </label>
+                <input
+                    class="form-input"
+                    id="counter-start"
+                    type="number"
+                    prop:value=move || counter.with(|c| c.start_value.to_string())
+                    on:input=move |ev| {
+                        let value = event_target_value(&ev).parse().unwrap_or(1);
+                        update(&|c| c.start_value = value);
+                    }
+                />
+            </div>
+            <div>
+                <label class="form-label" for="counter-increment">
+                    "Increment"
+                </label>
+                <input
+                    class="form-input"
+                    id="counter-increment"
+                    type="number"
+                    min="1"
+                    prop:value=move || counter.with(|c| c.increment.to_string())
+                    on:input=move |ev| {
+                        let value = event_target_value(&ev).parse().unwrap_or(1);
+                        update(&|c| c.increment = value);
+                    }
+                />
+            </div>
+            <div>
+                <label class="form-label" for="counter-padding">
+                    "Digits"
+                </label>
+                <input
+                    class="form-input"
+                    id="counter-padding"
+                    type="number"
+                    min="0"
+                    max="12"
+                    prop:value=move || counter.with(|c| c.padding.to_string())
+                    on:input=move |ev| {
+                        let value = event_target_value(&ev).parse().unwrap_or(0);
+                        update(&|c| c.padding = value);
+                    }
+                />
+            </div>
+            <div>
+                <label class="form-label" for="counter-reset">
+                    "Start over"
+                </label>
+                <select
+                    class="form-select"
+                    id="counter-reset"
+                    prop:value=move || counter.with(|c| reset_period_value(&c.reset_period))
+                    on:change=move |ev| {
+                        let period = match event_target_value(&ev).as_str() {
+                            "daily" => ResetPeriod::Daily,
+                            "monthly" => ResetPeriod::Monthly,
+                            "yearly" => ResetPeriod::Yearly,
+                            _ => ResetPeriod::Never,
+                        };
+                        update(&|c| c.reset_period = period.clone());
+                    }
+                >
+                    <option value="never">"Never"</option>
+                    <option value="daily">"Every day"</option>
+                    <option value="monthly">"Every month"</option>
+                    <option value="yearly">"Every (fiscal) year"</option>
+                </select>
+            </div>
+            <div>
+                <label class="form-label" for="counter-fiscal-start">
+                    "Fiscal year starts in"
+                </label>
+                <select
+                    class="form-select"
+                    id="counter-fiscal-start"
+                    prop:value=move || counter.with(|c| c.fiscal_year_start.to_string())
+                    on:change=move |ev| {
+                        let month = event_target_value(&ev).parse().unwrap_or(1);
+                        update(&|c| c.fiscal_year_start = month);
+                    }
+                >
+                    {MONTHS
+                        .into_iter()
+                        .zip(1u8..)
+                        .map(|(name, month)| view! { <option value=month.to_string()>{name}</option> })
+                        .collect_view()}
+                </select>
+            </div>
+            <p class="md:col-span-3 text-xs text-gray-500">
+                "Prefix and suffix accept {YYYY}, {YY}, {MM}, {DD} and {FY}, the year the fiscal year started in. Numbers are assigned when an invoice is sent or paid, each numbering series counts on its own."
+            </p>
+        }
+    };
+
+    let formula_view = move || {
+        view! {
+            <div class="md:col-span-3">
+                <label class="form-label" for="formula-expression">
+                    "Formula"
+                </label>
+                <textarea
+                    class="form-textarea font-mono"
+                    id="formula-expression"
+                    rows="2"
+                    placeholder="round(total * 0.02, 2)"
+                    prop:value=move || expression.get()
+                    on:input=move |ev| set_expression(event_target_value(&ev))
+                ></textarea>
+            </div>
+            <div class="md:col-span-3 text-sm">
+                {move || match formula_status() {
+                    Ok(names) if names.is_empty() => {
+                        view! { <p class="text-gray-500">"Uses no other fields"</p> }.into_any()
+                    }
+                    Ok(names) => {
+                        view! { <p class="text-gray-700">"Uses " {names.join(", ")}</p> }
+                            .into_any()
+                    }
+                    Err(error) => view! { <p class="text-red-600">{error}</p> }.into_any(),
+                }}
+            </div>
+            <p class="md:col-span-3 text-xs text-gray-500">
+                "Refer to fields by id or as {Field Name}, and to subtotal, discount_total, charge_total, tax_total and total. Combine them with + - * / %, comparisons, && and ||, join text with &, and use if, round, floor, ceil, abs, min, max, upper, lower, trim, len, concat, text, number, date, today, add_days, days_between, year, month and day."
+            </p>
+        }
+    };
+
+    let custom_view = move || {
+        view! {
+            <div>
+                <label class="form-label" for="custom-generator">
+                    "Generator"
+                </label>
+                <select
+                    class="form-select"
+                    id="custom-generator"
+                    prop:value=move || generator_id.get()
+                    on:change=move |ev| set_generator(event_target_value(&ev))
+                >
+                    {registry
+                        .list_generators()
+                        .into_iter()
+                        .map(|(id, description)| view! { <option value=id>{description}</option> })
+                        .collect_view()}
+                </select>
+            </div>
+            <div class="md:col-span-2">
+                <label class="form-label">"Looks like"</label>
+                <p class="py-2 font-mono text-sm text-gray-700">{sample}</p>
+            </div>
+            {move || {
+                registry
+                    .parameters(&generator_id.get())
+                    .into_iter()
+                    .map(|parameter| {
+                        let key = parameter.key;
+                        let id = format!("generator-{key}");
+                        let input = if key == "source" {
+                            view! {
+                                <select
+                                    class="form-select"
+                                    id=id.clone()
+                                    prop:value=move || parameter_value(key)
+                                    on:change=move |ev| set_parameter(key, event_target_value(&ev))
+                                >
+                                    <option value="">"None"</option>
+                                    <For
+                                        each=move || fields.get()
+                                        key=|field| field.id.clone()
+                                        children=|field| {
+                                            view! { <option value=field.id>{field.name}</option> }
+                                        }
+                                    />
+                                </select>
+                            }
+                                .into_any()
+                        } else {
+                            view! {
+                                <input
+                                    class="form-input"
+                                    id=id.clone()
+                                    type="text"
+                                    placeholder=parameter.default
+                                    prop:value=move || parameter_value(key)
+                                    on:input=move |ev| set_parameter(key, event_target_value(&ev))
+                                />
+                            }
+                                .into_any()
+                        };
+                        view! {
+                            <div>
+                                <label class="form-label" for=id>
+                                    {parameter.label}
+                                </label>
+                                {input}
+                            </div>
+                        }
+                    })
+                    .collect_view()
+            }}
+        }
+    };
+
+    view! {
+        <div class="md:col-span-2 lg:col-span-3 grid grid-cols-1 md:grid-cols-3 gap-4 rounded-md border border-gray-200 p-4">
+            <div>
+                <label class="form-label" for="auto-generation-kind">
+                    "Generate"
+                </label>
+                <select
+                    class="form-select"
+                    id="auto-generation-kind"
+                    prop:value=move || kind.get()
+                    on:change=move |ev| match event_target_value(&ev).as_str() {
+                        "formula" => set_expression(expression.get_untracked()),
+                        "custom" => {
+                            let first = registry.list_generators().into_iter().next();
+                            set_generator(first.map(|(id, _)| id).unwrap_or_default());
+                        }
+                        _ => set_rule.set(Some(counter.get_untracked().to_rule())),
+                    }
+                >
+                    <option value="counter">"A number from a counter"</option>
+                    <option value="formula">"A value from a formula"</option>
+                    <option value="custom">"A generator"</option>
+                </select>
+            </div>
+            <div class="hidden md:block md:col-span-2"></div>
+            {move || match kind.get() {
+                "formula" => formula_view().into_any(),
+                "custom" => custom_view().into_any(),
+                _ => counter_view().into_any(),
+            }}
+        </div>
+    }
+}
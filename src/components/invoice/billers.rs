@@ -0,0 +1,148 @@
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::*;
+use crate::shared::dialog::{confirm, prompt};
+
+/// A business identity invoices are issued from, holding the values of the biller
+/// fields together with the branding and numbering used for it
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct BillerProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub values: HashMap<String, FieldItemValue>,
+    #[serde(default)]
+    pub logo: Option<ImageAsset>,
+    #[serde(default)]
+    pub template_id: Option<String>,
+    /// Invoices of this profile are numbered in their own sequence
+    #[serde(default)]
+    pub number_series: String,
+}
+
+/// Biller fields with a switcher between the saved profiles
+#[component]
+pub fn BillerCard(
+    fields: ReadSignal<Vec<FieldItem>>,
+    form_values: RwSignal<HashMap<String, FieldItemValue>>,
+    #[prop(into)] profiles: Signal<Vec<BillerProfile>>,
+    /// Profile of the current invoice
+    #[prop(into)]
+    selected: Signal<Option<String>>,
+    number_series: RwSignal<String>,
+    on_select: Callback<BillerProfile>,
+    /// Saves the current biller details as a new profile with the given name
+    on_create: Callback<String>,
+    on_update: Callback<()>,
+    on_delete: Callback<()>,
+) -> impl IntoView {
+    let current = move || {
+        let id = selected.get()?;
+        profiles.with(|profiles| profiles.iter().find(|p| p.id == id).cloned())
+    };
+
+    let on_change = move |ev| {
+        let id = event_target_value(&ev);
+        let profile =
+            profiles.with_untracked(|profiles| profiles.iter().find(|p| p.id == id).cloned());
+        if let Some(profile) = profile {
+            on_select.run(profile);
+        }
+    };
+
+    let on_save_new = move |_| {
+        if let Some(name) = prompt("Name of the new biller profile", "") {
+            let name = name.trim().to_string();
+            if !name.is_empty() {
+                on_create.run(name);
+            }
+        }
+    };
+
+    let on_remove = move |_| {
+        let Some(profile) = current() else {
+            return;
+        };
+        if confirm(&format!("Delete biller profile \"{}\"?", profile.name)) {
+            on_delete.run(());
+        }
+    };
+
+    view! {
+        <div class="space-y-2">
+            <div class="bg-white shadow rounded-lg p-4 space-y-3">
+                <div class="grid grid-cols-1 md:grid-cols-2 gap-3">
+                    <div>
+                        <label class="form-label" for="biller-profile">
+                            "Biller profile"
+                        </label>
+                        <select class="form-select" id="biller-profile" on:change=on_change>
+                            <option value="" selected=move || selected.get().is_none() disabled>
+                                "Choose a profile"
+                            </option>
+                            {move || {
+                                profiles
+                                    .get()
+                                    .into_iter()
+                                    .map(|profile| {
+                                        let id = profile.id.clone();
+                                        view! {
+                                            <option
+                                                value=profile.id
+                                                selected=move || {
+                                                    selected.get().as_ref() == Some(&id)
+                                                }
+                                            >
+                                                {profile.name}
+                                            </option>
+                                        }
+                                    })
+                                    .collect_view()
+                            }}
+                        </select>
+                    </div>
+                    <div>
+                        <label class="form-label" for="number-series">
+                            "Numbering series"
+                        </label>
+                        <input
+                            class="form-input"
+                            id="number-series"
+                            placeholder="Default"
+                            prop:value=number_series
+                            on:input=move |ev| number_series.set(event_target_value(&ev))
+                        />
+                    </div>
+                </div>
+                <div class="flex flex-wrap gap-2">
+                    <button
+                        type="button"
+                        class="rounded-md border border-gray-300 px-3 py-1.5 text-sm hover:bg-gray-50"
+                        on:click=on_save_new
+                    >
+                        "Save as profile"
+                    </button>
+                    <button
+                        type="button"
+                        class="rounded-md border border-gray-300 px-3 py-1.5 text-sm hover:bg-gray-50 disabled:opacity-50"
+                        disabled=move || selected.get().is_none()
+                        on:click=move |_| on_update.run(())
+                    >
+                        "Update profile"
+                    </button>
+                    <button
+                        type="button"
+                        class="rounded-md border border-red-300 px-3 py-1.5 text-sm text-red-700 hover:bg-red-50 disabled:opacity-50"
+                        disabled=move || selected.get().is_none()
+                        on:click=on_remove
+                    >
+                        "Delete profile"
+                    </button>
+                </div>
+            </div>
+            <BillerFields fields=fields form_values=form_values />
+        </div>
+    }
+}
@@ -1,28 +1,125 @@
 use super::*;
+use crate::shared::templates::TemplatePackage;
 use leptos::prelude::*;
+use leptos_router::components::A;
+use rust_decimal::Decimal;
 use std::collections::HashMap;
 
 #[component]
 pub fn Builder(
     line_items: RwSignal<Vec<LineItem>>,
+    snapshot: Memo<InvoiceSnapshot>,
     taxes: ReadSignal<Vec<TaxItem>>,
     discounts: ReadSignal<Vec<DiscountItem>>,
     charges: ReadSignal<Vec<ChargeItem>>,
     custom_fields: ReadSignal<Vec<FieldItem>>,
+    products: ReadSignal<Vec<ProductItem>>,
     extra_info: RwSignal<HashMap<String, FieldItemValue>>,
     invoice_info: RwSignal<HashMap<String, FieldItemValue>>,
     biller_info: RwSignal<HashMap<String, FieldItemValue>>,
     client_info: RwSignal<HashMap<String, FieldItemValue>>,
+    rounding: RwSignal<RoundingPolicy>,
+    status: RwSignal<InvoiceStatus>,
+    payments: RwSignal<Vec<Payment>>,
+    #[prop(into)] invoice_id: Signal<String>,
+    needs_review: RwSignal<bool>,
+    document_type: ReadSignal<DocumentType>,
+    on_document_type: Callback<DocumentType>,
+    quote_status: RwSignal<QuoteStatus>,
+    valid_until: RwSignal<String>,
+    quote_id: ReadSignal<Option<String>>,
+    converted_invoice_id: ReadSignal<Option<String>>,
+    on_quote_status: Callback<QuoteStatus>,
+    on_convert: Callback<()>,
+    credits: ReadSignal<Vec<Credit>>,
+    credited_invoice_id: ReadSignal<Option<String>>,
+    credited_invoice_number: ReadSignal<String>,
+    on_credit_status: Callback<InvoiceStatus>,
+    created_credit_note: ReadSignal<Option<String>>,
+    on_credit: Callback<HashMap<String, Decimal>>,
+    on_finalize: Callback<InvoiceStatus>,
+    template_id: RwSignal<String>,
+    #[prop(into)] templates: Signal<Vec<TemplatePackage>>,
+    logo: RwSignal<Option<ImageAsset>>,
+    signature: RwSignal<Option<ImageAsset>>,
+    currency: RwSignal<String>,
+    tax_exempt: RwSignal<bool>,
+    tax_exemption_note: RwSignal<String>,
+    prices_include_tax: RwSignal<bool>,
+    place_of_supply: RwSignal<String>,
+    vat_rules: RwSignal<bool>,
+    vat_supply: RwSignal<VatSupply>,
+    #[prop(into)] clients: Signal<Vec<Client>>,
+    on_client_select: Callback<Client>,
+    on_client_save: Callback<()>,
+    #[prop(into)] biller_profiles: Signal<Vec<BillerProfile>>,
+    biller_profile_id: RwSignal<Option<String>>,
+    number_series: RwSignal<String>,
+    on_biller_select: Callback<BillerProfile>,
+    on_biller_create: Callback<String>,
+    on_biller_update: Callback<()>,
+    on_biller_delete: Callback<()>,
 ) -> impl IntoView {
+    let total = Memo::new(move |_| snapshot.with(calculate_invoice).net_payable);
+
+    let is_invoice = move || document_type.get() == DocumentType::Invoice;
+    let is_credit_note = move || document_type.get() == DocumentType::CreditNote;
+    let number = move |field_id: &'static str| {
+        Signal::derive(move || {
+            invoice_info.with(|info| {
+                info.get(field_id)
+                    .map(|field| field.value.to_string())
+                    .unwrap_or_default()
+            })
+        })
+    };
+    // The type is fixed once the document went out and got its number
+    let is_draft =
+        move || status.get() == InvoiceStatus::Draft && quote_status.get() == QuoteStatus::Draft;
+
     view! {
         <div class="w-full px-4 py-6">
             <div class="grid grid-cols-1 lg:grid-cols-[3fr,1fr] gap-8 w-full">
                 <div class="space-y-6">
-                    <InvoiceFields fields=custom_fields form_values=invoice_info />
+                    <Show when=move || needs_review.get()>
+                        <div class="flex items-center justify-between gap-4 rounded-lg border border-yellow-200 bg-yellow-50 p-4 text-sm text-yellow-800">
+                            <span>
+                                "Created by a recurring schedule. Check the details before sending it."
+                            </span>
+                            <button
+                                type="button"
+                                class="whitespace-nowrap font-medium hover:text-yellow-900"
+                                on:click=move |_| needs_review.set(false)
+                            >
+                                "Mark as reviewed"
+                            </button>
+                        </div>
+                    </Show>
+                    <InvoiceFields
+                        fields=custom_fields
+                        form_values=invoice_info
+                        document_type=document_type
+                    />
 
                     <div class="grid grid-cols-1 lg:grid-cols-2 gap-6">
-                        <BillerFields fields=custom_fields form_values=biller_info />
-                        <ClientFields fields=custom_fields form_values=client_info />
+                        <BillerCard
+                            fields=custom_fields
+                            form_values=biller_info
+                            profiles=biller_profiles
+                            selected=biller_profile_id
+                            number_series=number_series
+                            on_select=on_biller_select
+                            on_create=on_biller_create
+                            on_update=on_biller_update
+                            on_delete=on_biller_delete
+                        />
+                        <ClientCard
+                            fields=custom_fields
+                            form_values=client_info
+                            clients=clients
+                            on_select=on_client_select
+                            on_save=on_client_save
+                        />
                     </div>
 
                     <LineItems
@@ -31,23 +128,205 @@ pub fn Builder(
                         discounts=discounts
                         charges=charges
                         custom_fields=custom_fields
+                        products=products
+                        snapshot=snapshot
                     />
 
                 </div>
 
                 <div class="space-y-6">
+                    <div class="bg-white shadow rounded-lg p-4 space-y-4">
+                        <TemplateSelect selected=template_id templates=templates />
+                        <Show when=move || !is_credit_note()>
+                            <div>
+                                <label class="form-label" for="document-type">
+                                    "Document"
+                                </label>
+                                <select
+                                    class="form-select"
+                                    id="document-type"
+                                    name="document-type"
+                                    disabled=move || !is_draft()
+                                    on:change=move |ev| {
+                                        if let Some(value) = DocumentType::from_label(
+                                            &event_target_value(&ev),
+                                        ) {
+                                            on_document_type.run(value);
+                                        }
+                                    }
+                                >
+                                    {DocumentType::SELECTABLE
+                                        .into_iter()
+                                        .map(|value| {
+                                            view! {
+                                                <option
+                                                    value=value.label()
+                                                    selected=move || document_type.get() == value
+                                                >
+                                                    {value.label()}
+                                                </option>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </select>
+                                {move || {
+                                    quote_id
+                                        .get()
+                                        .map(|quote_id| {
+                                            view! {
+                                                <A
+                                                    href=format!("/invoices/{quote_id}")
+                                                    attr:class="mt-1 block text-xs text-indigo-600 hover:text-indigo-900"
+                                                >
+                                                    "Converted from a quote"
+                                                </A>
+                                            }
+                                        })
+                                }}
+                            </div>
+                        </Show>
+                        <Show
+                            when=is_invoice
+                            fallback=move || {
+                                move || match document_type.get() {
+                                    DocumentType::CreditNote => {
+                                        view! {
+                                            <CreditNoteDetails
+                                                number=number(CREDIT_NOTE_NUMBER_FIELD_ID)
+                                                status=status.read_only()
+                                                credited_invoice_id=credited_invoice_id
+                                                credited_invoice_number=credited_invoice_number
+                                                on_status=on_credit_status
+                                            />
+                                        }
+                                            .into_any()
+                                    }
+                                    _ => {
+                                        view! {
+                                            <QuoteDetails
+                                                number=number(QUOTE_NUMBER_FIELD_ID)
+                                                quote_status=quote_status
+                                                valid_until=valid_until
+                                                converted_invoice_id=converted_invoice_id
+                                                on_status=on_quote_status
+                                                on_convert=on_convert
+                                            />
+                                        }
+                                            .into_any()
+                                    }
+                                }
+                            }
+                        >
+                            <div>
+                                <label class="form-label" for="invoice-status">
+                                    "Status"
+                                </label>
+                                <select
+                                    class="form-select"
+                                    id="invoice-status"
+                                    name="invoice-status"
+                                    on:change=move |ev| {
+                                        if let Some(value) = InvoiceStatus::from_label(
+                                            &event_target_value(&ev),
+                                        ) {
+                                            if status.get_untracked() == InvoiceStatus::Draft
+                                                && value.is_final()
+                                            {
+                                                on_finalize.run(value);
+                                            } else {
+                                                status.set(value);
+                                            }
+                                        }
+                                    }
+                                >
+                                    {InvoiceStatus::SELECTABLE
+                                        .into_iter()
+                                        .map(|value| {
+                                            view! {
+                                                <option
+                                                    value=value.label()
+                                                    selected=move || status.get() == value
+                                                >
+                                                    {value.label()}
+                                                </option>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </select>
+                            </div>
+                        </Show>
+                        <div>
+                            <label class="form-label" for="invoice-currency">
+                                "Currency"
+                            </label>
+                            <input
+                                class="form-input"
+                                id="invoice-currency"
+                                prop:value=currency
+                                on:input=move |ev| currency.set(event_target_value(&ev))
+                            />
+                        </div>
+                        <div>
+                            <label class="flex items-center gap-2 text-sm text-gray-700">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=tax_exempt
+                                    on:change=move |ev| tax_exempt.set(event_target_checked(&ev))
+                                />
+                                "Tax exempt"
+                            </label>
+                            <Show when=move || tax_exempt.get()>
+                                <input
+                                    class="form-input mt-2"
+                                    placeholder="Exemption reason or certificate"
+                                    prop:value=tax_exemption_note
+                                    on:input=move |ev| {
+                                        tax_exemption_note.set(event_target_value(&ev))
+                                    }
+                                />
+                            </Show>
+                            <label class="mt-2 flex items-center gap-2 text-sm text-gray-700">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=prices_include_tax
+                                    on:change=move |ev| {
+                                        prices_include_tax.set(event_target_checked(&ev))
+                                    }
+                                />
+                                "Prices include tax"
+                            </label>
+                            <label class="mt-2 flex items-center gap-2 text-sm text-gray-700">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=vat_rules
+                                    on:change=move |ev| vat_rules.set(event_target_checked(&ev))
+                                />
+                                "Apply EU VAT rules"
+                            </label>
+                            <Show when=move || vat_rules.get()>
+                                <div class="mt-2">
+                                    <VatCard
+                                        biller_info=biller_info
+                                        client_info=client_info
+                                        vat_supply=vat_supply
+                                    />
+                                </div>
+                            </Show>
+                        </div>
+                    </div>
+                    <Show when=move || {
+                        taxes.with(|taxes| taxes.iter().any(|tax| tax.kind == TaxKind::Gst))
+                    }>
+                        <div class="bg-white shadow rounded-lg p-4">
+                            <GstCard
+                                biller_info=biller_info
+                                client_info=client_info
+                                place_of_supply=place_of_supply
+                            />
+                        </div>
+                    </Show>
                     // <div class="grid grid-cols-1 md:grid-cols-2 gap-6">
                     // <div>
-                    // <label class="form-label" for="template">
-                    // "Template"
-                    // </label>
-                    // <select class="form-select" id="template" name="template">
-                    // <option>"Default Template"</option>
-                    // <option>"Modern"</option>
-                    // <option>"Classic"</option>
-                    // </select>
-                    // </div>
-                    // <div>
                     // <label class="form-label" for="locale">
                     // "Locale"
                     // </label>
@@ -62,11 +341,31 @@ pub fn Builder(
 
                     // <TotalsCharges fields=custom_fields form_values=client_info />
                     <TotalsCard
-                        line_items=line_items
-                        taxes=taxes
-                        discounts=discounts
-                        charges=charges
+                        snapshot=snapshot
+                        rounding=rounding
+                        payments=payments.read_only()
+                        credits=credits
                     />
+                    <Show when=is_invoice>
+                        <PaymentsCard
+                            payments=payments
+                            total=total
+                            status=status.read_only()
+                            invoice_info=invoice_info.read_only()
+                            credits=credits
+                        />
+                        <CreditNotesCard
+                            status=status.read_only()
+                            line_items=line_items.read_only()
+                            credits=credits
+                            created=created_credit_note
+                            on_create=on_credit
+                        />
+                    </Show>
+                    <Show when=move || !is_credit_note()>
+                        <RecurringCard invoice_id=invoice_id />
+                    </Show>
+                    <AssetsCard logo=logo signature=signature />
                     <ExtraInfoFields fields=custom_fields form_values=extra_info />
                 </div>
             </div>
@@ -103,11 +402,16 @@ pub fn Builder(
 pub fn InvoiceFields(
     fields: ReadSignal<Vec<FieldItem>>,
     form_values: RwSignal<HashMap<String, FieldItemValue>>,
+    #[prop(into)] document_type: Signal<DocumentType>,
 ) -> impl IntoView {
     let groups = RwSignal::new(vec![]);
 
-    let invoice_filter = Box::new(|field: &FieldItem| field.category == FieldCategory::Invoice)
-        as Box<dyn Fn(&FieldItem) -> bool + Send + Sync>;
+    // Quotes and credit notes have a number of their own and no due date
+    let invoice_filter = Box::new(move |field: &FieldItem| {
+        field.category == FieldCategory::Invoice
+            && (document_type.get() == DocumentType::Invoice
+                || (field.id != INVOICE_NUMBER_FIELD_ID && field.id != DUE_DATE_FIELD_ID))
+    }) as Box<dyn Fn(&FieldItem) -> bool + Send + Sync>;
 
     view! {
         <TailwindCollapsibleFieldCard
@@ -180,7 +484,7 @@ pub fn ClientFields(
             fields=fields
             groups=groups.read_only()
             form_values=form_values
-            title="Biller".to_string()
+            title="Client".to_string()
             initially_collapsed=false
             field_filter=invoice_filter
         />
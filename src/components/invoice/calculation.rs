@@ -0,0 +1,1544 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// Plain, signal-free copy of everything the totals depend on
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct InvoiceSnapshot {
+    pub line_items: Vec<LineItem>,
+    pub discounts: Vec<DiscountItem>,
+    pub charges: Vec<ChargeItem>,
+    #[serde(default)]
+    pub rounding: RoundingPolicy,
+    /// Leaves every charged tax out, for clients that are exempt. Withholding taxes
+    /// still apply.
+    #[serde(default)]
+    pub tax_exempt: bool,
+    /// Unit prices are gross, taxes are taken out of them instead of added on top
+    #[serde(default)]
+    pub prices_include_tax: bool,
+    /// Splits GST taxes into CGST and SGST or IGST, unsplit while unknown
+    #[serde(default)]
+    pub gst_supply: Option<GstSupply>,
+    /// Category the EU VAT rules give every charged standard tax
+    #[serde(default)]
+    pub vat_category: Option<TaxCategory>,
+}
+
+/// Whether the unit price of a line already includes its taxes
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum PriceBasis {
+    /// Follows `InvoiceSnapshot::prices_include_tax`
+    #[default]
+    Invoice,
+    Net,
+    Gross,
+}
+
+impl PriceBasis {
+    pub const ALL: [PriceBasis; 3] = [PriceBasis::Invoice, PriceBasis::Net, PriceBasis::Gross];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PriceBasis::Invoice => "As invoice",
+            PriceBasis::Net => "Excl. tax",
+            PriceBasis::Gross => "Incl. tax",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|basis| basis.label() == label)
+    }
+
+    /// Whether the price includes tax on an invoice with `prices_include_tax`
+    pub fn includes_tax(&self, prices_include_tax: bool) -> bool {
+        match self {
+            PriceBasis::Invoice => prices_include_tax,
+            PriceBasis::Net => false,
+            PriceBasis::Gross => true,
+        }
+    }
+}
+
+/// Computed amounts for a single line item, rounded to `MONEY_SCALE`
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct LineBreakdown {
+    pub line_id: String,
+    pub gross: Decimal,           // quantity * unit_price
+    pub discount: Decimal,        // Line-scoped discounts
+    pub global_discount: Decimal, // Share of invoice-level discounts allocated to this line
+    pub taxable: Decimal,         // gross - discount - global_discount
+    pub charges: Decimal,         // Line-scoped charges (not taxed)
+    pub net: Decimal,             // gross - discount + charges (before global discounts and tax)
+    pub tax_total: Decimal,
+    pub total: Decimal, // taxable + charges + tax_total
+    #[serde(default)]
+    pub includes_tax: bool, // gross includes tax_total, taxable is what is left without it
+    pub taxes: Vec<TaxLine>,
+}
+
+/// Amount contributed by a single tax, either per line or aggregated for the invoice
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TaxLine {
+    pub tax_id: String,
+    pub name: String,
+    pub tax_type: TaxType,
+    pub rate: Decimal,
+    /// Position of the tax in the order taxes apply
+    #[serde(default)]
+    pub order: u32,
+    pub taxable: Decimal,
+    pub amount: Decimal,
+    /// Taken out of tax inclusive prices rather than added on top
+    #[serde(default)]
+    pub included: bool,
+    #[serde(default)]
+    pub kind: TaxKind,
+    #[serde(default)]
+    pub category: TaxCategory,
+    /// Why the tax is not charged, empty for charged taxes
+    #[serde(default)]
+    pub exemption_reason: String,
+}
+
+/// Full invoice breakdown shared by the line item grid, totals card and exports
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct InvoiceBreakdown {
+    pub lines: Vec<LineBreakdown>,
+    pub subtotal: Decimal,
+    pub line_discounts: Decimal,
+    pub global_discounts: Decimal,
+    pub total_discounts: Decimal,
+    pub line_charges: Decimal,
+    pub global_charges: Decimal,
+    pub total_charges: Decimal,
+    pub taxes: Vec<TaxLine>,
+    pub tax_total: Decimal,
+    /// Part of `tax_total` already in the subtotal because prices include it
+    #[serde(default)]
+    pub tax_included: Decimal,
+    pub grand_total: Decimal,
+    /// Taxes the client withholds from what it pays, e.g. TDS or IRPF
+    #[serde(default)]
+    pub withholding: Vec<TaxLine>,
+    #[serde(default)]
+    pub withholding_total: Decimal,
+    /// What the client pays, the grand total less the withheld taxes
+    #[serde(default)]
+    pub net_payable: Decimal,
+}
+
+impl InvoiceBreakdown {
+    pub fn line(&self, line_id: &str) -> Option<&LineBreakdown> {
+        self.lines.iter().find(|line| line.line_id == line_id)
+    }
+
+    /// Why taxes are not charged, each reason once in tax order
+    pub fn exemption_notes(&self) -> Vec<String> {
+        let mut notes = Vec::<String>::new();
+        for tax in &self.taxes {
+            if !tax.exemption_reason.is_empty() && !notes.contains(&tax.exemption_reason) {
+                notes.push(tax.exemption_reason.clone());
+            }
+        }
+        notes
+    }
+}
+
+const HUNDRED: Decimal = Decimal::ONE_HUNDRED;
+
+fn discount_amount(discount: &DiscountItem, base: Decimal) -> Decimal {
+    match discount.discount_type {
+        DiscountType::Percentage => base * discount.value / HUNDRED,
+        DiscountType::FixedAmount => discount.value,
+    }
+}
+
+/// Sign fixed amounts take on a line or invoice worth `amount`, negative on credits
+fn direction(amount: Decimal) -> Decimal {
+    if amount < Decimal::ZERO {
+        -Decimal::ONE
+    } else {
+        Decimal::ONE
+    }
+}
+
+/// Unrounded tax amount
+fn tax_amount(tax_type: &TaxType, rate: Decimal, taxable: Decimal) -> Decimal {
+    match tax_type {
+        TaxType::Percentage => taxable * rate / HUNDRED,
+        TaxType::FixedAmount => rate,
+    }
+}
+
+/// Calculates a single line on its own, without any invoice-level discounts.
+/// `PriceBasis::Invoice` counts as excluding tax here.
+///
+/// Order of operations: line discounts are taken off the gross amount, taxes are
+/// computed on what remains, and line charges are added last, taxed only by taxes
+/// that apply to charges. Taxes apply by ascending `order`, a compound tax is also
+/// charged on the taxes of a lower order.
+pub fn calculate_line(item: &LineItem, policy: &RoundingPolicy) -> LineBreakdown {
+    calculate_line_with_allocation(
+        item,
+        Decimal::ZERO,
+        policy,
+        item.price_basis.includes_tax(false),
+    )
+}
+
+/// Taxes of a line in the order they apply, by `order` and then by name. Withheld
+/// taxes are left out, they do not add to the line.
+fn ordered_taxes(item: &LineItem) -> Vec<&TaxItem> {
+    let mut taxes = item
+        .taxes
+        .iter()
+        .filter(|tax| tax.kind != TaxKind::Withholding)
+        .collect::<Vec<_>>();
+    taxes.sort_by(|a, b| {
+        a.order
+            .cmp(&b.order)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    taxes
+}
+
+/// Base and amount of each of the ordered `taxes` on a line worth `price` with
+/// `charges`. Compound taxes are also charged on the taxes of a lower order. Amounts
+/// are only rounded with a `policy`.
+fn tax_chain(
+    taxes: &[&TaxItem],
+    price: Decimal,
+    charges: Decimal,
+    policy: Option<&RoundingPolicy>,
+) -> Vec<(Decimal, Decimal)> {
+    let mut chain: Vec<(Decimal, Decimal)> = Vec::with_capacity(taxes.len());
+    for tax in taxes {
+        let mut base = price;
+        if tax.apply_to_charges {
+            base += charges;
+        }
+        if tax.compound {
+            base += taxes
+                .iter()
+                .zip(&chain)
+                .filter(|(prior, _)| prior.order < tax.order)
+                .map(|(_, (_, amount))| *amount)
+                .sum::<Decimal>();
+        }
+        // Zero rated, exempt and reverse charged taxes are listed but charge nothing
+        let amount = if tax.category.is_charged() {
+            tax_amount(&tax.tax_type, tax.rate, base)
+        } else {
+            Decimal::ZERO
+        };
+        chain.push((base, policy.map_or(amount, |policy| policy.round(amount))));
+    }
+    chain
+}
+
+/// Net price and charges inside the tax inclusive `price` and `charges` of a line.
+/// Charges include only the taxes applied to them. Every tax grows linearly with
+/// what it is charged on, so the totals at zero and one give the rates to divide by.
+fn net_amounts(taxes: &[&TaxItem], price: Decimal, charges: Decimal) -> (Decimal, Decimal) {
+    let total = |price: Decimal, charges: Decimal| {
+        price
+            + charges
+            + tax_chain(taxes, price, charges, None)
+                .iter()
+                .map(|(_, amount)| *amount)
+                .sum::<Decimal>()
+    };
+    let fixed = total(Decimal::ZERO, Decimal::ZERO);
+    let net_price = (price - fixed)
+        .checked_div(total(Decimal::ONE, Decimal::ZERO) - fixed)
+        .unwrap_or_default();
+    let net_charges = charges
+        .checked_div(total(Decimal::ZERO, Decimal::ONE) - fixed)
+        .unwrap_or_default();
+    (net_price, net_charges)
+}
+
+fn calculate_line_with_allocation(
+    item: &LineItem,
+    global_discount: Decimal,
+    policy: &RoundingPolicy,
+    includes_tax: bool,
+) -> LineBreakdown {
+    let gross = policy.round(item.quantity * item.unit_price);
+    let sign = direction(gross);
+
+    let discount = sign
+        * item
+            .discounts
+            .iter()
+            .map(|d| policy.round(discount_amount(d, gross.abs())))
+            .sum::<Decimal>()
+            .min(gross.abs());
+
+    let charges = sign
+        * item
+            .charges
+            .iter()
+            .map(|c| policy.round(c.amount))
+            .sum::<Decimal>();
+    let taxed = gross - discount - global_discount;
+
+    // Tax inclusive lines have their taxes computed on the net amounts inside them,
+    // the net figure is then whatever remains so that nothing is lost to rounding
+    let ordered = ordered_taxes(item);
+    let (price, charged) = if includes_tax {
+        net_amounts(&ordered, taxed.abs(), charges.abs())
+    } else {
+        (taxed.abs(), charges.abs())
+    };
+    let taxes = ordered
+        .iter()
+        .zip(tax_chain(&ordered, price, charged, Some(policy)))
+        .map(|(tax, (base, amount))| TaxLine {
+            tax_id: tax.id.clone(),
+            name: tax.name.clone(),
+            tax_type: tax.tax_type.clone(),
+            rate: if tax.category.is_charged() {
+                tax.rate
+            } else {
+                Decimal::ZERO
+            },
+            order: tax.order,
+            taxable: sign * policy.round(base),
+            amount: sign * amount,
+            included: includes_tax,
+            kind: tax.kind,
+            category: tax.category,
+            exemption_reason: tax.exemption_note(),
+        })
+        .collect::<Vec<_>>();
+
+    let tax_total = taxes.iter().map(|t| t.amount).sum::<Decimal>();
+    let taxable = if includes_tax {
+        taxed - tax_total
+    } else {
+        taxed
+    };
+
+    LineBreakdown {
+        line_id: item.id.clone(),
+        gross,
+        discount,
+        global_discount,
+        taxable,
+        charges,
+        taxes,
+        tax_total,
+        net: gross - discount + charges,
+        total: taxable + charges + tax_total,
+        includes_tax,
+    }
+}
+
+/// Spreads `total` over the lines in proportion to their discounted amount. Every
+/// share is rounded and the last line takes the remainder, so the shares always add
+/// up. Only lines on the same side of zero as the whole invoice take a share.
+fn allocate(total: Decimal, lines: &[LineBreakdown], policy: &RoundingPolicy) -> Vec<Decimal> {
+    let amount = |line: &LineBreakdown| line.gross - line.discount;
+    let base = lines.iter().map(amount).sum::<Decimal>();
+    if base.is_zero() || total.is_zero() {
+        return vec![Decimal::ZERO; lines.len()];
+    }
+
+    let takes_share = |line: &LineBreakdown| (amount(line) * base) > Decimal::ZERO;
+    let last = lines.iter().rposition(takes_share);
+    let mut allocated = Decimal::ZERO;
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            if Some(index) == last {
+                total - allocated
+            } else if takes_share(line) {
+                let share = policy.round(total * amount(line) / base);
+                allocated += share;
+                share
+            } else {
+                Decimal::ZERO
+            }
+        })
+        .collect()
+}
+
+/// The taxes `tax` is charged as on the invoice of `snapshot`
+fn applied_taxes(tax: &TaxItem, snapshot: &InvoiceSnapshot) -> Vec<TaxItem> {
+    match (tax.kind, snapshot.gst_supply, snapshot.vat_category) {
+        (TaxKind::Gst, Some(supply), _) => gst_components(tax, supply),
+        (TaxKind::Standard, _, Some(category)) if tax.category.is_charged() => {
+            vec![TaxItem {
+                category,
+                ..tax.clone()
+            }]
+        }
+        _ => vec![tax.clone()],
+    }
+}
+
+/// Calculates the whole invoice.
+///
+/// Invoice-level (`GlobalInvoice`) discounts are computed on the subtotal after line
+/// discounts and spread over the lines in proportion to their discounted amount, so
+/// that taxes are always charged on what the client actually pays. Invoice-level
+/// charges are added after tax.
+///
+/// With `TaxRounding::PerLine` the sum of all line totals plus global charges is
+/// exactly the grand total. With `TaxRounding::PerInvoice` each tax is rounded once
+/// on its invoice-wide amount, which may differ by a cent from the sum of the line
+/// figures.
+///
+/// Credited lines have a negative quantity. Fixed discounts, charges and taxes take
+/// the sign of their line, or of the discounted subtotal for invoice-level ones, so
+/// crediting every line of an invoice gives its totals with the sign flipped.
+///
+/// Lines priced with tax have their taxes taken out of the discounted amount rather
+/// than added to it. Those taxes are counted in `tax_included` and left out of the
+/// grand total, which is therefore always the sum of the gross prices entered.
+///
+/// GST taxes are charged as the components of `gst_supply`, each at its share of
+/// the rate. Charged standard taxes take `vat_category` when the VAT rules set one.
+pub fn calculate_invoice(snapshot: &InvoiceSnapshot) -> InvoiceBreakdown {
+    let policy = &snapshot.rounding;
+    let adjusted_items;
+    let line_items = if snapshot.tax_exempt {
+        adjusted_items = snapshot
+            .line_items
+            .iter()
+            .map(|item| LineItem {
+                // Income tax is still withheld on an exempt supply
+                taxes: item
+                    .taxes
+                    .iter()
+                    .filter(|tax| tax.kind == TaxKind::Withholding)
+                    .cloned()
+                    .collect(),
+                ..item.clone()
+            })
+            .collect::<Vec<_>>();
+        &adjusted_items
+    } else if snapshot.gst_supply.is_some() || snapshot.vat_category.is_some() {
+        adjusted_items = snapshot
+            .line_items
+            .iter()
+            .map(|item| LineItem {
+                taxes: item
+                    .taxes
+                    .iter()
+                    .flat_map(|tax| applied_taxes(tax, snapshot))
+                    .collect(),
+                ..item.clone()
+            })
+            .collect::<Vec<_>>();
+        &adjusted_items
+    } else {
+        &snapshot.line_items
+    };
+
+    let includes_tax = |item: &LineItem| item.price_basis.includes_tax(snapshot.prices_include_tax);
+    let standalone = line_items
+        .iter()
+        .map(|item| calculate_line_with_allocation(item, Decimal::ZERO, policy, includes_tax(item)))
+        .collect::<Vec<_>>();
+
+    let subtotal = standalone.iter().map(|l| l.gross).sum::<Decimal>();
+    let line_discounts = standalone.iter().map(|l| l.discount).sum::<Decimal>();
+    let discounted_subtotal = subtotal - line_discounts;
+    let sign = direction(discounted_subtotal);
+
+    let global_discounts = sign
+        * snapshot
+            .discounts
+            .iter()
+            .filter(|d| d.scope == DiscountScope::GlobalInvoice)
+            .map(|d| policy.round(discount_amount(d, discounted_subtotal.abs())))
+            .sum::<Decimal>()
+            .min(discounted_subtotal.abs());
+
+    let shares = allocate(global_discounts, &standalone, policy);
+    let lines = line_items
+        .iter()
+        .zip(shares)
+        .map(|(item, share)| {
+            calculate_line_with_allocation(item, share, policy, includes_tax(item))
+        })
+        .collect::<Vec<_>>();
+
+    let line_charges = lines.iter().map(|l| l.charges).sum::<Decimal>();
+    let global_charges = sign
+        * snapshot
+            .charges
+            .iter()
+            .filter(|c| c.scope == ChargeScope::GlobalInvoice)
+            .map(|c| policy.round(c.amount))
+            .sum::<Decimal>();
+
+    let mut taxes: Vec<TaxLine> = Vec::new();
+    for tax in lines.iter().flat_map(|l| l.taxes.iter()) {
+        let amount = match policy.tax_rounding {
+            TaxRounding::PerLine => tax.amount,
+            TaxRounding::PerInvoice => {
+                direction(tax.taxable) * tax_amount(&tax.tax_type, tax.rate, tax.taxable.abs())
+            }
+        };
+        match taxes
+            .iter_mut()
+            .find(|t| t.tax_id == tax.tax_id && t.included == tax.included)
+        {
+            Some(existing) => {
+                existing.taxable += tax.taxable;
+                existing.amount += amount;
+            }
+            None => taxes.push(TaxLine {
+                amount,
+                ..tax.clone()
+            }),
+        }
+    }
+    for tax in taxes.iter_mut() {
+        tax.amount = policy.round(tax.amount);
+    }
+    taxes.sort_by(|a, b| {
+        a.order
+            .cmp(&b.order)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.tax_id.cmp(&b.tax_id))
+    });
+
+    let tax_total = taxes.iter().map(|t| t.amount).sum::<Decimal>();
+    let tax_included = taxes
+        .iter()
+        .filter(|t| t.included)
+        .map(|t| t.amount)
+        .sum::<Decimal>();
+    let total_discounts = line_discounts + global_discounts;
+    let total_charges = line_charges + global_charges;
+    let grand_total = subtotal - total_discounts + total_charges + tax_total - tax_included;
+
+    // Withheld taxes are charged on the taxable amount of their lines, the sum per
+    // tax is rounded once unless taxes are rounded per line
+    let mut withholding: Vec<TaxLine> = Vec::new();
+    for (item, line) in line_items.iter().zip(&lines) {
+        let line_sign = direction(line.gross);
+        for tax in item.taxes.iter().filter(|t| t.kind == TaxKind::Withholding) {
+            let mut base = line.taxable.abs();
+            if tax.apply_to_charges {
+                base += line.charges.abs();
+            }
+            let amount = tax_amount(&tax.tax_type, tax.rate, base);
+            let amount = match policy.tax_rounding {
+                TaxRounding::PerLine => policy.round(amount),
+                TaxRounding::PerInvoice => amount,
+            };
+            match withholding.iter_mut().find(|t| t.tax_id == tax.id) {
+                Some(existing) => {
+                    existing.taxable += line_sign * base;
+                    existing.amount += line_sign * amount;
+                }
+                None => withholding.push(TaxLine {
+                    tax_id: tax.id.clone(),
+                    name: tax.name.clone(),
+                    tax_type: tax.tax_type.clone(),
+                    rate: tax.rate,
+                    order: tax.order,
+                    taxable: line_sign * base,
+                    amount: line_sign * amount,
+                    kind: tax.kind,
+                    ..Default::default()
+                }),
+            }
+        }
+    }
+    for tax in withholding.iter_mut() {
+        tax.amount = policy.round(tax.amount);
+    }
+    withholding.sort_by(|a, b| {
+        a.order
+            .cmp(&b.order)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.tax_id.cmp(&b.tax_id))
+    });
+    let withholding_total = withholding.iter().map(|t| t.amount).sum::<Decimal>();
+
+    InvoiceBreakdown {
+        lines,
+        subtotal,
+        line_discounts,
+        global_discounts,
+        total_discounts,
+        line_charges,
+        global_charges,
+        total_charges,
+        taxes,
+        tax_total,
+        tax_included,
+        grand_total,
+        withholding,
+        withholding_total,
+        net_payable: grand_total - withholding_total,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+    use std::collections::{HashMap, HashSet};
+
+    fn tax(id: &str, tax_type: TaxType, rate: Decimal) -> TaxItem {
+        TaxItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            tax_type,
+            rate,
+            ..Default::default()
+        }
+    }
+
+    fn discount(
+        id: &str,
+        discount_type: DiscountType,
+        value: Decimal,
+        scope: DiscountScope,
+    ) -> DiscountItem {
+        DiscountItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            discount_type,
+            value,
+            scope,
+            is_default: false,
+        }
+    }
+
+    fn charge(id: &str, amount: Decimal, scope: ChargeScope) -> ChargeItem {
+        ChargeItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            amount,
+            scope,
+            is_default: false,
+        }
+    }
+
+    fn line(
+        id: &str,
+        quantity: Decimal,
+        unit_price: Decimal,
+        taxes: Vec<TaxItem>,
+        discounts: Vec<DiscountItem>,
+        charges: Vec<ChargeItem>,
+    ) -> LineItem {
+        LineItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            quantity,
+            unit_price,
+            taxes: taxes.into_iter().collect::<HashSet<_>>(),
+            discounts: discounts.into_iter().collect::<HashSet<_>>(),
+            charges: charges.into_iter().collect::<HashSet<_>>(),
+            custom_fields: HashMap::new(),
+            ..Default::default()
+        }
+    }
+
+    fn assert_amount(actual: Decimal, expected: Decimal) {
+        assert_eq!(actual, expected, "expected {expected}, got {actual}");
+    }
+
+    fn assert_consistent(breakdown: &InvoiceBreakdown) {
+        let lines_total = breakdown.lines.iter().map(|l| l.total).sum::<Decimal>();
+        assert_amount(
+            lines_total + breakdown.global_charges,
+            breakdown.grand_total,
+        );
+    }
+
+    #[test]
+    fn empty_invoice_is_zero() {
+        let breakdown = calculate_invoice(&InvoiceSnapshot::default());
+        assert_amount(breakdown.grand_total, dec!(0.0));
+        assert!(breakdown.lines.is_empty());
+    }
+
+    #[test]
+    fn percentage_tax_on_line() {
+        let item = line(
+            "a",
+            dec!(2.0),
+            dec!(50.0),
+            vec![tax("vat", TaxType::Percentage, dec!(10.0))],
+            vec![],
+            vec![],
+        );
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item],
+            ..Default::default()
+        });
+        assert_amount(breakdown.subtotal, dec!(100.0));
+        assert_amount(breakdown.tax_total, dec!(10.0));
+        assert_amount(breakdown.grand_total, dec!(110.0));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn tax_exempt_invoice_has_no_taxes() {
+        let item = line(
+            "a",
+            dec!(2.0),
+            dec!(50.0),
+            vec![
+                tax("vat", TaxType::Percentage, dec!(10.0)),
+                tax("flat", TaxType::FixedAmount, dec!(25.0)),
+            ],
+            vec![],
+            vec![],
+        );
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item],
+            tax_exempt: true,
+            ..Default::default()
+        });
+        assert!(breakdown.taxes.is_empty());
+        assert!(breakdown.lines[0].taxes.is_empty());
+        assert_amount(breakdown.tax_total, dec!(0.0));
+        assert_amount(breakdown.grand_total, dec!(100.0));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn tax_exempt_invoice_still_withholds() {
+        let irpf = TaxItem {
+            kind: TaxKind::Withholding,
+            ..tax("irpf", TaxType::Percentage, dec!(15))
+        };
+        let item = line(
+            "a",
+            dec!(2),
+            dec!(50),
+            vec![tax("vat", TaxType::Percentage, dec!(21)), irpf],
+            vec![],
+            vec![],
+        );
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item],
+            tax_exempt: true,
+            ..Default::default()
+        });
+        assert_consistent(&breakdown);
+        assert!(breakdown.taxes.is_empty());
+        assert_amount(breakdown.tax_total, dec!(0));
+        assert_amount(breakdown.grand_total, dec!(100));
+        assert_eq!(breakdown.withholding.len(), 1);
+        assert_amount(breakdown.withholding_total, dec!(15));
+        assert_amount(breakdown.net_payable, dec!(85));
+    }
+
+    #[test]
+    fn fixed_amount_tax_is_counted_in_totals() {
+        let item = line(
+            "a",
+            dec!(1.0),
+            dec!(100.0),
+            vec![tax("flat", TaxType::FixedAmount, dec!(25.0))],
+            vec![],
+            vec![],
+        );
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item],
+            ..Default::default()
+        });
+        assert_amount(breakdown.tax_total, dec!(25.0));
+        assert_amount(breakdown.grand_total, dec!(125.0));
+        assert_eq!(breakdown.taxes.len(), 1);
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn taxes_are_not_compounded() {
+        let item = line(
+            "a",
+            dec!(1.0),
+            dec!(100.0),
+            vec![
+                tax("vat", TaxType::Percentage, dec!(10.0)),
+                tax("service", TaxType::Percentage, dec!(5.0)),
+            ],
+            vec![],
+            vec![],
+        );
+        let breakdown = calculate_line(&item, &RoundingPolicy::default());
+        assert_amount(breakdown.tax_total, dec!(15.0));
+        assert_amount(breakdown.total, dec!(115.0));
+    }
+
+    #[test]
+    fn compound_taxes_apply_on_lower_order_taxes() {
+        let gst = tax("gst", TaxType::Percentage, dec!(5));
+        let qst = TaxItem {
+            order: 1,
+            compound: true,
+            ..tax("qst", TaxType::Percentage, dec!(9.975))
+        };
+        let item = line("a", dec!(1), dec!(100), vec![gst, qst], vec![], vec![]);
+        let breakdown = calculate_line(&item, &RoundingPolicy::default());
+        // QST on 105.00 = 10.47375
+        let amounts = breakdown.taxes.iter().map(|t| t.amount).collect::<Vec<_>>();
+        assert_eq!(amounts, vec![dec!(5.00), dec!(10.47)]);
+        assert_amount(breakdown.taxes[1].taxable, dec!(105));
+        assert_amount(breakdown.total, dec!(115.47));
+
+        // A compound tax of the same order is still computed side by side
+        let flat_order = line(
+            "b",
+            dec!(1),
+            dec!(100),
+            vec![
+                tax("gst", TaxType::Percentage, dec!(5)),
+                TaxItem {
+                    compound: true,
+                    ..tax("qst", TaxType::Percentage, dec!(10))
+                },
+            ],
+            vec![],
+            vec![],
+        );
+        let breakdown = calculate_line(&flat_order, &RoundingPolicy::default());
+        assert_amount(breakdown.tax_total, dec!(15));
+    }
+
+    #[test]
+    fn only_taxes_applied_to_charges_tax_them() {
+        let item = line(
+            "a",
+            dec!(1),
+            dec!(100),
+            vec![
+                TaxItem {
+                    apply_to_charges: true,
+                    ..tax("vat", TaxType::Percentage, dec!(10))
+                },
+                tax("levy", TaxType::Percentage, dec!(5)),
+            ],
+            vec![],
+            vec![charge("ship", dec!(20), ChargeScope::LineItem)],
+        );
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item],
+            ..Default::default()
+        });
+        let line = &breakdown.lines[0];
+        assert_amount(line.taxes[0].amount, dec!(5));
+        assert_amount(line.taxes[1].amount, dec!(12));
+        assert_amount(line.taxes[1].taxable, dec!(120));
+        assert_amount(breakdown.grand_total, dec!(137));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn compound_taxes_come_out_of_gross_prices() {
+        let gst = tax("gst", TaxType::Percentage, dec!(5));
+        let qst = TaxItem {
+            order: 1,
+            compound: true,
+            apply_to_charges: true,
+            ..tax("qst", TaxType::Percentage, dec!(9.975))
+        };
+        let item = line(
+            "a",
+            dec!(1),
+            dec!(115.47),
+            vec![gst, qst],
+            vec![],
+            vec![charge("ship", dec!(11), ChargeScope::LineItem)],
+        );
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item],
+            prices_include_tax: true,
+            ..Default::default()
+        });
+        // 115.47 / 1.1547375 = 99.9968 net, 11 / 1.09975 = 10.0023 net charges
+        let amounts = breakdown.taxes.iter().map(|t| t.amount).collect::<Vec<_>>();
+        assert_eq!(amounts, vec![dec!(5.00), dec!(11.47)]);
+        assert_amount(breakdown.grand_total, dec!(126.47));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn line_percentage_discount_applies_before_tax() {
+        let item = line(
+            "a",
+            dec!(1.0),
+            dec!(200.0),
+            vec![tax("vat", TaxType::Percentage, dec!(10.0))],
+            vec![discount(
+                "d",
+                DiscountType::Percentage,
+                dec!(10.0),
+                DiscountScope::LineItem,
+            )],
+            vec![],
+        );
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item],
+            ..Default::default()
+        });
+        assert_amount(breakdown.line_discounts, dec!(20.0));
+        assert_amount(breakdown.tax_total, dec!(18.0));
+        assert_amount(breakdown.grand_total, dec!(198.0));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn line_fixed_discount_applies_before_tax() {
+        let item = line(
+            "a",
+            dec!(2.0),
+            dec!(100.0),
+            vec![tax("vat", TaxType::Percentage, dec!(10.0))],
+            vec![discount(
+                "d",
+                DiscountType::FixedAmount,
+                dec!(50.0),
+                DiscountScope::LineItem,
+            )],
+            vec![],
+        );
+        let breakdown = calculate_line(&item, &RoundingPolicy::default());
+        assert_amount(breakdown.discount, dec!(50.0));
+        assert_amount(breakdown.taxable, dec!(150.0));
+        assert_amount(breakdown.tax_total, dec!(15.0));
+        assert_amount(breakdown.total, dec!(165.0));
+    }
+
+    #[test]
+    fn line_discount_never_exceeds_gross() {
+        let item = line(
+            "a",
+            dec!(1.0),
+            dec!(30.0),
+            vec![],
+            vec![discount(
+                "d",
+                DiscountType::FixedAmount,
+                dec!(50.0),
+                DiscountScope::LineItem,
+            )],
+            vec![],
+        );
+        let breakdown = calculate_line(&item, &RoundingPolicy::default());
+        assert_amount(breakdown.discount, dec!(30.0));
+        assert_amount(breakdown.total, dec!(0.0));
+    }
+
+    #[test]
+    fn global_percentage_discount_reduces_taxable_amount() {
+        let items = vec![
+            line(
+                "a",
+                dec!(1.0),
+                dec!(100.0),
+                vec![tax("vat", TaxType::Percentage, dec!(10.0))],
+                vec![],
+                vec![],
+            ),
+            line("b", dec!(1.0), dec!(300.0), vec![], vec![], vec![]),
+        ];
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: items,
+            discounts: vec![discount(
+                "g",
+                DiscountType::Percentage,
+                dec!(10.0),
+                DiscountScope::GlobalInvoice,
+            )],
+            ..Default::default()
+        });
+        assert_amount(breakdown.global_discounts, dec!(40.0));
+        assert_amount(breakdown.line("a").unwrap().global_discount, dec!(10.0));
+        assert_amount(breakdown.line("b").unwrap().global_discount, dec!(30.0));
+        assert_amount(breakdown.tax_total, dec!(9.0));
+        assert_amount(breakdown.grand_total, dec!(369.0));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn global_fixed_discount_is_allocated_proportionally() {
+        let items = vec![
+            line(
+                "a",
+                dec!(1.0),
+                dec!(100.0),
+                vec![tax("vat", TaxType::Percentage, dec!(20.0))],
+                vec![],
+                vec![],
+            ),
+            line(
+                "b",
+                dec!(1.0),
+                dec!(100.0),
+                vec![tax("vat", TaxType::Percentage, dec!(20.0))],
+                vec![],
+                vec![],
+            ),
+        ];
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: items,
+            discounts: vec![discount(
+                "g",
+                DiscountType::FixedAmount,
+                dec!(50.0),
+                DiscountScope::GlobalInvoice,
+            )],
+            ..Default::default()
+        });
+        assert_amount(breakdown.global_discounts, dec!(50.0));
+        assert_amount(breakdown.taxes[0].taxable, dec!(150.0));
+        assert_amount(breakdown.tax_total, dec!(30.0));
+        assert_amount(breakdown.grand_total, dec!(180.0));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn line_scoped_discounts_in_catalog_are_not_applied_globally() {
+        let items = vec![line("a", dec!(1.0), dec!(100.0), vec![], vec![], vec![])];
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: items,
+            discounts: vec![discount(
+                "l",
+                DiscountType::FixedAmount,
+                dec!(10.0),
+                DiscountScope::LineItem,
+            )],
+            charges: vec![charge("c", dec!(5.0), ChargeScope::LineItem)],
+            ..Default::default()
+        });
+        assert_amount(breakdown.total_discounts, dec!(0.0));
+        assert_amount(breakdown.total_charges, dec!(0.0));
+        assert_amount(breakdown.grand_total, dec!(100.0));
+    }
+
+    #[test]
+    fn line_charges_are_added_untaxed() {
+        let item = line(
+            "a",
+            dec!(1.0),
+            dec!(100.0),
+            vec![tax("vat", TaxType::Percentage, dec!(10.0))],
+            vec![],
+            vec![charge("handling", dec!(10.0), ChargeScope::LineItem)],
+        );
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item],
+            ..Default::default()
+        });
+        assert_amount(breakdown.line_charges, dec!(10.0));
+        assert_amount(breakdown.tax_total, dec!(10.0));
+        assert_amount(breakdown.grand_total, dec!(120.0));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn global_charges_are_added_after_tax() {
+        let item = line(
+            "a",
+            dec!(1.0),
+            dec!(100.0),
+            vec![tax("vat", TaxType::Percentage, dec!(10.0))],
+            vec![],
+            vec![],
+        );
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item],
+            discounts: vec![],
+            charges: vec![charge("shipping", dec!(5.0), ChargeScope::GlobalInvoice)],
+            ..Default::default()
+        });
+        assert_amount(breakdown.global_charges, dec!(5.0));
+        assert_amount(breakdown.tax_total, dec!(10.0));
+        assert_amount(breakdown.grand_total, dec!(115.0));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn taxes_are_aggregated_across_lines() {
+        let vat = tax("vat", TaxType::Percentage, dec!(10.0));
+        let flat = tax("flat", TaxType::FixedAmount, dec!(2.0));
+        let items = vec![
+            line(
+                "a",
+                dec!(1.0),
+                dec!(100.0),
+                vec![vat.clone(), flat.clone()],
+                vec![],
+                vec![],
+            ),
+            line("b", dec!(2.0), dec!(50.0), vec![vat, flat], vec![], vec![]),
+        ];
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: items,
+            ..Default::default()
+        });
+        assert_eq!(breakdown.taxes.len(), 2);
+        let vat_line = breakdown.taxes.iter().find(|t| t.tax_id == "vat").unwrap();
+        let flat_line = breakdown.taxes.iter().find(|t| t.tax_id == "flat").unwrap();
+        assert_amount(vat_line.amount, dec!(20.0));
+        assert_amount(vat_line.taxable, dec!(200.0));
+        assert_amount(flat_line.amount, dec!(4.0));
+        assert_amount(breakdown.grand_total, dec!(224.0));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn every_scope_and_type_combined() {
+        let items = vec![line(
+            "a",
+            dec!(4.0),
+            dec!(25.0),
+            vec![
+                tax("vat", TaxType::Percentage, dec!(10.0)),
+                tax("flat", TaxType::FixedAmount, dec!(3.0)),
+            ],
+            vec![
+                discount(
+                    "lp",
+                    DiscountType::Percentage,
+                    dec!(10.0),
+                    DiscountScope::LineItem,
+                ),
+                discount(
+                    "lf",
+                    DiscountType::FixedAmount,
+                    dec!(10.0),
+                    DiscountScope::LineItem,
+                ),
+            ],
+            vec![charge("lc", dec!(7.0), ChargeScope::LineItem)],
+        )];
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: items,
+            discounts: vec![
+                discount(
+                    "gp",
+                    DiscountType::Percentage,
+                    dec!(50.0),
+                    DiscountScope::GlobalInvoice,
+                ),
+                discount(
+                    "gf",
+                    DiscountType::FixedAmount,
+                    dec!(5.0),
+                    DiscountScope::GlobalInvoice,
+                ),
+            ],
+            charges: vec![charge("gc", dec!(4.0), ChargeScope::GlobalInvoice)],
+            ..Default::default()
+        });
+        // 100 gross - 20 line discounts = 80; 50% + 5 global = 45 off => 35 taxable
+        assert_amount(breakdown.line_discounts, dec!(20.0));
+        assert_amount(breakdown.global_discounts, dec!(45.0));
+        assert_amount(breakdown.tax_total, dec!(3.5) + dec!(3.0));
+        assert_amount(
+            breakdown.grand_total,
+            dec!(35.0) + dec!(7.0) + dec!(6.5) + dec!(4.0),
+        );
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn credited_lines_mirror_the_original() {
+        let snapshot = |quantity: Decimal| InvoiceSnapshot {
+            line_items: vec![
+                line(
+                    "a",
+                    quantity,
+                    dec!(25.0),
+                    vec![
+                        tax("vat", TaxType::Percentage, dec!(10.0)),
+                        tax("flat", TaxType::FixedAmount, dec!(3.0)),
+                    ],
+                    vec![discount(
+                        "lf",
+                        DiscountType::FixedAmount,
+                        dec!(10.0),
+                        DiscountScope::LineItem,
+                    )],
+                    vec![charge("lc", dec!(7.0), ChargeScope::LineItem)],
+                ),
+                line(
+                    "b",
+                    quantity,
+                    dec!(12.5),
+                    vec![tax("vat", TaxType::Percentage, dec!(10.0))],
+                    vec![],
+                    vec![],
+                ),
+            ],
+            discounts: vec![discount(
+                "gf",
+                DiscountType::FixedAmount,
+                dec!(5.0),
+                DiscountScope::GlobalInvoice,
+            )],
+            charges: vec![charge("gc", dec!(4.0), ChargeScope::GlobalInvoice)],
+            ..Default::default()
+        };
+        let original = calculate_invoice(&snapshot(dec!(3.0)));
+        let credit = calculate_invoice(&snapshot(dec!(-3.0)));
+
+        assert_amount(credit.line_discounts, -original.line_discounts);
+        assert_amount(credit.global_discounts, -original.global_discounts);
+        assert_amount(credit.total_charges, -original.total_charges);
+        assert_amount(credit.tax_total, -original.tax_total);
+        assert_amount(credit.grand_total, -original.grand_total);
+        assert_consistent(&credit);
+    }
+
+    fn with_policy(mode: RoundingMode, tax_rounding: TaxRounding) -> RoundingPolicy {
+        RoundingPolicy { mode, tax_rounding }
+    }
+
+    #[test]
+    fn half_up_rounds_midpoints_away_from_zero() {
+        let item = line(
+            "a",
+            dec!(1),
+            dec!(2.50),
+            vec![tax("vat", TaxType::Percentage, dec!(5))],
+            vec![],
+            vec![],
+        );
+        // 2.50 * 5% = 0.125
+        let policy = with_policy(RoundingMode::HalfUp, TaxRounding::PerLine);
+        assert_amount(calculate_line(&item, &policy).tax_total, dec!(0.13));
+    }
+
+    #[test]
+    fn half_even_rounds_midpoints_to_even() {
+        let item = line(
+            "a",
+            dec!(1),
+            dec!(2.50),
+            vec![tax("vat", TaxType::Percentage, dec!(5))],
+            vec![],
+            vec![],
+        );
+        let policy = with_policy(RoundingMode::HalfEven, TaxRounding::PerLine);
+        assert_amount(calculate_line(&item, &policy).tax_total, dec!(0.12));
+
+        let item = line(
+            "b",
+            dec!(1),
+            dec!(2.70),
+            vec![tax("vat", TaxType::Percentage, dec!(5))],
+            vec![],
+            vec![],
+        );
+        // 2.70 * 5% = 0.135
+        assert_amount(calculate_line(&item, &policy).tax_total, dec!(0.14));
+    }
+
+    #[test]
+    fn per_line_and_per_invoice_tax_rounding_differ() {
+        let vat = tax("vat", TaxType::Percentage, dec!(5));
+        let items = (0..3)
+            .map(|i| {
+                line(
+                    &i.to_string(),
+                    dec!(1),
+                    dec!(0.10),
+                    vec![vat.clone()],
+                    vec![],
+                    vec![],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Each line: 0.10 * 5% = 0.005
+        let per_line = calculate_invoice(&InvoiceSnapshot {
+            line_items: items.clone(),
+            rounding: with_policy(RoundingMode::HalfUp, TaxRounding::PerLine),
+            ..Default::default()
+        });
+        assert_amount(per_line.tax_total, dec!(0.03));
+        assert_consistent(&per_line);
+
+        let per_invoice = calculate_invoice(&InvoiceSnapshot {
+            line_items: items,
+            rounding: with_policy(RoundingMode::HalfUp, TaxRounding::PerInvoice),
+            ..Default::default()
+        });
+        assert_amount(per_invoice.tax_total, dec!(0.02));
+        assert_amount(per_invoice.grand_total, dec!(0.32));
+    }
+
+    #[test]
+    fn global_discount_shares_add_up_exactly() {
+        let items = (0..3)
+            .map(|i| line(&i.to_string(), dec!(1), dec!(10), vec![], vec![], vec![]))
+            .collect::<Vec<_>>();
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: items,
+            discounts: vec![discount(
+                "g",
+                DiscountType::FixedAmount,
+                dec!(10),
+                DiscountScope::GlobalInvoice,
+            )],
+            ..Default::default()
+        });
+        let shares = breakdown
+            .lines
+            .iter()
+            .map(|l| l.global_discount)
+            .collect::<Vec<_>>();
+        assert_eq!(shares, vec![dec!(3.33), dec!(3.33), dec!(3.34)]);
+        assert_amount(breakdown.grand_total, dec!(20));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn tax_inclusive_prices_add_up_to_what_was_entered() {
+        let vat = tax("vat", TaxType::Percentage, dec!(19));
+        let reduced = tax("reduced", TaxType::Percentage, dec!(7));
+        let items = vec![
+            line("a", dec!(3), dec!(9.99), vec![vat.clone()], vec![], vec![]),
+            line("b", dec!(1), dec!(4.99), vec![reduced], vec![], vec![]),
+            line("c", dec!(7), dec!(0.05), vec![vat], vec![], vec![]),
+        ];
+        let global = discount(
+            "g",
+            DiscountType::Percentage,
+            dec!(10),
+            DiscountScope::GlobalInvoice,
+        );
+
+        for tax_rounding in [TaxRounding::PerLine, TaxRounding::PerInvoice] {
+            let breakdown = calculate_invoice(&InvoiceSnapshot {
+                line_items: items.clone(),
+                rounding: with_policy(RoundingMode::HalfUp, tax_rounding),
+                prices_include_tax: true,
+                ..Default::default()
+            });
+            assert_amount(breakdown.grand_total, dec!(35.31));
+            assert_amount(breakdown.tax_included, breakdown.tax_total);
+            assert!(breakdown.taxes.iter().all(|t| t.included));
+
+            let discounted = calculate_invoice(&InvoiceSnapshot {
+                line_items: items.clone(),
+                discounts: vec![global.clone()],
+                rounding: with_policy(RoundingMode::HalfUp, tax_rounding),
+                prices_include_tax: true,
+                ..Default::default()
+            });
+            assert_amount(discounted.grand_total, dec!(31.78));
+        }
+
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: items,
+            prices_include_tax: true,
+            ..Default::default()
+        });
+        // 29.97 / 1.19 = 25.1849 net, 4.79 VAT
+        let first = breakdown.line("a").unwrap();
+        assert_amount(first.tax_total, dec!(4.79));
+        assert_amount(first.taxable, dec!(25.18));
+        assert_amount(first.total, dec!(29.97));
+        assert_consistent(&breakdown);
+    }
+
+    #[test]
+    fn several_taxes_come_out_of_one_gross_price() {
+        let item = LineItem {
+            price_basis: PriceBasis::Gross,
+            ..line(
+                "a",
+                dec!(1),
+                dec!(100),
+                vec![
+                    tax("state", TaxType::Percentage, dec!(10)),
+                    tax("city", TaxType::Percentage, dec!(5)),
+                    tax("levy", TaxType::FixedAmount, dec!(2)),
+                ],
+                vec![],
+                vec![],
+            )
+        };
+        // (100 - 2) / 1.15 = 85.2174 net
+        let breakdown = calculate_line(&item, &RoundingPolicy::default());
+        let amounts = breakdown.taxes.iter().map(|t| t.amount).collect::<Vec<_>>();
+        assert_eq!(amounts, vec![dec!(4.26), dec!(2), dec!(8.52)]);
+        assert_amount(breakdown.taxable, dec!(85.22));
+        assert_amount(breakdown.total, dec!(100));
+    }
+
+    #[test]
+    fn gross_and_net_lines_mix_on_one_invoice() {
+        let vat = tax("vat", TaxType::Percentage, dec!(10));
+        let gross = LineItem {
+            price_basis: PriceBasis::Gross,
+            ..line(
+                "gross",
+                dec!(1),
+                dec!(110),
+                vec![vat.clone()],
+                vec![],
+                vec![],
+            )
+        };
+        let net = line("net", dec!(1), dec!(100), vec![vat], vec![], vec![]);
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![gross, net],
+            ..Default::default()
+        });
+        assert_amount(breakdown.subtotal, dec!(210));
+        assert_amount(breakdown.tax_total, dec!(20));
+        assert_amount(breakdown.tax_included, dec!(10));
+        assert_amount(breakdown.grand_total, dec!(220));
+        assert_eq!(breakdown.taxes.len(), 2);
+        assert_consistent(&breakdown);
+
+        let as_net = LineItem {
+            price_basis: PriceBasis::Net,
+            ..line("net", dec!(1), dec!(100), vec![], vec![], vec![])
+        };
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![as_net],
+            prices_include_tax: true,
+            ..Default::default()
+        });
+        assert!(!breakdown.lines[0].includes_tax);
+    }
+
+    #[test]
+    fn vat_categories_list_taxes_without_charging_them() {
+        let vat = tax("vat", TaxType::Percentage, dec!(20));
+        let medical = TaxItem {
+            category: TaxCategory::Exempt,
+            exemption_reason: "Medical care, Article 132".to_string(),
+            ..tax("medical", TaxType::Percentage, dec!(20))
+        };
+        let items = vec![
+            line("a", dec!(2), dec!(50), vec![vat], vec![], vec![]),
+            line("b", dec!(1), dec!(80), vec![medical], vec![], vec![]),
+        ];
+
+        let domestic = calculate_invoice(&InvoiceSnapshot {
+            line_items: items.clone(),
+            ..Default::default()
+        });
+        assert_amount(domestic.tax_total, dec!(20));
+        assert_eq!(
+            domestic.exemption_notes(),
+            vec!["Medical care, Article 132"]
+        );
+
+        // Only the charged standard tax takes the category of the VAT rules
+        let reverse_charged = calculate_invoice(&InvoiceSnapshot {
+            line_items: items,
+            vat_category: Some(TaxCategory::ReverseCharge),
+            ..Default::default()
+        });
+        assert_amount(reverse_charged.tax_total, dec!(0));
+        assert_amount(reverse_charged.grand_total, dec!(180));
+        let vat = &reverse_charged.taxes[1];
+        assert_eq!(vat.category, TaxCategory::ReverseCharge);
+        assert_amount(vat.rate, dec!(0));
+        assert_amount(vat.taxable, dec!(100));
+        assert_eq!(
+            reverse_charged.exemption_notes(),
+            vec![
+                "Medical care, Article 132",
+                TaxCategory::ReverseCharge.default_reason()
+            ]
+        );
+    }
+
+    #[test]
+    fn withheld_taxes_reduce_what_is_payable() {
+        let vat = tax("vat", TaxType::Percentage, dec!(21));
+        let irpf = TaxItem {
+            kind: TaxKind::Withholding,
+            ..tax("irpf", TaxType::Percentage, dec!(15))
+        };
+        let items = vec![
+            line(
+                "a",
+                dec!(3),
+                dec!(333.33),
+                vec![vat.clone(), irpf.clone()],
+                vec![],
+                vec![],
+            ),
+            line("b", dec!(1), dec!(100), vec![vat], vec![], vec![]),
+        ];
+
+        let breakdown = calculate_invoice(&InvoiceSnapshot {
+            line_items: items.clone(),
+            ..Default::default()
+        });
+        assert_consistent(&breakdown);
+        // 999.99 + 100 + 21% VAT, IRPF only on the first line's 999.99
+        assert_amount(breakdown.tax_total, dec!(231.00));
+        assert_amount(breakdown.grand_total, dec!(1330.99));
+        assert_eq!(breakdown.withholding.len(), 1);
+        assert_amount(breakdown.withholding[0].taxable, dec!(999.99));
+        assert_amount(breakdown.withholding_total, dec!(150.00));
+        assert_amount(breakdown.net_payable, dec!(1180.99));
+        assert!(breakdown.taxes.iter().all(|t| t.tax_id != "irpf"));
+
+        // Crediting the lines gives the withheld amount back with the sign flipped
+        let credited = calculate_invoice(&InvoiceSnapshot {
+            line_items: items
+                .into_iter()
+                .map(|item| LineItem {
+                    quantity: -item.quantity,
+                    ..item
+                })
+                .collect(),
+            ..Default::default()
+        });
+        assert_amount(credited.withholding_total, dec!(-150.00));
+        assert_amount(credited.net_payable, dec!(-1180.99));
+    }
+
+    #[test]
+    fn legacy_float_amounts_deserialize() {
+        let json = r#"{
+            "id": "a",
+            "name": "Legacy",
+            "quantity": 3.0,
+            "unit_price": 19.99,
+            "taxes": [{"id": "vat", "name": "VAT", "tax_type": "Percentage", "rate": 10.0}],
+            "discounts": [],
+            "charges": [],
+            "custom_fields": {}
+        }"#;
+        let item: LineItem = serde_json::from_str(json).unwrap();
+        assert_amount(item.quantity, dec!(3));
+        assert_amount(item.unit_price, dec!(19.99));
+
+        let breakdown = calculate_line(&item, &RoundingPolicy::default());
+        assert_amount(breakdown.gross, dec!(59.97));
+        assert_amount(breakdown.total, dec!(65.97));
+
+        let round_trip: LineItem =
+            serde_json::from_str(&serde_json::to_string(&item).unwrap()).unwrap();
+        assert_eq!(round_trip, item);
+    }
+
+    #[test]
+    fn snapshot_without_rounding_policy_uses_default() {
+        let json = r#"{"line_items": [], "discounts": [], "charges": []}"#;
+        let snapshot: InvoiceSnapshot = serde_json::from_str(json).unwrap();
+        assert_eq!(snapshot.rounding, RoundingPolicy::default());
+    }
+}
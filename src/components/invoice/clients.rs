@@ -0,0 +1,259 @@
+use leptos::prelude::*;
+use phosphor_leptos::{Icon, MAGNIFYING_GLASS};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use time::Date;
+use time::macros::format_description;
+
+use super::*;
+use crate::components::ui::{
+    AutocompleteConfig, AutocompleteDataSource, AutocompleteGroup, AutocompleteItem,
+    GroupedAutocomplete, StaticDataSource,
+};
+
+/// Values applied to an invoice when its client is picked from the directory
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct ClientDefaults {
+    /// Currency symbol, e.g. "€"
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Days between the issue date and the due date
+    #[serde(default)]
+    pub payment_terms_days: Option<u32>,
+    #[serde(default)]
+    pub tax_exempt: bool,
+    /// Shown on the invoice when `tax_exempt` is set, e.g. a certificate number
+    #[serde(default)]
+    pub tax_exemption_note: String,
+    #[serde(default)]
+    pub template_id: Option<String>,
+}
+
+/// An entry of the client directory, holding the values of the client fields
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Client {
+    pub id: String,
+    #[serde(default)]
+    pub values: HashMap<String, FieldItemValue>,
+    #[serde(default)]
+    pub defaults: ClientDefaults,
+    /// Archived clients stay in the directory but are not offered on invoices
+    #[serde(default)]
+    pub archived: bool,
+}
+
+impl Client {
+    pub fn new(values: HashMap<String, FieldItemValue>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            values,
+            ..Default::default()
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.values
+            .get(CLIENT_NAME_FIELD_ID)
+            .map(|field| field.value.to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "Unnamed client".to_string())
+    }
+
+    /// Every value except the name, in a stable order
+    pub fn details(&self) -> Vec<String> {
+        let mut details = self
+            .values
+            .values()
+            .filter(|field| field.id != CLIENT_NAME_FIELD_ID)
+            .map(|field| field.value.to_string())
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<_>>();
+        details.sort();
+        details
+    }
+}
+
+/// Adds `days` to an ISO `YYYY-MM-DD` date, `None` when the date is not set
+pub fn add_days(date: &str, days: u32) -> Option<String> {
+    let format = format_description!("[year]-[month]-[day]");
+    let due = Date::parse(date, format).ok()? + time::Duration::days(days.into());
+    due.format(format).ok()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClientSuggestion {
+    pub id: String,
+    pub name: String,
+    pub details: String,
+}
+
+impl From<&Client> for ClientSuggestion {
+    fn from(client: &Client) -> Self {
+        Self {
+            id: client.id.clone(),
+            name: client.name(),
+            details: client.details().join(", "),
+        }
+    }
+}
+
+impl AutocompleteItem for ClientSuggestion {
+    type Id = String;
+
+    fn id(&self) -> Self::Id {
+        self.id.clone()
+    }
+
+    fn search_text(&self) -> String {
+        format!("{} {}", self.name, self.details)
+    }
+
+    fn display_text(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// Searches the clients that are not archived
+#[derive(Clone, Copy)]
+pub struct ClientDataSource {
+    clients: Signal<Vec<Client>>,
+}
+
+impl AutocompleteDataSource<ClientSuggestion> for ClientDataSource {
+    fn search(&self, query: &str) -> Vec<AutocompleteGroup<ClientSuggestion>> {
+        let items = self.clients.with_untracked(|clients| {
+            clients
+                .iter()
+                .filter(|client| !client.archived)
+                .map(ClientSuggestion::from)
+                .collect()
+        });
+        let groups = vec![AutocompleteGroup {
+            id: "clients".to_string(),
+            name: "Clients".to_string(),
+            items,
+        }];
+
+        StaticDataSource::new(groups, false).search(query)
+    }
+}
+
+/// Client fields with a directory search on top
+#[component]
+pub fn ClientCard(
+    fields: ReadSignal<Vec<FieldItem>>,
+    form_values: RwSignal<HashMap<String, FieldItemValue>>,
+    #[prop(into)] clients: Signal<Vec<Client>>,
+    on_select: Callback<Client>,
+    on_save: Callback<()>,
+) -> impl IntoView {
+    view! {
+        <div class="space-y-2">
+            <div class="bg-white shadow rounded-lg p-4 flex items-end gap-2">
+                <div class="flex-1">
+                    <ClientPicker clients=clients on_select=on_select />
+                </div>
+                <button
+                    type="button"
+                    class="rounded-md border border-gray-300 px-3 py-2 text-sm hover:bg-gray-50"
+                    on:click=move |_| on_save.run(())
+                >
+                    "Save as client"
+                </button>
+            </div>
+            <ClientFields fields=fields form_values=form_values />
+        </div>
+    }
+}
+
+#[component]
+pub fn ClientPicker(
+    #[prop(into)] clients: Signal<Vec<Client>>,
+    on_select: Callback<Client>,
+) -> impl IntoView {
+    let config = AutocompleteConfig {
+        min_query_length: 1,
+        max_results_per_group: Some(8),
+        debounce_ms: 200.0,
+        case_sensitive: false,
+    };
+    let data_source = ClientDataSource { clients };
+    let on_pick = Callback::new(move |suggestion: ClientSuggestion| {
+        let client = clients.with_untracked(|clients| {
+            clients
+                .iter()
+                .find(|client| client.id == suggestion.id)
+                .cloned()
+        });
+        if let Some(client) = client {
+            on_select.run(client);
+        }
+    });
+
+    view! {
+        <GroupedAutocomplete
+            data_source=data_source
+            config=config
+            on_select=on_pick
+            render=move |props, actions| {
+                view! {
+                    <div>
+                        <label class="form-label" for="client-search">
+                            "Client"
+                        </label>
+                        <div class="relative">
+                            <input
+                                class="form-input"
+                                id="client-search"
+                                placeholder="Search the client directory"
+                                type="text"
+                                autocomplete="off"
+                                prop:value=move || props.query.get()
+                                on:input=move |ev| actions.set_query.set(event_target_value(&ev))
+                                on:keydown=move |ev| actions.handle_key_down.run(ev)
+                            />
+                            <div class="absolute inset-y-0 right-0 pr-3 flex items-center pointer-events-none text-gray-400">
+                                <Icon icon=MAGNIFYING_GLASS />
+                            </div>
+
+                            <Show when=move || {
+                                props.is_open.get() && !props.results.get().is_empty()
+                            }>
+                                <div class="absolute z-10 mt-1 w-full bg-white shadow-lg max-h-60 rounded-md py-1 ring-1 ring-black ring-opacity-5 overflow-auto">
+                                    <For
+                                        each=move || {
+                                            props
+                                                .results
+                                                .get()
+                                                .into_iter()
+                                                .flat_map(|group| group.items)
+                                                .collect::<Vec<_>>()
+                                        }
+                                        key=|item| item.id()
+                                        children=move |item| {
+                                            let picked = item.clone();
+                                            view! {
+                                                <div
+                                                    class="px-4 py-2 cursor-pointer hover:bg-gray-100"
+                                                    on:click=move |_| actions.select_item.run(picked.clone())
+                                                >
+                                                    <div class="text-sm font-medium text-gray-900">
+                                                        {item.display_text()}
+                                                    </div>
+                                                    <div class="text-xs text-gray-500 truncate">
+                                                        {item.details.clone()}
+                                                    </div>
+                                                </div>
+                                            }
+                                        }
+                                    />
+                                </div>
+                            </Show>
+                        </div>
+                    </div>
+                }
+                    .into_any()
+            }
+        />
+    }
+}
@@ -0,0 +1,604 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::*;
+use crate::shared::storage::{Database, StorageError};
+
+/// Statuses of a credit note, it is never paid
+pub const CREDIT_NOTE_STATUSES: [InvoiceStatus; 3] = [
+    InvoiceStatus::Draft,
+    InvoiceStatus::Sent,
+    InvoiceStatus::Void,
+];
+
+/// Issued credit note, recorded on the invoice it corrects
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Credit {
+    pub credit_note_id: String,
+    pub number: String,
+    /// ISO `YYYY-MM-DD`
+    pub date: String,
+    /// Credited amount, positive while the credit note total is negative
+    pub amount: Decimal,
+}
+
+pub fn amount_credited(credits: &[Credit]) -> Decimal {
+    credits.iter().map(|credit| credit.amount).sum()
+}
+
+/// Counter numbering credit notes, a yearly series of its own like the quotes
+pub fn credit_note_number_field() -> FieldItem {
+    FieldItem {
+        id: CREDIT_NOTE_NUMBER_FIELD_ID.to_string(),
+        name: "Credit Note Number".to_string(),
+        field_type: FieldType::AutoGenerated,
+        category: FieldCategory::Invoice,
+        auto_generation: Some(AutoGenerationRule::Counter {
+            prefix: Some("CN-{YYYY}-".to_string()),
+            suffix: None,
+            start_value: 1,
+            increment: 1,
+            reset_period: Some(ResetPeriod::Yearly),
+            padding: Some(4),
+            fiscal_year_start: None,
+        }),
+        ..Default::default()
+    }
+}
+
+/// Quantities of the invoice lines that `credit_lines`, the lines of the credit
+/// notes issued against the invoice, already credit
+pub fn credited_quantities<'a>(
+    credit_lines: impl IntoIterator<Item = &'a LineItem>,
+) -> HashMap<String, Decimal> {
+    let mut credited = HashMap::<String, Decimal>::new();
+    for item in credit_lines {
+        if let Some(line_id) = &item.credited_line_id {
+            *credited.entry(line_id.clone()).or_default() -= item.quantity;
+        }
+    }
+    credited
+}
+
+/// Draft credit note for the invoice `invoice` calculated as `snapshot`, issued on
+/// `issue_date`. `quantities` maps line item ids to the quantity to credit, capped
+/// at what is left of the invoiced quantity after the `credited` quantities; lines
+/// without a quantity are left out. Credited lines get a negative quantity, the
+/// rest of the invoice (parties, fields, tax settings) is copied.
+///
+/// Fixed invoice-level discounts and charges are credited in proportion to the
+/// credited part of the discounted subtotal, percentage ones apply as they are.
+pub fn credit_note_for(
+    invoice: &InvoiceDocument,
+    snapshot: &InvoiceSnapshot,
+    quantities: &HashMap<String, Decimal>,
+    credited: &HashMap<String, Decimal>,
+    issue_date: &str,
+) -> (InvoiceDocument, Vec<LineItem>) {
+    let mut credit_note = invoice.clone();
+    credit_note.id = uuid::Uuid::new_v4().to_string();
+    credit_note.document_type = DocumentType::CreditNote;
+    credit_note.status = InvoiceStatus::Draft;
+    credit_note.credited_invoice_id = Some(invoice.id.clone());
+    credit_note.credited_invoice_number = invoice.number();
+    credit_note.credits.clear();
+    credit_note.payments.clear();
+    credit_note.recurring_profile_id = None;
+    credit_note.needs_review = false;
+    credit_note.quote_status = QuoteStatus::default();
+    credit_note.valid_until.clear();
+    credit_note.quote_id = None;
+    credit_note.converted_invoice_id = None;
+    credit_note.invoice_info.remove(INVOICE_NUMBER_FIELD_ID);
+    credit_note.invoice_info.remove(QUOTE_NUMBER_FIELD_ID);
+    credit_note.invoice_info.remove(DUE_DATE_FIELD_ID);
+    credit_note
+        .invoice_info
+        .entry(ISSUE_DATE_FIELD_ID.to_string())
+        .or_insert_with(|| FieldItemValue {
+            id: ISSUE_DATE_FIELD_ID.to_string(),
+            label: "Issue Date".to_string(),
+            value: FieldValue::Date(String::new()),
+        })
+        .value = FieldValue::Date(issue_date.to_string());
+
+    let line_items: Vec<LineItem> = snapshot
+        .line_items
+        .iter()
+        .filter_map(|item| {
+            let left = item.quantity - credited.get(&item.id).copied().unwrap_or_default();
+            let quantity = (*quantities.get(&item.id)?).min(left);
+            (quantity > Decimal::ZERO).then(|| LineItem {
+                id: uuid::Uuid::new_v4().to_string(),
+                quantity: -quantity,
+                credited_line_id: Some(item.id.clone()),
+                ..item.clone()
+            })
+        })
+        .collect();
+
+    let discounted_subtotal = |line_items: Vec<LineItem>| {
+        let totals = calculate_invoice(&InvoiceSnapshot {
+            line_items,
+            discounts: vec![],
+            charges: vec![],
+            ..snapshot.clone()
+        });
+        (totals.subtotal - totals.line_discounts).abs()
+    };
+    let invoiced = discounted_subtotal(snapshot.line_items.clone());
+    let share = if invoiced.is_zero() {
+        Decimal::ZERO
+    } else {
+        discounted_subtotal(line_items.clone()) / invoiced
+    };
+    let prorate = |amount: Decimal| snapshot.rounding.round(amount * share);
+    credit_note.discounts = Some(
+        invoice_discounts(&snapshot.discounts)
+            .into_iter()
+            .map(|discount| match discount.discount_type {
+                DiscountType::Percentage => discount,
+                DiscountType::FixedAmount => DiscountItem {
+                    value: prorate(discount.value),
+                    ..discount
+                },
+            })
+            .collect(),
+    );
+    credit_note.charges = Some(
+        invoice_charges(&snapshot.charges)
+            .into_iter()
+            .map(|charge| ChargeItem {
+                amount: prorate(charge.amount),
+                ..charge
+            })
+            .collect(),
+    );
+
+    (credit_note, line_items)
+}
+
+/// Stores a draft credit note for `invoice`, calculated as `snapshot`, and returns
+/// its id. Lines already credited by other credit notes that are not void are
+/// only credited for what is left of them.
+pub async fn create_credit_note(
+    invoice: InvoiceDocument,
+    snapshot: InvoiceSnapshot,
+    quantities: HashMap<String, Decimal>,
+) -> Result<String, StorageError> {
+    let mut db = Database::open().await?;
+    let mut credit_lines = vec![];
+    for credit_note in db.list_invoices().await? {
+        if credit_note.credited_invoice_id.as_ref() == Some(&invoice.id)
+            && credit_note.status != InvoiceStatus::Void
+        {
+            credit_lines.extend(db.load_line_items(&credit_note.id).await?);
+        }
+    }
+    let credited = credited_quantities(&credit_lines);
+
+    let (credit_note, credit_items) =
+        credit_note_for(&invoice, &snapshot, &quantities, &credited, &today_iso());
+    if credit_items.is_empty() {
+        return Err(StorageError::UnexpectedResult(
+            "The selected lines are already credited".to_string(),
+        ));
+    }
+    db.save_line_items(&credit_note.id, &credit_items).await?;
+    db.save_invoice(&credit_note).await?;
+    Ok(credit_note.id)
+}
+
+/// Records `amount` as credited by `credit_note` on the invoice it corrects. Only
+/// sent credit notes count, one back in draft, voided or deleted (no `amount`)
+/// is taken off the invoice again.
+pub async fn record_credit(
+    db: &mut Database,
+    credit_note: &InvoiceDocument,
+    amount: Option<Decimal>,
+) -> Result<(), StorageError> {
+    let Some(invoice_id) = &credit_note.credited_invoice_id else {
+        return Ok(());
+    };
+    let Some(mut invoice) = db.load_invoice(invoice_id).await? else {
+        return Ok(());
+    };
+
+    invoice
+        .credits
+        .retain(|credit| credit.credit_note_id != credit_note.id);
+    if let Some(amount) = amount.filter(|_| credit_note.status.is_final()) {
+        invoice.credits.push(Credit {
+            credit_note_id: credit_note.id.clone(),
+            number: credit_note.number(),
+            date: credit_note.invoice_value(ISSUE_DATE_FIELD_ID),
+            amount,
+        });
+        invoice.credits.sort_by(|a, b| a.date.cmp(&b.date));
+    }
+    db.save_invoice(&invoice).await
+}
+
+/// Records the credit of the credit note open in `state` on the invoice it corrects
+pub async fn sync_credit(state: InvoiceBuilderState) -> Result<(), StorageError> {
+    let credit_note = untrack(|| state.document());
+    let total = calculate_invoice(&untrack(|| state.snapshot())).net_payable;
+    let mut db = Database::open().await?;
+    record_credit(&mut db, &credit_note, Some(-total)).await
+}
+
+/// Credit notes issued against an invoice and a form to credit its lines
+#[component]
+pub fn CreditNotesCard(
+    status: ReadSignal<InvoiceStatus>,
+    line_items: ReadSignal<Vec<LineItem>>,
+    credits: ReadSignal<Vec<Credit>>,
+    /// Draft credit note made from this form, linked until the page is left
+    created: ReadSignal<Option<String>>,
+    on_create: Callback<HashMap<String, Decimal>>,
+) -> impl IntoView {
+    let quantities = RwSignal::new(HashMap::<String, String>::new());
+    let error = RwSignal::new(None::<String>);
+
+    let credit_all = move |_| {
+        quantities.set(line_items.with_untracked(|items| {
+            items
+                .iter()
+                .map(|item| (item.id.clone(), item.quantity.normalize().to_string()))
+                .collect()
+        }));
+    };
+
+    let create = move |ev: web_sys::SubmitEvent| {
+        ev.prevent_default();
+        let selected = quantities.with_untracked(|quantities| {
+            quantities
+                .iter()
+                .filter_map(|(id, text)| {
+                    parse_decimal(text)
+                        .filter(|quantity| *quantity > Decimal::ZERO)
+                        .map(|quantity| (id.clone(), quantity))
+                })
+                .collect::<HashMap<_, _>>()
+        });
+        if selected.is_empty() {
+            error.set(Some(
+                "Enter a quantity to credit on at least one line".to_string(),
+            ));
+            return;
+        }
+        on_create.run(selected);
+        quantities.set(HashMap::new());
+        error.set(None);
+    };
+
+    view! {
+        <div class="bg-white shadow rounded-lg p-4">
+            <h2 class="text-lg font-semibold mb-4">"Credit notes"</h2>
+
+            <For
+                each=move || credits.get()
+                key=|credit| credit.credit_note_id.clone()
+                children=move |credit| {
+                    view! {
+                        <div class="flex items-center justify-between gap-2 text-sm border-b py-2">
+                            <A
+                                href=format!("/invoices/{}", credit.credit_note_id)
+                                attr:class="font-medium text-indigo-600 hover:text-indigo-900"
+                            >
+                                {credit.number}
+                            </A>
+                            <span class="text-xs text-gray-500">{credit.date}</span>
+                            <span>{format_amount(credit.amount)}</span>
+                        </div>
+                    }
+                }
+            />
+            {move || {
+                created
+                    .get()
+                    .map(|credit_note_id| {
+                        view! {
+                            <A
+                                href=format!("/invoices/{credit_note_id}")
+                                attr:class="mt-2 block text-sm text-indigo-600 hover:text-indigo-900"
+                            >
+                                "Open the new credit note"
+                            </A>
+                        }
+                    })
+            }}
+
+            <Show
+                when=move || status.get().is_final()
+                fallback=|| {
+                    view! {
+                        <p class="text-sm text-gray-500">
+                            "Credit notes can be made once the invoice is sent."
+                        </p>
+                    }
+                }
+            >
+                <form class="mt-4 space-y-2" on:submit=create>
+                    <For
+                        each=move || line_items.get()
+                        key=|item| item.id.clone()
+                        children=move |item| {
+                            let input_id = format!("credit-{}", item.id);
+                            let value_id = item.id.clone();
+                            let input_item_id = item.id.clone();
+                            view! {
+                                <div class="flex items-center gap-2 text-sm">
+                                    <label class="flex-1 truncate" for=input_id.clone()>
+                                        {item.name.clone()}
+                                    </label>
+                                    <input
+                                        class="form-input w-24"
+                                        id=input_id
+                                        type="number"
+                                        step="any"
+                                        min="0"
+                                        max=item.quantity.normalize().to_string()
+                                        placeholder="0"
+                                        prop:value=move || {
+                                            quantities
+                                                .with(|quantities| {
+                                                    quantities.get(&value_id).cloned().unwrap_or_default()
+                                                })
+                                        }
+                                        on:input=move |ev| {
+                                            let value = event_target_value(&ev);
+                                            quantities
+                                                .update(|quantities| {
+                                                    quantities.insert(input_item_id.clone(), value);
+                                                });
+                                        }
+                                    />
+                                    <span class="w-16 text-right text-xs text-gray-500">
+                                        {format!("of {}", item.quantity.normalize())}
+                                    </span>
+                                </div>
+                            }
+                        }
+                    />
+                    {move || {
+                        error.get().map(|error| view! { <p class="text-sm text-red-600">{error}</p> })
+                    }}
+                    <div class="flex gap-2">
+                        <button
+                            type="button"
+                            class="rounded-md border border-gray-300 py-2 px-4 text-sm font-medium text-gray-700 hover:bg-gray-50"
+                            on:click=credit_all
+                        >
+                            "Credit all"
+                        </button>
+                        <button
+                            type="submit"
+                            class="flex-1 rounded-md bg-indigo-600 py-2 px-4 text-sm font-medium text-white hover:bg-indigo-700"
+                        >
+                            "Create credit note"
+                        </button>
+                    </div>
+                </form>
+            </Show>
+        </div>
+    }
+}
+
+/// Number, status and corrected invoice of a credit note
+#[component]
+pub fn CreditNoteDetails(
+    #[prop(into)] number: Signal<String>,
+    status: ReadSignal<InvoiceStatus>,
+    credited_invoice_id: ReadSignal<Option<String>>,
+    credited_invoice_number: ReadSignal<String>,
+    on_status: Callback<InvoiceStatus>,
+) -> impl IntoView {
+    view! {
+        <p class="text-sm text-gray-700">
+            {move || match number.get() {
+                number if number.is_empty() => "Numbered once the credit note is sent".to_string(),
+                number => format!("Credit note {number}"),
+            }}
+        </p>
+        {move || {
+            credited_invoice_id
+                .get()
+                .map(|invoice_id| {
+                    view! {
+                        <A
+                            href=format!("/invoices/{invoice_id}")
+                            attr:class="block text-sm text-indigo-600 hover:text-indigo-900"
+                        >
+                            {format!("Credits invoice {}", credited_invoice_number.get())}
+                        </A>
+                    }
+                })
+        }}
+        <div>
+            <label class="form-label" for="credit-note-status">
+                "Status"
+            </label>
+            <select
+                class="form-select"
+                id="credit-note-status"
+                name="credit-note-status"
+                on:change=move |ev| {
+                    if let Some(value) = InvoiceStatus::from_label(&event_target_value(&ev)) {
+                        on_status.run(value);
+                    }
+                }
+            >
+                {CREDIT_NOTE_STATUSES
+                    .into_iter()
+                    .map(|value| {
+                        view! {
+                            <option value=value.label() selected=move || status.get() == value>
+                                {value.label()}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
+            </select>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::invoice::test_support::field_entry;
+    use rust_decimal::dec;
+
+    fn item(id: &str, quantity: Decimal) -> LineItem {
+        LineItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            quantity,
+            unit_price: dec!(10),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn credit_note_credits_the_selected_quantities() {
+        let invoice = InvoiceDocument {
+            id: "invoice".to_string(),
+            status: InvoiceStatus::Sent,
+            invoice_info: HashMap::from([field_entry(
+                INVOICE_NUMBER_FIELD_ID,
+                "Invoice Number",
+                FieldValue::AutoGenerated("INV-2025-0007".to_string()),
+            )]),
+            ..Default::default()
+        };
+        let snapshot = InvoiceSnapshot {
+            line_items: vec![item("a", dec!(3)), item("b", dec!(2)), item("c", dec!(1))],
+            ..Default::default()
+        };
+        let quantities = HashMap::from([
+            ("a".to_string(), dec!(3)),
+            ("b".to_string(), dec!(5)),
+            ("c".to_string(), dec!(0)),
+        ]);
+
+        let (credit_note, line_items) = credit_note_for(
+            &invoice,
+            &snapshot,
+            &quantities,
+            &HashMap::new(),
+            "2025-04-02",
+        );
+
+        assert_eq!(credit_note.document_type, DocumentType::CreditNote);
+        assert_eq!(credit_note.status, InvoiceStatus::Draft);
+        assert_eq!(credit_note.credited_invoice_id.as_deref(), Some("invoice"));
+        assert_eq!(credit_note.credited_invoice_number, "INV-2025-0007");
+        assert_eq!(credit_note.number(), "");
+        assert_eq!(credit_note.invoice_value(ISSUE_DATE_FIELD_ID), "2025-04-02");
+        let credited = line_items
+            .iter()
+            .map(|item| (item.name.as_str(), item.quantity))
+            .collect::<Vec<_>>();
+        assert_eq!(credited, vec![("a", dec!(-3)), ("b", dec!(-2))]);
+        assert_eq!(line_items[0].credited_line_id.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn lines_are_credited_only_for_what_is_left() {
+        let snapshot = InvoiceSnapshot {
+            line_items: vec![item("a", dec!(3)), item("b", dec!(2))],
+            ..Default::default()
+        };
+        let quantities = HashMap::from([("a".to_string(), dec!(3)), ("b".to_string(), dec!(2))]);
+        let (_, first) = credit_note_for(
+            &InvoiceDocument::default(),
+            &snapshot,
+            &HashMap::from([("a".to_string(), dec!(2))]),
+            &HashMap::new(),
+            "2025-04-02",
+        );
+
+        let credited = credited_quantities(&first);
+        assert_eq!(credited, HashMap::from([("a".to_string(), dec!(2))]));
+        let (_, second) = credit_note_for(
+            &InvoiceDocument::default(),
+            &snapshot,
+            &quantities,
+            &credited,
+            "2025-04-03",
+        );
+        let quantities = second
+            .iter()
+            .map(|item| (item.name.as_str(), item.quantity))
+            .collect::<Vec<_>>();
+        assert_eq!(quantities, vec![("a", dec!(-1)), ("b", dec!(-2))]);
+    }
+
+    #[test]
+    fn fixed_invoice_adjustments_are_prorated() {
+        let shipping = ChargeItem {
+            id: "shipping".to_string(),
+            name: "Shipping".to_string(),
+            description: String::new(),
+            amount: dec!(10),
+            scope: ChargeScope::GlobalInvoice,
+            is_default: false,
+        };
+        let discount = |id: &str, discount_type: DiscountType, value: Decimal| DiscountItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            discount_type,
+            value,
+            scope: DiscountScope::GlobalInvoice,
+            is_default: false,
+        };
+        let snapshot = InvoiceSnapshot {
+            line_items: vec![item("a", dec!(3)), item("b", dec!(1))],
+            discounts: vec![
+                discount("fixed", DiscountType::FixedAmount, dec!(4)),
+                discount("percent", DiscountType::Percentage, dec!(10)),
+            ],
+            charges: vec![shipping],
+            ..Default::default()
+        };
+
+        let (credit_note, line_items) = credit_note_for(
+            &InvoiceDocument::default(),
+            &snapshot,
+            &HashMap::from([("b".to_string(), dec!(1))]),
+            &HashMap::new(),
+            "2025-04-02",
+        );
+
+        // The credited line is a quarter of the invoice
+        let discounts = credit_note.discounts.clone().unwrap_or_default();
+        assert_eq!(discounts[0].value, dec!(1));
+        assert_eq!(discounts[1].value, dec!(10));
+        let charges = credit_note.charges.clone().unwrap_or_default();
+        assert_eq!(charges[0].amount, dec!(2.5));
+
+        let totals = calculate_invoice(&credit_note.snapshot(line_items, vec![], vec![]));
+        // A quarter of the invoice total of 40 - 4 - 4 + 10
+        assert_eq!(totals.grand_total, dec!(-10.5));
+    }
+
+    #[test]
+    fn credits_reduce_the_balance_due() {
+        let payments = vec![Payment {
+            amount: dec!(30),
+            ..Default::default()
+        }];
+        let credits = vec![Credit {
+            amount: dec!(50),
+            ..Default::default()
+        }];
+
+        assert_eq!(balance_due(dec!(100), &payments, &credits), dec!(20));
+        assert_eq!(balance_due(dec!(100), &payments, &[]), dec!(70));
+    }
+}
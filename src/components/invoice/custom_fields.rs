@@ -4,6 +4,11 @@ use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use time::{Date, Duration, OffsetDateTime};
 
+use super::{
+    AutoGenerationEditor, AutoValueGeneratorRegistry, DefaultGeneratorRegistry, Formula,
+    FormulaValue, check_generator_parameters, expand_date_tokens, fiscal_year_start_month,
+    formula_order, validate_formula_field,
+};
 use crate::components::editable_grid::{
     FormData, FormValidation, ItemData, ValidationResult, use_editable_grid, validation::validators,
 };
@@ -100,6 +105,9 @@ pub enum AutoGenerationRule {
         increment: i64,
         reset_period: Option<ResetPeriod>,
         padding: Option<usize>, // Zero-padding for numbers
+        /// Month (1-12) a yearly reset happens in, January when not set
+        #[serde(default)]
+        fiscal_year_start: Option<u8>,
     },
     Formula {
         expression: String,        // Custom formula expression
@@ -182,6 +190,23 @@ pub enum FieldValue {
     Array(Vec<String>), // For repeatable fields
 }
 
+impl std::fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::Text(v)
+            | FieldValue::Email(v)
+            | FieldValue::Phone(v)
+            | FieldValue::Dropdown(v)
+            | FieldValue::Date(v)
+            | FieldValue::Textarea(v)
+            | FieldValue::AutoGenerated(v) => write!(f, "{v}"),
+            FieldValue::Number(n) => write!(f, "{n}"),
+            FieldValue::Checkbox(b) => write!(f, "{}", if *b { "Yes" } else { "No" }),
+            FieldValue::Array(values) => write!(f, "{}", values.join(", ")),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct FieldItemValue {
     pub id: String,
@@ -243,6 +268,8 @@ impl Eq for FieldItem {}
 // Enhanced form structure
 #[derive(Default, Clone, PartialEq, Debug)]
 pub struct FieldForm {
+    /// Id of the field being edited, formulas refer to fields by id
+    pub id: Option<String>,
     pub name: String,
     pub field_type: FieldType,
     pub category: FieldCategory,
@@ -291,11 +318,14 @@ impl AutoGenerationRule {
                 prefix,
                 suffix,
                 start_value,
-                increment,
-                reset_period,
                 padding,
+                fiscal_year_start,
+                ..
             } => {
+                // Counter states hold the value reserved when the invoice was finalized
                 let current_count = context.counter_states.get(field_id).unwrap_or(start_value);
+                let date = context.current_date.date();
+                let fiscal_start = fiscal_year_start_month(*fiscal_year_start);
                 let formatted_number = if let Some(pad) = padding {
                     format!("{:0width$}", current_count, width = pad)
                 } else {
@@ -304,11 +334,11 @@ impl AutoGenerationRule {
 
                 let mut result = String::new();
                 if let Some(p) = prefix {
-                    result.push_str(p);
+                    result.push_str(&expand_date_tokens(p, date, fiscal_start));
                 }
                 result.push_str(&formatted_number);
                 if let Some(s) = suffix {
-                    result.push_str(s);
+                    result.push_str(&expand_date_tokens(s, date, fiscal_start));
                 }
 
                 Ok(result)
@@ -333,38 +363,44 @@ impl AutoGenerationRule {
 
                 Ok(formatted)
             }
-            AutoGenerationRule::Formula {
-                expression,
-                dependencies,
-            } => {
-                // Placeholder for formula evaluation - implement based on your needs
-                Ok(format!("FORMULA({})", expression))
+            AutoGenerationRule::Formula { expression, .. } => {
+                // Current values are keyed by field id, callers may add names and totals
+                let variables = context
+                    .current_values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), FormulaValue::from(value)))
+                    .collect();
+                Formula::parse(expression)
+                    .and_then(|formula| formula.evaluate(&variables))
+                    .map(|value| value.to_string())
+                    .map_err(|error| error.to_string())
             }
             AutoGenerationRule::Custom {
                 generator_id,
                 parameters,
-            } => {
-                // Placeholder for custom generator lookup
-                Ok(format!("CUSTOM({})", generator_id))
-            }
+            } => DefaultGeneratorRegistry.generate(generator_id, parameters, context),
         }
     }
 }
 
 impl DateDefault {
     pub fn resolve(&self) -> Result<String, String> {
-        let today = OffsetDateTime::now_utc().date();
+        self.resolve_on(OffsetDateTime::now_utc().date())
+    }
 
+    /// Resolves the default as if `today` were the current date, used when a
+    /// recurring invoice is issued on a later day
+    pub fn resolve_on(&self, today: Date) -> Result<String, String> {
         match self {
             DateDefault::Today => Ok(today.to_string()),
-            DateDefault::TodayPlus { days } => {
-                let target = today + Duration::days(*days);
-                Ok(target.to_string())
-            }
-            DateDefault::TodayMinus { days } => {
-                let target = today - Duration::days(*days);
-                Ok(target.to_string())
-            }
+            DateDefault::TodayPlus { days } => today
+                .checked_add(Duration::days(*days))
+                .map(|target| target.to_string())
+                .ok_or_else(|| "Date out of range".to_string()),
+            DateDefault::TodayMinus { days } => today
+                .checked_sub(Duration::days(*days))
+                .map(|target| target.to_string())
+                .ok_or_else(|| "Date out of range".to_string()),
             DateDefault::StartOfMonth => {
                 let start = Date::from_calendar_date(today.year(), today.month(), 1)
                     .map_err(|e| format!("Date error: {}", e))?;
@@ -428,6 +464,7 @@ impl FormData for FieldItem {
 
     fn to_form_props(&self) -> Self::FormProps {
         FieldForm {
+            id: Some(self.id.clone()),
             name: self.name.clone(),
             field_type: self.field_type.clone(),
             category: self.category.clone(),
@@ -452,7 +489,10 @@ impl FormData for FieldItem {
 
     fn from_form_props(props: &Self::FormProps) -> Self {
         Self {
-            id: uuid::Uuid::new_v4().to_string(),
+            id: props
+                .id
+                .clone()
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
             name: props.name.clone(),
             field_type: props.field_type.clone(),
             category: props.category.clone(),
@@ -522,6 +562,12 @@ impl ItemData for FieldItem {
                             "###",
                             suffix.as_deref().unwrap_or("")
                         )),
+                        AutoGenerationRule::Formula { expression, .. } => {
+                            Some(format!("Formula: {}", expression))
+                        }
+                        AutoGenerationRule::Custom { generator_id, .. } => {
+                            Some(format!("Generator: {}", generator_id))
+                        }
                         _ => Some("Auto-generated".to_string()),
                     }
                 } else {
@@ -579,6 +625,16 @@ impl FormValidation for FieldForm {
             });
         }
 
+        if let Some(AutoGenerationRule::Custom { parameters, .. }) = &self.auto_generation
+            && let Err(error) = check_generator_parameters(parameters)
+        {
+            results.push(ValidationResult {
+                is_valid: false,
+                errors: vec![error],
+                ..Default::default()
+            });
+        }
+
         validators::combine_results(results)
     }
 }
@@ -606,6 +662,7 @@ pub fn Fields(
     let (placeholder_value, set_placeholder_value) = signal(None::<String>);
     let (help_text_value, set_help_text_value) = signal(None::<String>);
     let (is_readonly_value, set_is_readonly_value) = signal(false);
+    let (form_error, set_form_error) = signal(None::<String>);
 
     // Update form fields when grid state changes
     Effect::new(move |_| {
@@ -624,6 +681,7 @@ pub fn Fields(
         set_placeholder_value.set(form.placeholder);
         set_help_text_value.set(form.help_text);
         set_is_readonly_value.set(form.is_readonly);
+        set_form_error.set(None);
     });
 
     // Handle form submission
@@ -631,6 +689,7 @@ pub fn Fields(
         ev.prevent_default();
 
         let form_data = FieldForm {
+            id: grid.form_state.get_untracked().current_form.id,
             name: name_value.get(),
             field_type: field_type_value.get(),
             category: category_value.get(),
@@ -652,8 +711,24 @@ pub fn Fields(
             is_readonly: is_readonly_value.get(),
         };
 
+        // Formulas are checked against the fields as they will be after saving
+        let field = FieldItem::from_form_props(&form_data);
+        let mut fields = state.get_untracked();
+        fields.retain(|existing| existing.id != field.id);
+        fields.push(field);
+        let formula_check = fields
+            .iter()
+            .try_for_each(|formula| validate_formula_field(formula, &fields))
+            .and_then(|_| formula_order(&fields).map(|_| ()));
         let validation = form_data.validate();
-        if validation.is_valid {
+        set_form_error.set(
+            formula_check
+                .err()
+                .map(|error| error.to_string())
+                .or_else(|| validation.errors.first().cloned()),
+        );
+
+        if validation.is_valid && form_error.get_untracked().is_none() {
             grid.actions.submit_form.run(form_data);
             // Reset form
             set_name_value.set(String::new());
@@ -802,6 +877,16 @@ pub fn Fields(
                                     }
                                         .into_any()
                                 }
+                                FieldType::AutoGenerated => {
+                                    view! {
+                                        <AutoGenerationEditor
+                                            rule=auto_generation_value
+                                            set_rule=set_auto_generation_value
+                                            fields=state
+                                        />
+                                    }
+                                        .into_any()
+                                }
                                 _ => view! { <div></div> }.into_any(),
                             }
                         }}
@@ -851,6 +936,12 @@ pub fn Fields(
                         </div>
                     </div>
 
+                    {move || {
+                        form_error
+                            .get()
+                            .map(|error| view! { <p class="mt-4 text-sm text-red-600">{error}</p> })
+                    }}
+
                     // Submit buttons
                     <div class="mt-6 flex justify-end gap-3">
                         <Show when=move || grid.form_state.get().is_editing>
@@ -1,5 +1,6 @@
 use crate::components::invoice::{
-    FieldGroup, FieldItem, FieldItemValue, FieldType, FieldValue, GenerationContext,
+    AutoGenerationRule, FieldGroup, FieldItem, FieldItemValue, FieldType, FieldValue, Formula,
+    FormulaInputs, GenerationContext, GeneratorParameter, formula_order, formula_variables,
 };
 use leptos::prelude::*;
 use std::collections::HashMap;
@@ -77,6 +78,10 @@ pub trait AutoValueGeneratorRegistry: Send + Sync {
         context: &GenerationContext,
     ) -> Result<String, String>;
     fn list_generators(&self) -> Vec<(String, String)>; // (id, description) pairs
+    /// Parameters a generator reads, offered in the field editor
+    fn parameters(&self, _generator_id: &str) -> Vec<GeneratorParameter> {
+        vec![]
+    }
 }
 
 /// Field validation trait
@@ -169,6 +174,8 @@ pub fn HeadlessFieldsRenderer(
             .collect::<HashMap<String, FieldGroup>>()
     });
 
+    let formula_inputs = use_context::<FormulaInputs>();
+
     // Auto-value generation effect
     Effect::new({
         let config = config.clone();
@@ -179,16 +186,57 @@ pub fn HeadlessFieldsRenderer(
 
             let fields_map = fields_map.get();
 
+            // Generators read the values of this card and, by id or name, of the others
+            let mut input_values = formula_inputs
+                .map(|inputs| {
+                    inputs.0.with(|values| {
+                        values
+                            .iter()
+                            .map(|(key, value)| (key.clone(), FieldValue::Text(value.to_string())))
+                            .collect::<HashMap<_, _>>()
+                    })
+                })
+                .unwrap_or_default();
+            form_values.with(|values| {
+                input_values.extend(
+                    values
+                        .iter()
+                        .map(|(id, field)| (id.clone(), field.value.clone())),
+                )
+            });
+
             for field in fields_map.values() {
                 if matches!(field.field_type, FieldType::AutoGenerated) {
                     if let Some(ref auto_gen) = field.auto_generation {
+                        // Counter numbers are only reserved when the invoice is finalized,
+                        // formulas are kept up to date by their own effect
+                        if matches!(
+                            auto_gen,
+                            AutoGenerationRule::Counter { .. } | AutoGenerationRule::Formula { .. }
+                        ) {
+                            continue;
+                        }
+
+                        // Custom generators hand out a value once, it stays with the invoice
+                        let is_custom = matches!(auto_gen, AutoGenerationRule::Custom { .. });
+                        let is_set = form_values.with_untracked(|values| {
+                            values
+                                .get(&field.id)
+                                .is_some_and(|value| !value.value.to_string().is_empty())
+                        });
+                        if is_custom && is_set {
+                            continue;
+                        }
+
                         let (current_values, counter_states) = render_context.with(|context| {
-                            let current_values = context
-                                .current_values
-                                .clone()
-                                .into_iter()
-                                .map(|(k, v)| (k, v.value))
-                                .collect();
+                            let mut current_values = input_values.clone();
+                            current_values.extend(
+                                context
+                                    .current_values
+                                    .clone()
+                                    .into_iter()
+                                    .map(|(k, v)| (k, v.value)),
+                            );
 
                             let counter_states = context.counter_states.clone();
 
@@ -201,8 +249,24 @@ pub fn HeadlessFieldsRenderer(
                             current_date: OffsetDateTime::now_utc(),
                         };
 
-                        match auto_gen.generate(&generation_context, &field.id) {
+                        let generated = match (auto_gen, &auto_generator_registry) {
+                            (
+                                AutoGenerationRule::Custom {
+                                    generator_id,
+                                    parameters,
+                                },
+                                Some(registry),
+                            ) => registry.generate(generator_id, parameters, &generation_context),
+                            _ => auto_gen.generate(&generation_context, &field.id),
+                        };
+
+                        match generated {
                             Ok(generated_value) => {
+                                if is_custom {
+                                    render_context.update(|ctx| {
+                                        ctx.validation_errors.remove(&field.id);
+                                    });
+                                }
                                 form_values.update(|values| {
                                     values.insert(
                                         field.id.clone(),
@@ -246,6 +310,103 @@ pub fn HeadlessFieldsRenderer(
         }
     });
 
+    // Formula fields follow the values they read, in dependency order
+    Effect::new({
+        let config = config.clone();
+        move |_| {
+            if !config.auto_generation_enabled {
+                return;
+            }
+
+            let all_fields = filtered_fields.get();
+            let set_error = |field_id: &str, error: Option<String>| {
+                let current = render_context
+                    .with_untracked(|ctx| ctx.validation_errors.get(field_id).cloned());
+                let errors = error.map(|error| vec![error]);
+                if current != errors {
+                    render_context.update(|ctx| match errors {
+                        Some(errors) => {
+                            ctx.validation_errors.insert(field_id.to_string(), errors);
+                        }
+                        None => {
+                            ctx.validation_errors.remove(field_id);
+                        }
+                    });
+                }
+            };
+
+            let order = match formula_order(&all_fields) {
+                Ok(order) => order,
+                Err(error) => {
+                    for field in all_fields.iter().filter(|field| {
+                        matches!(
+                            field.auto_generation,
+                            Some(AutoGenerationRule::Formula { .. })
+                        )
+                    }) {
+                        set_error(&field.id, Some(error.to_string()));
+                    }
+                    return;
+                }
+            };
+            if order.is_empty() {
+                return;
+            }
+
+            // Values of this form win over the ones provided for the whole invoice
+            let values = form_values.with(|values| {
+                values
+                    .iter()
+                    .map(|(id, field)| (id.clone(), field.value.clone()))
+                    .collect::<HashMap<_, _>>()
+            });
+            let mut variables = formula_inputs
+                .map(|inputs| inputs.0.get())
+                .unwrap_or_default();
+            variables.extend(formula_variables(&all_fields, &values, None));
+
+            for field in order {
+                let Some(AutoGenerationRule::Formula { expression, .. }) = &field.auto_generation
+                else {
+                    continue;
+                };
+                let result =
+                    Formula::parse(expression).and_then(|formula| formula.evaluate(&variables));
+                let value = match result {
+                    Ok(value) => value,
+                    Err(error) => {
+                        set_error(&field.id, Some(error.to_string()));
+                        continue;
+                    }
+                };
+                set_error(&field.id, None);
+
+                let text = value.to_string();
+                variables.insert(field.name.to_lowercase(), value.clone());
+                variables.insert(field.id.clone(), value);
+
+                let changed = form_values.with_untracked(|values| {
+                    values
+                        .get(&field.id)
+                        .map(|current| current.value.to_string())
+                        != Some(text.clone())
+                });
+                if changed {
+                    form_values.update(|values| {
+                        values.insert(
+                            field.id.clone(),
+                            FieldItemValue {
+                                id: field.id.clone(),
+                                label: field.name.clone(),
+                                value: FieldValue::AutoGenerated(text),
+                            },
+                        );
+                    });
+                }
+            }
+        }
+    });
+
     // Validation effect
     Effect::new({
         let config = config.clone();
@@ -1,7 +1,9 @@
 use leptos::prelude::*;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 
+use super::{deserialize_amount, format_amount, parse_decimal};
 use crate::components::{
     editable_grid::{
         FormData, FormValidation, ItemData, ValidationResult, use_editable_grid,
@@ -44,25 +46,30 @@ impl std::fmt::Display for DiscountScope {
 }
 
 // 2. Define your data structure for Discounts
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct DiscountItem {
     pub id: String,
     pub name: String,
     pub description: String,
     pub discount_type: DiscountType,
-    pub value: f64, // Amount for fixed, percentage for percentage
+    #[serde(deserialize_with = "deserialize_amount")]
+    pub value: Decimal, // Amount for fixed, percentage for percentage
     pub scope: DiscountScope,
     pub is_default: bool,
 }
 
-impl PartialEq for DiscountItem {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
-    }
+/// Discounts of the catalog that apply to the whole invoice
+pub fn invoice_discounts(discounts: &[DiscountItem]) -> Vec<DiscountItem> {
+    discounts
+        .iter()
+        .filter(|discount| discount.scope == DiscountScope::GlobalInvoice)
+        .cloned()
+        .collect()
 }
 
 impl Eq for DiscountItem {}
 
+// By id only, items equal by value share their id so this agrees with `PartialEq`
 impl Hash for DiscountItem {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
@@ -114,7 +121,7 @@ impl FormData for DiscountItem {
             name: String::new(),
             description: String::new(),
             discount_type: DiscountType::default(),
-            value: 0.0,
+            value: Decimal::ZERO,
             scope: DiscountScope::default(),
             is_default: false,
         }
@@ -137,7 +144,7 @@ impl FormData for DiscountItem {
             name: props.name.clone(),
             description: props.description.clone(),
             discount_type: props.discount_type.clone(),
-            value: props.value.parse::<f64>().unwrap_or(0.0),
+            value: parse_decimal(&props.value).unwrap_or_default(),
             scope: props.scope.clone(),
             is_default: props.is_default,
         }
@@ -159,7 +166,7 @@ impl ItemData for DiscountItem {
 
     fn get_metadata(&self) -> Vec<(String, String)> {
         let formatted_value = match self.discount_type {
-            DiscountType::FixedAmount => format!("${:.2}", self.value),
+            DiscountType::FixedAmount => format!("${}", format_amount(self.value)),
             DiscountType::Percentage => format!("{}%", self.value),
         };
 
@@ -184,18 +191,20 @@ impl FormValidation for DiscountForm {
 
         // Validate value is a valid number and within appropriate range
         if !self.value.is_empty() {
-            if self.value.parse::<f64>().is_err() {
+            if parse_decimal(&self.value).is_none() {
                 results.push(ValidationResult::new().with_field_error(
                     "value".to_string(),
                     "Value must be a valid number".to_string(),
                 ));
-            } else if let Ok(value) = self.value.parse::<f64>() {
-                if value < 0.0 {
+            } else if let Some(value) = parse_decimal(&self.value) {
+                if value.is_sign_negative() {
                     results.push(ValidationResult::new().with_field_error(
                         "value".to_string(),
                         "Value cannot be negative".to_string(),
                     ));
-                } else if matches!(self.discount_type, DiscountType::Percentage) && value > 100.0 {
+                } else if matches!(self.discount_type, DiscountType::Percentage)
+                    && value > Decimal::ONE_HUNDRED
+                {
                     results.push(ValidationResult::new().with_field_error(
                         "value".to_string(),
                         "Percentage cannot exceed 100%".to_string(),
@@ -536,7 +545,7 @@ pub fn Discounts(state: RwSignal<Vec<DiscountItem>>) -> impl IntoView {
                                                     <span class="px-2 py-1 text-xs bg-blue-100 text-blue-800 rounded">
                                                         {match item.discount_type {
                                                             DiscountType::Percentage => format!("{}%", item.value),
-                                                            DiscountType::FixedAmount => format!("${:.2}", item.value),
+                                                            DiscountType::FixedAmount => format!("${}", format_amount(item.value)),
                                                         }}
                                                     </span>
                                                 </div>
@@ -638,7 +647,7 @@ pub fn Discounts(state: RwSignal<Vec<DiscountItem>>) -> impl IntoView {
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
                                                             {match item.discount_type {
                                                                 DiscountType::Percentage => format!("{:.2}%", item.value),
-                                                                DiscountType::FixedAmount => format!("${:.2}", item.value),
+                                                                DiscountType::FixedAmount => format!("${}", format_amount(item.value)),
                                                             }}
                                                         </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
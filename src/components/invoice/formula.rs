@@ -0,0 +1,952 @@
+use leptos::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use time::macros::format_description;
+use time::{Date, Duration, OffsetDateTime};
+
+use super::*;
+
+/// Invoice totals formulas can use next to field values
+pub const FORMULA_TOTALS: [&str; 5] = [
+    "subtotal",
+    "discount_total",
+    "charge_total",
+    "tax_total",
+    "total",
+];
+
+/// Functions with the number of arguments they take
+const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("if", 3, 3),
+    ("round", 1, 2),
+    ("floor", 1, 1),
+    ("ceil", 1, 1),
+    ("abs", 1, 1),
+    ("min", 1, usize::MAX),
+    ("max", 1, usize::MAX),
+    ("upper", 1, 1),
+    ("lower", 1, 1),
+    ("trim", 1, 1),
+    ("len", 1, 1),
+    ("concat", 0, usize::MAX),
+    ("text", 1, 1),
+    ("number", 1, 1),
+    ("date", 1, 1),
+    ("today", 0, 0),
+    ("add_days", 2, 2),
+    ("days_between", 2, 2),
+    ("year", 1, 1),
+    ("month", 1, 1),
+    ("day", 1, 1),
+];
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum FormulaError {
+    #[error("{message} at position {position}")]
+    Syntax { position: usize, message: String },
+    #[error("unknown function {0}")]
+    UnknownFunction(String),
+    #[error("{0} takes {1} arguments")]
+    Arity(String, String),
+    #[error("unknown field {0}")]
+    UnknownField(String),
+    #[error("{0} is used but not listed as a dependency")]
+    UndeclaredDependency(String),
+    #[error("formulas depend on each other: {}", .0.join(" → "))]
+    Cycle(Vec<String>),
+    #[error("{0}")]
+    Type(String),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("result is out of range")]
+    Overflow,
+}
+
+/// A value produced while evaluating a formula
+#[derive(Clone, PartialEq, Debug)]
+pub enum FormulaValue {
+    Number(Decimal),
+    Text(String),
+    Bool(bool),
+    Date(Date),
+}
+
+impl FormulaValue {
+    fn type_name(&self) -> &'static str {
+        match self {
+            FormulaValue::Number(_) => "a number",
+            FormulaValue::Text(_) => "text",
+            FormulaValue::Bool(_) => "a condition",
+            FormulaValue::Date(_) => "a date",
+        }
+    }
+
+    fn as_number(&self) -> Result<Decimal, FormulaError> {
+        match self {
+            FormulaValue::Number(number) => Ok(*number),
+            FormulaValue::Text(text) if text.trim().is_empty() => Ok(Decimal::ZERO),
+            FormulaValue::Text(text) => parse_decimal(text)
+                .ok_or_else(|| FormulaError::Type(format!("expected a number, got \"{text}\""))),
+            other => Err(FormulaError::Type(format!(
+                "expected a number, got {}",
+                other.type_name()
+            ))),
+        }
+    }
+
+    fn as_date(&self) -> Result<Date, FormulaError> {
+        match self {
+            FormulaValue::Date(date) => Ok(*date),
+            FormulaValue::Text(text) => parse_date(text)
+                .ok_or_else(|| FormulaError::Type(format!("expected a date, got \"{text}\""))),
+            other => Err(FormulaError::Type(format!(
+                "expected a date, got {}",
+                other.type_name()
+            ))),
+        }
+    }
+
+    /// Empty text and zero count as false
+    fn is_true(&self) -> bool {
+        match self {
+            FormulaValue::Bool(value) => *value,
+            FormulaValue::Number(number) => !number.is_zero(),
+            FormulaValue::Text(text) => !text.is_empty(),
+            FormulaValue::Date(_) => true,
+        }
+    }
+}
+
+impl std::fmt::Display for FormulaValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormulaValue::Number(number) => write!(f, "{}", number.normalize()),
+            FormulaValue::Text(text) => write!(f, "{text}"),
+            FormulaValue::Bool(value) => write!(f, "{}", if *value { "Yes" } else { "No" }),
+            FormulaValue::Date(date) => write!(f, "{date}"),
+        }
+    }
+}
+
+impl From<&FieldValue> for FormulaValue {
+    fn from(value: &FieldValue) -> Self {
+        match value {
+            FieldValue::Number(number) => {
+                FormulaValue::Number(Decimal::try_from(*number).unwrap_or_default())
+            }
+            FieldValue::Checkbox(checked) => FormulaValue::Bool(*checked),
+            FieldValue::Date(text) => parse_date(text)
+                .map(FormulaValue::Date)
+                .unwrap_or_else(|| FormulaValue::Text(text.clone())),
+            other => FormulaValue::Text(other.to_string()),
+        }
+    }
+}
+
+fn parse_date(text: &str) -> Option<Date> {
+    Date::parse(text.trim(), format_description!("[year]-[month]-[day]")).ok()
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Number(Decimal),
+    Text(String),
+    Name(String),
+    /// `{...}`, a field id or name that is not a plain identifier
+    Reference(String),
+    Operator(&'static str),
+    Open,
+    Close,
+    Comma,
+}
+
+const OPERATORS: [&str; 15] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "&",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, FormulaError> {
+    let chars = source.char_indices().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    let syntax = |position: usize, message: &str| FormulaError::Syntax {
+        position,
+        message: message.to_string(),
+    };
+
+    while i < chars.len() {
+        let (position, c) = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(|(_, n)| n.is_ascii_digit()))
+        {
+            let start = i;
+            while i < chars.len() && (chars[i].1.is_ascii_digit() || chars[i].1 == '.') {
+                i += 1;
+            }
+            let text = chars[start..i].iter().map(|(_, c)| c).collect::<String>();
+            let number = text
+                .parse::<Decimal>()
+                .map_err(|_| syntax(position, "invalid number"))?;
+            tokens.push((position, Token::Number(number)));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') {
+                i += 1;
+            }
+            let name = chars[start..i].iter().map(|(_, c)| c).collect::<String>();
+            tokens.push((position, Token::Name(name)));
+        } else if c == '"' || c == '\'' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(syntax(position, "unterminated text")),
+                    Some((_, '\\')) if i + 1 < chars.len() => {
+                        text.push(chars[i + 1].1);
+                        i += 2;
+                    }
+                    Some((_, quote)) if *quote == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some((_, other)) => {
+                        text.push(*other);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push((position, Token::Text(text)));
+        } else if c == '{' {
+            let end = chars[i..]
+                .iter()
+                .position(|(_, c)| *c == '}')
+                .ok_or_else(|| syntax(position, "missing }"))?;
+            let reference = chars[i + 1..i + end]
+                .iter()
+                .map(|(_, c)| c)
+                .collect::<String>();
+            if reference.trim().is_empty() {
+                return Err(syntax(position, "empty field reference"));
+            }
+            tokens.push((position, Token::Reference(reference.trim().to_string())));
+            i += end + 1;
+        } else if c == '(' {
+            tokens.push((position, Token::Open));
+            i += 1;
+        } else if c == ')' {
+            tokens.push((position, Token::Close));
+            i += 1;
+        } else if c == ',' {
+            tokens.push((position, Token::Comma));
+            i += 1;
+        } else {
+            let rest = &source[position..];
+            let operator = OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(**operator))
+                .ok_or_else(|| syntax(position, &format!("unexpected \"{c}\"")))?;
+            tokens.push((position, Token::Operator(operator)));
+            i += operator.chars().count();
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Expr {
+    Literal(FormulaValue),
+    Reference(String),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Binary operators from the loosest to the tightest binding
+const PRECEDENCE: [&[&str]; 5] = [
+    &["||"],
+    &["&&"],
+    &["==", "!=", "<", "<=", ">", ">="],
+    &["+", "-", "&"],
+    &["*", "/", "%"],
+];
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map(|(position, _)| *position)
+            .unwrap_or(self.end)
+    }
+
+    fn error(&self, message: &str) -> FormulaError {
+        FormulaError::Syntax {
+            position: self.position(),
+            message: message.to_string(),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(_, token)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, FormulaError> {
+        let Some(operators) = PRECEDENCE.get(level) else {
+            return self.unary();
+        };
+
+        let mut left = self.binary(level + 1)?;
+        while let Some(Token::Operator(operator)) = self.peek() {
+            let operator = *operator;
+            if !operators.contains(&operator) {
+                break;
+            }
+            self.index += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, FormulaError> {
+        match self.peek() {
+            Some(Token::Operator(operator @ ("-" | "!"))) => {
+                let operator = *operator;
+                self.index += 1;
+                Ok(Expr::Unary(operator, Box::new(self.unary()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, FormulaError> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expr::Literal(FormulaValue::Number(number))),
+            Some(Token::Text(text)) => Ok(Expr::Literal(FormulaValue::Text(text))),
+            Some(Token::Reference(reference)) => Ok(Expr::Reference(reference)),
+            Some(Token::Name(name)) if name == "true" || name == "false" => {
+                Ok(Expr::Literal(FormulaValue::Bool(name == "true")))
+            }
+            Some(Token::Name(name)) if self.peek() == Some(&Token::Open) => {
+                self.index += 1;
+                let arguments = self.arguments()?;
+                check_arity(&name, arguments.len())?;
+                Ok(Expr::Call(name, arguments))
+            }
+            Some(Token::Name(name)) => Ok(Expr::Reference(name)),
+            Some(Token::Open) => {
+                let expr = self.binary(0)?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => {
+                        self.index -= 1;
+                        Err(self.error("missing )"))
+                    }
+                }
+            }
+            _ => {
+                self.index -= 1;
+                Err(self.error("expected a value"))
+            }
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Expr>, FormulaError> {
+        let mut arguments = vec![];
+        if self.peek() == Some(&Token::Close) {
+            self.index += 1;
+            return Ok(arguments);
+        }
+        loop {
+            arguments.push(self.binary(0)?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::Close) => return Ok(arguments),
+                _ => {
+                    self.index -= 1;
+                    return Err(self.error("expected , or )"));
+                }
+            }
+        }
+    }
+}
+
+fn check_arity(name: &str, count: usize) -> Result<(), FormulaError> {
+    let (_, min, max) = FUNCTIONS
+        .iter()
+        .find(|(function, _, _)| *function == name)
+        .ok_or_else(|| FormulaError::UnknownFunction(name.to_string()))?;
+    if (*min..=*max).contains(&count) {
+        return Ok(());
+    }
+    let expected = match (min, max) {
+        (min, max) if min == max => min.to_string(),
+        (min, &usize::MAX) => format!("at least {min}"),
+        (min, max) => format!("{min} to {max}"),
+    };
+    Err(FormulaError::Arity(name.to_string(), expected))
+}
+
+/// A parsed formula expression.
+///
+/// Formulas combine field values, written as a field id or `{Field Name}`, and the
+/// invoice totals with arithmetic (`+ - * / %`), comparisons, `&&`, `||`, `!`, text
+/// joined by `&` or `+`, and functions such as `if`, `round`, `upper` or
+/// `days_between`. A date plus or minus a number moves it by that many days, the
+/// difference of two dates is a number of days.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Formula {
+    expr: Expr,
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Self, FormulaError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            index: 0,
+            end: source.len(),
+        };
+        let expr = parser.binary(0)?;
+        if parser.index < parser.tokens.len() {
+            return Err(parser.error("unexpected input"));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Field ids, names and totals the formula reads
+    pub fn references(&self) -> BTreeSet<String> {
+        fn collect(expr: &Expr, references: &mut BTreeSet<String>) {
+            match expr {
+                Expr::Literal(_) => {}
+                Expr::Reference(reference) => {
+                    references.insert(reference.clone());
+                }
+                Expr::Unary(_, operand) => collect(operand, references),
+                Expr::Binary(_, left, right) => {
+                    collect(left, references);
+                    collect(right, references);
+                }
+                Expr::Call(_, arguments) => {
+                    for argument in arguments {
+                        collect(argument, references);
+                    }
+                }
+            }
+        }
+
+        let mut references = BTreeSet::new();
+        collect(&self.expr, &mut references);
+        references
+    }
+
+    pub fn evaluate(
+        &self,
+        variables: &HashMap<String, FormulaValue>,
+    ) -> Result<FormulaValue, FormulaError> {
+        evaluate(&self.expr, variables)
+    }
+}
+
+fn evaluate(
+    expr: &Expr,
+    variables: &HashMap<String, FormulaValue>,
+) -> Result<FormulaValue, FormulaError> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Reference(reference) => variables
+            .get(reference)
+            .or_else(|| variables.get(&reference.to_lowercase()))
+            .cloned()
+            .ok_or_else(|| FormulaError::UnknownField(reference.clone())),
+        Expr::Unary(operator, operand) => {
+            let value = evaluate(operand, variables)?;
+            match *operator {
+                "-" => Ok(FormulaValue::Number(-value.as_number()?)),
+                _ => Ok(FormulaValue::Bool(!value.is_true())),
+            }
+        }
+        Expr::Binary("&&", left, right) => Ok(FormulaValue::Bool(
+            evaluate(left, variables)?.is_true() && evaluate(right, variables)?.is_true(),
+        )),
+        Expr::Binary("||", left, right) => Ok(FormulaValue::Bool(
+            evaluate(left, variables)?.is_true() || evaluate(right, variables)?.is_true(),
+        )),
+        Expr::Binary(operator, left, right) => binary(
+            operator,
+            evaluate(left, variables)?,
+            evaluate(right, variables)?,
+        ),
+        Expr::Call(name, arguments) if name == "if" => {
+            if evaluate(&arguments[0], variables)?.is_true() {
+                evaluate(&arguments[1], variables)
+            } else {
+                evaluate(&arguments[2], variables)
+            }
+        }
+        Expr::Call(name, arguments) => {
+            let arguments = arguments
+                .iter()
+                .map(|argument| evaluate(argument, variables))
+                .collect::<Result<Vec<_>, _>>()?;
+            call(name, &arguments)
+        }
+    }
+}
+
+fn binary(
+    operator: &str,
+    left: FormulaValue,
+    right: FormulaValue,
+) -> Result<FormulaValue, FormulaError> {
+    use FormulaValue::*;
+
+    match (operator, left, right) {
+        ("&", left, right) => Ok(Text(format!("{left}{right}"))),
+        ("+", Text(left), right) => Ok(Text(format!("{left}{right}"))),
+        ("+", left @ (Number(_) | Bool(_)), Text(right)) => Ok(Text(format!("{left}{right}"))),
+        ("+", Date(date), Number(number)) | ("+", Number(number), Date(date)) => {
+            Ok(Date(add_days(date, number)?))
+        }
+        ("-", Date(date), Number(number)) => Ok(Date(add_days(date, -number)?)),
+        ("-", Date(left), Date(right)) => Ok(Number((left - right).whole_days().into())),
+        ("==", left, right) => Ok(Bool(compare(&left, &right)? == std::cmp::Ordering::Equal)),
+        ("!=", left, right) => Ok(Bool(compare(&left, &right)? != std::cmp::Ordering::Equal)),
+        ("<", left, right) => Ok(Bool(compare(&left, &right)?.is_lt())),
+        ("<=", left, right) => Ok(Bool(compare(&left, &right)?.is_le())),
+        (">", left, right) => Ok(Bool(compare(&left, &right)?.is_gt())),
+        (">=", left, right) => Ok(Bool(compare(&left, &right)?.is_ge())),
+        (operator, left, right) => {
+            let (left, right) = (left.as_number()?, right.as_number()?);
+            let result = match operator {
+                "+" => left.checked_add(right),
+                "-" => left.checked_sub(right),
+                "*" => left.checked_mul(right),
+                _ if right.is_zero() => return Err(FormulaError::DivisionByZero),
+                "/" => left.checked_div(right),
+                _ => left.checked_rem(right),
+            };
+            result.map(Number).ok_or(FormulaError::Overflow)
+        }
+    }
+}
+
+/// `date` moved by the whole days of `days`, an error past the supported dates
+fn add_days(date: Date, days: Decimal) -> Result<Date, FormulaError> {
+    let days = days.trunc().to_i32().ok_or(FormulaError::Overflow)?;
+    date.checked_add(Duration::days(days.into()))
+        .ok_or(FormulaError::Overflow)
+}
+
+fn compare(left: &FormulaValue, right: &FormulaValue) -> Result<std::cmp::Ordering, FormulaError> {
+    use FormulaValue::*;
+
+    match (left, right) {
+        (Number(left), right) => Ok(left.cmp(&right.as_number()?)),
+        (left, Number(right)) => Ok(left.as_number()?.cmp(right)),
+        (Date(left), right) => Ok(left.cmp(&right.as_date()?)),
+        (left, Date(right)) => Ok(left.as_date()?.cmp(right)),
+        (Bool(left), Bool(right)) => Ok(left.cmp(right)),
+        (left, right) => Ok(left.to_string().cmp(&right.to_string())),
+    }
+}
+
+fn call(name: &str, arguments: &[FormulaValue]) -> Result<FormulaValue, FormulaError> {
+    use FormulaValue::*;
+
+    let number = |index: usize| arguments[index].as_number();
+    let date = |index: usize| arguments[index].as_date();
+    let text = |index: usize| arguments[index].to_string();
+
+    match name {
+        "round" => {
+            let digits = match arguments.get(1) {
+                Some(digits) => digits.as_number()?.to_u32().unwrap_or(0),
+                None => 0,
+            };
+            Ok(Number(number(0)?.round_dp_with_strategy(
+                digits,
+                RoundingStrategy::MidpointAwayFromZero,
+            )))
+        }
+        "floor" => Ok(Number(number(0)?.floor())),
+        "ceil" => Ok(Number(number(0)?.ceil())),
+        "abs" => Ok(Number(number(0)?.abs())),
+        "min" | "max" => {
+            let numbers = (0..arguments.len())
+                .map(number)
+                .collect::<Result<Vec<_>, _>>()?;
+            let result = if name == "min" {
+                numbers.into_iter().min()
+            } else {
+                numbers.into_iter().max()
+            };
+            Ok(Number(result.unwrap_or_default()))
+        }
+        "upper" => Ok(Text(text(0).to_uppercase())),
+        "lower" => Ok(Text(text(0).to_lowercase())),
+        "trim" => Ok(Text(text(0).trim().to_string())),
+        "len" => Ok(Number(text(0).chars().count().into())),
+        "concat" => Ok(Text(arguments.iter().map(ToString::to_string).collect())),
+        "text" => Ok(Text(text(0))),
+        "number" => Ok(Number(number(0)?)),
+        "date" => Ok(Date(date(0)?)),
+        "today" => Ok(Date(OffsetDateTime::now_utc().date())),
+        "add_days" => Ok(Date(add_days(date(0)?, number(1)?)?)),
+        "days_between" => Ok(Number((date(1)? - date(0)?).whole_days().into())),
+        "year" => Ok(Number(date(0)?.year().into())),
+        "month" => Ok(Number((date(0)?.month() as u8).into())),
+        "day" => Ok(Number(date(0)?.day().into())),
+        other => Err(FormulaError::UnknownFunction(other.to_string())),
+    }
+}
+
+/// The field a formula reference points to, by id or else by name
+pub fn resolve_reference<'a>(reference: &str, fields: &'a [FieldItem]) -> Option<&'a FieldItem> {
+    fields
+        .iter()
+        .find(|field| field.id == reference)
+        .or_else(|| {
+            fields
+                .iter()
+                .find(|field| field.name.eq_ignore_ascii_case(reference))
+        })
+}
+
+/// Ids of the fields `formula` reads, the totals are not fields
+pub fn formula_dependencies(
+    formula: &Formula,
+    fields: &[FieldItem],
+) -> Result<Vec<String>, FormulaError> {
+    formula
+        .references()
+        .iter()
+        .filter(|reference| !FORMULA_TOTALS.contains(&reference.as_str()))
+        .map(|reference| {
+            resolve_reference(reference, fields)
+                .map(|field| field.id.clone())
+                .ok_or_else(|| FormulaError::UnknownField(reference.clone()))
+        })
+        .collect()
+}
+
+/// Checks that a formula field only reads fields it lists as dependencies, and
+/// that those exist
+pub fn validate_formula_field(field: &FieldItem, fields: &[FieldItem]) -> Result<(), FormulaError> {
+    let Some(AutoGenerationRule::Formula {
+        expression,
+        dependencies,
+    }) = &field.auto_generation
+    else {
+        return Ok(());
+    };
+
+    for dependency in dependencies {
+        if !fields.iter().any(|field| &field.id == dependency) {
+            return Err(FormulaError::UnknownField(dependency.clone()));
+        }
+    }
+    for used in formula_dependencies(&Formula::parse(expression)?, fields)? {
+        if !dependencies.contains(&used) {
+            let name = resolve_reference(&used, fields).map_or(used.clone(), |f| f.name.clone());
+            return Err(FormulaError::UndeclaredDependency(name));
+        }
+    }
+    Ok(())
+}
+
+fn formula_dependency_ids(field: &FieldItem) -> Option<&[String]> {
+    match &field.auto_generation {
+        Some(AutoGenerationRule::Formula { dependencies, .. })
+            if field.field_type == FieldType::AutoGenerated =>
+        {
+            Some(dependencies)
+        }
+        _ => None,
+    }
+}
+
+/// Formula fields of `fields` in an order where every formula comes after the
+/// formulas it depends on
+pub fn formula_order(fields: &[FieldItem]) -> Result<Vec<&FieldItem>, FormulaError> {
+    fn visit<'a>(
+        field: &'a FieldItem,
+        by_id: &HashMap<&str, &'a FieldItem>,
+        done: &mut HashSet<&'a str>,
+        path: &mut Vec<&'a FieldItem>,
+        order: &mut Vec<&'a FieldItem>,
+    ) -> Result<(), FormulaError> {
+        if done.contains(field.id.as_str()) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|visiting| visiting.id == field.id) {
+            let mut cycle = path[start..]
+                .iter()
+                .map(|field| field.name.clone())
+                .collect::<Vec<_>>();
+            cycle.push(field.name.clone());
+            return Err(FormulaError::Cycle(cycle));
+        }
+
+        path.push(field);
+        for dependency in formula_dependency_ids(field).unwrap_or_default() {
+            if let Some(dependency) = by_id.get(dependency.as_str()) {
+                visit(dependency, by_id, done, path, order)?;
+            }
+        }
+        path.pop();
+
+        done.insert(&field.id);
+        order.push(field);
+        Ok(())
+    }
+
+    let by_id = fields
+        .iter()
+        .filter(|field| formula_dependency_ids(field).is_some())
+        .map(|field| (field.id.as_str(), field))
+        .collect::<HashMap<_, _>>();
+    let mut done = HashSet::new();
+    let mut order = vec![];
+
+    for field in fields {
+        if by_id.contains_key(field.id.as_str()) {
+            visit(field, &by_id, &mut done, &mut vec![], &mut order)?;
+        }
+    }
+    Ok(order)
+}
+
+/// Values a formula can read: every field by id and by lowercase name, then the
+/// invoice totals
+pub fn formula_variables(
+    fields: &[FieldItem],
+    values: &HashMap<String, FieldValue>,
+    totals: Option<&InvoiceBreakdown>,
+) -> HashMap<String, FormulaValue> {
+    let mut variables = HashMap::new();
+    for field in fields {
+        let value = values
+            .get(&field.id)
+            .map(FormulaValue::from)
+            .unwrap_or_else(|| match field.field_type {
+                FieldType::Number => FormulaValue::Number(Decimal::ZERO),
+                FieldType::Checkbox => FormulaValue::Bool(field.default_checked),
+                _ => FormulaValue::Text(String::new()),
+            });
+        variables.insert(field.name.to_lowercase(), value.clone());
+        variables.insert(field.id.clone(), value);
+    }
+    if let Some(totals) = totals {
+        let amounts = [
+            totals.subtotal,
+            totals.total_discounts,
+            totals.total_charges,
+            totals.tax_total,
+            totals.grand_total,
+        ];
+        for (name, amount) in FORMULA_TOTALS.into_iter().zip(amounts) {
+            variables.insert(name.to_string(), FormulaValue::Number(amount));
+        }
+    }
+    variables
+}
+
+/// Values of the fields on every card and the invoice totals, so a formula can
+/// read fields outside the card it is shown on
+#[derive(Clone, Copy)]
+pub struct FormulaInputs(pub Memo<HashMap<String, FormulaValue>>);
+
+pub fn provide_formula_inputs(state: InvoiceBuilderState, snapshot: Memo<InvoiceSnapshot>) {
+    let inputs = Memo::new(move |_| {
+        let fields = state.custom_fields.with(|fields| {
+            fields
+                .iter()
+                .filter(|field| field.category != FieldCategory::LineItem)
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+        let mut values = HashMap::new();
+        for info in [
+            state.invoice_info,
+            state.biller_info,
+            state.client_info,
+            state.extra_info,
+        ] {
+            info.with(|info| {
+                values.extend(
+                    info.iter()
+                        .map(|(id, field)| (id.clone(), field.value.clone())),
+                )
+            });
+        }
+        formula_variables(&fields, &values, Some(&snapshot.with(calculate_invoice)))
+    });
+    provide_context(FormulaInputs(inputs));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    fn eval(source: &str, variables: &[(&str, FormulaValue)]) -> Result<String, FormulaError> {
+        let variables = variables
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        Formula::parse(source)?
+            .evaluate(&variables)
+            .map(|value| value.to_string())
+    }
+
+    fn formula_field(id: &str, expression: &str, dependencies: &[&str]) -> FieldItem {
+        FieldItem {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            field_type: FieldType::AutoGenerated,
+            auto_generation: Some(AutoGenerationRule::Formula {
+                expression: expression.to_string(),
+                dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn arithmetic_follows_precedence() {
+        assert_eq!(eval("1 + 2 * 3", &[]).unwrap(), "7");
+        assert_eq!(eval("(1 + 2) * 3", &[]).unwrap(), "9");
+        assert_eq!(eval("-2 + 10 % 4", &[]).unwrap(), "0");
+        assert_eq!(eval("round(10 / 3, 2)", &[]).unwrap(), "3.33");
+        assert_eq!(eval("1 / 0", &[]), Err(FormulaError::DivisionByZero));
+    }
+
+    #[test]
+    fn text_and_conditionals() {
+        let total = ("total", FormulaValue::Number(dec!(120)));
+
+        assert_eq!(
+            eval("upper('inv') & '-' & 7", &[]).unwrap(),
+            "INV-7".to_string()
+        );
+        assert_eq!(
+            eval("if(total > 100, \"large\", \"small\")", &[total]).unwrap(),
+            "large"
+        );
+    }
+
+    #[test]
+    fn date_arithmetic() {
+        let variables = [(
+            "issue date",
+            FormulaValue::Date(time::macros::date!(2024 - 01 - 30)),
+        )];
+
+        assert_eq!(eval("{Issue Date} + 3", &variables).unwrap(), "2024-02-02");
+        assert_eq!(
+            eval("days_between({Issue Date}, '2024-03-01')", &variables).unwrap(),
+            "31"
+        );
+        assert_eq!(eval("month(date('2024-07-04'))", &[]).unwrap(), "7");
+    }
+
+    #[test]
+    fn out_of_range_results_are_errors() {
+        assert_eq!(
+            eval("79228162514264337593543950335 * 10", &[]),
+            Err(FormulaError::Overflow)
+        );
+        assert_eq!(
+            eval("79228162514264337593543950335 + 1", &[]),
+            Err(FormulaError::Overflow)
+        );
+        assert_eq!(eval("10 % 0", &[]), Err(FormulaError::DivisionByZero));
+        assert_eq!(
+            eval("date('9999-12-31') + 1", &[]),
+            Err(FormulaError::Overflow)
+        );
+        assert_eq!(
+            eval("add_days('2024-01-01', 99999999999)", &[]),
+            Err(FormulaError::Overflow)
+        );
+        assert_eq!(
+            eval("date('2024-01-01') - 99999999", &[]),
+            Err(FormulaError::Overflow)
+        );
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_problem() {
+        assert!(matches!(
+            Formula::parse("1 +"),
+            Err(FormulaError::Syntax { position: 3, .. })
+        ));
+        assert_eq!(
+            Formula::parse("nope(1)"),
+            Err(FormulaError::UnknownFunction("nope".to_string()))
+        );
+        assert!(matches!(
+            Formula::parse("round()"),
+            Err(FormulaError::Arity(..))
+        ));
+    }
+
+    #[test]
+    fn undeclared_dependencies_are_rejected() {
+        let price = FieldItem {
+            id: "price".to_string(),
+            name: "Price".to_string(),
+            ..Default::default()
+        };
+        let declared = formula_field("net", "{Price} * 2", &["price"]);
+        let undeclared = formula_field("gross", "{Price} * 2", &[]);
+        let fields = vec![price, declared.clone(), undeclared.clone()];
+
+        assert_eq!(validate_formula_field(&declared, &fields), Ok(()));
+        assert_eq!(
+            validate_formula_field(&undeclared, &fields),
+            Err(FormulaError::UndeclaredDependency("Price".to_string()))
+        );
+    }
+
+    #[test]
+    fn formulas_are_ordered_and_cycles_found() {
+        let fields = vec![
+            formula_field("b", "a + 1", &["a"]),
+            formula_field("a", "1", &[]),
+        ];
+        let order = formula_order(&fields).unwrap();
+        assert_eq!(
+            order.iter().map(|f| f.id.as_str()).collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+
+        let cyclic = vec![
+            formula_field("a", "b", &["b"]),
+            formula_field("b", "a", &["a"]),
+        ];
+        assert_eq!(
+            formula_order(&cyclic).map(|_| ()),
+            Err(FormulaError::Cycle(vec![
+                "A".to_string(),
+                "B".to_string(),
+                "A".to_string()
+            ]))
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+
+use super::*;
+
+/// Characters of short IDs, without the easily confused 0, 1, I and O
+const SHORT_ID_ALPHABET: &[u8; 32] = b"23456789ABCDEFGHJKLMNPQRSTUVWXYZ";
+
+/// Largest length, digit or letter count a generator setting may ask for
+pub const MAX_GENERATED_LENGTH: usize = 64;
+
+/// Settings holding a count, capped at `MAX_GENERATED_LENGTH`
+const COUNT_PARAMETERS: [&str; 3] = ["length", "digits", "letters"];
+
+/// A setting of a custom generator, edited in the field editor
+#[derive(Clone, PartialEq, Debug)]
+pub struct GeneratorParameter {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub default: &'static str,
+}
+
+const fn parameter(
+    key: &'static str,
+    label: &'static str,
+    default: &'static str,
+) -> GeneratorParameter {
+    GeneratorParameter {
+        key,
+        label,
+        default,
+    }
+}
+
+/// Generators available to `AutoGenerationRule::Custom` out of the box
+#[derive(Clone, Copy, Default)]
+pub struct DefaultGeneratorRegistry;
+
+impl DefaultGeneratorRegistry {
+    const GENERATORS: [(&'static str, &'static str); 6] = [
+        ("uuid", "Random UUID"),
+        ("short_id", "Short random ID"),
+        ("rf_reference", "RF creditor reference (ISO 11649)"),
+        ("luhn", "Number with a Luhn check digit"),
+        ("pin", "Random PIN"),
+        ("client_code", "ID from the client code"),
+    ];
+}
+
+impl AutoValueGeneratorRegistry for DefaultGeneratorRegistry {
+    fn generate(
+        &self,
+        generator_id: &str,
+        parameters: &HashMap<String, String>,
+        context: &GenerationContext,
+    ) -> Result<String, String> {
+        let defaults = self.parameters(generator_id);
+        let param = |key: &str| {
+            parameters
+                .get(key)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .or_else(|| {
+                    defaults
+                        .iter()
+                        .find(|parameter| parameter.key == key)
+                        .map(|parameter| parameter.default)
+                })
+                .unwrap_or_default()
+        };
+        let number = |key: &str| {
+            param(key)
+                .parse::<usize>()
+                .map(|count| count.min(MAX_GENERATED_LENGTH))
+                .map_err(|_| format!("{key} must be a whole number"))
+        };
+        // Value of the field named by the `source` parameter, empty when not filled in
+        let source = || {
+            context
+                .current_values
+                .get(param("source"))
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        };
+
+        match generator_id {
+            "uuid" => Ok(uuid::Uuid::new_v4().to_string()),
+            "short_id" => Ok(format!(
+                "{}{}",
+                param("prefix"),
+                short_id(number("length")?)?
+            )),
+            "rf_reference" => {
+                let base = match source() {
+                    value if value.trim().is_empty() => random_digits(number("length")?)?,
+                    value => value,
+                };
+                rf_creditor_reference(&base)
+            }
+            "luhn" => {
+                let digits = source()
+                    .chars()
+                    .filter(char::is_ascii_digit)
+                    .collect::<String>();
+                let digits = match digits {
+                    digits if digits.is_empty() => random_digits(number("length")?)?,
+                    digits => digits,
+                };
+                Ok(format!(
+                    "{}{digits}{}",
+                    param("prefix"),
+                    luhn_check_digit(&digits)
+                ))
+            }
+            "pin" => random_digits(number("length")?),
+            "client_code" => {
+                let code = source()
+                    .chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .take(number("letters")?)
+                    .collect::<String>()
+                    .to_uppercase();
+                if code.is_empty() {
+                    return Err("Fill in the client name first".to_string());
+                }
+                Ok(format!(
+                    "{code}{}{}",
+                    param("separator"),
+                    random_digits(number("digits")?)?
+                ))
+            }
+            other => Err(format!("Unknown generator {other}")),
+        }
+    }
+
+    fn list_generators(&self) -> Vec<(String, String)> {
+        Self::GENERATORS
+            .iter()
+            .map(|(id, description)| (id.to_string(), description.to_string()))
+            .collect()
+    }
+
+    fn parameters(&self, generator_id: &str) -> Vec<GeneratorParameter> {
+        match generator_id {
+            "short_id" => vec![
+                parameter("prefix", "Prefix", ""),
+                parameter("length", "Length", "8"),
+            ],
+            "rf_reference" => vec![
+                parameter("source", "Reference from field", ""),
+                parameter("length", "Random digits when empty", "10"),
+            ],
+            "luhn" => vec![
+                parameter("source", "Number from field", ""),
+                parameter("length", "Random digits when empty", "9"),
+                parameter("prefix", "Prefix", ""),
+            ],
+            "pin" => vec![parameter("length", "Digits", "4")],
+            "client_code" => vec![
+                parameter("source", "Client code from field", CLIENT_NAME_FIELD_ID),
+                parameter("letters", "Letters", "3"),
+                parameter("separator", "Separator", "-"),
+                parameter("digits", "Random digits", "4"),
+            ],
+            _ => vec![],
+        }
+    }
+}
+
+fn random_bytes(count: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![0; count];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("No random numbers available: {e}"))?;
+    Ok(bytes)
+}
+
+/// Checks the counts among `parameters` before a field using them is saved
+pub fn check_generator_parameters(parameters: &HashMap<String, String>) -> Result<(), String> {
+    COUNT_PARAMETERS.iter().try_for_each(|key| {
+        let value = parameters.get(*key).map(|value| value.trim());
+        match value
+            .filter(|value| !value.is_empty())
+            .map(str::parse::<usize>)
+        {
+            None => Ok(()),
+            Some(Ok(count)) if count <= MAX_GENERATED_LENGTH => Ok(()),
+            Some(Ok(_)) => Err(format!("{key} can be at most {MAX_GENERATED_LENGTH}")),
+            Some(Err(_)) => Err(format!("{key} must be a whole number")),
+        }
+    })
+}
+
+fn short_id(length: usize) -> Result<String, String> {
+    Ok(random_bytes(length)?
+        .into_iter()
+        .map(|byte| SHORT_ID_ALPHABET[usize::from(byte) % SHORT_ID_ALPHABET.len()] as char)
+        .collect())
+}
+
+fn random_digits(length: usize) -> Result<String, String> {
+    // 250 is the largest multiple of 10 a byte holds, higher bytes would favour low digits
+    let mut digits = String::with_capacity(length);
+    while digits.len() < length {
+        for byte in random_bytes(length)? {
+            if byte < 250 && digits.len() < length {
+                digits.push(char::from(b'0' + byte % 10));
+            }
+        }
+    }
+    Ok(digits)
+}
+
+/// Digit appended to `digits` so the Luhn checksum of the whole number is zero
+pub fn luhn_check_digit(digits: &str) -> u32 {
+    let sum: u32 = digits
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, digit)| match (i % 2 == 0, digit * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => digit,
+        })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+/// ISO 11649 creditor reference for `reference`, written in groups of four such as
+/// `RF18 5390 0754 7034`
+pub fn rf_creditor_reference(reference: &str) -> Result<String, String> {
+    let reference = reference
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase();
+    if reference.is_empty() || reference.len() > 21 {
+        return Err("A creditor reference holds 1 to 21 letters and digits".to_string());
+    }
+
+    // Letters count as 10 to 35, the check digits make the whole reference 1 modulo 97
+    let remainder = format!("{reference}RF00")
+        .chars()
+        .filter_map(|c| c.to_digit(36))
+        .fold(0u32, |remainder, value| {
+            let shift = if value > 9 { 100 } else { 10 };
+            (remainder * shift + value) % 97
+        });
+    let full = format!("RF{:02}{reference}", 98 - remainder);
+
+    Ok(full
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(values: &[(&str, &str)]) -> GenerationContext {
+        GenerationContext {
+            current_values: values
+                .iter()
+                .map(|(id, value)| (id.to_string(), FieldValue::Text(value.to_string())))
+                .collect(),
+            counter_states: HashMap::new(),
+            current_date: time::OffsetDateTime::now_utc(),
+        }
+    }
+
+    #[test]
+    fn rf_reference_check_digits() {
+        assert_eq!(
+            rf_creditor_reference("539007547034").unwrap(),
+            "RF18 5390 0754 7034"
+        );
+        assert_eq!(rf_creditor_reference("a1").unwrap(), "RF90 A1");
+        assert!(rf_creditor_reference("").is_err());
+    }
+
+    #[test]
+    fn luhn_check_digit_matches_known_numbers() {
+        assert_eq!(luhn_check_digit("7992739871"), 3);
+        assert_eq!(luhn_check_digit("4539 1488 0343 646"), 7);
+        assert_eq!(luhn_check_digit("0"), 0);
+    }
+
+    #[test]
+    fn client_code_uses_the_source_field() {
+        let registry = DefaultGeneratorRegistry;
+        let parameters = HashMap::from([("digits".to_string(), "2".to_string())]);
+
+        let code = registry
+            .generate(
+                "client_code",
+                &parameters,
+                &context(&[(CLIENT_NAME_FIELD_ID, "Acme Corp")]),
+            )
+            .unwrap();
+        assert!(code.starts_with("ACM-"), "{code}");
+        assert_eq!(code.len(), 6);
+        assert!(
+            registry
+                .generate("client_code", &parameters, &context(&[]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn random_generators_respect_their_length() {
+        let registry = DefaultGeneratorRegistry;
+        let parameters = HashMap::from([("length".to_string(), "6".to_string())]);
+
+        let pin = registry
+            .generate("pin", &parameters, &context(&[]))
+            .unwrap();
+        assert_eq!(pin.len(), 6);
+        assert!(pin.chars().all(|c| c.is_ascii_digit()));
+
+        let id = registry
+            .generate("short_id", &parameters, &context(&[]))
+            .unwrap();
+        assert_eq!(id.len(), 6);
+    }
+
+    #[test]
+    fn counts_are_capped() {
+        let registry = DefaultGeneratorRegistry;
+        let parameters = HashMap::from([("length".to_string(), "1000000".to_string())]);
+
+        let pin = registry
+            .generate("pin", &parameters, &context(&[]))
+            .unwrap();
+        assert_eq!(pin.len(), MAX_GENERATED_LENGTH);
+        assert!(check_generator_parameters(&parameters).is_err());
+
+        let parameters = HashMap::from([("digits".to_string(), "64".to_string())]);
+        assert!(check_generator_parameters(&parameters).is_ok());
+        let parameters = HashMap::from([("digits".to_string(), "four".to_string())]);
+        assert!(check_generator_parameters(&parameters).is_err());
+    }
+}
@@ -0,0 +1,538 @@
+use leptos::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::*;
+
+/// GST state codes, the first two characters of a GSTIN
+pub const GST_STATES: &[(&str, &str)] = &[
+    ("01", "Jammu and Kashmir"),
+    ("02", "Himachal Pradesh"),
+    ("03", "Punjab"),
+    ("04", "Chandigarh"),
+    ("05", "Uttarakhand"),
+    ("06", "Haryana"),
+    ("07", "Delhi"),
+    ("08", "Rajasthan"),
+    ("09", "Uttar Pradesh"),
+    ("10", "Bihar"),
+    ("11", "Sikkim"),
+    ("12", "Arunachal Pradesh"),
+    ("13", "Nagaland"),
+    ("14", "Manipur"),
+    ("15", "Mizoram"),
+    ("16", "Tripura"),
+    ("17", "Meghalaya"),
+    ("18", "Assam"),
+    ("19", "West Bengal"),
+    ("20", "Jharkhand"),
+    ("21", "Odisha"),
+    ("22", "Chhattisgarh"),
+    ("23", "Madhya Pradesh"),
+    ("24", "Gujarat"),
+    ("26", "Dadra and Nagar Haveli and Daman and Diu"),
+    ("27", "Maharashtra"),
+    ("29", "Karnataka"),
+    ("30", "Goa"),
+    ("31", "Lakshadweep"),
+    ("32", "Kerala"),
+    ("33", "Tamil Nadu"),
+    ("34", "Puducherry"),
+    ("35", "Andaman and Nicobar Islands"),
+    ("36", "Telangana"),
+    ("37", "Andhra Pradesh"),
+    ("38", "Ladakh"),
+    ("96", "Other Country"),
+    ("97", "Other Territory"),
+];
+
+/// Union territories without a legislature levy UTGST in place of SGST
+const UNION_TERRITORIES: [&str; 5] = ["04", "26", "31", "35", "38"];
+
+const GSTIN_CHARACTERS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GstinError {
+    #[error("A GSTIN has 15 characters")]
+    Length,
+    #[error("A GSTIN only has letters and digits")]
+    Character,
+    #[error("Unknown state code {0}")]
+    StateCode(String),
+    #[error("Check character should be {0}")]
+    Checksum(char),
+}
+
+/// Name of the state with GST state code `code`
+pub fn gst_state_name(code: &str) -> Option<&'static str> {
+    GST_STATES
+        .iter()
+        .find(|(state, _)| *state == code)
+        .map(|(_, name)| *name)
+}
+
+/// Checks the length, state code and check character of a GSTIN
+pub fn validate_gstin(gstin: &str) -> Result<(), GstinError> {
+    let gstin = gstin.trim().to_ascii_uppercase();
+    if gstin.len() != 15 {
+        return Err(GstinError::Length);
+    }
+    let values = gstin
+        .bytes()
+        .map(|c| GSTIN_CHARACTERS.iter().position(|&allowed| allowed == c))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(GstinError::Character)?;
+    if gst_state_name(&gstin[..2]).is_none() {
+        return Err(GstinError::StateCode(gstin[..2].to_string()));
+    }
+
+    // Every second character counts twice, the digits of each product in base 36
+    // are added up and the check character brings the sum to a multiple of 36
+    let sum = values[..14]
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let product = value * (1 + index % 2);
+            product / 36 + product % 36
+        })
+        .sum::<usize>();
+    let check = (36 - sum % 36) % 36;
+    if values[14] != check {
+        return Err(GstinError::Checksum(GSTIN_CHARACTERS[check] as char));
+    }
+    Ok(())
+}
+
+/// State code of `gstin`, `None` unless it is valid
+pub fn gstin_state(gstin: &str) -> Option<String> {
+    validate_gstin(gstin)
+        .ok()
+        .map(|()| gstin.trim()[..2].to_string())
+}
+
+/// Whether GST on a supply is split between the centre and the state
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum GstSupply {
+    /// CGST and SGST, or UTGST in a union territory without a legislature
+    IntraState { union_territory: bool },
+    /// IGST
+    InterState,
+}
+
+impl GstSupply {
+    /// Supply from `supplier_state` to `place_of_supply`, both GST state codes
+    pub fn between(supplier_state: &str, place_of_supply: &str) -> Self {
+        if supplier_state == place_of_supply {
+            GstSupply::IntraState {
+                union_territory: UNION_TERRITORIES.contains(&place_of_supply),
+            }
+        } else {
+            GstSupply::InterState
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GstSupply::IntraState {
+                union_territory: false,
+            } => "Intra-state, CGST and SGST",
+            GstSupply::IntraState {
+                union_territory: true,
+            } => "Intra-state, CGST and UTGST",
+            GstSupply::InterState => "Inter-state, IGST",
+        }
+    }
+}
+
+/// The taxes a GST tax is levied as. Within a state the rate is split in half
+/// between CGST and SGST, across states all of it is IGST.
+pub fn gst_components(tax: &TaxItem, supply: GstSupply) -> Vec<TaxItem> {
+    let component = |key: &str, rate: Decimal| TaxItem {
+        id: format!("{}:{key}", tax.id),
+        name: key.to_uppercase(),
+        rate,
+        ..tax.clone()
+    };
+    match supply {
+        GstSupply::IntraState { union_territory } => {
+            // A fixed amount is split into whole cents, the state half takes the odd one
+            let central = match tax.tax_type {
+                TaxType::Percentage => tax.rate / Decimal::TWO,
+                TaxType::FixedAmount => RoundingPolicy::default().round(tax.rate / Decimal::TWO),
+            };
+            let state = if union_territory { "utgst" } else { "sgst" };
+            vec![
+                component("cgst", central),
+                component(state, tax.rate - central),
+            ]
+        }
+        GstSupply::InterState => vec![component("igst", tax.rate)],
+    }
+}
+
+/// Value of the field `field_id`, or for field definitions made before that id
+/// existed, of the field whose label passes `legacy_label`. Of several such fields
+/// the one with the lowest id is used, so the choice does not change between runs.
+pub fn field_value(
+    values: &HashMap<String, FieldItemValue>,
+    field_id: &str,
+    legacy_label: impl Fn(&str) -> bool,
+) -> String {
+    let legacy_id = || {
+        values
+            .iter()
+            .filter(|(_, value)| legacy_label(&value.label.to_uppercase()))
+            .map(|(id, _)| id)
+            .min()
+    };
+    values
+        .get(field_id)
+        .or_else(|| values.get(legacy_id()?))
+        .map(|value| value.value.to_string().trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Value of the tax id field `field_id`, or of a field labelled like a GSTIN or
+/// VAT number for field definitions made before these ids existed
+pub fn tax_id(values: &HashMap<String, FieldItemValue>, field_id: &str) -> String {
+    field_value(values, field_id, |label| {
+        label.contains("GSTIN") || label.contains("VAT") || label.contains("TAX ID")
+    })
+}
+
+/// GSTIN of the biller
+pub fn biller_gstin(biller_info: &HashMap<String, FieldItemValue>) -> String {
+    tax_id(biller_info, BILLER_TAX_ID_FIELD_ID)
+}
+
+/// GSTIN of the client
+pub fn client_gstin(client_info: &HashMap<String, FieldItemValue>) -> String {
+    tax_id(client_info, CLIENT_TAX_ID_FIELD_ID)
+}
+
+/// State code of the place of supply, `place_of_supply` when chosen on the invoice
+/// and the state of the client GSTIN otherwise
+pub fn place_of_supply_code(
+    client_info: &HashMap<String, FieldItemValue>,
+    place_of_supply: &str,
+) -> Option<String> {
+    if place_of_supply.is_empty() {
+        gstin_state(&client_gstin(client_info))
+    } else {
+        Some(place_of_supply.to_string())
+    }
+}
+
+/// GST treatment of an invoice, `None` until the biller GSTIN and the place of
+/// supply are known
+pub fn gst_supply(
+    biller_info: &HashMap<String, FieldItemValue>,
+    client_info: &HashMap<String, FieldItemValue>,
+    place_of_supply: &str,
+) -> Option<GstSupply> {
+    let supplier_state = gstin_state(&biller_gstin(biller_info))?;
+    let place_of_supply = place_of_supply_code(client_info, place_of_supply)?;
+    Some(GstSupply::between(&supplier_state, &place_of_supply))
+}
+
+/// GST charged on the lines sharing an HSN or SAC code
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct HsnSummaryRow {
+    pub code: String,
+    pub taxable: Decimal,
+    pub taxes: Vec<TaxLine>,
+    pub tax_total: Decimal,
+}
+
+/// HSN or SAC code of a line, also found by label on older field definitions
+fn hsn_code(item: &LineItem) -> String {
+    field_value(&item.custom_fields, HSN_SAC_FIELD_ID, |label| {
+        label.contains("HSN") || label.contains("SAC")
+    })
+}
+
+/// GST by HSN or SAC code, in code order. Lines without GST are left out.
+pub fn hsn_summary(line_items: &[LineItem], breakdown: &InvoiceBreakdown) -> Vec<HsnSummaryRow> {
+    let mut rows = BTreeMap::<String, HsnSummaryRow>::new();
+    for item in line_items {
+        let Some(line) = breakdown.line(&item.id) else {
+            continue;
+        };
+        let gst = line.taxes.iter().filter(|tax| tax.kind == TaxKind::Gst);
+        if gst.clone().next().is_none() {
+            continue;
+        }
+
+        let code = hsn_code(item);
+        let row = rows.entry(code.clone()).or_insert_with(|| HsnSummaryRow {
+            code,
+            ..Default::default()
+        });
+        row.taxable += line.taxable;
+        for tax in gst {
+            row.tax_total += tax.amount;
+            match row.taxes.iter_mut().find(|t| t.tax_id == tax.tax_id) {
+                Some(existing) => {
+                    existing.taxable += tax.taxable;
+                    existing.amount += tax.amount;
+                }
+                None => row.taxes.push(tax.clone()),
+            }
+        }
+    }
+    rows.into_values().collect()
+}
+
+/// GSTINs and place of supply of an invoice with GST
+#[component]
+pub fn GstCard(
+    #[prop(into)] biller_info: Signal<HashMap<String, FieldItemValue>>,
+    #[prop(into)] client_info: Signal<HashMap<String, FieldItemValue>>,
+    place_of_supply: RwSignal<String>,
+) -> impl IntoView {
+    let status = move |gstin: String| match validate_gstin(&gstin) {
+        _ if gstin.is_empty() => "Not entered".to_string(),
+        Ok(()) => format!(
+            "{gstin}, {}",
+            gst_state_name(&gstin[..2].to_uppercase()).unwrap_or_default()
+        ),
+        Err(e) => format!("{gstin}: {e}"),
+    };
+    let biller_valid = move || validate_gstin(&biller_info.with(biller_gstin)).is_ok();
+    let client_valid = move || {
+        let gstin = client_info.with(client_gstin);
+        gstin.is_empty() || validate_gstin(&gstin).is_ok()
+    };
+    let supply = move || {
+        biller_info.with(|biller| {
+            client_info.with(|client| gst_supply(biller, client, &place_of_supply.get()))
+        })
+    };
+
+    view! {
+        <div class="space-y-2">
+            <h3 class="text-sm font-medium text-gray-900">"GST"</h3>
+            <p class=move || {
+                if biller_valid() { "text-xs text-gray-600" } else { "text-xs text-red-600" }
+            }>"Biller GSTIN: " {move || status(biller_info.with(biller_gstin))}</p>
+            <p class=move || {
+                if client_valid() { "text-xs text-gray-600" } else { "text-xs text-red-600" }
+            }>"Client GSTIN: " {move || status(client_info.with(client_gstin))}</p>
+            <div>
+                <label class="form-label" for="place-of-supply">
+                    "Place of supply"
+                </label>
+                <select
+                    class="form-select"
+                    id="place-of-supply"
+                    name="place-of-supply"
+                    on:change=move |ev| place_of_supply.set(event_target_value(&ev))
+                >
+                    <option value="" selected=move || place_of_supply.get().is_empty()>
+                        "State of the client GSTIN"
+                    </option>
+                    {GST_STATES
+                        .iter()
+                        .map(|(code, name)| {
+                            view! {
+                                <option value=*code selected=move || place_of_supply.get() == *code>
+                                    {format!("{code} {name}")}
+                                </option>
+                            }
+                        })
+                        .collect_view()}
+                </select>
+            </div>
+            <p class="text-xs text-gray-600">
+                {move || match supply() {
+                    Some(supply) => supply.label(),
+                    None => "Charged as entered until the biller GSTIN and place of supply are known",
+                }}
+            </p>
+        </div>
+    }
+}
+
+/// GST charged per HSN or SAC code
+#[component]
+pub fn HsnSummary(#[prop(into)] rows: Signal<Vec<HsnSummaryRow>>) -> impl IntoView {
+    view! {
+        <table class="w-full text-xs">
+            <thead>
+                <tr class="text-left text-gray-500">
+                    <th class="py-1 font-medium">"HSN/SAC"</th>
+                    <th class="py-1 font-medium text-right">"Taxable"</th>
+                    <th class="py-1 font-medium text-right">"Tax"</th>
+                </tr>
+            </thead>
+            <tbody>
+                <For
+                    each=move || rows.get()
+                    key=|row| (row.code.clone(), row.taxable, row.tax_total)
+                    children=move |row| {
+                        let taxes = row
+                            .taxes
+                            .iter()
+                            .map(|tax| format!("{} {}%: {}", tax.name, tax.rate, format_amount(tax.amount)))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        view! {
+                            <tr class="border-t border-gray-100 align-top">
+                                <td class="py-1">
+                                    {if row.code.is_empty() { "-".to_string() } else { row.code.clone() }}
+                                </td>
+                                <td class="py-1 text-right">{format_amount(row.taxable)}</td>
+                                <td class="py-1 text-right">
+                                    {format_amount(row.tax_total)}
+                                    <div class="text-gray-500">{taxes}</div>
+                                </td>
+                            </tr>
+                        }
+                    }
+                />
+            </tbody>
+        </table>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::invoice::test_support::field_entry;
+    use rust_decimal::dec;
+
+    fn field(id: &str, label: &str, value: &str) -> (String, FieldItemValue) {
+        field_entry(id, label, FieldValue::Text(value.to_string()))
+    }
+
+    #[test]
+    fn gstin_check_character_is_verified() {
+        assert_eq!(validate_gstin("27AAPFU0939F1ZV"), Ok(()));
+        assert_eq!(validate_gstin("29aagcb7383j1z4"), Ok(()));
+        assert_eq!(
+            validate_gstin("27AAPFU0939F1ZA"),
+            Err(GstinError::Checksum('V'))
+        );
+        assert_eq!(validate_gstin("27AAPFU0939F1Z"), Err(GstinError::Length));
+        assert_eq!(
+            validate_gstin("27AAPFU0939F1Z-"),
+            Err(GstinError::Character)
+        );
+        assert_eq!(
+            validate_gstin("99AAPFU0939F1ZV"),
+            Err(GstinError::StateCode("99".to_string()))
+        );
+    }
+
+    #[test]
+    fn place_of_supply_decides_the_split() {
+        let biller = HashMap::from([field(BILLER_TAX_ID_FIELD_ID, "Tax ID", "29AAGCB7383J1Z4")]);
+        let client = HashMap::from([field("legacy", "Client GSTIN", "27AAPFU0939F1ZV")]);
+
+        assert_eq!(
+            gst_supply(&biller, &client, ""),
+            Some(GstSupply::InterState)
+        );
+        assert_eq!(
+            gst_supply(&biller, &client, "29"),
+            Some(GstSupply::IntraState {
+                union_territory: false
+            })
+        );
+        assert_eq!(gst_supply(&HashMap::new(), &client, "29"), None);
+        assert_eq!(
+            GstSupply::between("04", "04"),
+            GstSupply::IntraState {
+                union_territory: true
+            }
+        );
+    }
+
+    #[test]
+    fn gst_is_split_within_a_state() {
+        let gst = TaxItem {
+            id: "gst".to_string(),
+            name: "GST 18%".to_string(),
+            rate: dec!(18),
+            kind: TaxKind::Gst,
+            ..Default::default()
+        };
+        let mut item = LineItem {
+            id: "a".to_string(),
+            quantity: dec!(1),
+            unit_price: dec!(999.99),
+            taxes: [gst.clone()].into(),
+            ..Default::default()
+        };
+        item.custom_fields
+            .extend([field(HSN_SAC_FIELD_ID, "HSN/SAC", "998314")]);
+
+        let intra = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item.clone()],
+            gst_supply: Some(GstSupply::IntraState {
+                union_territory: false,
+            }),
+            ..Default::default()
+        });
+        // 999.99 * 9% = 89.9991 for each half
+        let names = intra
+            .taxes
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["CGST", "SGST"]);
+        assert_eq!(intra.tax_total, dec!(180.00));
+        assert_eq!(intra.grand_total, dec!(1179.99));
+
+        let summary = hsn_summary(&[item.clone()], &intra);
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].code, "998314");
+        assert_eq!(summary[0].taxable, dec!(999.99));
+        assert_eq!(summary[0].tax_total, dec!(180.00));
+
+        let inter = calculate_invoice(&InvoiceSnapshot {
+            line_items: vec![item],
+            gst_supply: Some(GstSupply::InterState),
+            ..Default::default()
+        });
+        assert_eq!(inter.taxes.len(), 1);
+        assert_eq!(inter.taxes[0].name, "IGST");
+        assert_eq!(inter.taxes[0].rate, dec!(18));
+        assert_eq!(inter.tax_total, dec!(180.00));
+    }
+
+    #[test]
+    fn fixed_gst_is_split_into_whole_cents() {
+        let gst = TaxItem {
+            id: "gst".to_string(),
+            rate: dec!(0.05),
+            tax_type: TaxType::FixedAmount,
+            ..Default::default()
+        };
+        let halves = gst_components(
+            &gst,
+            GstSupply::IntraState {
+                union_territory: false,
+            },
+        );
+        let rates = halves.iter().map(|tax| tax.rate).collect::<Vec<_>>();
+        assert_eq!(rates, vec![dec!(0.03), dec!(0.02)]);
+    }
+
+    #[test]
+    fn legacy_tax_ids_are_found_by_the_lowest_field_id() {
+        let client = HashMap::from([
+            field("b", "Client GSTIN", "27AAPFU0939F1ZV"),
+            field("a", "VAT number", "29AAGCB7383J1Z4"),
+            field("c", "Notes", ""),
+        ]);
+        assert_eq!(tax_id(&client, CLIENT_TAX_ID_FIELD_ID), "29AAGCB7383J1Z4");
+
+        let client = HashMap::from([
+            field(CLIENT_TAX_ID_FIELD_ID, "Tax ID", " 27AAPFU0939F1ZV "),
+            field("a", "VAT number", "29AAGCB7383J1Z4"),
+        ]);
+        assert_eq!(tax_id(&client, CLIENT_TAX_ID_FIELD_ID), "27AAPFU0939F1ZV");
+    }
+}
@@ -1,7 +1,8 @@
 use leptos::prelude::*;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::collections::HashSet;
-use std::{borrow::Borrow, collections::HashMap};
 
 use super::*;
 use crate::components::{
@@ -9,31 +10,51 @@ use crate::components::{
         FormData, FormValidation, ItemData, ValidationResult, use_editable_grid,
         validation::validators,
     },
-    ui::Badge,
+    ui::{AutocompleteConfig, AutocompleteItem, Badge, GroupedAutocomplete},
 };
 
 // 1. Define your data structure for Line Items
 #[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
 pub struct LineItem {
     pub id: String,
+    #[serde(default)]
+    pub sku: String,
     pub name: String,
-    pub quantity: f64,
-    pub unit_price: f64,
+    #[serde(default)]
+    pub description: String,
+    /// Unit of measure, e.g. "h" or "pcs"
+    #[serde(default)]
+    pub unit: String,
+    #[serde(deserialize_with = "deserialize_amount")]
+    pub quantity: Decimal,
+    #[serde(deserialize_with = "deserialize_amount")]
+    pub unit_price: Decimal,
+    /// Whether `unit_price` includes the line taxes
+    #[serde(default)]
+    pub price_basis: PriceBasis,
     pub taxes: HashSet<TaxItem>,
     pub discounts: HashSet<DiscountItem>,
     pub charges: HashSet<ChargeItem>,
     pub custom_fields: HashMap<String, FieldItemValue>,
+    /// Line of the corrected invoice a credit note line credits
+    #[serde(default)]
+    pub credited_line_id: Option<String>,
 }
 
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct LineItemForm {
+    pub sku: String,
     pub name: String,
+    pub description: String,
+    pub unit: String,
     pub quantity: String,
     pub unit_price: String,
+    pub price_basis: PriceBasis,
     pub taxes: HashSet<TaxItem>,
     pub discounts: HashSet<DiscountItem>,
     pub charges: HashSet<ChargeItem>,
     pub custom_fields: HashMap<String, FieldItemValue>,
+    pub credited_line_id: Option<String>,
 }
 
 // 2. Implement required traits
@@ -43,25 +64,35 @@ impl FormData for LineItem {
     fn default() -> Self {
         Self {
             id: uuid::Uuid::new_v4().to_string(),
+            sku: String::new(),
             name: String::new(),
-            quantity: 0.0,
-            unit_price: 0.0,
+            description: String::new(),
+            unit: String::new(),
+            quantity: Decimal::ZERO,
+            unit_price: Decimal::ZERO,
+            price_basis: PriceBasis::default(),
             taxes: HashSet::new(),
             discounts: HashSet::new(),
             charges: HashSet::new(),
             custom_fields: HashMap::new(),
+            credited_line_id: None,
         }
     }
 
     fn to_form_props(&self) -> Self::FormProps {
         LineItemForm {
+            sku: self.sku.clone(),
             name: self.name.clone(),
+            description: self.description.clone(),
+            unit: self.unit.clone(),
             quantity: self.quantity.to_string(),
             unit_price: self.unit_price.to_string(),
+            price_basis: self.price_basis,
             taxes: self.taxes.clone(),
             discounts: self.discounts.clone(),
             charges: self.charges.clone(),
             custom_fields: self.custom_fields.clone(),
+            credited_line_id: self.credited_line_id.clone(),
         }
     }
 
@@ -70,13 +101,18 @@ impl FormData for LineItem {
         // based on the IDs. We'll use mock data here.
         Self {
             id: uuid::Uuid::new_v4().to_string(),
+            sku: props.sku.trim().to_string(),
             name: props.name.clone(),
-            quantity: props.quantity.parse::<f64>().unwrap_or(0.0),
-            unit_price: props.unit_price.parse::<f64>().unwrap_or(0.0),
+            description: props.description.clone(),
+            unit: props.unit.trim().to_string(),
+            quantity: parse_decimal(&props.quantity).unwrap_or_default(),
+            unit_price: parse_decimal(&props.unit_price).unwrap_or_default(),
+            price_basis: props.price_basis,
             taxes: props.taxes.clone(),
             discounts: props.discounts.clone(),
             charges: props.charges.clone(),
             custom_fields: props.custom_fields.clone(),
+            credited_line_id: props.credited_line_id.clone(),
         }
     }
 }
@@ -91,8 +127,7 @@ impl ItemData for LineItem {
     }
 
     fn get_subtitle(&self) -> Option<String> {
-        // Some(self.description.clone())
-        None
+        Some(self.description.clone()).filter(|description| !description.is_empty())
     }
 
     fn get_metadata(&self) -> Vec<(String, String)> {
@@ -112,14 +147,14 @@ impl FormValidation for LineItemForm {
             validators::required(&self.unit_price, "Unit Price"),
         ];
 
-        if self.quantity.parse::<f64>().is_err() {
+        if parse_decimal(&self.quantity).is_none() {
             results.push(ValidationResult::new().with_field_error(
                 "quantity".to_string(),
                 "Quantity must be a valid number".to_string(),
             ));
         }
 
-        if self.unit_price.parse::<f64>().is_err() {
+        if parse_decimal(&self.unit_price).is_none() {
             results.push(ValidationResult::new().with_field_error(
                 "unit-price".to_string(),
                 "Unit Price must be a valid number".to_string(),
@@ -130,85 +165,44 @@ impl FormValidation for LineItemForm {
     }
 }
 
-fn calculate_total<T, D, C>(
-    quantity: f64,
-    unit_price: f64,
-    taxes: T,
-    discounts: D,
-    charges: C,
-) -> f64
-where
-    T: IntoIterator,
-    T::Item: std::borrow::Borrow<TaxItem>,
-    D: IntoIterator,
-    D::Item: std::borrow::Borrow<DiscountItem>,
-    C: IntoIterator,
-    C::Item: std::borrow::Borrow<ChargeItem>,
-{
-    let mut base_total = quantity * unit_price;
-
-    // Apply taxes
-    for tax in taxes {
-        let tax = tax.borrow();
-        match tax.tax_type {
-            TaxType::Percentage => base_total += base_total * (tax.rate / 100.0),
-            TaxType::FixedAmount => base_total += tax.rate,
-        }
-    }
-
-    // Apply discounts
-    for discount in discounts {
-        let discount = discount.borrow();
-        match discount.discount_type {
-            DiscountType::Percentage => base_total -= base_total * (discount.value / 100.0),
-            DiscountType::FixedAmount => base_total -= discount.value,
-        }
+/// Parses the string form of a field value, as stored in defaults
+pub fn field_value_from_str(field_type: &FieldType, value: &str) -> FieldValue {
+    match field_type {
+        FieldType::Number => FieldValue::Number(value.parse::<f64>().unwrap_or(0.0)),
+        FieldType::Checkbox => FieldValue::Checkbox(value.to_lowercase() == "true"),
+        FieldType::Date => FieldValue::Date(value.to_string()),
+        FieldType::Dropdown => FieldValue::Dropdown(value.to_string()),
+        FieldType::Textarea => FieldValue::Textarea(value.to_string()),
+        FieldType::Email => FieldValue::Email(value.to_string()),
+        FieldType::Phone => FieldValue::Phone(value.to_string()),
+        FieldType::Text => FieldValue::Text(value.to_string()),
+        FieldType::AutoGenerated => FieldValue::AutoGenerated(value.to_string()),
     }
-
-    // Apply charges
-    for charge in charges {
-        let charge = charge.borrow();
-        base_total += charge.amount;
-    }
-
-    base_total
 }
 
-fn initialize_field_values(fields: &[FieldItem]) -> HashMap<String, FieldItemValue> {
+pub fn initialize_field_values(fields: &[FieldItem]) -> HashMap<String, FieldItemValue> {
     fields
         .iter()
         .filter(|f| f.category == FieldCategory::LineItem)
         .map(|f| {
-            let value = match f.field_type {
-                FieldType::Number => {
-                    let num = f.default_value.parse::<f64>().unwrap_or(0.0);
-                    FieldValue::Number(num)
-                }
-                FieldType::Checkbox => {
-                    let val = f.default_value.to_lowercase() == "true";
-                    FieldValue::Checkbox(val)
-                }
-                FieldType::Date => FieldValue::Date(f.default_value.clone()),
-                FieldType::Dropdown => FieldValue::Dropdown(f.default_value.clone()),
-                FieldType::Textarea => FieldValue::Textarea(f.default_value.clone()),
-                FieldType::Email => FieldValue::Email(f.default_value.clone()),
-                FieldType::Phone => FieldValue::Phone(f.default_value.clone()),
-                FieldType::Text => FieldValue::Text(f.default_value.clone()),
-                _ => todo!(),
-            };
-
             (
                 f.id.clone(),
                 FieldItemValue {
                     id: f.id.clone(),
                     label: f.name.clone(),
-                    value,
+                    value: field_value_from_str(&f.field_type, &f.default_value),
                 },
             )
         })
         .collect()
 }
 
+/// Adds `item` to `set`, replacing a copy with the same id made before it was edited
+fn replace_by_id<T: AutocompleteItem>(set: &mut HashSet<T>, item: T) {
+    set.retain(|selected| selected.id() != item.id());
+    set.insert(item);
+}
+
 // #[component]
 // pub fn LineItemFields(
 //     fields: ReadSignal<Vec<FieldItem>>,
@@ -237,14 +231,20 @@ pub fn LineItems(
     discounts: ReadSignal<Vec<DiscountItem>>,
     charges: ReadSignal<Vec<ChargeItem>>,
     custom_fields: ReadSignal<Vec<FieldItem>>,
+    products: ReadSignal<Vec<ProductItem>>,
+    snapshot: Memo<InvoiceSnapshot>,
 ) -> impl IntoView {
     let form_ref: NodeRef<leptos::html::Form> = NodeRef::new();
     let grid = use_editable_grid(state.read_only(), state.write_only(), form_ref);
 
     // Form field signals
+    let (sku_value, set_sku_value) = signal(String::new());
     let (name_value, set_name_value) = signal(String::new());
+    let (description_value, set_description_value) = signal(String::new());
+    let (unit_value, set_unit_value) = signal(String::new());
     let (quantity_value, set_quantity_value) = signal(String::new());
     let (unit_price_value, set_unit_price_value) = signal(String::new());
+    let (price_basis_value, set_price_basis_value) = signal(PriceBasis::default());
 
     let selected_items = RwSignal::new(HashSet::<LineChargeItemKind>::new());
     let (selected_taxes, set_selected_taxes) = signal(HashSet::<TaxItem>::new());
@@ -255,31 +255,59 @@ pub fn LineItems(
     //     Memo::new(move |_| initialize_field_values(&custom_fields.get()));
     let custom_field_values = RwSignal::new(initialize_field_values(&custom_fields.get()));
 
-    // Computed signal for line item total
+    // Total of the line being edited, calculated in its place on the invoice so
+    // the invoice settings and the invoice-level discounts apply as they will once saved
     let total = Signal::derive({
         move || {
-            let form = grid.form_state.get().current_form;
-            let quantity = form.quantity.parse::<f64>().unwrap_or(0.0);
-            let unit_price = form.unit_price.parse::<f64>().unwrap_or(0.0);
-
-            calculate_total(
-                quantity,
-                unit_price,
-                selected_taxes.get(),
-                selected_discounts.get(),
-                selected_charges.get(),
-            )
+            let form_state = grid.form_state.get();
+            let form = form_state.current_form;
+            let draft = LineItem {
+                quantity: parse_decimal(&form.quantity).unwrap_or_default(),
+                unit_price: parse_decimal(&form.unit_price).unwrap_or_default(),
+                price_basis: form.price_basis,
+                taxes: selected_taxes.get(),
+                discounts: selected_discounts.get(),
+                charges: selected_charges.get(),
+                ..Default::default()
+            };
+
+            let mut snapshot = snapshot.get();
+            let index = match form_state
+                .editing_index
+                .filter(|&index| index < snapshot.line_items.len())
+            {
+                Some(index) => {
+                    snapshot.line_items[index] = draft;
+                    index
+                }
+                None => {
+                    snapshot.line_items.push(draft);
+                    snapshot.line_items.len() - 1
+                }
+            };
+            calculate_invoice(&snapshot)
+                .lines
+                .get(index)
+                .map(|line| line.total)
+                .unwrap_or_default()
         }
     });
 
+    // Row totals come from the same breakdown as the totals card
+    let breakdown = Memo::new(move |_| snapshot.with(calculate_invoice));
+
     // Update form fields when grid state changes
     Effect::new({
         move |_| {
             let form_state = grid.form_state.get();
             let form = form_state.current_form;
+            set_sku_value.set(form.sku);
             set_name_value.set(form.name);
+            set_description_value.set(form.description);
+            set_unit_value.set(form.unit);
             set_quantity_value.set(form.quantity);
             set_unit_price_value.set(form.unit_price);
+            set_price_basis_value.set(form.price_basis);
             if form.custom_fields.is_empty() {
                 custom_field_values.set(initialize_field_values(&custom_fields.get()));
             } else {
@@ -290,25 +318,40 @@ pub fn LineItems(
         }
     });
 
+    // Not an input of the form, an edited credit note line keeps crediting its line
+    let credited_line_id = move || {
+        grid.form_state
+            .with_untracked(|form_state| form_state.current_form.credited_line_id.clone())
+    };
+
     // Handle form submission
     let handle_submit = move |ev: web_sys::SubmitEvent| {
         ev.prevent_default();
         let form_data = LineItemForm {
+            sku: sku_value.get(),
             name: name_value.get(),
+            description: description_value.get(),
+            unit: unit_value.get(),
             quantity: quantity_value.get(),
             unit_price: unit_price_value.get(),
+            price_basis: price_basis_value.get(),
             taxes: selected_taxes.get(),
             discounts: selected_discounts.get(),
             charges: selected_charges.get(),
             custom_fields: custom_field_values.get(),
+            credited_line_id: credited_line_id(),
         };
 
         let validation = form_data.validate();
         if validation.is_valid {
             grid.actions.submit_form.run(form_data);
+            set_sku_value.set(String::new());
             set_name_value.set(String::new());
+            set_description_value.set(String::new());
+            set_unit_value.set(String::new());
             set_quantity_value.set(String::new());
             set_unit_price_value.set(String::new());
+            set_price_basis_value.set(PriceBasis::default());
             set_selected_taxes.set(HashSet::new());
             set_selected_discounts.set(HashSet::new());
             set_selected_charges.set(HashSet::new());
@@ -320,18 +363,27 @@ pub fn LineItems(
     // Helper to update form state
     let update_form = move || {
         grid.actions.update_form.run(LineItemForm {
+            sku: sku_value.get(),
             name: name_value.get(),
+            description: description_value.get(),
+            unit: unit_value.get(),
             quantity: quantity_value.get(),
             unit_price: unit_price_value.get(),
+            price_basis: price_basis_value.get(),
             taxes: selected_taxes.get(),
             discounts: selected_discounts.get(),
             charges: selected_charges.get(),
             custom_fields: custom_field_values.get(),
+            credited_line_id: credited_line_id(),
         });
     };
 
-    let handle_name_input = move |ev| {
-        set_name_value.set(event_target_value(&ev));
+    let handle_description_input = move |ev| {
+        set_description_value.set(event_target_value(&ev));
+        update_form();
+    };
+    let handle_unit_input = move |ev| {
+        set_unit_value.set(event_target_value(&ev));
         update_form();
     };
     let handle_quantity_input = move |ev| {
@@ -342,22 +394,51 @@ pub fn LineItems(
         set_unit_price_value.set(event_target_value(&ev));
         update_form();
     };
+    let handle_price_basis_change = move |ev| {
+        if let Some(basis) = PriceBasis::from_label(&event_target_value(&ev)) {
+            set_price_basis_value.set(basis);
+            update_form();
+        }
+    };
+
+    // Picking a catalog product fills in the whole form
+    let on_product_select = Callback::new(move |product: ProductItem| {
+        let quantity = Some(quantity_value.get_untracked())
+            .filter(|quantity| !quantity.is_empty())
+            .unwrap_or_else(|| "1".to_string());
+        let form = product.line_item_form(quantity, &custom_fields.get_untracked());
+
+        set_sku_value.set(form.sku);
+        set_name_value.set(form.name);
+        set_description_value.set(form.description);
+        set_unit_value.set(form.unit);
+        set_quantity_value.set(form.quantity);
+        set_unit_price_value.set(form.unit_price);
+        set_price_basis_value.set(form.price_basis);
+        selected_items.set(HashSet::new());
+        set_selected_taxes.set(form.taxes);
+        set_selected_discounts.set(form.discounts);
+        set_selected_charges.set(form.charges);
+        custom_field_values.set(form.custom_fields);
+        update_form();
+    });
+    let product_source = ProductDataSource { products };
+    let product_config = AutocompleteConfig {
+        min_query_length: 1,
+        max_results_per_group: Some(8),
+        debounce_ms: 200.0,
+        case_sensitive: false,
+    };
 
     let on_select = Callback::new(move |charge| match charge {
         LineChargeItemKind::Tax(v) => {
-            set_selected_taxes.update(|items| {
-                items.insert(v);
-            });
+            set_selected_taxes.update(|items| replace_by_id(items, v));
         }
         LineChargeItemKind::Discount(v) => {
-            set_selected_discounts.update(|items| {
-                items.insert(v);
-            });
+            set_selected_discounts.update(|items| replace_by_id(items, v));
         }
         LineChargeItemKind::Charge(v) => {
-            set_selected_charges.update(|items| {
-                items.insert(v);
-            });
+            set_selected_charges.update(|items| replace_by_id(items, v));
         }
     });
 
@@ -395,18 +476,90 @@ pub fn LineItems(
                 <form node_ref=form_ref on:submit=handle_submit class="w-full">
                     <div class="flex flex-wrap items-start gap-x-6 gap-y-4 w-full">
                         <div class="flex flex-col flex-[2] min-w-[200px]">
-                            <label class="form-label" for="item-name">
-                                "Item Name"
+                            <GroupedAutocomplete
+                                data_source=product_source
+                                config=product_config
+                                on_select=on_product_select
+                                render=move |props, actions| {
+                                    view! {
+                                        <label class="form-label" for="item-name">
+                                            "Item Name"
+                                        </label>
+                                        <div class="relative">
+                                            <input
+                                                class="form-input w-full"
+                                                id="item-name"
+                                                name="item-name"
+                                                type="text"
+                                                autocomplete="off"
+                                                prop:value=move || name_value.get()
+                                                on:input=move |ev| {
+                                                    let value = event_target_value(&ev);
+                                                    set_name_value.set(value.clone());
+                                                    update_form();
+                                                    actions.set_query.set(value);
+                                                }
+                                                on:keydown=move |ev| actions.handle_key_down.run(ev)
+                                                placeholder="e.g., Website Development"
+                                                required
+                                            />
+                                            <Show when=move || {
+                                                props.is_open.get() && !props.results.get().is_empty()
+                                            }>
+                                                <div class="absolute z-10 mt-1 w-full bg-white shadow-lg max-h-60 rounded-md py-1 ring-1 ring-black ring-opacity-5 overflow-auto">
+                                                    <For
+                                                        each=move || {
+                                                            props
+                                                                .results
+                                                                .get()
+                                                                .into_iter()
+                                                                .flat_map(|group| group.items)
+                                                                .collect::<Vec<_>>()
+                                                        }
+                                                        key=|product| product.id()
+                                                        children=move |product| {
+                                                            let picked = product.clone();
+                                                            view! {
+                                                                <div
+                                                                    class="px-4 py-2 cursor-pointer hover:bg-gray-100"
+                                                                    on:click=move |_| actions.select_item.run(picked.clone())
+                                                                >
+                                                                    <div class="text-sm font-medium text-gray-900">
+                                                                        {product.display_text()}
+                                                                    </div>
+                                                                    <div class="text-xs text-gray-500 truncate">
+                                                                        {format!(
+                                                                            "{} {} {}",
+                                                                            product.sku,
+                                                                            format_amount(product.unit_price),
+                                                                            product.unit,
+                                                                        )}
+                                                                    </div>
+                                                                </div>
+                                                            }
+                                                        }
+                                                    />
+                                                </div>
+                                            </Show>
+                                        </div>
+                                    }
+                                        .into_any()
+                                }
+                            />
+                        </div>
+
+                        <div class="flex flex-col w-20">
+                            <label class="form-label" for="item-unit">
+                                "Unit"
                             </label>
                             <input
                                 class="form-input w-full"
-                                id="item-name"
-                                name="item-name"
+                                id="item-unit"
+                                name="item-unit"
                                 type="text"
-                                prop:value=move || name_value.get()
-                                on:input=handle_name_input
-                                placeholder="e.g., Website Development"
-                                required
+                                placeholder="pcs"
+                                prop:value=move || unit_value.get()
+                                on:input=handle_unit_input
                             />
                         </div>
 
@@ -444,6 +597,32 @@ pub fn LineItems(
                             />
                         </div>
 
+                        <div class="flex flex-col w-32">
+                            <label class="form-label" for="price-basis">
+                                "Price"
+                            </label>
+                            <select
+                                class="form-select w-full"
+                                id="price-basis"
+                                name="price-basis"
+                                on:change=handle_price_basis_change
+                            >
+                                {PriceBasis::ALL
+                                    .into_iter()
+                                    .map(|basis| {
+                                        view! {
+                                            <option
+                                                value=basis.label()
+                                                selected=move || price_basis_value.get() == basis
+                                            >
+                                                {basis.label()}
+                                            </option>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </select>
+                        </div>
+
                         <div class="flex flex-col flex-[2] min-w-[200px]">
                             <LineItemCharges
                                 form_state=grid.form_state
@@ -458,10 +637,24 @@ pub fn LineItems(
 
                         // <LineItemFields fields=custom_fields form_values=custom_field_values />
 
+                        <div class="flex flex-col w-full">
+                            <label class="form-label" for="item-description">
+                                "Description"
+                            </label>
+                            <input
+                                class="form-input w-full"
+                                id="item-description"
+                                name="item-description"
+                                type="text"
+                                prop:value=move || description_value.get()
+                                on:input=handle_description_input
+                            />
+                        </div>
+
                         <div class="flex flex-col bg-gray-100 rounded-lg p-2 min-w-[150px]">
                             <label class="form-label">"Total"</label>
                             <p class="text-xl font-bold text-gray-800">
-                                {move || format!("${:.2}", total.get())}
+                                {move || format!("${}", format_amount(total.get()))}
                             </p>
                         </div>
 
@@ -496,36 +689,21 @@ pub fn LineItems(
                                     children=move |item_state| {
                                         let index = item_state.index;
                                         let item = item_state.data.clone();
-                                        let total = calculate_total(
-                                            item.quantity,
-                                            item.unit_price,
-                                            &item.taxes,
-                                            &item.discounts,
-                                            &item.charges,
-                                        );
+                                        let total = breakdown
+                                            .with(|b| b.line(&item.id).map(|l| l.total))
+                                            .unwrap_or_default();
                                         view! {
                                             <div class="border rounded-lg p-4 bg-gray-50">
                                                 <div class="flex justify-between items-start mb-2">
                                                     <div>
                                                         <h3 class="font-semibold text-lg">{item.name.clone()}</h3>
+                                                        <p class="text-gray-600 text-sm">{item.description.clone()}</p>
                                                         <p class="text-gray-500 text-sm">
                                                             <For
                                                                 each=move || item.custom_fields.clone().into_iter()
                                                                 key=|(_, field)| field.id.clone()
                                                                 children=move |(_, field)| {
-                                                                    let value_str = match &field.value {
-                                                                        FieldValue::Text(v)
-                                                                        | FieldValue::Email(v)
-                                                                        | FieldValue::Phone(v)
-                                                                        | FieldValue::Textarea(v)
-                                                                        | FieldValue::Dropdown(v)
-                                                                        | FieldValue::Date(v) => v.clone(),
-                                                                        FieldValue::Number(n) => n.to_string(),
-                                                                        FieldValue::Checkbox(b) => {
-                                                                            if *b { "Yes".to_string() } else { "No".to_string() }
-                                                                        }
-                                                                        _ => todo!(),
-                                                                    };
+                                                                    let value_str = field.value.to_string();
 
                                                                     view! {
                                                                         <li>
@@ -538,12 +716,12 @@ pub fn LineItems(
                                                         </p>
                                                     </div>
                                                     <span class="px-2 py-1 text-xs bg-blue-100 text-blue-800 rounded">
-                                                        {format!("${:.2}", item.quantity * item.unit_price)}
+                                                        {format!("${}", format_amount(total))}
                                                     </span>
                                                 </div>
                                                 <div class="text-sm text-gray-600 mt-2">
-                                                    <p>"Qty: " {item.quantity}</p>
-                                                    <p>"Unit Price: " {format!("${total:.2}")}</p>
+                                                    <p>"Qty: " {item.quantity.to_string()} " " {item.unit.clone()}</p>
+                                                    <p>"Unit Price: " {format!("${}", format_amount(item.unit_price))}</p>
                                                 </div>
                                                 <div class="flex justify-between items-center mt-3 text-sm text-gray-600">
                                                     <div class="flex space-x-2">
@@ -663,35 +841,22 @@ pub fn LineItems(
                                             children=move |item_state| {
                                                 let index = item_state.index;
                                                 let item = item_state.data.clone();
-                                                let total = calculate_total(
-                                                    item.quantity,
-                                                    item.unit_price,
-                                                    &item.taxes,
-                                                    &item.discounts,
-                                                    &item.charges,
-                                                );
+                                                let total = breakdown
+                                                    .with(|b| b.line(&item.id).map(|l| l.total))
+                                                    .unwrap_or_default();
                                                 view! {
                                                     <tr>
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm font-medium text-gray-900">
                                                             {item.name.clone()}
+                                                            <p class="text-sm font-normal text-gray-500">
+                                                                {item.description.clone()}
+                                                            </p>
                                                             <ul class="mt-1 ml-4 list-disc list-inside text-sm text-gray-700">
                                                                 <For
                                                                     each=move || item.custom_fields.clone().into_iter()
                                                                     key=|(_, field)| field.id.clone()
                                                                     children=move |(_, field)| {
-                                                                        let value_str = match &field.value {
-                                                                            FieldValue::Text(v)
-                                                                            | FieldValue::Email(v)
-                                                                            | FieldValue::Phone(v)
-                                                                            | FieldValue::Textarea(v)
-                                                                            | FieldValue::Dropdown(v)
-                                                                            | FieldValue::Date(v) => v.clone(),
-                                                                            FieldValue::Number(n) => n.to_string(),
-                                                                            FieldValue::Checkbox(b) => {
-                                                                                if *b { "Yes".to_string() } else { "No".to_string() }
-                                                                            }
-                                                                            _ => todo!(),
-                                                                        };
+                                                                        let value_str = field.value.to_string();
 
                                                                         view! {
                                                                             <li>
@@ -704,10 +869,10 @@ pub fn LineItems(
                                                             </ul>
                                                         </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
-                                                            {item.quantity}
+                                                            {format!("{} {}", item.quantity, item.unit)}
                                                         </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
-                                                            {format!("${:.2}", item.unit_price)}
+                                                            {format!("${}", format_amount(item.unit_price))}
                                                         </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
                                                             <For
@@ -737,7 +902,7 @@ pub fn LineItems(
                                                             />
                                                         </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900 font-bold">
-                                                            {format!("${total:.2}")}
+                                                            {format!("${}", format_amount(total))}
                                                         </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-right text-sm font-medium">
                                                             <button
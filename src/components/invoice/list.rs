@@ -0,0 +1,740 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::components::A;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::future::Future;
+use std::pin::Pin;
+
+use super::*;
+use crate::components::ui::{Table, TableBody, TableCell, TableHead, TableHeader, TableRow};
+use crate::shared::dialog::confirm;
+use crate::shared::storage::{Catalog, Database, StorageError};
+
+/// One row of the invoice list
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct InvoiceSummary {
+    pub id: String,
+    pub document_type: DocumentType,
+    pub number: String,
+    pub client: String,
+    pub issue_date: String,
+    pub due_date: String,
+    pub total: Decimal,
+    pub status: InvoiceStatus, // Effective status, from the payments and the due date
+    /// Effective status of a quote, `None` for invoices
+    pub quote_status: Option<QuoteStatus>,
+    pub needs_review: bool, // Generated by a recurring schedule, not reviewed yet
+    search_text: String,    // Lowercased text matched by the search box
+}
+
+impl InvoiceSummary {
+    pub fn new(
+        document: &InvoiceDocument,
+        line_items: Vec<LineItem>,
+        discounts: Vec<DiscountItem>,
+        charges: Vec<ChargeItem>,
+        today: &str,
+    ) -> Self {
+        let due_date = document.invoice_value(DUE_DATE_FIELD_ID);
+        let search_text = document
+            .invoice_info
+            .values()
+            .chain(document.client_info.values())
+            .chain(document.biller_info.values())
+            .chain(document.extra_info.values())
+            .map(|field| field.value.to_string())
+            .chain(line_items.iter().map(|item| item.name.clone()))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+
+        let totals = calculate_invoice(&document.snapshot(line_items, discounts, charges));
+
+        Self {
+            id: document.id.clone(),
+            document_type: document.document_type,
+            number: document.number(),
+            client: document.client_value(CLIENT_NAME_FIELD_ID),
+            issue_date: document.invoice_value(ISSUE_DATE_FIELD_ID),
+            status: document.status.effective(
+                &due_date,
+                today,
+                totals.net_payable,
+                amount_paid(&document.payments) + amount_credited(&document.credits),
+            ),
+            quote_status: (document.document_type == DocumentType::Quote).then(|| {
+                document
+                    .quote_status
+                    .effective(&document.valid_until, today)
+            }),
+            due_date,
+            total: totals.grand_total,
+            needs_review: document.needs_review,
+            search_text,
+        }
+    }
+}
+
+impl InvoiceSummary {
+    pub fn status_label(&self) -> &'static str {
+        match self.quote_status {
+            Some(status) => status.label(),
+            None => self.status.label(),
+        }
+    }
+
+    pub fn badge_class(&self) -> &'static str {
+        match self.quote_status {
+            Some(status) => status.badge_class(),
+            None => self.status.badge_class(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum InvoiceSortKey {
+    Number,
+    Client,
+    #[default]
+    IssueDate,
+    DueDate,
+    Total,
+    Status,
+}
+
+/// Search and filter options of the invoice list, empty values match everything
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct InvoiceFilter {
+    pub query: String,
+    pub document_type: Option<DocumentType>,
+    pub status: Option<InvoiceStatus>, // Only matches invoices
+    pub quote_status: Option<QuoteStatus>,
+    pub issued_from: String, // ISO date, inclusive
+    pub issued_to: String,   // ISO date, inclusive
+    pub to_review: bool,
+}
+
+impl InvoiceFilter {
+    pub fn matches(&self, summary: &InvoiceSummary) -> bool {
+        let query = self.query.trim().to_lowercase();
+
+        (query.is_empty() || summary.search_text.contains(&query))
+            && self
+                .document_type
+                .is_none_or(|kind| kind == summary.document_type)
+            && self
+                .status
+                .is_none_or(|status| summary.quote_status.is_none() && status == summary.status)
+            && self
+                .quote_status
+                .is_none_or(|status| summary.quote_status == Some(status))
+            && (!self.to_review || summary.needs_review)
+            && (self.issued_from.is_empty() || summary.issue_date >= self.issued_from)
+            && (self.issued_to.is_empty()
+                || (!summary.issue_date.is_empty() && summary.issue_date <= self.issued_to))
+    }
+}
+
+/// Compares runs of digits by their value, so INV-2 comes before INV-10
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(first_a), Some(first_b)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        let digits = first_a.is_ascii_digit();
+        if digits != first_b.is_ascii_digit() {
+            return a.cmp(b);
+        }
+        let run = |text: &str| {
+            text.find(|c: char| c.is_ascii_digit() != digits)
+                .unwrap_or(text.len())
+        };
+        let (run_a, rest_a) = a.split_at(run(a));
+        let (run_b, rest_b) = b.split_at(run(b));
+        let ordering = if digits {
+            let (value_a, value_b) = (run_a.trim_start_matches('0'), run_b.trim_start_matches('0'));
+            value_a
+                .len()
+                .cmp(&value_b.len())
+                .then_with(|| value_a.cmp(value_b))
+                .then_with(|| run_a.len().cmp(&run_b.len()))
+        } else {
+            run_a.cmp(run_b)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        (a, b) = (rest_a, rest_b);
+    }
+}
+
+pub fn sort_summaries(summaries: &mut [InvoiceSummary], key: InvoiceSortKey, ascending: bool) {
+    summaries.sort_by(|a, b| {
+        let ordering = match key {
+            InvoiceSortKey::Number => natural_cmp(&a.number, &b.number),
+            InvoiceSortKey::Client => a.client.to_lowercase().cmp(&b.client.to_lowercase()),
+            InvoiceSortKey::IssueDate => a.issue_date.cmp(&b.issue_date),
+            InvoiceSortKey::DueDate => a.due_date.cmp(&b.due_date),
+            InvoiceSortKey::Total => a.total.cmp(&b.total),
+            InvoiceSortKey::Status => a.status_label().cmp(b.status_label()),
+        };
+        if ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    });
+}
+
+/// Storage operation started from a list row
+type RowAction = Pin<Box<dyn Future<Output = Result<(), StorageError>>>>;
+
+async fn load_summaries() -> Result<Vec<InvoiceSummary>, StorageError> {
+    let mut db = Database::open().await?;
+    // Only for invoices saved without their own invoice-level discounts and charges
+    let discounts: Vec<DiscountItem> = db.load_catalog(Catalog::Discounts).await?;
+    let charges: Vec<ChargeItem> = db.load_catalog(Catalog::Charges).await?;
+    let mut line_items = db.load_all_line_items().await?;
+    let today = today_iso();
+
+    Ok(db
+        .list_invoices()
+        .await?
+        .iter()
+        .map(|document| {
+            InvoiceSummary::new(
+                document,
+                line_items.remove(&document.id).unwrap_or_default(),
+                discounts.clone(),
+                charges.clone(),
+                &today,
+            )
+        })
+        .collect())
+}
+
+/// Copies an invoice and its line items into a new draft without an invoice number
+async fn duplicate_invoice(invoice_id: String) -> Result<(), StorageError> {
+    let mut db = Database::open().await?;
+    let Some(mut document) = db.load_invoice(&invoice_id).await? else {
+        return Ok(());
+    };
+
+    let line_items = db
+        .load_line_items(&invoice_id)
+        .await?
+        .into_iter()
+        .map(|item| LineItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            ..item
+        })
+        .collect::<Vec<_>>();
+
+    document.id = uuid::Uuid::new_v4().to_string();
+    document.status = InvoiceStatus::Draft;
+    document.payments.clear();
+    document.credits.clear();
+    document.recurring_profile_id = None;
+    document.needs_review = false;
+    document.quote_status = QuoteStatus::default();
+    document.converted_invoice_id = None;
+    document.invoice_info.remove(INVOICE_NUMBER_FIELD_ID);
+    document.invoice_info.remove(QUOTE_NUMBER_FIELD_ID);
+    document.invoice_info.remove(CREDIT_NOTE_NUMBER_FIELD_ID);
+
+    db.save_line_items(&document.id, &line_items).await?;
+    db.save_invoice(&document).await
+}
+
+async fn delete_invoice(invoice_id: String) -> Result<(), StorageError> {
+    let mut db = Database::open().await?;
+    // A deleted credit note no longer reduces what its invoice is owed
+    if let Some(document) = db.load_invoice(&invoice_id).await? {
+        record_credit(&mut db, &document, None).await?;
+    }
+    db.delete_invoice(&invoice_id).await
+}
+
+#[component]
+pub fn InvoiceList() -> impl IntoView {
+    let summaries = RwSignal::new(Vec::<InvoiceSummary>::new());
+    let error = RwSignal::new(None::<String>);
+    let loading = RwSignal::new(true);
+
+    let query = RwSignal::new(String::new());
+    let document_type = RwSignal::new(None::<DocumentType>);
+    let status = RwSignal::new(String::new());
+    let issued_from = RwSignal::new(String::new());
+    let issued_to = RwSignal::new(String::new());
+    let to_review = RwSignal::new(false);
+    let sort = RwSignal::new((InvoiceSortKey::default(), false));
+
+    let reload = move || {
+        spawn_local(async move {
+            match load_summaries().await {
+                Ok(loaded) => {
+                    summaries.set(loaded);
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(format!("Failed to load invoices: {e}"))),
+            }
+            loading.set(false);
+        });
+    };
+    reload();
+
+    // Schedules generate their invoices while the list is loading
+    if let Some(RecurringRuns(generated)) = use_context::<RecurringRuns>() {
+        Effect::new(move |_| {
+            if generated.get() > 0 {
+                reload();
+            }
+        });
+    }
+    let review_count = Memo::new(move |_| {
+        summaries.with(|rows| rows.iter().filter(|row| row.needs_review).count())
+    });
+
+    let run_action = move |action: RowAction| {
+        spawn_local(async move {
+            match action.await {
+                Ok(()) => reload(),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    let visible = Memo::new(move |_| {
+        let filter = InvoiceFilter {
+            query: query.get(),
+            document_type: document_type.get(),
+            status: match document_type.get() {
+                Some(DocumentType::Quote) => None,
+                _ => InvoiceStatus::from_label(&status.get()),
+            },
+            quote_status: match document_type.get() {
+                Some(DocumentType::Quote) => QuoteStatus::from_label(&status.get()),
+                _ => None,
+            },
+            issued_from: issued_from.get(),
+            issued_to: issued_to.get(),
+            to_review: to_review.get(),
+        };
+        let (key, ascending) = sort.get();
+
+        let mut visible = summaries
+            .get()
+            .into_iter()
+            .filter(|summary| filter.matches(summary))
+            .collect::<Vec<_>>();
+        sort_summaries(&mut visible, key, ascending);
+        visible
+    });
+
+    let sort_header = move |key: InvoiceSortKey, label: &'static str| {
+        view! {
+            <button
+                class="inline-flex items-center gap-1 hover:text-gray-900"
+                on:click=move |_| {
+                    sort.update(|(current, ascending)| {
+                        if *current == key {
+                            *ascending = !*ascending;
+                        } else {
+                            *current = key;
+                            *ascending = true;
+                        }
+                    })
+                }
+            >
+                {label}
+                <span class="text-xs">
+                    {move || match sort.get() {
+                        (current, true) if current == key => "▲",
+                        (current, false) if current == key => "▼",
+                        _ => "",
+                    }}
+                </span>
+            </button>
+        }
+    };
+
+    view! {
+        <div class="bg-white rounded-md shadow-sm border border-gray-200">
+            <div class="p-4 border-b border-gray-200 flex flex-wrap items-end gap-4">
+                <div class="flex-1 min-w-[12rem]">
+                    <label class="form-label" for="invoice-search">
+                        "Search"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="invoice-search"
+                        type="search"
+                        placeholder="Number, client, item..."
+                        prop:value=move || query.get()
+                        on:input=move |ev| query.set(event_target_value(&ev))
+                    />
+                </div>
+                <div>
+                    <label class="form-label" for="invoice-type-filter">
+                        "Type"
+                    </label>
+                    <select
+                        class="form-select"
+                        id="invoice-type-filter"
+                        on:change=move |ev| {
+                            document_type.set(DocumentType::from_label(&event_target_value(&ev)));
+                            status.set(String::new());
+                        }
+                    >
+                        <option value="">"All"</option>
+                        {DocumentType::ALL
+                            .into_iter()
+                            .map(|value| view! { <option value=value.label()>{value.label()}</option> })
+                            .collect_view()}
+                    </select>
+                </div>
+                <div>
+                    <label class="form-label" for="invoice-status-filter">
+                        "Status"
+                    </label>
+                    <select
+                        class="form-select"
+                        id="invoice-status-filter"
+                        on:change=move |ev| status.set(event_target_value(&ev))
+                    >
+                        <option value="">"All"</option>
+                        {move || {
+                            let labels = match document_type.get() {
+                                Some(DocumentType::Quote) => {
+                                    QuoteStatus::ALL.iter().map(QuoteStatus::label).collect::<Vec<_>>()
+                                }
+                                _ => InvoiceStatus::ALL.iter().map(InvoiceStatus::label).collect(),
+                            };
+                            labels
+                                .into_iter()
+                                .map(|label| view! { <option value=label>{label}</option> })
+                                .collect_view()
+                        }}
+                    </select>
+                </div>
+                <div>
+                    <label class="form-label" for="invoice-issued-from">
+                        "Issued from"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="invoice-issued-from"
+                        type="date"
+                        prop:value=move || issued_from.get()
+                        on:input=move |ev| issued_from.set(event_target_value(&ev))
+                    />
+                </div>
+                <div>
+                    <label class="form-label" for="invoice-issued-to">
+                        "Issued to"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="invoice-issued-to"
+                        type="date"
+                        prop:value=move || issued_to.get()
+                        on:input=move |ev| issued_to.set(event_target_value(&ev))
+                    />
+                </div>
+                <label class="flex items-center gap-2 h-10 text-sm text-gray-700">
+                    <input
+                        type="checkbox"
+                        prop:checked=to_review
+                        on:change=move |ev| to_review.set(event_target_checked(&ev))
+                    />
+                    {move || format!("To review ({})", review_count.get())}
+                </label>
+                <A
+                    href=move || format!("/invoices/{}", uuid::Uuid::new_v4())
+                    attr:class="inline-flex items-center justify-center rounded-2xl font-medium h-10 px-4 py-2 bg-gray-900 text-white hover:bg-gray-800"
+                >
+                    "New invoice"
+                </A>
+                <A
+                    href=move || {
+                        format!("/invoices/{}?type={}", uuid::Uuid::new_v4(), DocumentType::Quote.key())
+                    }
+                    attr:class="inline-flex items-center justify-center rounded-2xl font-medium h-10 px-4 py-2 border border-gray-300 text-gray-900 hover:bg-gray-50"
+                >
+                    "New quote"
+                </A>
+            </div>
+
+            <Show when=move || error.get().is_some()>
+                <div class="p-4 text-sm text-red-600">{move || error.get().unwrap_or_default()}</div>
+            </Show>
+
+            <Table>
+                <TableHeader>
+                    <TableRow>
+                        <TableHead>{sort_header(InvoiceSortKey::Number, "Number")}</TableHead>
+                        <TableHead>{sort_header(InvoiceSortKey::Client, "Client")}</TableHead>
+                        <TableHead>{sort_header(InvoiceSortKey::IssueDate, "Issued")}</TableHead>
+                        <TableHead>{sort_header(InvoiceSortKey::DueDate, "Due")}</TableHead>
+                        <TableHead class="text-right">
+                            {sort_header(InvoiceSortKey::Total, "Total")}
+                        </TableHead>
+                        <TableHead>{sort_header(InvoiceSortKey::Status, "Status")}</TableHead>
+                        <TableHead class="text-right">"Actions"</TableHead>
+                    </TableRow>
+                </TableHeader>
+                <TableBody>
+                    <For
+                        each=move || visible.get()
+                        key=|summary| summary.clone()
+                        children=move |summary| {
+                            let duplicate_id = summary.id.clone();
+                            let delete_id = summary.id.clone();
+                            let delete_label = if summary.number.is_empty() {
+                                "this draft".to_string()
+                            } else {
+                                format!(
+                                    "{} {}",
+                                    summary.document_type.label().to_lowercase(),
+                                    summary.number,
+                                )
+                            };
+                            let href = format!("/invoices/{}", summary.id);
+                            let badge_class = format!(
+                                "inline-flex items-center rounded-full px-2.5 py-0.5 text-xs font-semibold {}",
+                                summary.badge_class(),
+                            );
+                            let status_label = summary.status_label();
+                            view! {
+                                <TableRow>
+                                    <TableCell class="font-medium">
+                                        {if summary.number.is_empty() {
+                                            "—".to_string()
+                                        } else {
+                                            summary.number.clone()
+                                        }}
+                                    </TableCell>
+                                    <TableCell>{summary.client.clone()}</TableCell>
+                                    <TableCell>{summary.issue_date.clone()}</TableCell>
+                                    <TableCell>{summary.due_date.clone()}</TableCell>
+                                    <TableCell class="text-right">
+                                        {format!("${}", format_amount(summary.total))}
+                                    </TableCell>
+                                    <TableCell>
+                                        <span class=badge_class>{status_label}</span>
+                                        {summary
+                                            .needs_review
+                                            .then(|| {
+                                                view! {
+                                                    <span class="ml-1 inline-flex items-center rounded-full px-2.5 py-0.5 text-xs font-semibold bg-yellow-100 text-yellow-800">
+                                                        "To review"
+                                                    </span>
+                                                }
+                                            })}
+                                    </TableCell>
+                                    <TableCell class="text-right whitespace-nowrap space-x-3">
+                                        <A
+                                            href=href
+                                            attr:class="text-indigo-600 hover:text-indigo-900"
+                                        >
+                                            "Open"
+                                        </A>
+                                        <button
+                                            class="text-gray-600 hover:text-gray-900"
+                                            on:click=move |_| {
+                                                run_action(Box::pin(duplicate_invoice(duplicate_id.clone())))
+                                            }
+                                        >
+                                            "Duplicate"
+                                        </button>
+                                        <button
+                                            class="text-red-600 hover:text-red-900"
+                                            on:click=move |_| {
+                                                if confirm(&format!("Delete {delete_label}?")) {
+                                                    run_action(Box::pin(delete_invoice(delete_id.clone())))
+                                                }
+                                            }
+                                        >
+                                            "Delete"
+                                        </button>
+                                    </TableCell>
+                                </TableRow>
+                            }
+                        }
+                    />
+                </TableBody>
+            </Table>
+
+            <Show when=move || !loading.get() && visible.with(|rows| rows.is_empty())>
+                <p class="p-6 text-center text-sm text-gray-500">"No invoices found."</p>
+            </Show>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    fn summary(number: &str, client: &str, issue_date: &str, total: Decimal) -> InvoiceSummary {
+        InvoiceSummary {
+            id: number.to_string(),
+            document_type: DocumentType::Invoice,
+            number: number.to_string(),
+            client: client.to_string(),
+            issue_date: issue_date.to_string(),
+            due_date: String::new(),
+            total,
+            status: InvoiceStatus::Sent,
+            quote_status: None,
+            needs_review: false,
+            search_text: format!("{number} {client}").to_lowercase(),
+        }
+    }
+
+    fn charge(amount: Decimal) -> ChargeItem {
+        ChargeItem {
+            id: "shipping".to_string(),
+            name: "Shipping".to_string(),
+            description: String::new(),
+            amount,
+            scope: ChargeScope::GlobalInvoice,
+            is_default: false,
+        }
+    }
+
+    #[test]
+    fn totals_use_the_charges_kept_with_the_invoice() {
+        let line_items = vec![LineItem {
+            quantity: dec!(1),
+            unit_price: dec!(100),
+            ..Default::default()
+        }];
+        let catalog = vec![charge(dec!(25))];
+        let sent = InvoiceDocument {
+            status: InvoiceStatus::Sent,
+            charges: Some(vec![charge(dec!(5))]),
+            ..Default::default()
+        };
+        let legacy = InvoiceDocument {
+            charges: None,
+            ..sent.clone()
+        };
+
+        let total = |document: &InvoiceDocument| {
+            InvoiceSummary::new(
+                document,
+                line_items.clone(),
+                vec![],
+                catalog.clone(),
+                "2025-01-01",
+            )
+            .total
+        };
+        assert_eq!(total(&sent), dec!(105));
+        assert_eq!(total(&legacy), dec!(125));
+    }
+
+    #[test]
+    fn filter_matches_query_type_status_and_dates() {
+        let mut quote = summary("Q-1", "Globex", "2025-02-10", dec!(50));
+        quote.document_type = DocumentType::Quote;
+        quote.quote_status = Some(QuoteStatus::Sent);
+        let invoice = summary("INV-1", "Acme Corp", "2025-01-15", dec!(100));
+
+        let matches = |filter: InvoiceFilter| {
+            [&invoice, &quote]
+                .into_iter()
+                .filter(|summary| filter.matches(summary))
+                .map(|summary| summary.number.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(matches(InvoiceFilter::default()), ["INV-1", "Q-1"]);
+        assert_eq!(
+            matches(InvoiceFilter {
+                query: " ACME ".to_string(),
+                ..Default::default()
+            }),
+            ["INV-1"]
+        );
+        assert_eq!(
+            matches(InvoiceFilter {
+                document_type: Some(DocumentType::Quote),
+                ..Default::default()
+            }),
+            ["Q-1"]
+        );
+        // An invoice status never matches a quote, whatever its own status
+        assert_eq!(
+            matches(InvoiceFilter {
+                status: Some(InvoiceStatus::Sent),
+                ..Default::default()
+            }),
+            ["INV-1"]
+        );
+        assert_eq!(
+            matches(InvoiceFilter {
+                quote_status: Some(QuoteStatus::Sent),
+                ..Default::default()
+            }),
+            ["Q-1"]
+        );
+        assert_eq!(
+            matches(InvoiceFilter {
+                issued_from: "2025-02-01".to_string(),
+                issued_to: "2025-02-10".to_string(),
+                ..Default::default()
+            }),
+            ["Q-1"]
+        );
+        assert!(
+            matches(InvoiceFilter {
+                to_review: true,
+                ..Default::default()
+            })
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn summaries_sort_by_key_in_either_direction() {
+        let mut summaries = vec![
+            summary("INV-2", "beta", "2025-01-02", dec!(20)),
+            summary("INV-3", "Alpha", "2025-01-03", dec!(5)),
+            summary("INV-1", "Gamma", "2025-01-01", dec!(100)),
+        ];
+        let numbers = |summaries: &[InvoiceSummary]| {
+            summaries
+                .iter()
+                .map(|summary| summary.number.clone())
+                .collect::<Vec<_>>()
+        };
+
+        sort_summaries(&mut summaries, InvoiceSortKey::Total, true);
+        assert_eq!(numbers(&summaries), ["INV-3", "INV-2", "INV-1"]);
+
+        sort_summaries(&mut summaries, InvoiceSortKey::IssueDate, false);
+        assert_eq!(numbers(&summaries), ["INV-3", "INV-2", "INV-1"]);
+
+        // Client names sort without regard to case
+        sort_summaries(&mut summaries, InvoiceSortKey::Client, true);
+        assert_eq!(numbers(&summaries), ["INV-3", "INV-2", "INV-1"]);
+
+        sort_summaries(&mut summaries, InvoiceSortKey::Number, true);
+        assert_eq!(numbers(&summaries), ["INV-1", "INV-2", "INV-3"]);
+
+        // Numbers sort by value, not character by character
+        summaries.push(summary("INV-10", "Delta", "2025-01-04", dec!(1)));
+        summaries.push(summary("INV-002", "Delta", "2025-01-04", dec!(1)));
+        sort_summaries(&mut summaries, InvoiceSortKey::Number, true);
+        assert_eq!(
+            numbers(&summaries),
+            ["INV-1", "INV-2", "INV-002", "INV-3", "INV-10"]
+        );
+        sort_summaries(&mut summaries, InvoiceSortKey::Number, false);
+        assert_eq!(numbers(&summaries)[0], "INV-10");
+    }
+}
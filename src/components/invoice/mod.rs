@@ -1,25 +1,71 @@
+mod assets;
+mod auto_generation_editor;
+mod billers;
 mod builder;
+mod calculation;
+mod clients;
+mod credit_notes;
 mod custom_fields;
 mod custom_fields_renderer;
 mod discounts;
 mod fields_card;
+mod formula;
+mod generators;
+mod gst;
 mod line_items;
+mod list;
 mod line_items_charges;
+mod money;
 mod other_charges;
+mod payments;
+mod persistence;
+mod preview;
+mod products;
+mod quotes;
+mod recurring;
+mod sequences;
 mod state;
+mod status;
 mod taxes;
+mod template_data;
+mod template_gallery;
+#[cfg(test)]
+mod test_support;
 mod totals;
+mod vat;
 mod view;
 
+pub use assets::*;
+pub use auto_generation_editor::*;
+pub use billers::*;
 pub use builder::*;
+pub use calculation::*;
+pub use clients::*;
+pub use credit_notes::*;
 pub use custom_fields::*;
 pub use custom_fields_renderer::*;
 pub use discounts::*;
 pub use fields_card::*;
+pub use formula::*;
+pub use generators::*;
+pub use gst::*;
 pub use line_items::*;
+pub use list::*;
 pub use line_items_charges::*;
+pub use money::*;
 pub use other_charges::*;
+pub use payments::*;
+pub use persistence::*;
+pub use preview::*;
+pub use products::*;
+pub use quotes::*;
+pub use recurring::*;
+pub use sequences::*;
 pub use state::*;
+pub use status::*;
 pub use taxes::*;
+pub use template_data::*;
+pub use template_gallery::*;
 pub use totals::*;
+pub use vat::*;
 pub use view::*;
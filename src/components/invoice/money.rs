@@ -0,0 +1,118 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Deserializer, Serialize, de};
+use std::fmt;
+
+/// Number of decimal places monetary amounts are rounded to
+pub const MONEY_SCALE: u32 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum RoundingMode {
+    /// Midpoints are rounded away from zero: 0.125 -> 0.13
+    #[default]
+    HalfUp,
+    /// Banker's rounding, midpoints go to the nearest even digit: 0.125 -> 0.12, 0.135 -> 0.14
+    #[serde(alias = "Bankers")]
+    HalfEven,
+}
+
+impl RoundingMode {
+    pub fn strategy(&self) -> RoundingStrategy {
+        match self {
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            RoundingMode::HalfEven => RoundingStrategy::MidpointNearestEven,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RoundingMode::HalfUp => "Half up",
+            RoundingMode::HalfEven => "Half even (banker's)",
+        }
+    }
+}
+
+/// Where tax amounts are rounded
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum TaxRounding {
+    /// Each line's tax is rounded, the invoice tax is the sum of the rounded amounts
+    #[default]
+    PerLine,
+    /// Line taxes are summed at full precision and rounded once per tax on the invoice
+    PerInvoice,
+}
+
+impl TaxRounding {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaxRounding::PerLine => "Per line",
+            TaxRounding::PerInvoice => "Per invoice",
+        }
+    }
+}
+
+/// Per-invoice rounding policy applied by the calculation engine
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct RoundingPolicy {
+    #[serde(default)]
+    pub mode: RoundingMode,
+    #[serde(default)]
+    pub tax_rounding: TaxRounding,
+}
+
+impl RoundingPolicy {
+    pub fn round(&self, amount: Decimal) -> Decimal {
+        amount.round_dp_with_strategy(MONEY_SCALE, self.mode.strategy())
+    }
+}
+
+/// Parses user input into a `Decimal`, accepting surrounding whitespace
+pub fn parse_decimal(value: &str) -> Option<Decimal> {
+    value.trim().parse::<Decimal>().ok()
+}
+
+/// Formats an amount with exactly `MONEY_SCALE` decimal places
+pub fn format_amount(amount: Decimal) -> String {
+    let rounded =
+        amount.round_dp_with_strategy(MONEY_SCALE, RoundingStrategy::MidpointAwayFromZero);
+    format!("{:.*}", MONEY_SCALE as usize, rounded)
+}
+
+/// Deserializes an amount stored either as a decimal string or as a JSON number.
+/// Invoices saved before amounts moved to `Decimal` hold plain floats, which the
+/// string-only `Decimal` deserializer rejects
+pub fn deserialize_amount<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+    struct AmountVisitor;
+
+    impl de::Visitor<'_> for AmountVisitor {
+        type Value = Decimal;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a decimal number or string")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Decimal, E> {
+            parse_amount(value).ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<Decimal, E> {
+            // Going through the shortest display form keeps 19.99 as 19.99 instead of
+            // the binary approximation
+            parse_amount(&value.to_string())
+                .ok_or_else(|| E::invalid_value(de::Unexpected::Float(value), &self))
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Decimal, E> {
+            Ok(Decimal::from(value))
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Decimal, E> {
+            Ok(Decimal::from(value))
+        }
+    }
+
+    fn parse_amount(value: &str) -> Option<Decimal> {
+        parse_decimal(value).or_else(|| Decimal::from_scientific(value.trim()).ok())
+    }
+
+    deserializer.deserialize_any(AmountVisitor)
+}
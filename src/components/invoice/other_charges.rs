@@ -1,7 +1,9 @@
 use leptos::prelude::*;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 
+use super::{deserialize_amount, format_amount, parse_decimal};
 use crate::components::{
     editable_grid::{
         FormData, FormValidation, ItemData, ValidationResult, use_editable_grid,
@@ -28,24 +30,29 @@ impl std::fmt::Display for ChargeScope {
 }
 
 // 2. Define your data structure for Other Charges
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct ChargeItem {
     pub id: String,
     pub name: String,
     pub description: String,
-    pub amount: f64,
+    #[serde(deserialize_with = "deserialize_amount")]
+    pub amount: Decimal,
     pub scope: ChargeScope,
     pub is_default: bool,
 }
 
-impl PartialEq for ChargeItem {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
-    }
+/// Charges of the catalog that apply to the whole invoice
+pub fn invoice_charges(charges: &[ChargeItem]) -> Vec<ChargeItem> {
+    charges
+        .iter()
+        .filter(|charge| charge.scope == ChargeScope::GlobalInvoice)
+        .cloned()
+        .collect()
 }
 
 impl Eq for ChargeItem {}
 
+// By id only, items equal by value share their id so this agrees with `PartialEq`
 impl Hash for ChargeItem {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
@@ -86,7 +93,7 @@ impl FormData for ChargeItem {
             id: uuid::Uuid::new_v4().to_string(),
             name: String::new(),
             description: String::new(),
-            amount: 0.0,
+            amount: Decimal::ZERO,
             scope: ChargeScope::default(),
             is_default: false,
         }
@@ -107,7 +114,7 @@ impl FormData for ChargeItem {
             id: uuid::Uuid::new_v4().to_string(),
             name: props.name.clone(),
             description: props.description.clone(),
-            amount: props.amount.parse::<f64>().unwrap_or(0.0),
+            amount: parse_decimal(&props.amount).unwrap_or_default(),
             scope: props.scope.clone(),
             is_default: props.is_default,
         }
@@ -147,12 +154,12 @@ impl FormValidation for ChargeForm {
 
         // Validate amount is a valid number and not negative
         if !self.amount.is_empty() {
-            if self.amount.parse::<f64>().is_err() {
+            if parse_decimal(&self.amount).is_none() {
                 results.push(ValidationResult::new().with_field_error(
                     "amount".to_string(),
                     "Amount must be a valid number".to_string(),
                 ));
-            } else if self.amount.parse::<f64>().unwrap_or(0.0) < 0.0 {
+            } else if parse_decimal(&self.amount).is_some_and(|amount| amount.is_sign_negative()) {
                 results.push(ValidationResult::new().with_field_error(
                     "amount".to_string(),
                     "Amount cannot be negative".to_string(),
@@ -425,7 +432,7 @@ pub fn OtherCharges(state: RwSignal<Vec<ChargeItem>>) -> impl IntoView {
                                                         </p>
                                                     </div>
                                                     <span class="px-2 py-1 text-xs bg-blue-100 text-blue-800 rounded">
-                                                        {format!("${:.2}", item.amount)}
+                                                        {format!("${}", format_amount(item.amount))}
                                                     </span>
                                                 </div>
                                                 <div class="flex justify-between items-center mt-3 text-sm text-gray-600">
@@ -515,7 +522,7 @@ pub fn OtherCharges(state: RwSignal<Vec<ChargeItem>>) -> impl IntoView {
                                                             {item.description.clone()}
                                                         </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
-                                                            {format!("${:.2}", item.amount)}
+                                                            {format!("${}", format_amount(item.amount))}
                                                         </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
                                                             {item.scope.to_string()}
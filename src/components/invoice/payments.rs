@@ -0,0 +1,369 @@
+use leptos::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum PaymentMethod {
+    #[default]
+    BankTransfer,
+    Card,
+    Cash,
+    Cheque,
+    Other,
+}
+
+impl PaymentMethod {
+    pub const ALL: [PaymentMethod; 5] = [
+        PaymentMethod::BankTransfer,
+        PaymentMethod::Card,
+        PaymentMethod::Cash,
+        PaymentMethod::Cheque,
+        PaymentMethod::Other,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaymentMethod::BankTransfer => "Bank transfer",
+            PaymentMethod::Card => "Card",
+            PaymentMethod::Cash => "Cash",
+            PaymentMethod::Cheque => "Cheque",
+            PaymentMethod::Other => "Other",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|method| method.label() == label)
+    }
+}
+
+/// Money received for an invoice
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Payment {
+    pub id: String,
+    /// ISO `YYYY-MM-DD`
+    pub date: String,
+    pub amount: Decimal,
+    #[serde(default)]
+    pub method: PaymentMethod,
+    /// Transaction or cheque number
+    #[serde(default)]
+    pub reference: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+pub fn amount_paid(payments: &[Payment]) -> Decimal {
+    payments.iter().map(|payment| payment.amount).sum()
+}
+
+/// What is still owed after payments and credit notes, negative when the client
+/// paid more than the total
+pub fn balance_due(total: Decimal, payments: &[Payment], credits: &[Credit]) -> Decimal {
+    total - amount_paid(payments) - amount_credited(credits)
+}
+
+/// Records payments against the invoice and shows what is left to pay
+#[component]
+pub fn PaymentsCard(
+    payments: RwSignal<Vec<Payment>>,
+    #[prop(into)] total: Signal<Decimal>,
+    status: ReadSignal<InvoiceStatus>,
+    invoice_info: ReadSignal<HashMap<String, FieldItemValue>>,
+    credits: ReadSignal<Vec<Credit>>,
+) -> impl IntoView {
+    let date = RwSignal::new(today_iso());
+    let amount = RwSignal::new(String::new());
+    let method = RwSignal::new(PaymentMethod::default());
+    let reference = RwSignal::new(String::new());
+    let note = RwSignal::new(String::new());
+    let error = RwSignal::new(None::<String>);
+
+    let balance = Memo::new(move |_| {
+        payments
+            .with(|payments| credits.with(|credits| balance_due(total.get(), payments, credits)))
+    });
+
+    let display_status = move || {
+        let due_date = invoice_info.with(|info| {
+            info.get(DUE_DATE_FIELD_ID)
+                .map(|field| field.value.to_string())
+                .unwrap_or_default()
+        });
+        // Credit notes settle the invoice like payments do
+        let paid = payments.with(|payments| amount_paid(payments))
+            + credits.with(|credits| amount_credited(credits));
+        status
+            .get()
+            .effective(&due_date, &today_iso(), total.get(), paid)
+    };
+
+    let add_payment = move |ev: web_sys::SubmitEvent| {
+        ev.prevent_default();
+        // An empty amount settles the balance
+        let value = match amount.get_untracked().trim() {
+            "" => Some(balance.get_untracked()),
+            text => parse_decimal(text),
+        };
+        let Some(value) = value.filter(|value| *value > Decimal::ZERO) else {
+            error.set(Some("Enter an amount above zero".to_string()));
+            return;
+        };
+        if date.with_untracked(|date| date.is_empty()) {
+            error.set(Some("Enter the payment date".to_string()));
+            return;
+        }
+
+        payments.update(|payments| {
+            payments.push(Payment {
+                id: uuid::Uuid::new_v4().to_string(),
+                date: date.get_untracked(),
+                amount: value,
+                method: method.get_untracked(),
+                reference: reference.get_untracked().trim().to_string(),
+                note: note.get_untracked().trim().to_string(),
+            });
+            payments.sort_by(|a, b| a.date.cmp(&b.date));
+        });
+        amount.set(String::new());
+        reference.set(String::new());
+        note.set(String::new());
+        error.set(None);
+    };
+
+    view! {
+        <div class="bg-white shadow rounded-lg p-4">
+            <div class="flex items-center justify-between mb-4">
+                <h2 class="text-lg font-semibold">"Payments"</h2>
+                {move || {
+                    let status = display_status();
+                    view! {
+                        <span class=format!(
+                            "px-2 py-0.5 rounded-full text-xs font-medium {}",
+                            status.badge_class(),
+                        )>{status.label()}</span>
+                    }
+                }}
+            </div>
+
+            <For
+                each=move || payments.get()
+                key=|payment| payment.id.clone()
+                children=move |payment| {
+                    let id = payment.id.clone();
+                    let details = [payment.method.label().to_string(), payment.reference, payment.note]
+                        .into_iter()
+                        .filter(|detail| !detail.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(" · ");
+                    view! {
+                        <div class="flex items-start justify-between gap-2 text-sm border-b py-2">
+                            <div>
+                                <div class="font-medium">{payment.date}</div>
+                                <div class="text-xs text-gray-500">{details}</div>
+                            </div>
+                            <div class="flex items-center gap-2">
+                                <span>{format_amount(payment.amount)}</span>
+                                <button
+                                    type="button"
+                                    class="text-xs text-red-600 hover:text-red-800"
+                                    on:click=move |_| {
+                                        payments.update(|payments| payments.retain(|p| p.id != id))
+                                    }
+                                >
+                                    "Remove"
+                                </button>
+                            </div>
+                        </div>
+                    }
+                }
+            />
+
+            <form class="mt-4 grid grid-cols-2 gap-2" on:submit=add_payment>
+                <div>
+                    <label class="form-label" for="payment-date">
+                        "Date"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="payment-date"
+                        type="date"
+                        prop:value=date
+                        on:input=move |ev| date.set(event_target_value(&ev))
+                    />
+                </div>
+                <div>
+                    <label class="form-label" for="payment-amount">
+                        "Amount"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="payment-amount"
+                        type="number"
+                        step="any"
+                        min="0"
+                        placeholder=move || format_amount(balance.get().max(Decimal::ZERO))
+                        prop:value=amount
+                        on:input=move |ev| amount.set(event_target_value(&ev))
+                    />
+                </div>
+                <div>
+                    <label class="form-label" for="payment-method">
+                        "Method"
+                    </label>
+                    <select
+                        class="form-select"
+                        id="payment-method"
+                        on:change=move |ev| {
+                            if let Some(value) = PaymentMethod::from_label(&event_target_value(&ev)) {
+                                method.set(value);
+                            }
+                        }
+                    >
+                        {PaymentMethod::ALL
+                            .into_iter()
+                            .map(|value| {
+                                view! {
+                                    <option
+                                        value=value.label()
+                                        selected=move || method.get() == value
+                                    >
+                                        {value.label()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                </div>
+                <div>
+                    <label class="form-label" for="payment-reference">
+                        "Reference"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="payment-reference"
+                        type="text"
+                        prop:value=reference
+                        on:input=move |ev| reference.set(event_target_value(&ev))
+                    />
+                </div>
+                <div class="col-span-2">
+                    <label class="form-label" for="payment-note">
+                        "Note"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="payment-note"
+                        type="text"
+                        prop:value=note
+                        on:input=move |ev| note.set(event_target_value(&ev))
+                    />
+                </div>
+                {move || {
+                    error.get().map(|error| view! { <p class="col-span-2 text-sm text-red-600">{error}</p> })
+                }}
+                <button
+                    type="submit"
+                    class="col-span-2 rounded-md bg-indigo-600 py-2 px-4 text-sm font-medium text-white hover:bg-indigo-700"
+                >
+                    "Record payment"
+                </button>
+            </form>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    fn payments(amounts: &[Decimal]) -> Vec<Payment> {
+        amounts
+            .iter()
+            .map(|amount| Payment {
+                amount: *amount,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn credit(amount: Decimal) -> Credit {
+        Credit {
+            amount,
+            ..Default::default()
+        }
+    }
+
+    /// Status on the PaymentsCard and the invoice list, credits count as paid
+    fn status(total: Decimal, payments: &[Payment], credits: &[Credit]) -> InvoiceStatus {
+        InvoiceStatus::Sent.effective(
+            "2025-05-01",
+            "2025-04-01",
+            total,
+            amount_paid(payments) + amount_credited(credits),
+        )
+    }
+
+    #[test]
+    fn payments_settle_the_balance() {
+        let partial = payments(&[dec!(25), dec!(15)]);
+        assert_eq!(amount_paid(&partial), dec!(40));
+        assert_eq!(balance_due(dec!(100), &partial, &[]), dec!(60));
+        assert_eq!(
+            status(dec!(100), &partial, &[]),
+            InvoiceStatus::PartiallyPaid
+        );
+
+        let exact = payments(&[dec!(60), dec!(40)]);
+        assert_eq!(balance_due(dec!(100), &exact, &[]), dec!(0));
+        assert_eq!(status(dec!(100), &exact, &[]), InvoiceStatus::Paid);
+
+        // The client is owed the difference
+        let over = payments(&[dec!(120)]);
+        assert_eq!(balance_due(dec!(100), &over, &[]), dec!(-20));
+        assert_eq!(status(dec!(100), &over, &[]), InvoiceStatus::Paid);
+
+        assert_eq!(balance_due(dec!(100), &[], &[]), dec!(100));
+        assert_eq!(status(dec!(100), &[], &[]), InvoiceStatus::Sent);
+    }
+
+    #[test]
+    fn credit_notes_settle_the_balance_like_payments() {
+        let paid = payments(&[dec!(30)]);
+
+        let partial = [credit(dec!(20))];
+        assert_eq!(balance_due(dec!(100), &paid, &partial), dec!(50));
+        assert_eq!(
+            status(dec!(100), &paid, &partial),
+            InvoiceStatus::PartiallyPaid
+        );
+
+        let rest = [credit(dec!(20)), credit(dec!(50))];
+        assert_eq!(balance_due(dec!(100), &paid, &rest), dec!(0));
+        assert_eq!(status(dec!(100), &paid, &rest), InvoiceStatus::Paid);
+
+        // Fully credited without any payment
+        let full = [credit(dec!(100))];
+        assert_eq!(balance_due(dec!(100), &[], &full), dec!(0));
+        assert_eq!(status(dec!(100), &[], &full), InvoiceStatus::Paid);
+    }
+
+    #[test]
+    fn partly_settled_invoices_past_due_are_overdue() {
+        let paid = payments(&[dec!(30)]);
+        let credits = [credit(dec!(20))];
+        let settled = amount_paid(&paid) + amount_credited(&credits);
+
+        assert_eq!(
+            InvoiceStatus::Sent.effective("2025-05-01", "2025-05-02", dec!(100), settled),
+            InvoiceStatus::Overdue
+        );
+        assert_eq!(
+            InvoiceStatus::Sent.effective("2025-05-01", "2025-05-02", dec!(50), settled),
+            InvoiceStatus::Paid
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use leptos::ev;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_use::{use_event_listener, use_window, watch_debounced};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::future::Future;
+use std::rc::Rc;
+
+use super::*;
+use crate::shared::storage::{Catalog, Database, StorageError};
+
+/// Delay between the last edit and writing it to IndexedDB
+const SAVE_DEBOUNCE_MS: f64 = 500.0;
+
+/// Connection shared by the loading and saving of one builder. Saves running at
+/// the same time open a connection of their own rather than waiting for it.
+#[derive(Clone, Default)]
+struct SharedDatabase(Rc<RefCell<Option<Database>>>);
+
+impl SharedDatabase {
+    /// Runs `f` on the idle connection, opening one when there is none. A connection
+    /// that failed is dropped so the next save starts from a fresh one.
+    async fn run<T>(
+        &self,
+        f: impl AsyncFnOnce(&mut Database) -> Result<T, StorageError>,
+    ) -> Result<T, StorageError> {
+        let idle = self.0.borrow_mut().take();
+        let mut db = match idle {
+            Some(db) => db,
+            None => Database::open().await?,
+        };
+        let result = f(&mut db).await;
+        if result.is_ok() {
+            *self.0.borrow_mut() = Some(db);
+        }
+        result
+    }
+}
+
+/// Loads the saved catalogs and an invoice into `state`, then writes every later
+/// change back. With no `invoice_id` the most recently edited invoice is opened, an
+/// id that was never saved starts an empty invoice under that id. Nothing is written
+/// until loading succeeded, so the demo data from `InvoiceBuilderState::new()` never
+/// replaces saved work, and a new invoice is only stored once the user edits it.
+pub fn use_invoice_persistence(state: InvoiceBuilderState, invoice_id: Option<String>) {
+    let loaded = RwSignal::new(false);
+    let edited = RwSignal::new(false);
+    let db = SharedDatabase::default();
+
+    // Default values filled in by the field cards are no reason to store a new
+    // invoice, only input from the user is
+    let _ = use_event_listener(use_window(), ev::input, move |_| edited.set(true));
+    let _ = use_event_listener(use_window(), ev::change, move |_| edited.set(true));
+    let _ = use_event_listener(use_window(), ev::submit, move |_| edited.set(true));
+
+    spawn_local({
+        let db = db.clone();
+        async move {
+            match db
+                .run(async |db| load_state(db, state, invoice_id).await)
+                .await
+            {
+                Ok(stored) => {
+                    // Every change to an invoice that is already stored is saved
+                    if stored {
+                        edited.set(true);
+                    }
+                    loaded.set(true);
+                }
+                Err(e) => {
+                    log::error!("Failed to load saved invoices, changes will not be saved: {e}")
+                }
+            }
+        }
+    });
+
+    persist_on_change(
+        &db,
+        loaded,
+        edited,
+        move || state.taxes.get(),
+        |db, taxes| save_catalog(db, Catalog::Taxes, taxes, |tax| &tax.id),
+    );
+    persist_on_change(
+        &db,
+        loaded,
+        edited,
+        move || state.discounts.get(),
+        |db, discounts| save_catalog(db, Catalog::Discounts, discounts, |discount| &discount.id),
+    );
+    persist_on_change(
+        &db,
+        loaded,
+        edited,
+        move || state.charges.get(),
+        |db, charges| save_catalog(db, Catalog::Charges, charges, |charge| &charge.id),
+    );
+    persist_on_change(
+        &db,
+        loaded,
+        edited,
+        move || state.custom_fields.get(),
+        |db, fields| save_catalog(db, Catalog::Fields, fields, |field| &field.id),
+    );
+    persist_on_change(
+        &db,
+        loaded,
+        edited,
+        move || state.products.get(),
+        |db, products| save_catalog(db, Catalog::Products, products, |product| &product.id),
+    );
+    persist_on_change(
+        &db,
+        loaded,
+        edited,
+        move || (state.id.get(), state.line_items.get()),
+        |db, (invoice_id, line_items)| save_line_items(db, invoice_id, line_items),
+    );
+    persist_on_change(&db, loaded, edited, move || state.document(), save_invoice);
+}
+
+/// Loads the catalogs and the invoice, true when the invoice was stored before
+async fn load_state(
+    db: &mut Database,
+    state: InvoiceBuilderState,
+    invoice_id: Option<String>,
+) -> Result<bool, StorageError> {
+    // First run: keep the default catalogs and store them so they are there next time
+    if db.is_new() {
+        save_catalogs(db, state).await?;
+        if let Some(invoice_id) = invoice_id {
+            start_document(state, invoice_id);
+        }
+        return Ok(false);
+    }
+
+    state.taxes.set(db.load_catalog(Catalog::Taxes).await?);
+    state
+        .discounts
+        .set(db.load_catalog(Catalog::Discounts).await?);
+    state.charges.set(db.load_catalog(Catalog::Charges).await?);
+    state
+        .custom_fields
+        .set(db.load_catalog(Catalog::Fields).await?);
+    state
+        .products
+        .set(db.load_catalog(Catalog::Products).await?);
+
+    let document = match &invoice_id {
+        Some(invoice_id) => db.load_invoice(invoice_id).await?,
+        None => db.load_latest_invoice().await?,
+    };
+    match (document, invoice_id) {
+        (Some(document), _) => {
+            let line_items = db.load_line_items(&document.id).await?;
+            state.apply_document(document, line_items);
+            Ok(true)
+        }
+        (None, Some(invoice_id)) => {
+            start_document(state, invoice_id);
+            Ok(false)
+        }
+        (None, None) => Ok(false),
+    }
+}
+
+/// Replaces whatever `state` holds with an empty document under `invoice_id`
+fn start_document(state: InvoiceBuilderState, invoice_id: String) {
+    let document_type = state.document_type.get_untracked();
+    state.apply_document(InvoiceDocument::new(invoice_id, document_type), vec![]);
+    state.set_document_type(document_type);
+}
+
+async fn save_catalogs(db: &mut Database, state: InvoiceBuilderState) -> Result<(), StorageError> {
+    db.save_catalog(Catalog::Taxes, &state.taxes.get_untracked(), |tax| &tax.id)
+        .await?;
+    db.save_catalog(
+        Catalog::Discounts,
+        &state.discounts.get_untracked(),
+        |discount| &discount.id,
+    )
+    .await?;
+    db.save_catalog(Catalog::Charges, &state.charges.get_untracked(), |charge| {
+        &charge.id
+    })
+    .await?;
+    db.save_catalog(
+        Catalog::Fields,
+        &state.custom_fields.get_untracked(),
+        |field| &field.id,
+    )
+    .await?;
+    db.save_catalog(
+        Catalog::Products,
+        &state.products.get_untracked(),
+        |product| &product.id,
+    )
+    .await?;
+    Ok(())
+}
+
+async fn save_catalog<T: Serialize>(
+    db: SharedDatabase,
+    catalog: Catalog,
+    items: Vec<T>,
+    id: fn(&T) -> &str,
+) -> Result<(), StorageError> {
+    db.run(async |db| db.save_catalog(catalog, &items, id).await)
+        .await
+}
+
+async fn save_line_items(
+    db: SharedDatabase,
+    invoice_id: String,
+    items: Vec<LineItem>,
+) -> Result<(), StorageError> {
+    db.run(async |db| db.save_line_items(&invoice_id, &items).await)
+        .await
+}
+
+async fn save_invoice(db: SharedDatabase, document: InvoiceDocument) -> Result<(), StorageError> {
+    db.run(async |db| db.save_invoice(&document).await).await
+}
+
+/// Calls `save` with the latest value of `source` once it stops changing, after
+/// loading finished and the user edited something
+fn persist_on_change<T, S, F, Fut>(
+    db: &SharedDatabase,
+    loaded: RwSignal<bool>,
+    edited: RwSignal<bool>,
+    source: S,
+    save: F,
+) where
+    T: Clone + 'static,
+    S: Fn() -> T + 'static,
+    F: Fn(SharedDatabase, T) -> Fut + Clone + 'static,
+    Fut: Future<Output = Result<(), StorageError>> + 'static,
+{
+    let db = db.clone();
+    let _ = watch_debounced(
+        source,
+        move |value, _, _| {
+            if !loaded.get_untracked() || !edited.get_untracked() {
+                return;
+            }
+
+            let save = save(db.clone(), value.clone());
+            spawn_local(async move {
+                if let Err(e) = save.await {
+                    log::error!("Failed to save invoice data: {e}");
+                }
+            });
+        },
+        SAVE_DEBOUNCE_MS,
+    );
+}
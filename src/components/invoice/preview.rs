@@ -0,0 +1,56 @@
+use leptos::prelude::*;
+use typst::foundations::Bytes;
+
+use super::*;
+use crate::components::pdf_viewer::PdfViewer;
+use crate::shared::templates::{TemplatePackage, resolve_template};
+use crate::shared::typst::VirtualFile;
+
+/// Files the template reads: the invoice data and the uploaded images
+fn invoice_files(state: InvoiceBuilderState) -> Vec<VirtualFile> {
+    let mut files = vec![VirtualFile::new(
+        TEMPLATE_DATA_PATH,
+        Bytes::new(state.template_data().to_json().into_bytes()),
+    )];
+    if let Some(logo) = state.logo.get() {
+        files.push(logo.virtual_file(AssetKind::Logo));
+    }
+    if let Some(signature) = state.signature.get() {
+        files.push(signature.virtual_file(AssetKind::Signature));
+    }
+    files
+}
+
+/// Template gallery and a live PDF of the invoice being edited
+#[component]
+pub fn InvoicePreview(
+    state: InvoiceBuilderState,
+    /// Built-in and user templates to choose from
+    #[prop(into)]
+    templates: Signal<Vec<TemplatePackage>>,
+) -> impl IntoView {
+    let data_files = Signal::derive(move || invoice_files(state));
+
+    view! {
+        <section class="p-6 space-y-6">
+            <TemplateGallery
+                selected=state.template_id
+                templates=templates
+                files=data_files
+            />
+            // Remount on template changes, the new template renders without waiting
+            // for the debounce
+            {move || {
+                let template = templates
+                    .with(|templates| resolve_template(&state.template_id.get(), templates));
+                let template_files = template.virtual_files();
+                let files = Signal::derive(move || {
+                    let mut files = template_files.clone();
+                    files.extend(data_files.get());
+                    files
+                });
+                view! { <PdfViewer source=template.source files=files /> }
+            }}
+        </section>
+    }
+}
@@ -0,0 +1,601 @@
+use leptos::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use super::*;
+use crate::components::{
+    editable_grid::{
+        FormData, FormValidation, ItemData, ValidationResult, use_editable_grid,
+        validation::validators,
+    },
+    ui::{AutocompleteDataSource, AutocompleteGroup, AutocompleteItem, StaticDataSource},
+};
+
+/// A product or service from the catalog, picked when adding a line item
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProductItem {
+    pub id: String,
+    #[serde(default)]
+    pub sku: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Unit of measure, e.g. "h" or "pcs"
+    #[serde(default)]
+    pub unit: String,
+    pub unit_price: Decimal,
+    /// Whether `unit_price` includes the product taxes, copied to its line items
+    #[serde(default)]
+    pub price_basis: PriceBasis,
+    #[serde(default)]
+    pub taxes: HashSet<TaxItem>,
+    #[serde(default)]
+    pub discounts: HashSet<DiscountItem>,
+    #[serde(default)]
+    pub charges: HashSet<ChargeItem>,
+    /// Defaults for line item fields by field id, e.g. an HSN or SAC code
+    #[serde(default)]
+    pub field_values: HashMap<String, String>,
+}
+
+impl PartialEq for ProductItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for ProductItem {}
+
+impl Hash for ProductItem {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl AutocompleteItem for ProductItem {
+    type Id = String;
+
+    fn id(&self) -> Self::Id {
+        self.id.clone()
+    }
+
+    fn search_text(&self) -> String {
+        format!("{} {} {}", self.sku, self.name, self.description)
+    }
+
+    fn display_text(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl ProductItem {
+    /// Line item field values with the product's defaults over the field defaults
+    pub fn line_field_values(&self, fields: &[FieldItem]) -> HashMap<String, FieldItemValue> {
+        let mut values = initialize_field_values(fields);
+        for field in fields {
+            let (Some(value), Some(entry)) =
+                (self.field_values.get(&field.id), values.get_mut(&field.id))
+            else {
+                continue;
+            };
+            entry.value = field_value_from_str(&field.field_type, value);
+        }
+        values
+    }
+
+    /// The line item form filled in from this product
+    pub fn line_item_form(&self, quantity: String, fields: &[FieldItem]) -> LineItemForm {
+        LineItemForm {
+            sku: self.sku.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            unit: self.unit.clone(),
+            quantity,
+            unit_price: self.unit_price.to_string(),
+            price_basis: self.price_basis,
+            taxes: self.taxes.clone(),
+            discounts: self.discounts.clone(),
+            charges: self.charges.clone(),
+            custom_fields: self.line_field_values(fields),
+            credited_line_id: None,
+        }
+    }
+}
+
+/// Searches the catalog by SKU, name and description
+#[derive(Clone, Copy)]
+pub struct ProductDataSource {
+    pub products: ReadSignal<Vec<ProductItem>>,
+}
+
+impl AutocompleteDataSource<ProductItem> for ProductDataSource {
+    fn search(&self, query: &str) -> Vec<AutocompleteGroup<ProductItem>> {
+        let groups = vec![AutocompleteGroup {
+            id: "products".to_string(),
+            name: "Products & Services".to_string(),
+            items: self.products.get_untracked(),
+        }];
+
+        StaticDataSource::new(groups, false).search(query)
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct ProductForm {
+    pub sku: String,
+    pub name: String,
+    pub description: String,
+    pub unit: String,
+    pub unit_price: String,
+    pub price_basis: PriceBasis,
+    pub taxes: HashSet<TaxItem>,
+    pub discounts: HashSet<DiscountItem>,
+    pub charges: HashSet<ChargeItem>,
+    pub field_values: HashMap<String, String>,
+}
+
+impl FormData for ProductItem {
+    type FormProps = ProductForm;
+
+    fn default() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn to_form_props(&self) -> Self::FormProps {
+        ProductForm {
+            sku: self.sku.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            unit: self.unit.clone(),
+            unit_price: self.unit_price.to_string(),
+            price_basis: self.price_basis,
+            taxes: self.taxes.clone(),
+            discounts: self.discounts.clone(),
+            charges: self.charges.clone(),
+            field_values: self.field_values.clone(),
+        }
+    }
+
+    fn from_form_props(props: &Self::FormProps) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            sku: props.sku.trim().to_string(),
+            name: props.name.clone(),
+            description: props.description.clone(),
+            unit: props.unit.trim().to_string(),
+            unit_price: parse_decimal(&props.unit_price).unwrap_or_default(),
+            price_basis: props.price_basis,
+            taxes: props.taxes.clone(),
+            discounts: props.discounts.clone(),
+            charges: props.charges.clone(),
+            field_values: props
+                .field_values
+                .iter()
+                .filter(|(_, value)| !value.is_empty())
+                .map(|(id, value)| (id.clone(), value.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl ItemData for ProductItem {
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_title(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_subtitle(&self) -> Option<String> {
+        Some(self.description.clone())
+    }
+
+    fn get_metadata(&self) -> Vec<(String, String)> {
+        vec![
+            ("sku".to_string(), self.sku.clone()),
+            ("unit".to_string(), self.unit.clone()),
+            ("unit_price".to_string(), self.unit_price.to_string()),
+        ]
+    }
+}
+
+impl FormValidation for ProductForm {
+    fn validate(&self) -> ValidationResult {
+        let mut results = vec![
+            validators::required(&self.name, "Product Name"),
+            validators::min_length(&self.name, 2, "Product Name"),
+            validators::required(&self.unit_price, "Unit Price"),
+        ];
+
+        match parse_decimal(&self.unit_price) {
+            None => results.push(ValidationResult::new().with_field_error(
+                "unit_price".to_string(),
+                "Unit Price must be a valid number".to_string(),
+            )),
+            Some(price) if price.is_sign_negative() => {
+                results.push(ValidationResult::new().with_field_error(
+                    "unit_price".to_string(),
+                    "Unit Price cannot be negative".to_string(),
+                ))
+            }
+            Some(_) => {}
+        }
+
+        validators::combine_results(results)
+    }
+}
+
+/// Adds `item` to `set` when `checked`, removes it otherwise. A copy with the same
+/// id made before the item was edited is replaced.
+fn toggle<T: AutocompleteItem>(set: &mut HashSet<T>, item: T, checked: bool) {
+    set.retain(|selected| selected.id() != item.id());
+    if checked {
+        set.insert(item);
+    }
+}
+
+/// Whether `set` holds a copy of `item`, edited in the catalog since or not
+fn has_item<T: AutocompleteItem>(set: &HashSet<T>, item: &T) -> bool {
+    set.iter().any(|selected| selected.id() == item.id())
+}
+
+/// Product and service catalog management
+#[component]
+pub fn Products(
+    state: RwSignal<Vec<ProductItem>>,
+    taxes: ReadSignal<Vec<TaxItem>>,
+    discounts: ReadSignal<Vec<DiscountItem>>,
+    charges: ReadSignal<Vec<ChargeItem>>,
+    custom_fields: ReadSignal<Vec<FieldItem>>,
+) -> impl IntoView {
+    let form_ref: NodeRef<leptos::html::Form> = NodeRef::new();
+    let grid = use_editable_grid(state.read_only(), state.write_only(), form_ref);
+    let form = RwSignal::new(ProductForm::default());
+
+    // Update the form when an item is picked for editing
+    Effect::new(move |_| form.set(grid.form_state.get().current_form));
+
+    let update = move |change: &dyn Fn(&mut ProductForm)| {
+        form.update(|form| change(form));
+        grid.actions.update_form.run(form.get_untracked());
+    };
+
+    let handle_submit = move |ev: web_sys::SubmitEvent| {
+        ev.prevent_default();
+        let form_data = form.get_untracked();
+        if form_data.validate().is_valid {
+            grid.actions.submit_form.run(form_data);
+            form.set(ProductForm::default());
+        }
+    };
+
+    let line_fields = Memo::new(move |_| {
+        custom_fields
+            .get()
+            .into_iter()
+            .filter(|field| field.category == FieldCategory::LineItem)
+            .collect::<Vec<_>>()
+    });
+
+    let text_input = move |id: &'static str,
+                           label: &'static str,
+                           get: fn(&ProductForm) -> String,
+                           set: fn(&mut ProductForm, String)| {
+        view! {
+            <div>
+                <label class="form-label" for=id>
+                    {label}
+                </label>
+                <input
+                    class="form-input"
+                    id=id
+                    type="text"
+                    prop:value=move || form.with(get)
+                    on:input=move |ev| {
+                        let value = event_target_value(&ev);
+                        update(&|form| set(form, value.clone()));
+                    }
+                />
+            </div>
+        }
+    };
+
+    view! {
+        <div class="w-full px-4 py-6">
+            <div class="bg-white p-6 rounded-lg shadow-sm border border-gray-200">
+                <h3 class="text-lg font-medium leading-6 text-gray-900 mb-6">
+                    {move || {
+                        if grid.form_state.get().is_editing { "Edit Product" } else { "Add New Product" }
+                    }}
+                </h3>
+
+                <form node_ref=form_ref on:submit=handle_submit class="space-y-6">
+                    <div class="grid grid-cols-1 md:grid-cols-5 gap-6">
+                        {text_input("product-sku", "SKU", |f| f.sku.clone(), |f, v| f.sku = v)}
+                        {text_input("product-name", "Name", |f| f.name.clone(), |f, v| f.name = v)}
+                        {text_input("product-unit", "Unit", |f| f.unit.clone(), |f, v| f.unit = v)}
+                        <div>
+                            <label class="form-label" for="product-price">
+                                "Unit Price"
+                            </label>
+                            <input
+                                class="form-input"
+                                id="product-price"
+                                type="number"
+                                step="any"
+                                min="0"
+                                prop:value=move || form.with(|f| f.unit_price.clone())
+                                on:input=move |ev| {
+                                    let value = event_target_value(&ev);
+                                    update(&|f| f.unit_price = value.clone());
+                                }
+                                required
+                            />
+                        </div>
+                        <div>
+                            <label class="form-label" for="product-price-basis">
+                                "Price"
+                            </label>
+                            <select
+                                class="form-select"
+                                id="product-price-basis"
+                                on:change=move |ev| {
+                                    if let Some(basis) = PriceBasis::from_label(&event_target_value(&ev)) {
+                                        update(&|f| f.price_basis = basis);
+                                    }
+                                }
+                            >
+                                {PriceBasis::ALL
+                                    .into_iter()
+                                    .map(|basis| {
+                                        view! {
+                                            <option
+                                                value=basis.label()
+                                                selected=move || form.with(|f| f.price_basis == basis)
+                                            >
+                                                {basis.label()}
+                                            </option>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </select>
+                        </div>
+                    </div>
+                    <div>
+                        <label class="form-label" for="product-description">
+                            "Description"
+                        </label>
+                        <textarea
+                            class="form-textarea w-full"
+                            id="product-description"
+                            rows="2"
+                            prop:value=move || form.with(|f| f.description.clone())
+                            on:input=move |ev| {
+                                let value = event_target_value(&ev);
+                                update(&|f| f.description = value.clone());
+                            }
+                        />
+                    </div>
+
+                    <div class="grid grid-cols-1 md:grid-cols-3 gap-6">
+                        <fieldset>
+                            <legend class="form-label">"Default taxes"</legend>
+                            <For
+                                each=move || taxes.get()
+                                key=|tax| tax.id.clone()
+                                children=move |tax| {
+                                    let checked = tax.clone();
+                                    let label = tax.display_text();
+                                    view! {
+                                        <label class="flex items-center gap-2 text-sm text-gray-700">
+                                            <input
+                                                type="checkbox"
+                                                prop:checked=move || form.with(|f| has_item(&f.taxes, &checked))
+                                                on:change=move |ev| {
+                                                    let on = event_target_checked(&ev);
+                                                    update(&|f| toggle(&mut f.taxes, tax.clone(), on));
+                                                }
+                                            />
+                                            {label}
+                                        </label>
+                                    }
+                                }
+                            />
+                        </fieldset>
+                        <fieldset>
+                            <legend class="form-label">"Default discounts"</legend>
+                            <For
+                                each=move || discounts.get()
+                                key=|discount| discount.id.clone()
+                                children=move |discount| {
+                                    let checked = discount.clone();
+                                    let label = discount.display_text();
+                                    view! {
+                                        <label class="flex items-center gap-2 text-sm text-gray-700">
+                                            <input
+                                                type="checkbox"
+                                                prop:checked=move || {
+                                                    form.with(|f| has_item(&f.discounts, &checked))
+                                                }
+                                                on:change=move |ev| {
+                                                    let on = event_target_checked(&ev);
+                                                    update(&|f| toggle(&mut f.discounts, discount.clone(), on));
+                                                }
+                                            />
+                                            {label}
+                                        </label>
+                                    }
+                                }
+                            />
+                        </fieldset>
+                        <fieldset>
+                            <legend class="form-label">"Default charges"</legend>
+                            <For
+                                each=move || charges.get()
+                                key=|charge| charge.id.clone()
+                                children=move |charge| {
+                                    let checked = charge.clone();
+                                    let label = charge.display_text();
+                                    view! {
+                                        <label class="flex items-center gap-2 text-sm text-gray-700">
+                                            <input
+                                                type="checkbox"
+                                                prop:checked=move || {
+                                                    form.with(|f| has_item(&f.charges, &checked))
+                                                }
+                                                on:change=move |ev| {
+                                                    let on = event_target_checked(&ev);
+                                                    update(&|f| toggle(&mut f.charges, charge.clone(), on));
+                                                }
+                                            />
+                                            {label}
+                                        </label>
+                                    }
+                                }
+                            />
+                        </fieldset>
+                    </div>
+
+                    <Show when=move || !line_fields.get().is_empty()>
+                        <div class="grid grid-cols-1 md:grid-cols-4 gap-6">
+                            <For
+                                each=move || line_fields.get()
+                                key=|field| field.id.clone()
+                                children=move |field| {
+                                    let input_id = format!("product-field-{}", field.id);
+                                    let id = field.id.clone();
+                                    let field_id = field.id.clone();
+                                    view! {
+                                        <div>
+                                            <label class="form-label" for=input_id.clone()>
+                                                {field.name.clone()}
+                                            </label>
+                                            <input
+                                                class="form-input"
+                                                id=input_id
+                                                type="text"
+                                                placeholder=field.default_value.clone()
+                                                prop:value=move || {
+                                                    form.with(|f| f.field_values.get(&id).cloned().unwrap_or_default())
+                                                }
+                                                on:input=move |ev| {
+                                                    let value = event_target_value(&ev);
+                                                    update(
+                                                        &|f| {
+                                                            f.field_values.insert(field_id.clone(), value.clone());
+                                                        },
+                                                    );
+                                                }
+                                            />
+                                        </div>
+                                    }
+                                }
+                            />
+                        </div>
+                    </Show>
+
+                    <div class="flex justify-end gap-3">
+                        <Show when=move || grid.form_state.get().is_editing>
+                            <button
+                                class="inline-flex justify-center rounded-md border border-gray-300 bg-white py-2 px-4 text-sm font-medium text-gray-700 shadow-sm hover:bg-gray-50"
+                                type="button"
+                                on:click=move |_| {
+                                    grid.actions.cancel_edit.run(());
+                                    form.set(ProductForm::default());
+                                }
+                            >
+                                "Cancel"
+                            </button>
+                        </Show>
+                        <button
+                            class="inline-flex justify-center rounded-md border border-transparent bg-indigo-600 py-2 px-4 text-sm font-medium text-white shadow-sm hover:bg-indigo-700"
+                            type="submit"
+                        >
+                            {move || {
+                                if grid.form_state.get().is_editing { "Update Product" } else { "Add Product" }
+                            }}
+                        </button>
+                    </div>
+                </form>
+            </div>
+
+            <div class="mt-8 bg-white rounded-lg shadow-sm border border-gray-200 overflow-x-auto">
+                <table class="min-w-full divide-y divide-gray-200">
+                    <thead class="bg-gray-50">
+                        <tr>
+                            <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">
+                                "SKU"
+                            </th>
+                            <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">
+                                "Name"
+                            </th>
+                            <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">
+                                "Unit"
+                            </th>
+                            <th class="px-6 py-3 text-right text-xs font-medium text-gray-500 uppercase tracking-wider">
+                                "Unit Price"
+                            </th>
+                            <th class="relative px-6 py-3">
+                                <span class="sr-only">"Actions"</span>
+                            </th>
+                        </tr>
+                    </thead>
+                    <tbody class="bg-white divide-y divide-gray-200">
+                        <For
+                            each=move || grid.items.get()
+                            key=|item_state| item_state.data.id.clone()
+                            children=move |item_state| {
+                                let index = item_state.index;
+                                let item = item_state.data.clone();
+                                view! {
+                                    <tr>
+                                        <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
+                                            {item.sku.clone()}
+                                        </td>
+                                        <td class="px-6 py-4 text-sm">
+                                            <div class="font-medium text-gray-900">{item.name.clone()}</div>
+                                            <div class="text-gray-500">{item.description.clone()}</div>
+                                        </td>
+                                        <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
+                                            {item.unit.clone()}
+                                        </td>
+                                        <td class="px-6 py-4 whitespace-nowrap text-right text-sm text-gray-900">
+                                            {format_amount(item.unit_price)}
+                                        </td>
+                                        <td class="px-6 py-4 whitespace-nowrap text-right text-sm font-medium">
+                                            <button
+                                                class="text-indigo-600 hover:text-indigo-900 mr-4"
+                                                on:click=move |_| grid.actions.edit_item.run(index)
+                                            >
+                                                "Edit"
+                                            </button>
+                                            <button
+                                                class="text-red-600 hover:text-red-900"
+                                                on:click=move |_| grid.actions.delete_item.run(index)
+                                            >
+                                                "Delete"
+                                            </button>
+                                        </td>
+                                    </tr>
+                                }
+                            }
+                        />
+                        <Show when=move || grid.is_empty.get()>
+                            <tr>
+                                <td class="px-6 py-12 text-center text-sm text-gray-500" colspan="5">
+                                    "No products yet. Add your first product using the form above."
+                                </td>
+                            </tr>
+                        </Show>
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
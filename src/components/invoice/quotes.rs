@@ -0,0 +1,370 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use crate::shared::storage::{Database, StorageError};
+
+/// Days a new quote stays valid after its issue date
+pub const QUOTE_VALIDITY_DAYS: u32 = 30;
+
+/// Kind of document the builder edits, all share the editing state and templates
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum DocumentType {
+    #[default]
+    Invoice,
+    Quote,
+    CreditNote,
+}
+
+impl DocumentType {
+    pub const ALL: [DocumentType; 3] = [
+        DocumentType::Invoice,
+        DocumentType::Quote,
+        DocumentType::CreditNote,
+    ];
+
+    /// Types a draft can switch between, credit notes are only made from an invoice
+    pub const SELECTABLE: [DocumentType; 2] = [DocumentType::Invoice, DocumentType::Quote];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DocumentType::Invoice => "Invoice",
+            DocumentType::Quote => "Quote",
+            DocumentType::CreditNote => "Credit note",
+        }
+    }
+
+    /// Heading of the document in templates
+    pub fn title(&self) -> &'static str {
+        match self {
+            DocumentType::Invoice => "Invoice",
+            DocumentType::Quote => "Quote",
+            DocumentType::CreditNote => "Credit Note",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.label() == label)
+    }
+
+    /// Value of the `type` query parameter and of `document_type` in template data
+    pub fn key(&self) -> &'static str {
+        match self {
+            DocumentType::Invoice => "invoice",
+            DocumentType::Quote => "quote",
+            DocumentType::CreditNote => "credit_note",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.key() == key)
+    }
+}
+
+/// Lifecycle of a quote. `Expired` is never stored, a sent quote expires once its
+/// validity date has passed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum QuoteStatus {
+    #[default]
+    Draft,
+    Sent,
+    Accepted,
+    Declined,
+    Expired,
+}
+
+impl QuoteStatus {
+    pub const ALL: [QuoteStatus; 5] = [
+        QuoteStatus::Draft,
+        QuoteStatus::Sent,
+        QuoteStatus::Accepted,
+        QuoteStatus::Declined,
+        QuoteStatus::Expired,
+    ];
+
+    /// Statuses a user can set by hand
+    pub const SELECTABLE: [QuoteStatus; 4] = [
+        QuoteStatus::Draft,
+        QuoteStatus::Sent,
+        QuoteStatus::Accepted,
+        QuoteStatus::Declined,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            QuoteStatus::Draft => "Draft",
+            QuoteStatus::Sent => "Sent",
+            QuoteStatus::Accepted => "Accepted",
+            QuoteStatus::Declined => "Declined",
+            QuoteStatus::Expired => "Expired",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.label() == label)
+    }
+
+    /// A quote leaving draft went out to the client and gets its number
+    pub fn is_final(&self) -> bool {
+        !matches!(self, QuoteStatus::Draft)
+    }
+
+    /// Tailwind classes for the status badge
+    pub fn badge_class(&self) -> &'static str {
+        match self {
+            QuoteStatus::Draft => "bg-gray-100 text-gray-800",
+            QuoteStatus::Sent => "bg-blue-100 text-blue-800",
+            QuoteStatus::Accepted => "bg-green-100 text-green-800",
+            QuoteStatus::Declined => "bg-red-100 text-red-800",
+            QuoteStatus::Expired => "bg-yellow-100 text-yellow-800",
+        }
+    }
+
+    /// Status to display on `today`, both dates are ISO `YYYY-MM-DD` strings
+    pub fn effective(&self, valid_until: &str, today: &str) -> Self {
+        match self {
+            QuoteStatus::Sent if !valid_until.is_empty() && valid_until < today => {
+                QuoteStatus::Expired
+            }
+            status => *status,
+        }
+    }
+}
+
+impl std::fmt::Display for QuoteStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+/// Counter numbering quotes, a yearly series kept apart from the invoice numbers.
+/// It is not part of the field definitions, quotes have no other counters.
+pub fn quote_number_field() -> FieldItem {
+    FieldItem {
+        id: QUOTE_NUMBER_FIELD_ID.to_string(),
+        name: "Quote Number".to_string(),
+        field_type: FieldType::AutoGenerated,
+        category: FieldCategory::Invoice,
+        auto_generation: Some(AutoGenerationRule::Counter {
+            prefix: Some("QUO-{YYYY}-".to_string()),
+            suffix: None,
+            start_value: 1,
+            increment: 1,
+            reset_period: Some(ResetPeriod::Yearly),
+            padding: Some(4),
+            fiscal_year_start: None,
+        }),
+        ..Default::default()
+    }
+}
+
+/// Draft invoice for an accepted quote. Line items, field values and tax settings
+/// are copied, the quote number and validity stay behind and the invoice keeps
+/// the id of the quote it came from.
+pub fn invoice_from_quote(
+    quote: &InvoiceDocument,
+    line_items: &[LineItem],
+) -> (InvoiceDocument, Vec<LineItem>) {
+    let mut invoice = quote.clone();
+    invoice.id = uuid::Uuid::new_v4().to_string();
+    invoice.document_type = DocumentType::Invoice;
+    invoice.status = InvoiceStatus::Draft;
+    invoice.quote_status = QuoteStatus::default();
+    invoice.valid_until.clear();
+    invoice.quote_id = Some(quote.id.clone());
+    invoice.converted_invoice_id = None;
+    invoice.payments.clear();
+    invoice.recurring_profile_id = None;
+    invoice.needs_review = false;
+    invoice.invoice_info.remove(QUOTE_NUMBER_FIELD_ID);
+    invoice.invoice_info.remove(INVOICE_NUMBER_FIELD_ID);
+
+    let line_items = line_items
+        .iter()
+        .map(|item| LineItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            ..item.clone()
+        })
+        .collect();
+
+    (invoice, line_items)
+}
+
+/// Stores a draft invoice for `quote` and returns the quote, accepted and pointing
+/// to the new invoice
+pub async fn convert_quote(
+    quote: InvoiceDocument,
+    line_items: Vec<LineItem>,
+) -> Result<InvoiceDocument, StorageError> {
+    let (invoice, invoice_items) = invoice_from_quote(&quote, &line_items);
+    let mut db = Database::open().await?;
+    db.save_line_items(&invoice.id, &invoice_items).await?;
+    db.save_invoice(&invoice).await?;
+
+    let quote = InvoiceDocument {
+        quote_status: QuoteStatus::Accepted,
+        converted_invoice_id: Some(invoice.id),
+        ..quote
+    };
+    db.save_invoice(&quote).await?;
+    Ok(quote)
+}
+
+/// Status, validity and conversion of a quote
+#[component]
+pub fn QuoteDetails(
+    #[prop(into)] number: Signal<String>,
+    quote_status: RwSignal<QuoteStatus>,
+    valid_until: RwSignal<String>,
+    converted_invoice_id: ReadSignal<Option<String>>,
+    on_status: Callback<QuoteStatus>,
+    on_convert: Callback<()>,
+) -> impl IntoView {
+    let expired = move || {
+        quote_status
+            .get()
+            .effective(&valid_until.get(), &today_iso())
+            == QuoteStatus::Expired
+    };
+
+    view! {
+        <p class="text-sm text-gray-700">
+            {move || match number.get() {
+                number if number.is_empty() => "Numbered once the quote is sent".to_string(),
+                number => format!("Quote {number}"),
+            }}
+        </p>
+        <div>
+            <label class="form-label" for="quote-status">
+                "Status"
+            </label>
+            <select
+                class="form-select"
+                id="quote-status"
+                name="quote-status"
+                on:change=move |ev| {
+                    if let Some(value) = QuoteStatus::from_label(&event_target_value(&ev)) {
+                        if quote_status.get_untracked() == QuoteStatus::Draft && value.is_final() {
+                            on_status.run(value);
+                        } else {
+                            quote_status.set(value);
+                        }
+                    }
+                }
+            >
+                {QuoteStatus::SELECTABLE
+                    .into_iter()
+                    .map(|value| {
+                        view! {
+                            <option value=value.label() selected=move || quote_status.get() == value>
+                                {value.label()}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
+            </select>
+            <Show when=expired>
+                <p class="mt-1 text-xs text-yellow-700">"Expired, the validity date has passed"</p>
+            </Show>
+        </div>
+        <div>
+            <label class="form-label" for="quote-valid-until">
+                "Valid until"
+            </label>
+            <input
+                class="form-input"
+                id="quote-valid-until"
+                type="date"
+                prop:value=valid_until
+                on:input=move |ev| valid_until.set(event_target_value(&ev))
+            />
+        </div>
+        {move || match converted_invoice_id.get() {
+            Some(invoice_id) => {
+                view! {
+                    <A
+                        href=format!("/invoices/{invoice_id}")
+                        attr:class="block text-sm text-indigo-600 hover:text-indigo-900"
+                    >
+                        "Open the invoice of this quote"
+                    </A>
+                }
+                    .into_any()
+            }
+            None => {
+                view! {
+                    <button
+                        type="button"
+                        class="w-full rounded-md bg-indigo-600 py-2 px-4 text-sm font-medium text-white hover:bg-indigo-700"
+                        on:click=move |_| on_convert.run(())
+                    >
+                        "Convert to invoice"
+                    </button>
+                }
+                    .into_any()
+            }
+        }}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::invoice::test_support::field_entry;
+    use std::collections::HashMap;
+
+    #[test]
+    fn sent_quotes_expire_after_their_validity() {
+        assert_eq!(
+            QuoteStatus::Sent.effective("2025-03-01", "2025-03-02"),
+            QuoteStatus::Expired
+        );
+        assert_eq!(
+            QuoteStatus::Sent.effective("2025-03-01", "2025-03-01"),
+            QuoteStatus::Sent
+        );
+        assert_eq!(
+            QuoteStatus::Accepted.effective("2025-03-01", "2025-04-01"),
+            QuoteStatus::Accepted
+        );
+        assert_eq!(
+            QuoteStatus::Sent.effective("", "2025-04-01"),
+            QuoteStatus::Sent
+        );
+    }
+
+    #[test]
+    fn converted_invoice_links_back_to_the_quote() {
+        let quote = InvoiceDocument {
+            id: "quote".to_string(),
+            document_type: DocumentType::Quote,
+            quote_status: QuoteStatus::Accepted,
+            valid_until: "2025-03-01".to_string(),
+            invoice_info: HashMap::from([field_entry(
+                QUOTE_NUMBER_FIELD_ID,
+                "Quote Number",
+                FieldValue::AutoGenerated("QUO-2025-0001".to_string()),
+            )]),
+            tax_exempt: true,
+            ..Default::default()
+        };
+        let items = vec![LineItem {
+            id: "item".to_string(),
+            name: "Design".to_string(),
+            ..Default::default()
+        }];
+
+        let (invoice, line_items) = invoice_from_quote(&quote, &items);
+
+        assert_ne!(invoice.id, quote.id);
+        assert_eq!(invoice.document_type, DocumentType::Invoice);
+        assert_eq!(invoice.quote_id.as_deref(), Some("quote"));
+        assert_eq!(invoice.invoice_value(QUOTE_NUMBER_FIELD_ID), "");
+        assert!(invoice.valid_until.is_empty());
+        assert!(invoice.tax_exempt);
+        assert_eq!(line_items.len(), 1);
+        assert_eq!(line_items[0].name, "Design");
+        assert_ne!(line_items[0].id, "item");
+    }
+}
@@ -0,0 +1,813 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use serde::{Deserialize, Serialize};
+use time::macros::format_description;
+use time::{Date, Duration, Month};
+
+use super::*;
+use crate::shared::storage::{Catalog, Database, StorageError};
+
+/// Runs one schedule may catch up on at once, guards against a start date far in
+/// the past flooding the invoice list
+const MAX_CATCH_UP_RUNS: usize = 24;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum RecurrenceFrequency {
+    Weekly,
+    #[default]
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl RecurrenceFrequency {
+    pub const ALL: [RecurrenceFrequency; 4] = [
+        RecurrenceFrequency::Weekly,
+        RecurrenceFrequency::Monthly,
+        RecurrenceFrequency::Quarterly,
+        RecurrenceFrequency::Yearly,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecurrenceFrequency::Weekly => "Weekly",
+            RecurrenceFrequency::Monthly => "Monthly",
+            RecurrenceFrequency::Quarterly => "Quarterly",
+            RecurrenceFrequency::Yearly => "Yearly",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|frequency| frequency.label() == label)
+    }
+
+    /// Length of one step in months, `None` for weekly schedules
+    fn months(&self) -> Option<u32> {
+        match self {
+            RecurrenceFrequency::Weekly => None,
+            RecurrenceFrequency::Monthly => Some(1),
+            RecurrenceFrequency::Quarterly => Some(3),
+            RecurrenceFrequency::Yearly => Some(12),
+        }
+    }
+}
+
+/// Schedule that issues a copy of an invoice at regular intervals
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RecurringProfile {
+    pub id: String,
+    /// Invoice every run is copied from
+    pub source_invoice_id: String,
+    #[serde(default)]
+    pub frequency: RecurrenceFrequency,
+    /// Number of weeks, months, quarters or years between two runs
+    #[serde(default = "default_interval")]
+    pub interval: u32,
+    /// Day of the month runs fall on, the last day in shorter months. The day of
+    /// `start_date` when not set, ignored by weekly schedules.
+    #[serde(default)]
+    pub day_of_month: Option<u8>,
+    /// ISO date, no run happens before it
+    pub start_date: String,
+    /// ISO date of the last possible run, empty for no end
+    #[serde(default)]
+    pub end_date: String,
+    /// Stops after this many invoices
+    #[serde(default)]
+    pub max_runs: Option<u32>,
+    /// Invoices generated so far
+    #[serde(default)]
+    pub runs: u32,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_interval() -> u32 {
+    1
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl RecurringProfile {
+    pub fn new(source_invoice_id: String, start_date: String) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            source_invoice_id,
+            frequency: RecurrenceFrequency::default(),
+            interval: default_interval(),
+            day_of_month: None,
+            start_date,
+            end_date: String::new(),
+            max_runs: None,
+            runs: 0,
+            active: true,
+        }
+    }
+
+    /// Date of the `n`th run counting from zero, ignoring the end of the schedule
+    pub fn occurrence(&self, n: u32) -> Option<Date> {
+        let start = parse_date(&self.start_date)?;
+        let interval = self.interval.max(1);
+
+        let Some(months) = self.frequency.months() else {
+            let weeks = interval.checked_mul(n)?;
+            return start.checked_add(Duration::weeks(i64::from(weeks)));
+        };
+        let day = self.day_of_month.unwrap_or(start.day());
+        // The first run is the first matching day on or after the start date
+        let first = match month_day(start.year(), start.month(), day)? {
+            date if date < start => 1,
+            _ => 0,
+        };
+        let offset = months
+            .checked_mul(interval)?
+            .checked_mul(n)?
+            .checked_add(first)?;
+        let (year, month) = add_months(start.year(), start.month(), offset)?;
+        month_day(year, month, day)
+    }
+
+    /// Date the next invoice is due, `None` once the schedule has ended
+    pub fn next_run(&self) -> Option<Date> {
+        if !self.active || self.max_runs.is_some_and(|max_runs| self.runs >= max_runs) {
+            return None;
+        }
+        let date = self.occurrence(self.runs)?;
+        match parse_date(&self.end_date) {
+            Some(end_date) if date > end_date => None,
+            _ => Some(date),
+        }
+    }
+
+    /// Runs that are due on `today` and have not been generated yet, oldest first
+    pub fn due_runs(&self, today: Date) -> Vec<Date> {
+        let mut pending = self.clone();
+        let mut dates = vec![];
+        while let Some(date) = pending.next_run().filter(|date| *date <= today) {
+            if dates.len() == MAX_CATCH_UP_RUNS {
+                break;
+            }
+            dates.push(date);
+            pending.runs += 1;
+        }
+        dates
+    }
+
+    /// Short description such as "Every 2 months on day 15"
+    pub fn summary(&self) -> String {
+        let unit = match self.frequency {
+            RecurrenceFrequency::Weekly => "week",
+            RecurrenceFrequency::Monthly => "month",
+            RecurrenceFrequency::Quarterly => "quarter",
+            RecurrenceFrequency::Yearly => "year",
+        };
+        let every = match self.interval.max(1) {
+            1 => format!("Every {unit}"),
+            interval => format!("Every {interval} {unit}s"),
+        };
+        match (self.frequency, self.day_of_month) {
+            (RecurrenceFrequency::Weekly, _) | (_, None) => every,
+            (_, Some(day)) => format!("{every} on day {day}"),
+        }
+    }
+}
+
+fn parse_date(text: &str) -> Option<Date> {
+    Date::parse(text.trim(), format_description!("[year]-[month]-[day]")).ok()
+}
+
+fn add_months(year: i32, month: Month, months: u32) -> Option<(i32, Month)> {
+    let index =
+        (year * 12 + i32::from(month as u8 - 1)).checked_add(i32::try_from(months).ok()?)?;
+    let month = Month::try_from((index.rem_euclid(12) + 1) as u8).unwrap_or(Month::January);
+    Some((index.div_euclid(12), month))
+}
+
+/// `day` of the month, clamped to its last day
+fn month_day(year: i32, month: Month, day: u8) -> Option<Date> {
+    Date::from_calendar_date(year, month, day.clamp(1, month.length(year))).ok()
+}
+
+/// Copy of `source` issued on `issue_date` by the schedule `profile_id`. It is a
+/// draft waiting for review, without payments and without the generated values of
+/// the source, so it gets a number of its own. Date fields with a default are
+/// resolved as if today were the issue date, the due date keeps the payment
+/// terms of the source and a quote stays valid for as long as the source.
+pub fn recurring_invoice(
+    source: &InvoiceDocument,
+    line_items: &[LineItem],
+    fields: &[FieldItem],
+    profile_id: &str,
+    issue_date: Date,
+) -> (InvoiceDocument, Vec<LineItem>) {
+    let mut document = source.clone();
+    document.id = uuid::Uuid::new_v4().to_string();
+    document.status = InvoiceStatus::Draft;
+    document.payments.clear();
+    document.credits.clear();
+    document.recurring_profile_id = Some(profile_id.to_string());
+    document.needs_review = true;
+    document.quote_status = QuoteStatus::default();
+    document.converted_invoice_id = None;
+    document.invoice_info.remove(QUOTE_NUMBER_FIELD_ID);
+
+    // Days from the issue date of the source to one of its dates
+    let source_issue_date = parse_date(&source.invoice_value(ISSUE_DATE_FIELD_ID));
+    let offset = |date: &str| {
+        parse_date(date)
+            .zip(source_issue_date)
+            .map(|(date, source_issue_date)| date - source_issue_date)
+    };
+    let payment_terms = offset(&source.invoice_value(DUE_DATE_FIELD_ID));
+    document.valid_until = offset(&source.valid_until)
+        .and_then(|validity| issue_date.checked_add(validity))
+        .map(|valid_until| valid_until.to_string())
+        .unwrap_or_default();
+
+    for field in fields {
+        let Some(values) = document.values_mut(&field.category) else {
+            continue;
+        };
+        let date = match (&field.field_type, &field.date_default) {
+            (FieldType::AutoGenerated, _) => {
+                values.remove(&field.id);
+                continue;
+            }
+            _ if field.id == ISSUE_DATE_FIELD_ID => issue_date.to_string(),
+            (FieldType::Date, Some(date_default)) => match date_default.resolve_on(issue_date) {
+                Ok(date) => date,
+                Err(_) => continue,
+            },
+            _ if field.id == DUE_DATE_FIELD_ID => {
+                match payment_terms.and_then(|terms| issue_date.checked_add(terms)) {
+                    Some(due_date) => due_date.to_string(),
+                    None => {
+                        values.remove(&field.id);
+                        continue;
+                    }
+                }
+            }
+            _ => continue,
+        };
+        values.insert(
+            field.id.clone(),
+            FieldItemValue {
+                id: field.id.clone(),
+                label: field.name.clone(),
+                value: FieldValue::Date(date),
+            },
+        );
+    }
+
+    let line_items = line_items
+        .iter()
+        .map(|item| LineItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            ..item.clone()
+        })
+        .collect();
+
+    (document, line_items)
+}
+
+/// Issues every invoice that fell due since the app was last opened and returns
+/// how many were created. Each one is numbered right away and the schedule is
+/// stored after every invoice, so an interrupted run never repeats one.
+pub async fn generate_due_invoices() -> Result<usize, StorageError> {
+    let mut db = Database::open().await?;
+    let Some(today) = parse_date(&today_iso()) else {
+        return Ok(0);
+    };
+    let fields: Vec<FieldItem> = db.load_catalog(Catalog::Fields).await?;
+    let mut generated = 0;
+
+    for mut profile in db.list_recurring_profiles().await? {
+        let due = profile.due_runs(today);
+        if due.is_empty() {
+            continue;
+        }
+        let Some(source) = db.load_invoice(&profile.source_invoice_id).await? else {
+            log::warn!(
+                "Invoice repeated by schedule {} no longer exists",
+                profile.id
+            );
+            profile.active = false;
+            db.save_recurring_profile(&profile).await?;
+            continue;
+        };
+        let source_items = db.load_line_items(&source.id).await?;
+
+        for date in due {
+            let (mut document, line_items) =
+                recurring_invoice(&source, &source_items, &fields, &profile.id, date);
+            for (category, value) in take_counter_numbers(&mut db, &fields, &document).await? {
+                if let Some(values) = document.values_mut(&category) {
+                    values.insert(value.id.clone(), value);
+                }
+            }
+            db.save_line_items(&document.id, &line_items).await?;
+            db.save_invoice(&document).await?;
+
+            profile.runs += 1;
+            db.save_recurring_profile(&profile).await?;
+            generated += 1;
+        }
+    }
+
+    Ok(generated)
+}
+
+/// Number of invoices the schedules generated when the app was opened
+#[derive(Clone, Copy)]
+pub struct RecurringRuns(pub RwSignal<usize>);
+
+/// Generates the invoices of due schedules once at startup
+pub fn provide_recurring_runs() {
+    let generated = RwSignal::new(0);
+    provide_context(RecurringRuns(generated));
+
+    spawn_local(async move {
+        match generate_due_invoices().await {
+            Ok(0) => {}
+            Ok(count) => {
+                log::info!("Recurring schedules generated {count} invoices");
+                generated.set(count);
+            }
+            Err(e) => log::error!("Failed to generate recurring invoices: {e}"),
+        }
+    });
+}
+
+async fn load_profile(invoice_id: String) -> Result<Option<RecurringProfile>, StorageError> {
+    Database::open()
+        .await?
+        .load_recurring_profile_for(&invoice_id)
+        .await
+}
+
+async fn save_profile(profile: RecurringProfile) -> Result<(), StorageError> {
+    Database::open()
+        .await?
+        .save_recurring_profile(&profile)
+        .await
+}
+
+async fn delete_profile(profile_id: String) -> Result<(), StorageError> {
+    Database::open()
+        .await?
+        .delete_recurring_profile(&profile_id)
+        .await
+}
+
+/// Sets up a schedule that repeats the open invoice
+#[component]
+pub fn RecurringCard(#[prop(into)] invoice_id: Signal<String>) -> impl IntoView {
+    let profile = RwSignal::new(None::<RecurringProfile>);
+    let frequency = RwSignal::new(RecurrenceFrequency::default());
+    let interval = RwSignal::new("1".to_string());
+    let day_of_month = RwSignal::new(String::new());
+    let start_date = RwSignal::new(today_iso());
+    let end_date = RwSignal::new(String::new());
+    let max_runs = RwSignal::new(String::new());
+    let error = RwSignal::new(None::<String>);
+
+    let show_profile = move |loaded: Option<RecurringProfile>| {
+        let defaults = RecurringProfile::new(String::new(), today_iso());
+        let shown = loaded.as_ref().unwrap_or(&defaults);
+        frequency.set(shown.frequency);
+        interval.set(shown.interval.to_string());
+        day_of_month.set(
+            shown
+                .day_of_month
+                .map(|day| day.to_string())
+                .unwrap_or_default(),
+        );
+        start_date.set(shown.start_date.clone());
+        end_date.set(shown.end_date.clone());
+        max_runs.set(
+            shown
+                .max_runs
+                .map(|runs| runs.to_string())
+                .unwrap_or_default(),
+        );
+        profile.set(loaded);
+        error.set(None);
+    };
+
+    Effect::new(move |_| {
+        let invoice_id = invoice_id.get();
+        spawn_local(async move {
+            match load_profile(invoice_id).await {
+                Ok(loaded) => show_profile(loaded),
+                Err(e) => log::error!("Failed to load recurring schedule: {e}"),
+            }
+        });
+    });
+
+    let save = move |_| {
+        let Some(interval_value) = interval
+            .get_untracked()
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|n| *n > 0)
+        else {
+            error.set(Some("Repeat every 1 or more periods".to_string()));
+            return;
+        };
+        let day = match day_of_month.get_untracked().trim() {
+            "" => None,
+            text => match text.parse::<u8>() {
+                Ok(day @ 1..=31) => Some(day),
+                _ => {
+                    error.set(Some("Day of month must be between 1 and 31".to_string()));
+                    return;
+                }
+            },
+        };
+        let runs_limit = match max_runs.get_untracked().trim() {
+            "" => None,
+            text => match text.parse::<u32>() {
+                Ok(runs) if runs > 0 => Some(runs),
+                _ => {
+                    error.set(Some(
+                        "Number of invoices must be a whole number".to_string(),
+                    ));
+                    return;
+                }
+            },
+        };
+        let start = start_date.get_untracked();
+        if parse_date(&start).is_none() {
+            error.set(Some("Enter the date of the first invoice".to_string()));
+            return;
+        }
+        let end = end_date.get_untracked();
+        if !end.is_empty() && end < start {
+            error.set(Some("The end date is before the first invoice".to_string()));
+            return;
+        }
+
+        let mut updated = profile
+            .get_untracked()
+            .unwrap_or_else(|| RecurringProfile::new(invoice_id.get_untracked(), start.clone()));
+        // Changing when the schedule starts begins counting again
+        if updated.start_date != start || updated.frequency != frequency.get_untracked() {
+            updated.runs = 0;
+        }
+        updated.frequency = frequency.get_untracked();
+        updated.interval = interval_value;
+        updated.day_of_month = day;
+        updated.start_date = start;
+        updated.end_date = end;
+        updated.max_runs = runs_limit;
+        updated.active = true;
+
+        spawn_local(async move {
+            match save_profile(updated.clone()).await {
+                Ok(()) => show_profile(Some(updated)),
+                Err(e) => error.set(Some(format!("Failed to save the schedule: {e}"))),
+            }
+        });
+    };
+
+    let stop = move |_| {
+        let Some(current) = profile.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            match delete_profile(current.id).await {
+                Ok(()) => show_profile(None),
+                Err(e) => error.set(Some(format!("Failed to remove the schedule: {e}"))),
+            }
+        });
+    };
+
+    let status = move || {
+        profile.with(|profile| match profile {
+            None => "Not repeated".to_string(),
+            Some(profile) => {
+                let next = profile
+                    .next_run()
+                    .map(|date| format!("next on {date}"))
+                    .unwrap_or_else(|| "finished".to_string());
+                format!("{}, {next} ({} issued)", profile.summary(), profile.runs)
+            }
+        })
+    };
+
+    view! {
+        <div class="bg-white shadow rounded-lg p-4">
+            <h2 class="text-lg font-semibold">"Repeat"</h2>
+            <p class="text-sm text-gray-500 mb-4">{status}</p>
+
+            <div class="grid grid-cols-2 gap-2">
+                <div>
+                    <label class="form-label" for="recurring-frequency">
+                        "Frequency"
+                    </label>
+                    <select
+                        class="form-select"
+                        id="recurring-frequency"
+                        on:change=move |ev| {
+                            if let Some(value) = RecurrenceFrequency::from_label(
+                                &event_target_value(&ev),
+                            ) {
+                                frequency.set(value);
+                            }
+                        }
+                    >
+                        {RecurrenceFrequency::ALL
+                            .into_iter()
+                            .map(|value| {
+                                view! {
+                                    <option
+                                        value=value.label()
+                                        selected=move || frequency.get() == value
+                                    >
+                                        {value.label()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                </div>
+                <div>
+                    <label class="form-label" for="recurring-interval">
+                        "Every"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="recurring-interval"
+                        type="number"
+                        min="1"
+                        prop:value=interval
+                        on:input=move |ev| interval.set(event_target_value(&ev))
+                    />
+                </div>
+                <div>
+                    <label class="form-label" for="recurring-start">
+                        "First invoice"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="recurring-start"
+                        type="date"
+                        prop:value=start_date
+                        on:input=move |ev| start_date.set(event_target_value(&ev))
+                    />
+                </div>
+                <div>
+                    <label class="form-label" for="recurring-day">
+                        "Day of month"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="recurring-day"
+                        type="number"
+                        min="1"
+                        max="31"
+                        placeholder="Same as first"
+                        disabled=move || frequency.get() == RecurrenceFrequency::Weekly
+                        prop:value=day_of_month
+                        on:input=move |ev| day_of_month.set(event_target_value(&ev))
+                    />
+                </div>
+                <div>
+                    <label class="form-label" for="recurring-end">
+                        "Ends on"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="recurring-end"
+                        type="date"
+                        prop:value=end_date
+                        on:input=move |ev| end_date.set(event_target_value(&ev))
+                    />
+                </div>
+                <div>
+                    <label class="form-label" for="recurring-count">
+                        "Ends after"
+                    </label>
+                    <input
+                        class="form-input"
+                        id="recurring-count"
+                        type="number"
+                        min="1"
+                        placeholder="Invoices"
+                        prop:value=max_runs
+                        on:input=move |ev| max_runs.set(event_target_value(&ev))
+                    />
+                </div>
+                {move || {
+                    error.get().map(|error| view! { <p class="col-span-2 text-sm text-red-600">{error}</p> })
+                }}
+                <button
+                    type="button"
+                    class="rounded-md bg-indigo-600 py-2 px-4 text-sm font-medium text-white hover:bg-indigo-700"
+                    on:click=save
+                >
+                    {move || if profile.with(Option::is_some) { "Update schedule" } else { "Repeat invoice" }}
+                </button>
+                <Show when=move || profile.with(Option::is_some)>
+                    <button
+                        type="button"
+                        class="rounded-md border border-gray-300 py-2 px-4 text-sm font-medium text-gray-700 hover:bg-gray-50"
+                        on:click=stop
+                    >
+                        "Stop repeating"
+                    </button>
+                </Show>
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::invoice::test_support::field_entry;
+    use std::collections::HashMap;
+    use time::macros::date;
+
+    fn monthly(start_date: &str, day_of_month: Option<u8>) -> RecurringProfile {
+        RecurringProfile {
+            day_of_month,
+            ..RecurringProfile::new("source".to_string(), start_date.to_string())
+        }
+    }
+
+    #[test]
+    fn monthly_runs_clamp_to_short_months() {
+        let profile = monthly("2025-01-31", None);
+
+        assert_eq!(profile.occurrence(0), Some(date!(2025 - 01 - 31)));
+        assert_eq!(profile.occurrence(1), Some(date!(2025 - 02 - 28)));
+        assert_eq!(profile.occurrence(2), Some(date!(2025 - 03 - 31)));
+        assert_eq!(profile.occurrence(13), Some(date!(2026 - 02 - 28)));
+    }
+
+    #[test]
+    fn day_of_month_before_the_start_moves_to_next_month() {
+        let profile = monthly("2025-01-20", Some(5));
+
+        assert_eq!(profile.occurrence(0), Some(date!(2025 - 02 - 05)));
+        assert_eq!(profile.occurrence(1), Some(date!(2025 - 03 - 05)));
+
+        let quarterly = RecurringProfile {
+            frequency: RecurrenceFrequency::Quarterly,
+            ..monthly("2025-01-01", Some(15))
+        };
+        assert_eq!(quarterly.occurrence(3), Some(date!(2025 - 10 - 15)));
+    }
+
+    #[test]
+    fn weekly_and_yearly_steps() {
+        let weekly = RecurringProfile {
+            frequency: RecurrenceFrequency::Weekly,
+            interval: 2,
+            ..monthly("2025-12-25", Some(1))
+        };
+        assert_eq!(weekly.occurrence(1), Some(date!(2026 - 01 - 08)));
+
+        let yearly = RecurringProfile {
+            frequency: RecurrenceFrequency::Yearly,
+            ..monthly("2024-02-29", None)
+        };
+        assert_eq!(yearly.occurrence(1), Some(date!(2025 - 02 - 28)));
+        assert_eq!(yearly.occurrence(4), Some(date!(2028 - 02 - 29)));
+    }
+
+    #[test]
+    fn runs_past_the_calendar_have_no_date() {
+        let yearly = RecurringProfile {
+            frequency: RecurrenceFrequency::Yearly,
+            interval: u32::MAX,
+            ..monthly("2025-01-10", None)
+        };
+        assert_eq!(yearly.occurrence(2), None);
+
+        let weekly = RecurringProfile {
+            frequency: RecurrenceFrequency::Weekly,
+            interval: u32::MAX,
+            ..monthly("2025-01-10", None)
+        };
+        assert_eq!(weekly.occurrence(u32::MAX), None);
+        assert_eq!(weekly.next_run(), Some(date!(2025 - 01 - 10)));
+    }
+
+    #[test]
+    fn schedules_end_by_date_or_count() {
+        let mut profile = RecurringProfile {
+            end_date: "2025-03-15".to_string(),
+            ..monthly("2025-01-10", None)
+        };
+        assert_eq!(
+            profile.due_runs(date!(2025 - 12 - 31)),
+            vec![
+                date!(2025 - 01 - 10),
+                date!(2025 - 02 - 10),
+                date!(2025 - 03 - 10)
+            ]
+        );
+
+        profile.end_date.clear();
+        profile.max_runs = Some(2);
+        profile.runs = 1;
+        assert_eq!(
+            profile.due_runs(date!(2025 - 12 - 31)),
+            vec![date!(2025 - 02 - 10)]
+        );
+        profile.runs = 2;
+        assert_eq!(profile.next_run(), None);
+    }
+
+    #[test]
+    fn generated_invoice_rolls_dates_and_drops_the_number() {
+        let date_field = |id: &str, date_default: Option<DateDefault>| FieldItem {
+            id: id.to_string(),
+            field_type: FieldType::Date,
+            date_default,
+            ..Default::default()
+        };
+        let fields = vec![
+            FieldItem {
+                id: INVOICE_NUMBER_FIELD_ID.to_string(),
+                field_type: FieldType::AutoGenerated,
+                ..Default::default()
+            },
+            date_field(ISSUE_DATE_FIELD_ID, None),
+            date_field(DUE_DATE_FIELD_ID, None),
+            date_field("delivery", Some(DateDefault::EndOfMonth)),
+        ];
+        let source = InvoiceDocument {
+            id: "source".to_string(),
+            invoice_info: HashMap::from([
+                field_entry(
+                    INVOICE_NUMBER_FIELD_ID,
+                    "Invoice Number",
+                    FieldValue::AutoGenerated("INV-1".to_string()),
+                ),
+                field_entry(
+                    ISSUE_DATE_FIELD_ID,
+                    "Issue Date",
+                    FieldValue::Date("2025-01-10".to_string()),
+                ),
+                field_entry(
+                    DUE_DATE_FIELD_ID,
+                    "Due Date",
+                    FieldValue::Date("2025-01-24".to_string()),
+                ),
+            ]),
+            status: InvoiceStatus::Paid,
+            ..Default::default()
+        };
+
+        let (document, _) =
+            recurring_invoice(&source, &[], &fields, "profile", date!(2025 - 02 - 10));
+
+        assert_ne!(document.id, source.id);
+        assert_eq!(document.status, InvoiceStatus::Draft);
+        assert!(document.needs_review);
+        assert_eq!(document.recurring_profile_id.as_deref(), Some("profile"));
+        assert_eq!(document.invoice_value(INVOICE_NUMBER_FIELD_ID), "");
+        assert_eq!(document.invoice_value(ISSUE_DATE_FIELD_ID), "2025-02-10");
+        assert_eq!(document.invoice_value(DUE_DATE_FIELD_ID), "2025-02-24");
+        assert_eq!(document.invoice_value("delivery"), "2025-02-28");
+    }
+
+    #[test]
+    fn dates_past_the_calendar_are_left_unset() {
+        let fields = vec![FieldItem {
+            id: DUE_DATE_FIELD_ID.to_string(),
+            field_type: FieldType::Date,
+            ..Default::default()
+        }];
+        let source = InvoiceDocument {
+            invoice_info: HashMap::from([
+                field_entry(
+                    ISSUE_DATE_FIELD_ID,
+                    "Issue Date",
+                    FieldValue::Date("2025-01-10".to_string()),
+                ),
+                field_entry(
+                    DUE_DATE_FIELD_ID,
+                    "Due Date",
+                    FieldValue::Date("9999-12-31".to_string()),
+                ),
+            ]),
+            valid_until: "9999-12-31".to_string(),
+            ..Default::default()
+        };
+
+        let (document, _) =
+            recurring_invoice(&source, &[], &fields, "profile", date!(2025 - 02 - 10));
+
+        assert_eq!(document.invoice_value(DUE_DATE_FIELD_ID), "");
+        assert_eq!(document.valid_until, "");
+    }
+}
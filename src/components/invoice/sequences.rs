@@ -0,0 +1,288 @@
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use time::{Date, Month, OffsetDateTime};
+
+use super::*;
+use crate::shared::storage::{Database, StorageError};
+
+/// Month a fiscal year starts in, `None` and out of range values mean January
+pub fn fiscal_year_start_month(month: Option<u8>) -> Month {
+    month
+        .and_then(|month| Month::try_from(month).ok())
+        .unwrap_or(Month::January)
+}
+
+/// Calendar year the fiscal year containing `date` started in
+pub fn fiscal_year(date: Date, start: Month) -> i32 {
+    if (date.month() as u8) < (start as u8) {
+        date.year() - 1
+    } else {
+        date.year()
+    }
+}
+
+/// Identifies the period `date` falls in, a counter starts over when it changes
+pub fn period_key(reset_period: Option<&ResetPeriod>, date: Date, fiscal_start: Month) -> String {
+    match reset_period {
+        Some(ResetPeriod::Daily) => date.to_string(),
+        Some(ResetPeriod::Monthly) => format!("{}-{:02}", date.year(), date.month() as u8),
+        Some(ResetPeriod::Yearly) => fiscal_year(date, fiscal_start).to_string(),
+        Some(ResetPeriod::Never) | None => String::new(),
+    }
+}
+
+/// Replaces `{YYYY}`, `{YY}`, `{MM}`, `{DD}` and `{FY}` (the year the fiscal year
+/// started in) with the parts of `date`
+pub fn expand_date_tokens(text: &str, date: Date, fiscal_start: Month) -> String {
+    text.replace("{YYYY}", &format!("{:04}", date.year()))
+        .replace("{YY}", &format!("{:02}", date.year().rem_euclid(100)))
+        .replace("{MM}", &format!("{:02}", date.month() as u8))
+        .replace("{DD}", &format!("{:02}", date.day()))
+        .replace("{FY}", &fiscal_year(date, fiscal_start).to_string())
+}
+
+/// Counters are kept apart per field and numbering series
+pub fn sequence_key(field_id: &str, series: &str) -> String {
+    match series.trim() {
+        "" => field_id.to_string(),
+        series => format!("{field_id}/{series}"),
+    }
+}
+
+/// Row id of the counter of the sequence `key` in `period`. Every period keeps
+/// its own position, so an invoice dated back into an earlier period continues
+/// that period's numbers instead of starting it over.
+pub fn counter_id(key: &str, period: &str) -> String {
+    match period {
+        "" => key.to_string(),
+        period => format!("{key}@{period}"),
+    }
+}
+
+/// Stored position of one sequence in one period
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct CounterState {
+    pub key: String,
+    /// Period the values are handed out in, see `period_key`
+    pub period: String,
+    pub next_value: i64,
+}
+
+impl CounterState {
+    pub fn new(key: String, period: String, start_value: i64) -> Self {
+        Self {
+            key,
+            period,
+            next_value: start_value,
+        }
+    }
+
+    pub fn id(&self) -> String {
+        counter_id(&self.key, &self.period)
+    }
+
+    /// Hands out the next value
+    pub fn take(&mut self, increment: i64) -> i64 {
+        let value = self.next_value;
+        self.next_value += increment;
+        value
+    }
+}
+
+/// Issue date of the invoice, today when none is set
+fn numbering_date(invoice_info: &HashMap<String, FieldItemValue>) -> Date {
+    let format = time::macros::format_description!("[year]-[month]-[day]");
+    invoice_info
+        .get(ISSUE_DATE_FIELD_ID)
+        .and_then(|field| Date::parse(&field.value.to_string(), format).ok())
+        .unwrap_or_else(|| OffsetDateTime::now_utc().date())
+}
+
+/// Gives every empty counter field of the invoice its number. Called when an
+/// invoice, quote or credit note is finalized, drafts never consume a number,
+/// and values that are already set are kept so re-sending an invoice does not
+/// renumber it.
+pub async fn assign_counter_numbers(state: InvoiceBuilderState) -> Result<(), StorageError> {
+    let fields = state.custom_fields.get_untracked();
+    let document = untrack(|| state.document());
+    let mut db = Database::open().await?;
+
+    for (category, value) in take_counter_numbers(&mut db, &fields, &document).await? {
+        let values = match category {
+            FieldCategory::Invoice => state.invoice_info,
+            FieldCategory::Biller => state.biller_info,
+            FieldCategory::Client => state.client_info,
+            FieldCategory::ExtraInfo => state.extra_info,
+            FieldCategory::LineItem => continue,
+        };
+        values.update(|values| {
+            values.insert(value.id.clone(), value);
+        });
+    }
+
+    Ok(())
+}
+
+/// Context a counter field is generated in, with `value` as its counter
+fn counter_context(field_id: &str, value: i64, date: Date) -> GenerationContext {
+    GenerationContext {
+        current_values: HashMap::new(),
+        counter_states: HashMap::from([(field_id.to_string(), value)]),
+        current_date: date.midnight().assume_utc(),
+    }
+}
+
+/// Reserves a number for every counter field `document` has no value for and
+/// returns the new values with the category of their field
+pub async fn take_counter_numbers(
+    db: &mut Database,
+    fields: &[FieldItem],
+    document: &InvoiceDocument,
+) -> Result<Vec<(FieldCategory, FieldItemValue)>, StorageError> {
+    let date = numbering_date(&document.invoice_info);
+    let mut pending = vec![];
+    let mut numbers = vec![];
+    // The invoice counters are left for the invoice a quote turns into, and a
+    // credit note is numbered apart from the invoices it corrects
+    let quote_number = quote_number_field();
+    let credit_note_number = credit_note_number_field();
+    let counters: Vec<&FieldItem> = match document.document_type {
+        DocumentType::Invoice => fields
+            .iter()
+            .filter(|field| field.field_type == FieldType::AutoGenerated)
+            .collect(),
+        DocumentType::Quote => vec![&quote_number],
+        DocumentType::CreditNote => vec![&credit_note_number],
+    };
+
+    for field in counters {
+        let Some(
+            rule @ AutoGenerationRule::Counter {
+                start_value,
+                increment,
+                reset_period,
+                fiscal_year_start,
+                ..
+            },
+        ) = &field.auto_generation
+        else {
+            continue;
+        };
+        let Some(values) = document.values(&field.category) else {
+            continue;
+        };
+        if values
+            .get(&field.id)
+            .is_some_and(|value| !value.value.to_string().is_empty())
+        {
+            continue;
+        }
+
+        let fiscal_start = fiscal_year_start_month(*fiscal_year_start);
+        let period = period_key(reset_period.as_ref(), date, fiscal_start);
+        // A format that cannot be generated fails before any number is used up
+        rule.generate(&counter_context(&field.id, *start_value, date), &field.id)
+            .map_err(StorageError::UnexpectedResult)?;
+        pending.push((field, rule, period, *start_value, *increment));
+    }
+
+    for (field, rule, period, start_value, increment) in pending {
+        let value = db
+            .take_counter_value(
+                &sequence_key(&field.id, &document.number_series),
+                &period,
+                start_value,
+                increment,
+            )
+            .await?;
+        let number = rule
+            .generate(&counter_context(&field.id, value, date), &field.id)
+            .map_err(StorageError::UnexpectedResult)?;
+        numbers.push((
+            field.category.clone(),
+            FieldItemValue {
+                id: field.id.clone(),
+                label: field.name.clone(),
+                value: FieldValue::AutoGenerated(number),
+            },
+        ));
+    }
+
+    Ok(numbers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    #[test]
+    fn yearly_period_follows_fiscal_year_start() {
+        let april = Month::April;
+        let yearly = Some(&ResetPeriod::Yearly);
+
+        assert_eq!(period_key(yearly, date!(2024 - 03 - 31), april), "2023");
+        assert_eq!(period_key(yearly, date!(2024 - 04 - 01), april), "2024");
+        assert_eq!(
+            period_key(yearly, date!(2024 - 03 - 31), Month::January),
+            "2024"
+        );
+    }
+
+    #[test]
+    fn daily_and_monthly_periods() {
+        let day = date!(2024 - 08 - 05);
+
+        assert_eq!(
+            period_key(Some(&ResetPeriod::Daily), day, Month::January),
+            "2024-08-05"
+        );
+        assert_eq!(
+            period_key(Some(&ResetPeriod::Monthly), day, Month::January),
+            "2024-08"
+        );
+        assert_eq!(period_key(None, day, Month::January), "");
+    }
+
+    #[test]
+    fn counter_steps_by_its_increment() {
+        let mut state = CounterState::new("invoice_number".to_string(), "2024".to_string(), 1);
+
+        assert_eq!(state.take(1), 1);
+        assert_eq!(state.take(10), 2);
+        assert_eq!(state.take(10), 12);
+    }
+
+    #[test]
+    fn periods_keep_separate_counters() {
+        let state = CounterState::new("invoice_number".to_string(), "2024".to_string(), 1);
+
+        assert_eq!(state.id(), "invoice_number@2024");
+        assert_eq!(
+            counter_id("invoice_number", "2024-08"),
+            "invoice_number@2024-08"
+        );
+        assert_eq!(counter_id("invoice_number/EU", ""), "invoice_number/EU");
+    }
+
+    #[test]
+    fn date_tokens_expand() {
+        let day = date!(2025 - 02 - 07);
+
+        assert_eq!(
+            expand_date_tokens("INV-{YYYY}-{MM}-", day, Month::January),
+            "INV-2025-02-"
+        );
+        assert_eq!(
+            expand_date_tokens("{YY}{DD}/FY{FY}", day, Month::April),
+            "2507/FY2024"
+        );
+    }
+
+    #[test]
+    fn series_keep_separate_counters() {
+        assert_eq!(sequence_key("invoice_number", ""), "invoice_number");
+        assert_eq!(sequence_key("invoice_number", " EU "), "invoice_number/EU");
+    }
+}
@@ -1,19 +1,276 @@
 use super::*;
+use crate::shared::templates::DEFAULT_TEMPLATE_ID;
 use leptos::prelude::*;
+use rust_decimal::dec;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Ids of the built-in fields the invoice list and numbering rely on
+pub const INVOICE_NUMBER_FIELD_ID: &str = "invoice_number";
+pub const ISSUE_DATE_FIELD_ID: &str = "issue_date";
+pub const DUE_DATE_FIELD_ID: &str = "due_date";
+pub const CLIENT_NAME_FIELD_ID: &str = "client_name";
+/// Number of a quote, kept with the invoice-level values but not a field definition
+pub const QUOTE_NUMBER_FIELD_ID: &str = "quote_number";
+/// Number of a credit note, kept like the quote number
+pub const CREDIT_NOTE_NUMBER_FIELD_ID: &str = "credit_note_number";
+/// Tax ids of the biller and client, the GSTIN when invoicing in India
+pub const BILLER_TAX_ID_FIELD_ID: &str = "biller_tax_id";
+pub const CLIENT_TAX_ID_FIELD_ID: &str = "client_tax_id";
+/// Countries of the biller and client, for the EU VAT rules
+pub const BILLER_COUNTRY_FIELD_ID: &str = "biller_country";
+pub const CLIENT_COUNTRY_FIELD_ID: &str = "client_country";
+/// HSN code of goods or SAC of services on a line, for the GST summary
+pub const HSN_SAC_FIELD_ID: &str = "hsn_sac";
+
+/// Currency symbol of new invoices
+pub const DEFAULT_CURRENCY: &str = "$";
+
+/// Invoice-level values that are persisted next to the invoice's line items
 #[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct InvoiceDocument {
+    pub id: String,
+    #[serde(default)]
+    pub document_type: DocumentType,
+    #[serde(default)]
+    pub invoice_info: HashMap<String, FieldItemValue>,
+    #[serde(default)]
+    pub biller_info: HashMap<String, FieldItemValue>,
+    #[serde(default)]
+    pub client_info: HashMap<String, FieldItemValue>,
+    #[serde(default)]
+    pub extra_info: HashMap<String, FieldItemValue>,
+    #[serde(default)]
+    pub rounding: RoundingPolicy,
+    #[serde(default)]
+    pub status: InvoiceStatus,
+    #[serde(default = "default_template_id")]
+    pub template_id: String,
+    #[serde(default)]
+    pub logo: Option<ImageAsset>,
+    #[serde(default)]
+    pub signature: Option<ImageAsset>,
+    /// Directory entry the client details were taken from
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// Profile the biller details were taken from
+    #[serde(default)]
+    pub biller_profile_id: Option<String>,
+    /// Sequence the invoice number is drawn from, empty for the default one
+    #[serde(default)]
+    pub number_series: String,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default)]
+    pub tax_exempt: bool,
+    #[serde(default)]
+    pub tax_exemption_note: String,
+    /// Unit prices include tax unless a line says otherwise
+    #[serde(default)]
+    pub prices_include_tax: bool,
+    /// GST state code of the place of supply, empty for the state of the client GSTIN
+    #[serde(default)]
+    pub place_of_supply: String,
+    /// Lets the EU VAT rules choose the category of the taxes
+    #[serde(default)]
+    pub vat_rules: bool,
+    #[serde(default)]
+    pub vat_supply: VatSupply,
+    /// Invoice-level discounts the totals use, `None` for documents saved before
+    /// they were kept with the document, which use the discount catalog
+    #[serde(default)]
+    pub discounts: Option<Vec<DiscountItem>>,
+    /// Invoice-level charges the totals use, like `discounts`
+    #[serde(default)]
+    pub charges: Option<Vec<ChargeItem>>,
+    #[serde(default)]
+    pub payments: Vec<Payment>,
+    /// Schedule that generated this invoice
+    #[serde(default)]
+    pub recurring_profile_id: Option<String>,
+    /// Generated by a schedule and not looked at yet
+    #[serde(default)]
+    pub needs_review: bool,
+    #[serde(default)]
+    pub quote_status: QuoteStatus,
+    /// ISO date a quote can be accepted until, empty for no limit
+    #[serde(default)]
+    pub valid_until: String,
+    /// Quote this invoice was converted from
+    #[serde(default)]
+    pub quote_id: Option<String>,
+    /// Invoice this quote was converted to
+    #[serde(default)]
+    pub converted_invoice_id: Option<String>,
+    /// Issued credit notes against this invoice
+    #[serde(default)]
+    pub credits: Vec<Credit>,
+    /// Invoice this credit note corrects
+    #[serde(default)]
+    pub credited_invoice_id: Option<String>,
+    /// Number of the corrected invoice, printed on the credit note
+    #[serde(default)]
+    pub credited_invoice_number: String,
+}
+
+impl InvoiceDocument {
+    /// Empty document of `document_type` under `id`, as a new invoice starts
+    pub fn new(id: String, document_type: DocumentType) -> Self {
+        Self {
+            id,
+            document_type,
+            template_id: default_template_id(),
+            currency: default_currency(),
+            ..Default::default()
+        }
+    }
+
+    /// Display value of an invoice-level field
+    pub fn invoice_value(&self, field_id: &str) -> String {
+        field_text(&self.invoice_info, field_id)
+    }
+
+    /// Number of the document in its own series, empty until it is sent
+    pub fn number(&self) -> String {
+        match self.document_type {
+            DocumentType::Invoice => self.invoice_value(INVOICE_NUMBER_FIELD_ID),
+            DocumentType::Quote => self.invoice_value(QUOTE_NUMBER_FIELD_ID),
+            DocumentType::CreditNote => self.invoice_value(CREDIT_NOTE_NUMBER_FIELD_ID),
+        }
+    }
+
+    /// Display value of a client field
+    pub fn client_value(&self, field_id: &str) -> String {
+        field_text(&self.client_info, field_id)
+    }
+
+    /// Whether the invoice-level discounts and charges follow the catalogs. A sent
+    /// invoice or quote keeps the ones it was sent with, and a credit note the
+    /// share of its invoice's it was created with.
+    pub fn follows_catalog(&self) -> bool {
+        match self.document_type {
+            DocumentType::Invoice => self.status == InvoiceStatus::Draft,
+            DocumentType::Quote => self.quote_status == QuoteStatus::Draft,
+            DocumentType::CreditNote => false,
+        }
+    }
+
+    /// GST treatment from the biller and client GSTINs and the place of supply
+    pub fn gst_supply(&self) -> Option<GstSupply> {
+        gst_supply(&self.biller_info, &self.client_info, &self.place_of_supply)
+    }
+
+    /// Category the EU VAT rules choose, `None` while they are off
+    pub fn vat_category(&self) -> Option<TaxCategory> {
+        if !self.vat_rules {
+            return None;
+        }
+        vat_category(&self.biller_info, &self.client_info, self.vat_supply)
+    }
+
+    /// Everything the totals of the document depend on. The catalogs are only used
+    /// when the document has no invoice-level discounts and charges of its own.
+    pub fn snapshot(
+        &self,
+        line_items: Vec<LineItem>,
+        discounts: Vec<DiscountItem>,
+        charges: Vec<ChargeItem>,
+    ) -> InvoiceSnapshot {
+        InvoiceSnapshot {
+            line_items,
+            discounts: self.discounts.clone().unwrap_or(discounts),
+            charges: self.charges.clone().unwrap_or(charges),
+            rounding: self.rounding,
+            tax_exempt: self.tax_exempt,
+            prices_include_tax: self.prices_include_tax,
+            gst_supply: self.gst_supply(),
+            vat_category: self.vat_category(),
+        }
+    }
+
+    /// Values of the fields in `category`, line item fields live on the line items
+    pub fn values(&self, category: &FieldCategory) -> Option<&HashMap<String, FieldItemValue>> {
+        match category {
+            FieldCategory::Invoice => Some(&self.invoice_info),
+            FieldCategory::Biller => Some(&self.biller_info),
+            FieldCategory::Client => Some(&self.client_info),
+            FieldCategory::ExtraInfo => Some(&self.extra_info),
+            FieldCategory::LineItem => None,
+        }
+    }
+
+    pub fn values_mut(
+        &mut self,
+        category: &FieldCategory,
+    ) -> Option<&mut HashMap<String, FieldItemValue>> {
+        match category {
+            FieldCategory::Invoice => Some(&mut self.invoice_info),
+            FieldCategory::Biller => Some(&mut self.biller_info),
+            FieldCategory::Client => Some(&mut self.client_info),
+            FieldCategory::ExtraInfo => Some(&mut self.extra_info),
+            FieldCategory::LineItem => None,
+        }
+    }
+}
+
+fn default_template_id() -> String {
+    DEFAULT_TEMPLATE_ID.to_string()
+}
+
+fn default_currency() -> String {
+    DEFAULT_CURRENCY.to_string()
+}
+
+fn field_text(values: &HashMap<String, FieldItemValue>, field_id: &str) -> String {
+    values
+        .get(field_id)
+        .map(|field| field.value.to_string())
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
 pub struct InvoiceBuilderState {
+    pub id: RwSignal<String>,
+    pub document_type: RwSignal<DocumentType>,
     pub taxes: RwSignal<Vec<TaxItem>>,
     pub discounts: RwSignal<Vec<DiscountItem>>,
     pub charges: RwSignal<Vec<ChargeItem>>,
     pub custom_fields: RwSignal<Vec<FieldItem>>,
+    pub products: RwSignal<Vec<ProductItem>>,
     pub line_items: RwSignal<Vec<LineItem>>,
     pub extra_info: RwSignal<HashMap<String, FieldItemValue>>,
     pub biller_info: RwSignal<HashMap<String, FieldItemValue>>,
     pub client_info: RwSignal<HashMap<String, FieldItemValue>>,
     pub invoice_info: RwSignal<HashMap<String, FieldItemValue>>,
+    pub rounding: RwSignal<RoundingPolicy>,
+    pub status: RwSignal<InvoiceStatus>,
+    pub template_id: RwSignal<String>,
+    pub logo: RwSignal<Option<ImageAsset>>,
+    pub signature: RwSignal<Option<ImageAsset>>,
+    pub client_id: RwSignal<Option<String>>,
+    pub biller_profile_id: RwSignal<Option<String>>,
+    pub number_series: RwSignal<String>,
+    pub currency: RwSignal<String>,
+    pub tax_exempt: RwSignal<bool>,
+    pub tax_exemption_note: RwSignal<String>,
+    pub prices_include_tax: RwSignal<bool>,
+    pub place_of_supply: RwSignal<String>,
+    pub vat_rules: RwSignal<bool>,
+    pub vat_supply: RwSignal<VatSupply>,
+    /// Invoice-level discounts and charges kept once the document is sent, see
+    /// `InvoiceDocument::follows_catalog`
+    pub kept_discounts: RwSignal<Option<Vec<DiscountItem>>>,
+    pub kept_charges: RwSignal<Option<Vec<ChargeItem>>>,
+    pub payments: RwSignal<Vec<Payment>>,
+    pub recurring_profile_id: RwSignal<Option<String>>,
+    pub needs_review: RwSignal<bool>,
+    pub quote_status: RwSignal<QuoteStatus>,
+    pub valid_until: RwSignal<String>,
+    pub quote_id: RwSignal<Option<String>>,
+    pub converted_invoice_id: RwSignal<Option<String>>,
+    pub credits: RwSignal<Vec<Credit>>,
+    pub credited_invoice_id: RwSignal<Option<String>>,
+    pub credited_invoice_number: RwSignal<String>,
     // pub global_discounts: Vec<String>, // Applied discount IDs
     // pub global_charges: Vec<String>,   // Applied charge IDs
     // pub last_updated: String,
@@ -22,24 +279,29 @@ pub struct InvoiceBuilderState {
 impl InvoiceBuilderState {
     pub fn new() -> Self {
         Self {
+            id: RwSignal::new(uuid::Uuid::new_v4().to_string()),
+            document_type: RwSignal::new(DocumentType::default()),
             taxes: RwSignal::new(vec![
                 TaxItem {
                     id: uuid::Uuid::new_v4().to_string(),
                     name: "VAT".to_string(),
                     tax_type: TaxType::Percentage,
-                    rate: 10.00,
+                    rate: dec!(10.00),
+                    ..Default::default()
                 },
                 TaxItem {
                     id: uuid::Uuid::new_v4().to_string(),
                     name: "Service Tax".to_string(),
                     tax_type: TaxType::Percentage,
-                    rate: 5.00,
+                    rate: dec!(5.00),
+                    ..Default::default()
                 },
                 TaxItem {
                     id: uuid::Uuid::new_v4().to_string(),
                     name: "Flat Fee Tax".to_string(),
                     tax_type: TaxType::FixedAmount,
-                    rate: 25.00,
+                    rate: dec!(25.00),
+                    ..Default::default()
                 },
             ]),
             discounts: RwSignal::new(vec![
@@ -48,7 +310,7 @@ impl InvoiceBuilderState {
                     name: "Early Bird Discount".to_string(),
                     description: "10% discount for early payment".to_string(),
                     discount_type: DiscountType::Percentage,
-                    value: 10.0,
+                    value: dec!(10.0),
                     scope: DiscountScope::GlobalInvoice,
                     is_default: true,
                 },
@@ -57,7 +319,7 @@ impl InvoiceBuilderState {
                     name: "Bulk Order Discount".to_string(),
                     description: "$50 off for bulk orders".to_string(),
                     discount_type: DiscountType::FixedAmount,
-                    value: 50.0,
+                    value: dec!(50.0),
                     scope: DiscountScope::LineItem,
                     is_default: false,
                 },
@@ -66,7 +328,7 @@ impl InvoiceBuilderState {
                     name: "Loyalty Discount".to_string(),
                     description: "20% off for loyal customers".to_string(),
                     discount_type: DiscountType::Percentage,
-                    value: 20.0,
+                    value: dec!(20.0),
                     scope: DiscountScope::GlobalInvoice,
                     is_default: false,
                 },
@@ -76,7 +338,7 @@ impl InvoiceBuilderState {
                     id: uuid::Uuid::new_v4().to_string(),
                     name: "Shipping Fee".to_string(),
                     description: "Standard shipping charge".to_string(),
-                    amount: 5.00,
+                    amount: dec!(5.00),
                     scope: ChargeScope::GlobalInvoice,
                     is_default: true,
                 },
@@ -84,7 +346,7 @@ impl InvoiceBuilderState {
                     id: uuid::Uuid::new_v4().to_string(),
                     name: "Handling Fee".to_string(),
                     description: "Fee for handling fragile items".to_string(),
-                    amount: 10.00,
+                    amount: dec!(10.00),
                     scope: ChargeScope::LineItem,
                     is_default: false,
                 },
@@ -92,15 +354,15 @@ impl InvoiceBuilderState {
                     id: uuid::Uuid::new_v4().to_string(),
                     name: "Rush Order Fee".to_string(),
                     description: "Fee for expedited order processing".to_string(),
-                    amount: 25.00,
+                    amount: dec!(25.00),
                     scope: ChargeScope::GlobalInvoice,
                     is_default: false,
                 },
             ]),
             custom_fields: RwSignal::new(vec![
                 FieldItem {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    name: "SAC".to_string(),
+                    id: HSN_SAC_FIELD_ID.to_string(),
+                    name: "HSN/SAC".to_string(),
                     field_type: FieldType::Text,
                     category: FieldCategory::LineItem,
                     default_value: "998314".to_string(),
@@ -126,6 +388,15 @@ impl InvoiceBuilderState {
                     required: true,
                     ..Default::default()
                 },
+                FieldItem {
+                    id: BILLER_COUNTRY_FIELD_ID.to_string(),
+                    name: "Biller Country".to_string(),
+                    field_type: FieldType::Text,
+                    category: FieldCategory::Biller,
+                    default_value: String::new(),
+                    required: false,
+                    ..Default::default()
+                },
                 FieldItem {
                     id: uuid::Uuid::new_v4().to_string(),
                     name: "Biller Email".to_string(),
@@ -144,9 +415,27 @@ impl InvoiceBuilderState {
                     required: false,
                     ..Default::default()
                 },
-                // Client Details
+                FieldItem {
+                    id: BILLER_TAX_ID_FIELD_ID.to_string(),
+                    name: "Tax ID".to_string(),
+                    field_type: FieldType::Text,
+                    category: FieldCategory::Biller,
+                    default_value: String::new(),
+                    required: false,
+                    ..Default::default()
+                },
                 FieldItem {
                     id: uuid::Uuid::new_v4().to_string(),
+                    name: "Bank Details".to_string(),
+                    field_type: FieldType::Textarea,
+                    category: FieldCategory::Biller,
+                    default_value: String::new(),
+                    required: false,
+                    ..Default::default()
+                },
+                // Client Details
+                FieldItem {
+                    id: CLIENT_NAME_FIELD_ID.to_string(),
                     name: "Client Name".to_string(),
                     field_type: FieldType::Text,
                     category: FieldCategory::Client,
@@ -163,6 +452,15 @@ impl InvoiceBuilderState {
                     required: true,
                     ..Default::default()
                 },
+                FieldItem {
+                    id: CLIENT_COUNTRY_FIELD_ID.to_string(),
+                    name: "Client Country".to_string(),
+                    field_type: FieldType::Text,
+                    category: FieldCategory::Client,
+                    default_value: String::new(),
+                    required: false,
+                    ..Default::default()
+                },
                 FieldItem {
                     id: uuid::Uuid::new_v4().to_string(),
                     name: "Client Email".to_string(),
@@ -181,15 +479,15 @@ impl InvoiceBuilderState {
                     required: false,
                     ..Default::default()
                 },
-                // FieldItem {
-                //     id: uuid::Uuid::new_v4().to_string(),
-                //     name: "Client GSTIN".to_string(),
-                //     field_type: FieldType::Text,
-                //     category: FieldCategory::Client,
-                //     default_value: String::new(),
-                //     required: false,
-                //     ..Default::default()
-                // },
+                FieldItem {
+                    id: CLIENT_TAX_ID_FIELD_ID.to_string(),
+                    name: "Client Tax ID".to_string(),
+                    field_type: FieldType::Text,
+                    category: FieldCategory::Client,
+                    default_value: String::new(),
+                    required: false,
+                    ..Default::default()
+                },
                 FieldItem {
                     id: uuid::Uuid::new_v4().to_string(),
                     name: "Notes".to_string(),
@@ -209,16 +507,25 @@ impl InvoiceBuilderState {
                     ..Default::default()
                 },
                 FieldItem {
-                    id: uuid::Uuid::new_v4().to_string(),
+                    id: INVOICE_NUMBER_FIELD_ID.to_string(),
                     name: "Invoice Number".to_string(),
-                    field_type: FieldType::Text,
+                    field_type: FieldType::AutoGenerated,
                     category: FieldCategory::Invoice,
                     default_value: String::new(),
                     required: true,
+                    auto_generation: Some(AutoGenerationRule::Counter {
+                        prefix: Some("INV-{YYYY}-".to_string()),
+                        suffix: None,
+                        start_value: 1,
+                        increment: 1,
+                        reset_period: Some(ResetPeriod::Yearly),
+                        padding: Some(4),
+                        fiscal_year_start: None,
+                    }),
                     ..Default::default()
                 },
                 FieldItem {
-                    id: uuid::Uuid::new_v4().to_string(),
+                    id: ISSUE_DATE_FIELD_ID.to_string(),
                     name: "Issue Date".to_string(),
                     field_type: FieldType::Date,
                     category: FieldCategory::Invoice,
@@ -227,7 +534,7 @@ impl InvoiceBuilderState {
                     ..Default::default()
                 },
                 FieldItem {
-                    id: uuid::Uuid::new_v4().to_string(),
+                    id: DUE_DATE_FIELD_ID.to_string(),
                     name: "Due Date".to_string(),
                     field_type: FieldType::Date,
                     category: FieldCategory::Invoice,
@@ -245,11 +552,300 @@ impl InvoiceBuilderState {
                     ..Default::default()
                 },
             ]),
+            products: RwSignal::new(vec![]),
             line_items: RwSignal::new(vec![]),
             extra_info: RwSignal::new(HashMap::new()),
             biller_info: RwSignal::new(HashMap::new()),
             client_info: RwSignal::new(HashMap::new()),
             invoice_info: RwSignal::new(HashMap::new()),
+            rounding: RwSignal::new(RoundingPolicy::default()),
+            status: RwSignal::new(InvoiceStatus::default()),
+            template_id: RwSignal::new(default_template_id()),
+            logo: RwSignal::new(None),
+            signature: RwSignal::new(None),
+            client_id: RwSignal::new(None),
+            biller_profile_id: RwSignal::new(None),
+            number_series: RwSignal::new(String::new()),
+            currency: RwSignal::new(default_currency()),
+            tax_exempt: RwSignal::new(false),
+            tax_exemption_note: RwSignal::new(String::new()),
+            prices_include_tax: RwSignal::new(false),
+            place_of_supply: RwSignal::new(String::new()),
+            vat_rules: RwSignal::new(false),
+            vat_supply: RwSignal::new(VatSupply::default()),
+            kept_discounts: RwSignal::new(None),
+            kept_charges: RwSignal::new(None),
+            payments: RwSignal::new(vec![]),
+            recurring_profile_id: RwSignal::new(None),
+            needs_review: RwSignal::new(false),
+            quote_status: RwSignal::new(QuoteStatus::default()),
+            valid_until: RwSignal::new(String::new()),
+            quote_id: RwSignal::new(None),
+            converted_invoice_id: RwSignal::new(None),
+            credits: RwSignal::new(vec![]),
+            credited_invoice_id: RwSignal::new(None),
+            credited_invoice_number: RwSignal::new(String::new()),
+        }
+    }
+}
+
+impl InvoiceBuilderState {
+    /// Copies the current signal values into a plain snapshot for calculation/export.
+    /// Tracks only the signals the totals depend on, so editing notes or the logo
+    /// does not recalculate the invoice.
+    pub fn snapshot(&self) -> InvoiceSnapshot {
+        let (discounts, charges) = if self.follows_catalog() {
+            (
+                self.discounts
+                    .with(|discounts| invoice_discounts(discounts)),
+                self.charges.with(|charges| invoice_charges(charges)),
+            )
+        } else {
+            (
+                self.kept_discounts
+                    .get()
+                    .unwrap_or_else(|| self.discounts.get()),
+                self.kept_charges
+                    .get()
+                    .unwrap_or_else(|| self.charges.get()),
+            )
+        };
+        let place_of_supply = self.place_of_supply.get();
+        let vat_rules = self.vat_rules.get();
+        let vat_supply = self.vat_supply.get();
+        let (gst_supply, vat_category) = self.biller_info.with(|biller| {
+            self.client_info.with(|client| {
+                (
+                    gst_supply(biller, client, &place_of_supply),
+                    vat_rules
+                        .then(|| vat_category(biller, client, vat_supply))
+                        .flatten(),
+                )
+            })
+        });
+
+        InvoiceSnapshot {
+            line_items: self.line_items.get(),
+            discounts,
+            charges,
+            rounding: self.rounding.get(),
+            tax_exempt: self.tax_exempt.get(),
+            prices_include_tax: self.prices_include_tax.get(),
+            gst_supply,
+            vat_category,
+        }
+    }
+
+    /// Whether the invoice-level discounts and charges follow the catalogs, see
+    /// `InvoiceDocument::follows_catalog`
+    fn follows_catalog(&self) -> bool {
+        InvoiceDocument {
+            document_type: self.document_type.get(),
+            status: self.status.get(),
+            quote_status: self.quote_status.get(),
+            ..Default::default()
+        }
+        .follows_catalog()
+    }
+
+    /// Invoice-level values to persist, tracks every signal it reads
+    pub fn document(&self) -> InvoiceDocument {
+        let mut document = InvoiceDocument {
+            id: self.id.get(),
+            document_type: self.document_type.get(),
+            invoice_info: self.invoice_info.get(),
+            biller_info: self.biller_info.get(),
+            client_info: self.client_info.get(),
+            extra_info: self.extra_info.get(),
+            rounding: self.rounding.get(),
+            status: self.status.get(),
+            template_id: self.template_id.get(),
+            logo: self.logo.get(),
+            signature: self.signature.get(),
+            client_id: self.client_id.get(),
+            biller_profile_id: self.biller_profile_id.get(),
+            number_series: self.number_series.get(),
+            currency: self.currency.get(),
+            tax_exempt: self.tax_exempt.get(),
+            tax_exemption_note: self.tax_exemption_note.get(),
+            prices_include_tax: self.prices_include_tax.get(),
+            place_of_supply: self.place_of_supply.get(),
+            vat_rules: self.vat_rules.get(),
+            vat_supply: self.vat_supply.get(),
+            discounts: self.kept_discounts.get(),
+            charges: self.kept_charges.get(),
+            payments: self.payments.get(),
+            recurring_profile_id: self.recurring_profile_id.get(),
+            needs_review: self.needs_review.get(),
+            quote_status: self.quote_status.get(),
+            valid_until: self.valid_until.get(),
+            quote_id: self.quote_id.get(),
+            converted_invoice_id: self.converted_invoice_id.get(),
+            credits: self.credits.get(),
+            credited_invoice_id: self.credited_invoice_id.get(),
+            credited_invoice_number: self.credited_invoice_number.get(),
+        };
+        // Drafts are saved with the catalog adjustments they are shown with
+        if document.follows_catalog() {
+            document.discounts = Some(
+                self.discounts
+                    .with(|discounts| invoice_discounts(discounts)),
+            );
+            document.charges = Some(self.charges.with(|charges| invoice_charges(charges)));
+        }
+        document
+    }
+
+    /// Keeps the invoice-level discounts and charges of the catalogs with the
+    /// document, called when it is sent so later catalog changes leave it as it was
+    pub fn keep_adjustments(&self) {
+        self.kept_discounts.set(Some(
+            self.discounts
+                .with_untracked(|discounts| invoice_discounts(discounts)),
+        ));
+        self.kept_charges.set(Some(
+            self.charges
+                .with_untracked(|charges| invoice_charges(charges)),
+        ));
+    }
+
+    /// Data handed to the invoice templates
+    pub fn template_data(&self) -> TemplateData {
+        TemplateData::new(
+            &self.document(),
+            &self.snapshot(),
+            &self.custom_fields.get(),
+        )
+    }
+
+    /// Replaces the current invoice with a stored one
+    pub fn apply_document(&self, document: InvoiceDocument, line_items: Vec<LineItem>) {
+        self.id.set(document.id);
+        self.document_type.set(document.document_type);
+        self.invoice_info.set(document.invoice_info);
+        self.biller_info.set(document.biller_info);
+        self.client_info.set(document.client_info);
+        self.extra_info.set(document.extra_info);
+        self.rounding.set(document.rounding);
+        self.status.set(document.status);
+        self.template_id.set(document.template_id);
+        self.logo.set(document.logo);
+        self.signature.set(document.signature);
+        self.client_id.set(document.client_id);
+        self.biller_profile_id.set(document.biller_profile_id);
+        self.number_series.set(document.number_series);
+        self.currency.set(document.currency);
+        self.tax_exempt.set(document.tax_exempt);
+        self.tax_exemption_note.set(document.tax_exemption_note);
+        self.prices_include_tax.set(document.prices_include_tax);
+        self.place_of_supply.set(document.place_of_supply);
+        self.vat_rules.set(document.vat_rules);
+        self.vat_supply.set(document.vat_supply);
+        self.kept_discounts.set(document.discounts);
+        self.kept_charges.set(document.charges);
+        self.payments.set(document.payments);
+        self.recurring_profile_id.set(document.recurring_profile_id);
+        self.needs_review.set(document.needs_review);
+        self.quote_status.set(document.quote_status);
+        self.valid_until.set(document.valid_until);
+        self.quote_id.set(document.quote_id);
+        self.converted_invoice_id.set(document.converted_invoice_id);
+        self.credits.set(document.credits);
+        self.credited_invoice_id.set(document.credited_invoice_id);
+        self.credited_invoice_number
+            .set(document.credited_invoice_number);
+        self.line_items.set(line_items);
+    }
+
+    /// Switches between invoice and quote, a quote without a validity date stays
+    /// valid for `QUOTE_VALIDITY_DAYS` after its issue date
+    pub fn set_document_type(&self, document_type: DocumentType) {
+        self.document_type.set(document_type);
+        if document_type != DocumentType::Quote
+            || !self.valid_until.with_untracked(String::is_empty)
+        {
+            return;
+        }
+        let issue_date = match self
+            .invoice_info
+            .with_untracked(|info| field_text(info, ISSUE_DATE_FIELD_ID))
+        {
+            date if date.is_empty() => today_iso(),
+            date => date,
+        };
+        self.valid_until
+            .set(add_days(&issue_date, QUOTE_VALIDITY_DAYS).unwrap_or_default());
+    }
+
+    /// Switches the invoice to a biller profile. A profile without a logo or
+    /// template keeps the ones already chosen for the invoice.
+    pub fn apply_biller_profile(&self, profile: &BillerProfile) {
+        self.biller_profile_id.set(Some(profile.id.clone()));
+        self.biller_info.set(profile.values.clone());
+        self.number_series.set(profile.number_series.clone());
+        if profile.logo.is_some() {
+            self.logo.set(profile.logo.clone());
+        }
+        if let Some(template_id) = &profile.template_id {
+            self.template_id.set(template_id.clone());
+        }
+    }
+
+    /// The biller details of this invoice as a profile
+    pub fn biller_profile(&self, id: String, name: String) -> BillerProfile {
+        BillerProfile {
+            id,
+            name,
+            values: self.biller_info.get_untracked(),
+            logo: self.logo.get_untracked(),
+            template_id: Some(self.template_id.get_untracked()),
+            number_series: self.number_series.get_untracked(),
+        }
+    }
+
+    /// Fills the client details from a directory entry and applies its defaults.
+    /// The due date follows the payment terms once an issue date is set.
+    pub fn apply_client(&self, client: &Client) {
+        let defaults = &client.defaults;
+        self.client_id.set(Some(client.id.clone()));
+        self.client_info.set(client.values.clone());
+        self.place_of_supply.set(String::new());
+        self.tax_exempt.set(defaults.tax_exempt);
+        self.tax_exemption_note
+            .set(defaults.tax_exemption_note.clone());
+        if let Some(currency) = &defaults.currency {
+            self.currency.set(currency.clone());
+        }
+        if let Some(template_id) = &defaults.template_id {
+            self.template_id.set(template_id.clone());
+        }
+
+        let issue_date = self
+            .invoice_info
+            .with_untracked(|info| field_text(info, ISSUE_DATE_FIELD_ID));
+        let due_date = defaults
+            .payment_terms_days
+            .and_then(|days| add_days(&issue_date, days));
+        if let Some(due_date) = due_date {
+            let label = self
+                .custom_fields
+                .with_untracked(|fields| {
+                    fields
+                        .iter()
+                        .find(|field| field.id == DUE_DATE_FIELD_ID)
+                        .map(|field| field.name.clone())
+                })
+                .unwrap_or_else(|| "Due Date".to_string());
+            self.invoice_info.update(|info| {
+                info.insert(
+                    DUE_DATE_FIELD_ID.to_string(),
+                    FieldItemValue {
+                        id: DUE_DATE_FIELD_ID.to_string(),
+                        label,
+                        value: FieldValue::Date(due_date),
+                    },
+                );
+            });
         }
     }
 }
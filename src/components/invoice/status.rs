@@ -0,0 +1,167 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Lifecycle of an invoice. `PartiallyPaid` and `Overdue` are never stored, they
+/// are derived from the payments and the due date of a sent invoice.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum InvoiceStatus {
+    #[default]
+    Draft,
+    Sent,
+    Paid,
+    PartiallyPaid,
+    Overdue,
+    Void,
+}
+
+impl InvoiceStatus {
+    pub const ALL: [InvoiceStatus; 6] = [
+        InvoiceStatus::Draft,
+        InvoiceStatus::Sent,
+        InvoiceStatus::Paid,
+        InvoiceStatus::PartiallyPaid,
+        InvoiceStatus::Overdue,
+        InvoiceStatus::Void,
+    ];
+
+    /// Statuses a user can set by hand
+    pub const SELECTABLE: [InvoiceStatus; 4] = [
+        InvoiceStatus::Draft,
+        InvoiceStatus::Sent,
+        InvoiceStatus::Paid,
+        InvoiceStatus::Void,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InvoiceStatus::Draft => "Draft",
+            InvoiceStatus::Sent => "Sent",
+            InvoiceStatus::Paid => "Paid",
+            InvoiceStatus::PartiallyPaid => "Partially paid",
+            InvoiceStatus::Overdue => "Overdue",
+            InvoiceStatus::Void => "Void",
+        }
+    }
+
+    /// Sent and paid invoices are final, moving a draft there assigns its number
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            InvoiceStatus::Sent
+                | InvoiceStatus::Paid
+                | InvoiceStatus::PartiallyPaid
+                | InvoiceStatus::Overdue
+        )
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.label() == label)
+    }
+
+    /// Tailwind classes for the status badge
+    pub fn badge_class(&self) -> &'static str {
+        match self {
+            InvoiceStatus::Draft => "bg-gray-100 text-gray-800",
+            InvoiceStatus::Sent => "bg-blue-100 text-blue-800",
+            InvoiceStatus::Paid => "bg-green-100 text-green-800",
+            InvoiceStatus::PartiallyPaid => "bg-orange-100 text-orange-800",
+            InvoiceStatus::Overdue => "bg-red-100 text-red-800",
+            InvoiceStatus::Void => "bg-yellow-100 text-yellow-800",
+        }
+    }
+
+    /// Status of a finalized invoice once `amount_paid` of its `total` came in,
+    /// credit notes count as paid. An invoice marked as paid without recorded
+    /// payments stays paid.
+    pub fn with_payments(&self, total: Decimal, amount_paid: Decimal) -> Self {
+        match self {
+            InvoiceStatus::Draft | InvoiceStatus::Void => *self,
+            _ if amount_paid > Decimal::ZERO && amount_paid >= total => InvoiceStatus::Paid,
+            InvoiceStatus::Paid if amount_paid.is_zero() => InvoiceStatus::Paid,
+            _ if amount_paid > Decimal::ZERO => InvoiceStatus::PartiallyPaid,
+            _ => InvoiceStatus::Sent,
+        }
+    }
+
+    /// Status to display on `today`, both dates are ISO `YYYY-MM-DD` strings. Open
+    /// invoices past their due date are overdue.
+    pub fn effective(
+        &self,
+        due_date: &str,
+        today: &str,
+        total: Decimal,
+        amount_paid: Decimal,
+    ) -> Self {
+        match self.with_payments(total, amount_paid) {
+            InvoiceStatus::Sent | InvoiceStatus::PartiallyPaid
+                if !due_date.is_empty() && due_date < today =>
+            {
+                InvoiceStatus::Overdue
+            }
+            status => status,
+        }
+    }
+}
+
+impl std::fmt::Display for InvoiceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+/// Today's date as an ISO `YYYY-MM-DD` string
+pub fn today_iso() -> String {
+    let now = String::from(js_sys::Date::new_0().to_iso_string());
+    now.chars().take(10).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    #[test]
+    fn payments_decide_the_status() {
+        let sent = InvoiceStatus::Sent;
+
+        assert_eq!(sent.with_payments(dec!(100), dec!(0)), InvoiceStatus::Sent);
+        assert_eq!(
+            sent.with_payments(dec!(100), dec!(40)),
+            InvoiceStatus::PartiallyPaid
+        );
+        assert_eq!(
+            sent.with_payments(dec!(100), dec!(100)),
+            InvoiceStatus::Paid
+        );
+        assert_eq!(
+            sent.with_payments(dec!(100), dec!(120)),
+            InvoiceStatus::Paid
+        );
+        assert_eq!(
+            InvoiceStatus::Draft.with_payments(dec!(100), dec!(100)),
+            InvoiceStatus::Draft
+        );
+        assert_eq!(
+            InvoiceStatus::Paid.with_payments(dec!(100), dec!(0)),
+            InvoiceStatus::Paid
+        );
+    }
+
+    #[test]
+    fn open_invoices_past_due_are_overdue() {
+        let sent = InvoiceStatus::Sent;
+
+        assert_eq!(
+            sent.effective("2024-05-01", "2024-05-02", dec!(100), dec!(40)),
+            InvoiceStatus::Overdue
+        );
+        assert_eq!(
+            sent.effective("2024-05-01", "2024-05-02", dec!(100), dec!(100)),
+            InvoiceStatus::Paid
+        );
+        assert_eq!(
+            sent.effective("2024-05-03", "2024-05-02", dec!(100), dec!(0)),
+            InvoiceStatus::Sent
+        );
+    }
+}
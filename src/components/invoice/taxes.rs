@@ -1,7 +1,9 @@
 use leptos::prelude::*;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 
+use super::{deserialize_amount, format_amount, parse_decimal};
 use crate::components::{
     editable_grid::{
         FormData, FormValidation, ItemData, ValidationResult, use_editable_grid,
@@ -27,23 +29,185 @@ impl std::fmt::Display for TaxType {
     }
 }
 
+/// How a tax is levied. GST is split by place of supply, withheld taxes are
+/// deducted from what the client pays instead of added to the total.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum TaxKind {
+    #[default]
+    Standard,
+    /// Indian GST, charged as CGST and SGST or as IGST
+    Gst,
+    /// Withheld by the client, e.g. TDS in India or IRPF in Spain
+    Withholding,
+}
+
+impl TaxKind {
+    pub const ALL: [TaxKind; 3] = [TaxKind::Standard, TaxKind::Gst, TaxKind::Withholding];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaxKind::Standard => "Standard",
+            TaxKind::Gst => "GST",
+            TaxKind::Withholding => "Withholding",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.label() == label)
+            .unwrap_or_default()
+    }
+}
+
+/// VAT category of a tax, the codes are those of EN 16931 e-invoices
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum TaxCategory {
+    #[default]
+    Standard,
+    Reduced,
+    Zero,
+    Exempt,
+    /// VAT is accounted for by the client
+    ReverseCharge,
+    /// Goods sent to a business in another EU state
+    IntraCommunity,
+    /// Supplies to clients outside the EU
+    Export,
+}
+
+impl TaxCategory {
+    pub const ALL: [TaxCategory; 7] = [
+        TaxCategory::Standard,
+        TaxCategory::Reduced,
+        TaxCategory::Zero,
+        TaxCategory::Exempt,
+        TaxCategory::ReverseCharge,
+        TaxCategory::IntraCommunity,
+        TaxCategory::Export,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaxCategory::Standard => "Standard",
+            TaxCategory::Reduced => "Reduced",
+            TaxCategory::Zero => "Zero rated",
+            TaxCategory::Exempt => "Exempt",
+            TaxCategory::ReverseCharge => "Reverse charge",
+            TaxCategory::IntraCommunity => "Intra-community supply",
+            TaxCategory::Export => "Export",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|category| category.label() == label)
+            .unwrap_or_default()
+    }
+
+    /// Category code of EN 16931 (UNCL 5305)
+    pub fn code(&self) -> &'static str {
+        match self {
+            TaxCategory::Standard | TaxCategory::Reduced => "S",
+            TaxCategory::Zero => "Z",
+            TaxCategory::Exempt => "E",
+            TaxCategory::ReverseCharge => "AE",
+            TaxCategory::IntraCommunity => "K",
+            TaxCategory::Export => "G",
+        }
+    }
+
+    /// Whether the tax is charged at its rate, other categories charge nothing
+    pub fn is_charged(&self) -> bool {
+        matches!(self, TaxCategory::Standard | TaxCategory::Reduced)
+    }
+
+    /// Wording printed for categories that charge nothing, when the tax has none
+    pub fn default_reason(&self) -> &'static str {
+        match self {
+            TaxCategory::Standard | TaxCategory::Reduced | TaxCategory::Zero => "",
+            TaxCategory::Exempt => "Exempt from VAT",
+            TaxCategory::ReverseCharge => {
+                "Reverse charge, VAT to be accounted for by the recipient (Article 196 Directive 2006/112/EC)"
+            }
+            TaxCategory::IntraCommunity => {
+                "Exempt intra-community supply (Article 138 Directive 2006/112/EC)"
+            }
+            TaxCategory::Export => "Export outside the EU, exempt from VAT",
+        }
+    }
+}
+
 // 2. Define your data structure for Tax
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
 pub struct TaxItem {
     pub id: String,
     pub name: String,
     pub tax_type: TaxType,
-    pub rate: f64,
+    #[serde(deserialize_with = "deserialize_amount")]
+    pub rate: Decimal,
+    /// Taxes apply from the lowest order up
+    #[serde(default)]
+    pub order: u32,
+    /// Also charged on the taxes of a lower order
+    #[serde(default)]
+    pub compound: bool,
+    /// Also charged on line charges, which are otherwise untaxed
+    #[serde(default)]
+    pub apply_to_charges: bool,
+    #[serde(default)]
+    pub kind: TaxKind,
+    #[serde(default)]
+    pub category: TaxCategory,
+    /// Printed on invoices where the tax is not charged, e.g. a legal reference
+    #[serde(default)]
+    pub exemption_reason: String,
 }
 
-impl PartialEq for TaxItem {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+impl TaxItem {
+    /// What the tax is charged on, shown in the tax list
+    pub fn applies_to(&self) -> String {
+        let mut parts = vec!["Price"];
+        if self.apply_to_charges {
+            parts.push("charges");
+        }
+        if self.compound {
+            parts.push("lower order taxes");
+        }
+        parts.join(", ")
+    }
+
+    /// Type shown in the tax list, with the kind and category when not standard
+    pub fn type_label(&self) -> String {
+        let mut label = self.tax_type.to_string();
+        match self.kind {
+            TaxKind::Standard => {}
+            TaxKind::Gst => label.push_str(", GST"),
+            TaxKind::Withholding => label.push_str(", withheld"),
+        }
+        if self.category != TaxCategory::Standard {
+            label.push_str(", ");
+            label.push_str(self.category.label());
+        }
+        label
+    }
+
+    /// Why the tax is not charged, the category wording unless a reason was entered
+    pub fn exemption_note(&self) -> String {
+        if self.category.is_charged() {
+            String::new()
+        } else if self.exemption_reason.trim().is_empty() {
+            self.category.default_reason().to_string()
+        } else {
+            self.exemption_reason.trim().to_string()
+        }
     }
 }
 
 impl Eq for TaxItem {}
 
+// By id only, items equal by value share their id so this agrees with `PartialEq`
 impl Hash for TaxItem {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
@@ -80,6 +244,12 @@ pub struct TaxForm {
     pub name: String,
     pub tax_type: TaxType,
     pub rate: String, // Keep as string for form input handling
+    pub order: String,
+    pub compound: bool,
+    pub apply_to_charges: bool,
+    pub kind: TaxKind,
+    pub category: TaxCategory,
+    pub exemption_reason: String,
 }
 
 // 3. Implement required traits
@@ -89,9 +259,7 @@ impl FormData for TaxItem {
     fn default() -> Self {
         Self {
             id: uuid::Uuid::new_v4().to_string(),
-            name: String::new(),
-            tax_type: TaxType::default(),
-            rate: 0.0,
+            ..Default::default()
         }
     }
 
@@ -100,6 +268,12 @@ impl FormData for TaxItem {
             name: self.name.clone(),
             tax_type: self.tax_type.clone(),
             rate: self.rate.to_string(),
+            order: self.order.to_string(),
+            compound: self.compound,
+            apply_to_charges: self.apply_to_charges,
+            kind: self.kind,
+            category: self.category,
+            exemption_reason: self.exemption_reason.clone(),
         }
     }
 
@@ -108,7 +282,13 @@ impl FormData for TaxItem {
             id: uuid::Uuid::new_v4().to_string(),
             name: props.name.clone(),
             tax_type: props.tax_type.clone(),
-            rate: props.rate.parse::<f64>().unwrap_or(0.0),
+            rate: parse_decimal(&props.rate).unwrap_or_default(),
+            order: props.order.trim().parse().unwrap_or_default(),
+            compound: props.compound,
+            apply_to_charges: props.apply_to_charges,
+            kind: props.kind,
+            category: props.category,
+            exemption_reason: props.exemption_reason.clone(),
         }
     }
 }
@@ -124,7 +304,7 @@ impl ItemData for TaxItem {
 
     fn get_subtitle(&self) -> Option<String> {
         Some(match self.tax_type {
-            TaxType::FixedAmount => format!("${:.2}", self.rate),
+            TaxType::FixedAmount => format!("${}", format_amount(self.rate)),
             TaxType::Percentage => format!("{}%", self.rate),
         })
     }
@@ -133,6 +313,7 @@ impl ItemData for TaxItem {
         vec![
             ("tax_type".to_string(), self.tax_type.to_string()),
             ("rate".to_string(), self.rate.to_string()),
+            ("order".to_string(), self.order.to_string()),
         ]
     }
 }
@@ -148,18 +329,20 @@ impl FormValidation for TaxForm {
 
         // Validate rate is a valid number and within appropriate range
         if !self.rate.is_empty() {
-            if self.rate.parse::<f64>().is_err() {
+            if parse_decimal(&self.rate).is_none() {
                 results.push(ValidationResult::new().with_field_error(
                     "rate".to_string(),
                     "Rate must be a valid number".to_string(),
                 ));
-            } else if let Ok(rate) = self.rate.parse::<f64>() {
-                if rate < 0.0 {
+            } else if let Some(rate) = parse_decimal(&self.rate) {
+                if rate.is_sign_negative() {
                     results.push(ValidationResult::new().with_field_error(
                         "rate".to_string(),
                         "Rate cannot be negative".to_string(),
                     ));
-                } else if matches!(self.tax_type, TaxType::Percentage) && rate > 100.0 {
+                } else if matches!(self.tax_type, TaxType::Percentage)
+                    && rate > Decimal::ONE_HUNDRED
+                {
                     results.push(ValidationResult::new().with_field_error(
                         "rate".to_string(),
                         "Percentage cannot exceed 100%".to_string(),
@@ -167,6 +350,13 @@ impl FormValidation for TaxForm {
                 }
             }
         }
+
+        if !self.order.trim().is_empty() && self.order.trim().parse::<u32>().is_err() {
+            results.push(ValidationResult::new().with_field_error(
+                "order".to_string(),
+                "Order must be a whole number of zero or more".to_string(),
+            ));
+        }
         validators::combine_results(results)
     }
 }
@@ -181,6 +371,35 @@ pub fn Taxes(state: RwSignal<Vec<TaxItem>>) -> impl IntoView {
     let (name_value, set_name_value) = signal(String::new());
     let (tax_type_value, set_tax_type_value) = signal(TaxType::Percentage);
     let (rate_value, set_rate_value) = signal(String::new());
+    let (order_value, set_order_value) = signal(String::new());
+    let (compound_value, set_compound_value) = signal(false);
+    let (apply_to_charges_value, set_apply_to_charges_value) = signal(false);
+    let (kind_value, set_kind_value) = signal(TaxKind::Standard);
+    let (category_value, set_category_value) = signal(TaxCategory::Standard);
+    let (exemption_reason_value, set_exemption_reason_value) = signal(String::new());
+
+    let form_data = move || TaxForm {
+        name: name_value.get(),
+        tax_type: tax_type_value.get(),
+        rate: rate_value.get(),
+        order: order_value.get(),
+        compound: compound_value.get(),
+        apply_to_charges: apply_to_charges_value.get(),
+        kind: kind_value.get(),
+        category: category_value.get(),
+        exemption_reason: exemption_reason_value.get(),
+    };
+    let reset_form = move || {
+        set_name_value.set(String::new());
+        set_tax_type_value.set(TaxType::Percentage);
+        set_rate_value.set(String::new());
+        set_order_value.set(String::new());
+        set_compound_value.set(false);
+        set_apply_to_charges_value.set(false);
+        set_kind_value.set(TaxKind::Standard);
+        set_category_value.set(TaxCategory::Standard);
+        set_exemption_reason_value.set(String::new());
+    };
 
     // Update form fields when grid state changes
     Effect::new({
@@ -190,6 +409,12 @@ pub fn Taxes(state: RwSignal<Vec<TaxItem>>) -> impl IntoView {
             set_name_value.set(form.name);
             set_tax_type_value.set(form.tax_type);
             set_rate_value.set(form.rate);
+            set_order_value.set(form.order);
+            set_compound_value.set(form.compound);
+            set_apply_to_charges_value.set(form.apply_to_charges);
+            set_kind_value.set(form.kind);
+            set_category_value.set(form.category);
+            set_exemption_reason_value.set(form.exemption_reason);
         }
     });
 
@@ -197,30 +422,19 @@ pub fn Taxes(state: RwSignal<Vec<TaxItem>>) -> impl IntoView {
     let handle_submit = move |ev: web_sys::SubmitEvent| {
         ev.prevent_default();
 
-        let form_data = TaxForm {
-            name: name_value.get(),
-            tax_type: tax_type_value.get(),
-            rate: rate_value.get(),
-        };
+        let form_data = form_data();
 
         let validation = form_data.validate();
         if validation.is_valid {
             grid.actions.submit_form.run(form_data);
-            set_name_value.set(String::new());
-            set_tax_type_value.set(TaxType::Percentage);
-            set_rate_value.set(String::new());
+            reset_form();
         }
     };
 
     // Handle input changes
     let handle_name_input = move |ev| {
-        let value = event_target_value(&ev);
-        set_name_value.set(value.clone());
-        grid.actions.update_form.run(TaxForm {
-            name: value,
-            tax_type: tax_type_value.get(),
-            rate: rate_value.get(),
-        });
+        set_name_value.set(event_target_value(&ev));
+        grid.actions.update_form.run(form_data());
     };
 
     let handle_tax_type_change = move |ev| {
@@ -230,22 +444,43 @@ pub fn Taxes(state: RwSignal<Vec<TaxItem>>) -> impl IntoView {
         } else {
             TaxType::Percentage
         };
-        set_tax_type_value.set(tax_type.clone());
-        grid.actions.update_form.run(TaxForm {
-            name: name_value.get(),
-            tax_type,
-            rate: rate_value.get(),
-        });
+        set_tax_type_value.set(tax_type);
+        grid.actions.update_form.run(form_data());
     };
 
     let handle_rate_input = move |ev| {
-        let value = event_target_value(&ev);
-        set_rate_value.set(value.clone());
-        grid.actions.update_form.run(TaxForm {
-            name: name_value.get(),
-            tax_type: tax_type_value.get(),
-            rate: value,
-        });
+        set_rate_value.set(event_target_value(&ev));
+        grid.actions.update_form.run(form_data());
+    };
+
+    let handle_order_input = move |ev| {
+        set_order_value.set(event_target_value(&ev));
+        grid.actions.update_form.run(form_data());
+    };
+
+    let handle_compound_change = move |ev| {
+        set_compound_value.set(event_target_checked(&ev));
+        grid.actions.update_form.run(form_data());
+    };
+
+    let handle_apply_to_charges_change = move |ev| {
+        set_apply_to_charges_value.set(event_target_checked(&ev));
+        grid.actions.update_form.run(form_data());
+    };
+
+    let handle_kind_change = move |ev| {
+        set_kind_value.set(TaxKind::from_label(&event_target_value(&ev)));
+        grid.actions.update_form.run(form_data());
+    };
+
+    let handle_category_change = move |ev| {
+        set_category_value.set(TaxCategory::from_label(&event_target_value(&ev)));
+        grid.actions.update_form.run(form_data());
+    };
+
+    let handle_exemption_reason_input = move |ev| {
+        set_exemption_reason_value.set(event_target_value(&ev));
+        grid.actions.update_form.run(form_data());
     };
 
     view! {
@@ -321,6 +556,102 @@ pub fn Taxes(state: RwSignal<Vec<TaxItem>>) -> impl IntoView {
                                 required
                             />
                         </div>
+                        <div>
+                            <label class="form-label" for="tax-order">
+                                "Order"
+                            </label>
+                            <input
+                                class="form-input"
+                                id="tax-order"
+                                name="tax-order"
+                                placeholder="0"
+                                type="number"
+                                step="1"
+                                min="0"
+                                prop:value=move || order_value.get()
+                                on:input=handle_order_input
+                            />
+                            <p class="mt-1 text-xs text-gray-500">
+                                "Taxes with a lower order apply first"
+                            </p>
+                        </div>
+                        <div>
+                            <label class="form-label" for="tax-kind">
+                                "Kind"
+                            </label>
+                            <select
+                                class="form-select"
+                                id="tax-kind"
+                                name="tax-kind"
+                                prop:value=move || kind_value.get().label()
+                                on:change=handle_kind_change
+                            >
+                                {TaxKind::ALL
+                                    .into_iter()
+                                    .map(|kind| view! { <option value=kind.label()>{kind.label()}</option> })
+                                    .collect_view()}
+                            </select>
+                            <p class="mt-1 text-xs text-gray-500">
+                                "GST splits by place of supply, withheld taxes reduce what is payable"
+                            </p>
+                        </div>
+                        <div>
+                            <label class="form-label" for="tax-category">
+                                "VAT category"
+                            </label>
+                            <select
+                                class="form-select"
+                                id="tax-category"
+                                name="tax-category"
+                                prop:value=move || category_value.get().label()
+                                on:change=handle_category_change
+                            >
+                                {TaxCategory::ALL
+                                    .into_iter()
+                                    .map(|category| {
+                                        view! {
+                                            <option value=category.label()>{category.label()}</option>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </select>
+                            <p class="mt-1 text-xs text-gray-500">
+                                "Only standard and reduced taxes are charged"
+                            </p>
+                        </div>
+                        <div class="md:col-span-2">
+                            <label class="form-label" for="tax-exemption-reason">
+                                "Exemption reason"
+                            </label>
+                            <input
+                                class="form-input"
+                                id="tax-exemption-reason"
+                                name="tax-exemption-reason"
+                                type="text"
+                                placeholder=move || category_value.get().default_reason()
+                                disabled=move || category_value.get().is_charged()
+                                prop:value=move || exemption_reason_value.get()
+                                on:input=handle_exemption_reason_input
+                            />
+                        </div>
+                        <div class="flex flex-col justify-center gap-2">
+                            <label class="flex items-center gap-2 text-sm text-gray-700">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=move || compound_value.get()
+                                    on:change=handle_compound_change
+                                />
+                                "Compound, also charged on taxes of a lower order"
+                            </label>
+                            <label class="flex items-center gap-2 text-sm text-gray-700">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=move || apply_to_charges_value.get()
+                                    on:change=handle_apply_to_charges_change
+                                />
+                                "Apply to line charges"
+                            </label>
+                        </div>
                     </div>
                     <div class="mt-6 flex justify-end gap-3">
                         <Show when=move || grid.form_state.get().is_editing>
@@ -329,9 +660,7 @@ pub fn Taxes(state: RwSignal<Vec<TaxItem>>) -> impl IntoView {
                                 type="button"
                                 on:click=move |_| {
                                     grid.actions.cancel_edit.run(());
-                                    set_name_value.set(String::new());
-                                    set_tax_type_value.set(TaxType::Percentage);
-                                    set_rate_value.set(String::new());
+                                    reset_form();
                                 }
                             >
                                 "Cancel"
@@ -370,10 +699,18 @@ pub fn Taxes(state: RwSignal<Vec<TaxItem>>) -> impl IntoView {
                                                     <span class="px-2 py-1 text-xs bg-blue-100 text-blue-800 rounded">
                                                         {match item.tax_type {
                                                             TaxType::Percentage => format!("{}%", item.rate),
-                                                            TaxType::FixedAmount => format!("${:.2}", item.rate),
+                                                            TaxType::FixedAmount => format!("${}", format_amount(item.rate)),
                                                         }}
                                                     </span>
                                                 </div>
+                                                <p class="text-sm text-gray-500">
+                                                    {format!(
+                                                        "{}, order {}, on {}",
+                                                        item.type_label(),
+                                                        item.order,
+                                                        item.applies_to().to_lowercase(),
+                                                    )}
+                                                </p>
                                                 <div class="flex justify-end space-x-2 mt-3">
                                                     <button
                                                         class="px-3 py-1 text-sm bg-indigo-600 text-white rounded hover:bg-indigo-700"
@@ -431,6 +768,18 @@ pub fn Taxes(state: RwSignal<Vec<TaxItem>>) -> impl IntoView {
                                             >
                                                 "Rate"
                                             </th>
+                                            <th
+                                                class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider"
+                                                scope="col"
+                                            >
+                                                "Order"
+                                            </th>
+                                            <th
+                                                class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider"
+                                                scope="col"
+                                            >
+                                                "Applies To"
+                                            </th>
                                             <th class="relative px-6 py-3" scope="col">
                                                 <span class="sr-only">"Actions"</span>
                                             </th>
@@ -449,14 +798,20 @@ pub fn Taxes(state: RwSignal<Vec<TaxItem>>) -> impl IntoView {
                                                             {item.name.clone()}
                                                         </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
-                                                            {item.tax_type.to_string()}
+                                                            {item.type_label()}
                                                         </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
                                                             {match item.tax_type {
                                                                 TaxType::Percentage => format!("{:.2}%", item.rate),
-                                                                TaxType::FixedAmount => format!("${:.2}", item.rate),
+                                                                TaxType::FixedAmount => format!("${}", format_amount(item.rate)),
                                                             }}
                                                         </td>
+                                                        <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
+                                                            {item.order}
+                                                        </td>
+                                                        <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">
+                                                            {item.applies_to()}
+                                                        </td>
                                                         <td class="px-6 py-4 whitespace-nowrap text-right text-sm font-medium">
                                                             <button
                                                                 class="text-indigo-600 hover:text-indigo-900 mr-4"
@@ -483,7 +838,7 @@ pub fn Taxes(state: RwSignal<Vec<TaxItem>>) -> impl IntoView {
                                             <tr>
                                                 <td
                                                     class="px-6 py-12 text-center text-sm text-gray-500"
-                                                    colspan="4"
+                                                    colspan="6"
                                                 >
                                                     "No taxes found. Add your first tax using the form above."
                                                 </td>
@@ -0,0 +1,524 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::*;
+
+/// Where templates find the serialized `TemplateData`
+pub const TEMPLATE_DATA_PATH: &str = "/invoice.json";
+
+/// Data contract handed to Typst templates as JSON.
+///
+/// Amounts are pre-formatted strings with `MONEY_SCALE` decimals so templates never
+/// do floating point math on money. Custom fields are lists ordered like the field
+/// definitions, each entry carries its id, label and display value. Images are
+/// paths into the template file system, `null` when nothing was uploaded.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TemplateData {
+    /// `invoice`, `quote` or `credit_note`
+    pub document_type: String,
+    /// Heading of the document, "Invoice", "Quote" or "Credit Note"
+    pub title: String,
+    pub currency: String,
+    pub number: String,
+    pub issue_date: String,
+    pub due_date: String,
+    /// Date a quote can be accepted until, empty for invoices
+    pub valid_until: String,
+    /// Number of the invoice a credit note corrects, empty for other documents
+    pub credited_invoice_number: String,
+    pub status: String,
+    pub logo: Option<String>,
+    pub signature: Option<String>,
+    pub invoice: Vec<TemplateField>,
+    pub biller: Vec<TemplateField>,
+    pub client: Vec<TemplateField>,
+    pub extra: Vec<TemplateField>,
+    pub items: Vec<TemplateLineItem>,
+    pub totals: TemplateTotals,
+    /// Why no tax is charged, `None` unless the invoice is tax exempt
+    pub tax_exemption: Option<String>,
+    /// State code and name the GST split follows, `None` without GST
+    pub place_of_supply: Option<String>,
+    /// GST by HSN or SAC code, empty without GST
+    pub hsn_summary: Vec<TemplateHsnRow>,
+    /// Why taxes are not charged, e.g. the reverse charge wording
+    pub vat_notes: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TemplateField {
+    pub id: String,
+    pub label: String,
+    pub value: String,
+}
+
+/// A discount or charge as shown on the invoice, `value` is e.g. "10%" or "5.00"
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TemplateAdjustment {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub value: String,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TemplateTax {
+    pub id: String,
+    pub name: String,
+    pub rate: String, // e.g. "10%" or "25.00" for fixed amounts
+    pub taxable: String,
+    pub amount: String,
+    /// Already part of the prices, not added to the total
+    pub included: bool,
+    /// EN 16931 category code, e.g. "S" or "AE" for reverse charge
+    pub category: String,
+    /// Why the tax is not charged, empty for charged taxes
+    pub exemption_reason: String,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TemplateHsnRow {
+    pub code: String,
+    pub taxable: String,
+    pub tax_total: String,
+    pub taxes: Vec<TemplateTax>,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TemplateLineItem {
+    pub id: String,
+    pub sku: String,
+    pub name: String,
+    pub description: String,
+    pub quantity: String,
+    pub unit: String,
+    pub unit_price: String,
+    pub gross: String,
+    pub discount_total: String,
+    pub global_discount: String,
+    pub taxable: String,
+    pub charge_total: String,
+    pub tax_total: String,
+    pub total: String,
+    pub taxes: Vec<TemplateTax>,
+    pub discounts: Vec<TemplateAdjustment>,
+    pub charges: Vec<TemplateAdjustment>,
+    pub fields: Vec<TemplateField>,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TemplateTotals {
+    pub subtotal: String,
+    pub line_discounts: String,
+    pub global_discounts: String,
+    pub discount_total: String,
+    pub line_charges: String,
+    pub global_charges: String,
+    pub charge_total: String,
+    pub tax_total: String,
+    /// Part of `tax_total` included in the prices
+    pub tax_included: String,
+    pub grand_total: String,
+    /// Taxes the client withholds, deducted after the grand total
+    pub withholding: Vec<TemplateTax>,
+    pub withholding_total: String,
+    /// Grand total less the withheld taxes, what the client pays
+    pub net_payable: String,
+    pub amount_paid: String,
+    /// Sum of the credit notes issued against the invoice
+    pub amount_credited: String,
+    /// Negative when more than the total was paid
+    pub balance_due: String,
+    pub taxes: Vec<TemplateTax>,
+    pub discounts: Vec<TemplateAdjustment>, // Invoice-level discounts
+    pub charges: Vec<TemplateAdjustment>,   // Invoice-level charges
+}
+
+fn template_tax(tax: &TaxLine) -> TemplateTax {
+    TemplateTax {
+        id: tax.tax_id.clone(),
+        name: tax.name.clone(),
+        rate: match tax.tax_type {
+            TaxType::Percentage => format!("{}%", tax.rate.normalize()),
+            TaxType::FixedAmount => format_amount(tax.rate),
+        },
+        taxable: format_amount(tax.taxable),
+        amount: format_amount(tax.amount),
+        included: tax.included,
+        category: tax.category.code().to_string(),
+        exemption_reason: tax.exemption_reason.clone(),
+    }
+}
+
+fn template_discount(discount: &DiscountItem) -> TemplateAdjustment {
+    TemplateAdjustment {
+        id: discount.id.clone(),
+        name: discount.name.clone(),
+        description: discount.description.clone(),
+        value: match discount.discount_type {
+            DiscountType::Percentage => format!("{}%", discount.value.normalize()),
+            DiscountType::FixedAmount => format_amount(discount.value),
+        },
+    }
+}
+
+fn template_charge(charge: &ChargeItem) -> TemplateAdjustment {
+    TemplateAdjustment {
+        id: charge.id.clone(),
+        name: charge.name.clone(),
+        description: charge.description.clone(),
+        value: format_amount(charge.amount),
+    }
+}
+
+/// Values of one field category, ordered like the field definitions
+fn template_fields(
+    fields: &[FieldItem],
+    category: FieldCategory,
+    values: &HashMap<String, FieldItemValue>,
+) -> Vec<TemplateField> {
+    fields
+        .iter()
+        .filter(|field| field.category == category)
+        .map(|field| TemplateField {
+            id: field.id.clone(),
+            label: field.name.clone(),
+            value: values
+                .get(&field.id)
+                .map(|value| value.value.to_string())
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Sorts by name so the output does not depend on `HashSet` iteration order
+fn sorted_by_name<T: Clone>(items: impl Iterator<Item = T>, name: impl Fn(&T) -> &str) -> Vec<T> {
+    let mut items = items.collect::<Vec<_>>();
+    items.sort_by(|a, b| name(a).cmp(name(b)));
+    items
+}
+
+impl TemplateData {
+    pub fn new(
+        document: &InvoiceDocument,
+        snapshot: &InvoiceSnapshot,
+        fields: &[FieldItem],
+    ) -> Self {
+        let breakdown = calculate_invoice(snapshot);
+
+        let items = snapshot
+            .line_items
+            .iter()
+            .zip(&breakdown.lines)
+            .map(|(item, line)| TemplateLineItem {
+                id: item.id.clone(),
+                sku: item.sku.clone(),
+                name: item.name.clone(),
+                description: item.description.clone(),
+                quantity: item.quantity.normalize().to_string(),
+                unit: item.unit.clone(),
+                unit_price: format_amount(item.unit_price),
+                gross: format_amount(line.gross),
+                discount_total: format_amount(line.discount),
+                global_discount: format_amount(line.global_discount),
+                taxable: format_amount(line.taxable),
+                charge_total: format_amount(line.charges),
+                tax_total: format_amount(line.tax_total),
+                total: format_amount(line.total),
+                taxes: line.taxes.iter().map(template_tax).collect(),
+                discounts: sorted_by_name(item.discounts.iter().map(template_discount), |d| {
+                    &d.name
+                }),
+                charges: sorted_by_name(item.charges.iter().map(template_charge), |c| &c.name),
+                fields: template_fields(fields, FieldCategory::LineItem, &item.custom_fields),
+            })
+            .collect();
+
+        let totals = TemplateTotals {
+            subtotal: format_amount(breakdown.subtotal),
+            line_discounts: format_amount(breakdown.line_discounts),
+            global_discounts: format_amount(breakdown.global_discounts),
+            discount_total: format_amount(breakdown.total_discounts),
+            line_charges: format_amount(breakdown.line_charges),
+            global_charges: format_amount(breakdown.global_charges),
+            charge_total: format_amount(breakdown.total_charges),
+            tax_total: format_amount(breakdown.tax_total),
+            tax_included: format_amount(breakdown.tax_included),
+            grand_total: format_amount(breakdown.grand_total),
+            withholding: breakdown.withholding.iter().map(template_tax).collect(),
+            withholding_total: format_amount(breakdown.withholding_total),
+            net_payable: format_amount(breakdown.net_payable),
+            amount_paid: format_amount(amount_paid(&document.payments)),
+            amount_credited: format_amount(amount_credited(&document.credits)),
+            balance_due: format_amount(balance_due(
+                breakdown.net_payable,
+                &document.payments,
+                &document.credits,
+            )),
+            taxes: breakdown.taxes.iter().map(template_tax).collect(),
+            discounts: snapshot
+                .discounts
+                .iter()
+                .filter(|d| d.scope == DiscountScope::GlobalInvoice)
+                .map(template_discount)
+                .collect(),
+            charges: snapshot
+                .charges
+                .iter()
+                .filter(|c| c.scope == ChargeScope::GlobalInvoice)
+                .map(template_charge)
+                .collect(),
+        };
+
+        let hsn_summary = hsn_summary(&snapshot.line_items, &breakdown)
+            .into_iter()
+            .map(|row| TemplateHsnRow {
+                code: row.code,
+                taxable: format_amount(row.taxable),
+                tax_total: format_amount(row.tax_total),
+                taxes: row.taxes.iter().map(template_tax).collect(),
+            })
+            .collect();
+        let place_of_supply = snapshot
+            .gst_supply
+            .and_then(|_| place_of_supply_code(&document.client_info, &document.place_of_supply))
+            .map(|code| format!("{code} {}", gst_state_name(&code).unwrap_or_default()));
+
+        let status = match document.document_type {
+            DocumentType::Invoice | DocumentType::CreditNote => document
+                .status
+                .with_payments(
+                    breakdown.net_payable,
+                    amount_paid(&document.payments) + amount_credited(&document.credits),
+                )
+                .label(),
+            DocumentType::Quote => document.quote_status.label(),
+        };
+
+        Self {
+            document_type: document.document_type.key().to_string(),
+            title: document.document_type.title().to_string(),
+            currency: document.currency.clone(),
+            number: document.number(),
+            issue_date: document.invoice_value(ISSUE_DATE_FIELD_ID),
+            due_date: document.invoice_value(DUE_DATE_FIELD_ID),
+            valid_until: document.valid_until.clone(),
+            credited_invoice_number: document.credited_invoice_number.clone(),
+            status: status.to_string(),
+            logo: document
+                .logo
+                .as_ref()
+                .map(|logo| logo.path(AssetKind::Logo)),
+            signature: document
+                .signature
+                .as_ref()
+                .map(|signature| signature.path(AssetKind::Signature)),
+            invoice: template_fields(fields, FieldCategory::Invoice, &document.invoice_info),
+            biller: template_fields(fields, FieldCategory::Biller, &document.biller_info),
+            client: template_fields(fields, FieldCategory::Client, &document.client_info),
+            extra: template_fields(fields, FieldCategory::ExtraInfo, &document.extra_info),
+            items,
+            totals,
+            tax_exemption: document
+                .tax_exempt
+                .then(|| document.tax_exemption_note.clone()),
+            place_of_supply,
+            hsn_summary,
+            vat_notes: breakdown.exemption_notes(),
+        }
+    }
+
+    /// Made-up invoice used to preview templates in the editor
+    pub fn sample() -> Self {
+        let field = |id: &str, label: &str, value: &str| TemplateField {
+            id: id.to_string(),
+            label: label.to_string(),
+            value: value.to_string(),
+        };
+        let vat = |taxable: &str, amount: &str| TemplateTax {
+            id: "vat".to_string(),
+            name: "VAT".to_string(),
+            rate: "10%".to_string(),
+            taxable: taxable.to_string(),
+            amount: amount.to_string(),
+            included: false,
+            category: TaxCategory::Standard.code().to_string(),
+            exemption_reason: String::new(),
+        };
+        let item = |id: &str, name: &str, quantity: &str, unit_price: &str, gross: &str| {
+            TemplateLineItem {
+                id: id.to_string(),
+                name: name.to_string(),
+                quantity: quantity.to_string(),
+                unit_price: unit_price.to_string(),
+                gross: gross.to_string(),
+                discount_total: "0.00".to_string(),
+                global_discount: "0.00".to_string(),
+                taxable: gross.to_string(),
+                charge_total: "0.00".to_string(),
+                ..Default::default()
+            }
+        };
+
+        Self {
+            document_type: DocumentType::Invoice.key().to_string(),
+            title: DocumentType::Invoice.title().to_string(),
+            currency: "$".to_string(),
+            number: "INV-2024-001".to_string(),
+            issue_date: "2024-08-16".to_string(),
+            due_date: "2024-08-30".to_string(),
+            valid_until: String::new(),
+            credited_invoice_number: String::new(),
+            status: InvoiceStatus::Draft.label().to_string(),
+            logo: None,
+            signature: None,
+            invoice: vec![
+                field(INVOICE_NUMBER_FIELD_ID, "Invoice Number", "INV-2024-001"),
+                field(ISSUE_DATE_FIELD_ID, "Issue Date", "2024-08-16"),
+                field(DUE_DATE_FIELD_ID, "Due Date", "2024-08-30"),
+            ],
+            biller: vec![
+                field("biller_name", "Biller Name", "Tech Solutions, LLC"),
+                field(
+                    "biller_address",
+                    "Biller Address",
+                    "123 Silicon Alley, San Jose",
+                ),
+                field("biller_email", "Biller Email", "billing@techsolutions.com"),
+            ],
+            client: vec![
+                field(
+                    CLIENT_NAME_FIELD_ID,
+                    "Client Name",
+                    "Startup Innovations Co.",
+                ),
+                field(
+                    "client_address",
+                    "Client Address",
+                    "456 Innovation Drive, Palo Alto",
+                ),
+            ],
+            extra: vec![field("notes", "Notes", "Payment is due within 14 days.")],
+            items: vec![
+                TemplateLineItem {
+                    tax_total: "540.00".to_string(),
+                    total: "5940.00".to_string(),
+                    taxes: vec![vat("5400.00", "540.00")],
+                    ..item("1", "Frontend development", "45", "120.00", "5400.00")
+                },
+                TemplateLineItem {
+                    tax_total: "450.00".to_string(),
+                    total: "4950.00".to_string(),
+                    taxes: vec![vat("4500.00", "450.00")],
+                    ..item("2", "Backend API development", "30", "150.00", "4500.00")
+                },
+            ],
+            totals: TemplateTotals {
+                subtotal: "9900.00".to_string(),
+                line_discounts: "0.00".to_string(),
+                global_discounts: "0.00".to_string(),
+                discount_total: "0.00".to_string(),
+                line_charges: "0.00".to_string(),
+                global_charges: "0.00".to_string(),
+                charge_total: "0.00".to_string(),
+                tax_total: "990.00".to_string(),
+                tax_included: "0.00".to_string(),
+                grand_total: "10890.00".to_string(),
+                withholding: vec![],
+                withholding_total: "0.00".to_string(),
+                net_payable: "10890.00".to_string(),
+                amount_paid: "0.00".to_string(),
+                amount_credited: "0.00".to_string(),
+                balance_due: "10890.00".to_string(),
+                taxes: vec![vat("9900.00", "990.00")],
+                discounts: vec![],
+                charges: vec![],
+            },
+            tax_exemption: None,
+            place_of_supply: None,
+            hsn_summary: vec![],
+            vat_notes: vec![],
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        // Only strings, lists and maps, serialization cannot fail
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::invoice::test_support::field_entry;
+    use rust_decimal::dec;
+
+    #[test]
+    fn amounts_are_serialized_as_formatted_strings() {
+        let document = InvoiceDocument {
+            currency: "€".to_string(),
+            invoice_info: HashMap::from([field_entry(
+                INVOICE_NUMBER_FIELD_ID,
+                "Invoice Number",
+                FieldValue::Text("INV-7".to_string()),
+            )]),
+            ..Default::default()
+        };
+        let vat = TaxItem {
+            id: "vat".to_string(),
+            name: "VAT".to_string(),
+            rate: dec!(10),
+            ..Default::default()
+        };
+        let snapshot = InvoiceSnapshot {
+            line_items: vec![LineItem {
+                id: "a".to_string(),
+                name: "Consulting".to_string(),
+                quantity: dec!(1.50),
+                unit_price: dec!(10.5),
+                taxes: [vat].into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let data = TemplateData::new(&document, &snapshot, &[]);
+        let json = serde_json::to_value(&data).unwrap();
+
+        let keys = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in [
+            "document_type",
+            "title",
+            "currency",
+            "number",
+            "items",
+            "totals",
+            "hsn_summary",
+            "vat_notes",
+        ] {
+            assert!(keys.contains(&key.to_string()), "{key} missing");
+        }
+        assert_eq!(json["document_type"], "invoice");
+        assert_eq!(json["number"], "INV-7");
+        assert_eq!(json["currency"], "€");
+
+        let item = &json["items"][0];
+        assert_eq!(item["quantity"], "1.5");
+        assert_eq!(item["unit_price"], "10.50");
+        assert_eq!(item["gross"], "15.75");
+        assert_eq!(item["taxes"][0]["rate"], "10%");
+        assert_eq!(item["taxes"][0]["amount"], "1.58");
+
+        let totals = &json["totals"];
+        assert_eq!(totals["subtotal"], "15.75");
+        assert_eq!(totals["tax_total"], "1.58");
+        assert_eq!(totals["grand_total"], "17.33");
+        assert_eq!(totals["balance_due"], "17.33");
+        assert_eq!(totals["discount_total"], "0.00");
+    }
+}
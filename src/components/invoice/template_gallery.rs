@@ -0,0 +1,153 @@
+use base64::Engine;
+use base64::engine::general_purpose;
+use leptos::prelude::*;
+
+use crate::shared::fonts::{FontRegistry, use_font_registry};
+use crate::shared::templates::TemplatePackage;
+use crate::shared::typst::{InMemoryWorld, VirtualFile, render_svg};
+
+/// First page of `template` rendered with `files` as an image URL, and the font
+/// families the template asks for that are not available
+fn thumbnail(
+    template: &TemplatePackage,
+    files: &[VirtualFile],
+    registry: FontRegistry,
+) -> (Option<String>, Vec<String>) {
+    let world = InMemoryWorld::new(template.source.clone(), registry.fonts())
+        .with_files(template.virtual_files())
+        .with_files(files.to_vec());
+
+    match render_svg(&world) {
+        Ok(rendered) => (
+            Some(format!(
+                "data:image/svg+xml;base64,{}",
+                general_purpose::STANDARD.encode(rendered.output)
+            )),
+            registry.resolve_missing(&rendered.warnings),
+        ),
+        Err(e) => {
+            log::error!(
+                "Failed to render thumbnail for template {}: {e}",
+                template.id
+            );
+            (None, registry.resolve_missing(&e.diagnostics))
+        }
+    }
+}
+
+/// Templates with a thumbnail of the current invoice, click one to use it. The
+/// thumbnails re-render when fonts a template asked for finish loading.
+#[component]
+pub fn TemplateGallery(
+    selected: RwSignal<String>,
+    #[prop(into)] templates: Signal<Vec<TemplatePackage>>,
+    /// Files the templates read, rendered once when the gallery opens
+    #[prop(into)]
+    files: Signal<Vec<VirtualFile>>,
+) -> impl IntoView {
+    let files = files.get_untracked();
+    let registry = use_font_registry();
+
+    view! {
+        <div class="grid grid-cols-2 md:grid-cols-3 xl:grid-cols-5 gap-4">
+            {move || {
+                templates
+                    .get()
+                    .into_iter()
+                    .map(|template| {
+                        let (thumbnail, missing_fonts) = thumbnail(&template, &files, registry);
+                        let id = template.id.clone();
+                        let is_selected = move || selected.get() == id;
+                        let id = template.id.clone();
+                        view! {
+                            <button
+                                type="button"
+                                class=move || {
+                                    if is_selected() {
+                                        "rounded-lg border-2 border-indigo-600 p-2 text-left"
+                                    } else {
+                                        "rounded-lg border-2 border-gray-200 p-2 text-left hover:border-gray-400"
+                                    }
+                                }
+                                on:click=move |_| selected.set(id.clone())
+                            >
+                                <div class="h-40 flex items-center justify-center overflow-hidden bg-gray-50 rounded">
+                                    {match thumbnail {
+                                        Some(src) => {
+                                            view! {
+                                                <img
+                                                    class="max-h-40 object-contain shadow-sm bg-white"
+                                                    src=src
+                                                    alt=template.name.clone()
+                                                />
+                                            }
+                                                .into_any()
+                                        }
+                                        None => {
+                                            view! {
+                                                <span class="text-xs text-gray-400">
+                                                    "Preview unavailable"
+                                                </span>
+                                            }
+                                                .into_any()
+                                        }
+                                    }}
+                                </div>
+                                <div class="mt-2 text-sm font-medium text-gray-900">
+                                    {template.name}
+                                </div>
+                                <div class="text-xs text-gray-500">{template.description}</div>
+                                {(!missing_fonts.is_empty())
+                                    .then(|| {
+                                        view! {
+                                            <div class="text-xs text-amber-600">
+                                                "Missing fonts: " {missing_fonts.join(", ")}
+                                            </div>
+                                        }
+                                    })}
+                            </button>
+                        }
+                    })
+                    .collect_view()
+            }}
+        </div>
+    }
+}
+
+#[component]
+pub fn TemplateSelect(
+    selected: RwSignal<String>,
+    #[prop(into)] templates: Signal<Vec<TemplatePackage>>,
+) -> impl IntoView {
+    view! {
+        <div>
+            <label class="form-label" for="template">
+                "Template"
+            </label>
+            <select
+                class="form-select"
+                id="template"
+                name="template"
+                on:change=move |ev| selected.set(event_target_value(&ev))
+            >
+                {move || {
+                    templates
+                        .get()
+                        .into_iter()
+                        .map(|template| {
+                            let id = template.id.clone();
+                            view! {
+                                <option
+                                    value=template.id
+                                    selected=move || selected.get() == id
+                                >
+                                    {template.name}
+                                </option>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </select>
+        </div>
+    }
+}
@@ -0,0 +1,13 @@
+use super::{FieldItemValue, FieldValue};
+
+/// Entry of a field value map such as `InvoiceDocument::invoice_info`
+pub fn field_entry(id: &str, label: &str, value: FieldValue) -> (String, FieldItemValue) {
+    (
+        id.to_string(),
+        FieldItemValue {
+            id: id.to_string(),
+            label: label.to_string(),
+            value,
+        },
+    )
+}
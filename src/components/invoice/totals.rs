@@ -1,99 +1,41 @@
 use super::*;
 use leptos::prelude::*;
 
+const ROUNDING_MODES: [RoundingMode; 2] = [RoundingMode::HalfUp, RoundingMode::HalfEven];
+const TAX_ROUNDINGS: [TaxRounding; 2] = [TaxRounding::PerLine, TaxRounding::PerInvoice];
+
 #[component]
 pub fn TotalsCard(
-    line_items: RwSignal<Vec<LineItem>>,
-    taxes: ReadSignal<Vec<TaxItem>>,
-    discounts: ReadSignal<Vec<DiscountItem>>,
-    charges: ReadSignal<Vec<ChargeItem>>,
+    snapshot: Memo<InvoiceSnapshot>,
+    rounding: RwSignal<RoundingPolicy>,
+    payments: ReadSignal<Vec<Payment>>,
+    credits: ReadSignal<Vec<Credit>>,
 ) -> impl IntoView {
-    let totals = Memo::new(move |_| {
-        let items = line_items.get();
-
-        // Subtotal calculation
-        let mut subtotal = 0.0;
-        for item in &items {
-            subtotal += item.unit_price * item.quantity;
-        }
-
-        // Line-item discounts
-        let mut line_item_discounts = 0.0;
-        for item in &items {
-            for discount in &item.discounts {
-                match discount.discount_type {
-                    DiscountType::FixedAmount => {
-                        line_item_discounts += discount.value;
-                    }
-                    DiscountType::Percentage => {
-                        line_item_discounts +=
-                            (item.unit_price * item.quantity) * (discount.value / 100.0);
-                    }
-                }
-            }
-        }
+    let totals = Memo::new(move |_| snapshot.with(calculate_invoice));
 
-        // Global discounts
-        let mut global_discounts = 0.0;
-        for discount in discounts.get() {
-            if discount.scope == DiscountScope::GlobalInvoice {
-                match discount.discount_type {
-                    DiscountType::FixedAmount => {
-                        global_discounts += discount.value;
-                    }
-                    DiscountType::Percentage => {
-                        global_discounts += subtotal * (discount.value / 100.0);
-                    }
-                }
-            }
-        }
-
-        let total_discounts = line_item_discounts + global_discounts;
-
-        // Charges
-        let mut line_item_charges = 0.0;
-        for item in &items {
-            for charge in &item.charges {
-                line_item_charges += charge.amount;
-            }
-        }
+    let hsn_rows = Memo::new(move |_| {
+        snapshot.with(|snapshot| hsn_summary(&snapshot.line_items, &totals.get()))
+    });
 
-        let mut global_charges = 0.0;
-        for charge in charges.get() {
-            if charge.scope == ChargeScope::GlobalInvoice {
-                global_charges += charge.amount;
-            }
+    let handle_mode_change = move |ev| {
+        let value = event_target_value(&ev);
+        if let Some(mode) = ROUNDING_MODES
+            .into_iter()
+            .find(|m| format!("{m:?}") == value)
+        {
+            rounding.update(|policy| policy.mode = mode);
         }
-
-        let total_charges = line_item_charges + global_charges;
-
-        // Tax calculation
-        let mut tax_total = 0.0;
-        for item in &items {
-            let item_total = item.unit_price * item.quantity;
-            let item_discount = item.discounts.iter().fold(0.0, |acc, d| {
-                acc + match d.discount_type {
-                    DiscountType::FixedAmount => d.value,
-                    DiscountType::Percentage => item_total * (d.value / 100.0),
-                }
-            });
-            let taxable_amount = item_total - item_discount;
-
-            for tax in &item.taxes {
-                tax_total += taxable_amount * (tax.rate / 100.0);
-            }
+    };
+
+    let handle_tax_rounding_change = move |ev| {
+        let value = event_target_value(&ev);
+        if let Some(tax_rounding) = TAX_ROUNDINGS
+            .into_iter()
+            .find(|r| format!("{r:?}") == value)
+        {
+            rounding.update(|policy| policy.tax_rounding = tax_rounding);
         }
-
-        let final_total = subtotal - total_discounts + total_charges + tax_total;
-
-        (
-            subtotal,
-            total_discounts,
-            total_charges,
-            tax_total,
-            final_total,
-        )
-    });
+    };
 
     view! {
         <div class="bg-white shadow rounded-lg p-4">
@@ -101,27 +43,196 @@ pub fn TotalsCard(
 
             <div class="flex justify-between text-sm mb-1">
                 <span>"Subtotal"</span>
-                <span>{format!("{:.2}", totals.get().0)}</span>
+                <span>{move || format_amount(totals.get().subtotal)}</span>
             </div>
 
             <div class="flex justify-between text-sm mb-1">
                 <span>"Discounts"</span>
-                <span class="text-red-600">- {format!("{:.2}", totals.get().1)}</span>
+                <span class="text-red-600">
+                    - {move || format_amount(totals.get().total_discounts)}
+                </span>
             </div>
 
             <div class="flex justify-between text-sm mb-1">
                 <span>"Charges"</span>
-                <span class="text-green-600">+ {format!("{:.2}", totals.get().2)}</span>
+                <span class="text-green-600">
+                    + {move || format_amount(totals.get().total_charges)}
+                </span>
             </div>
 
+            <For
+                each=move || totals.get().taxes
+                key=|tax| (tax.tax_id.clone(), tax.included, tax.amount)
+                children=move |tax| {
+                    let label = match tax.tax_type {
+                        TaxType::Percentage => format!("{} ({}%)", tax.name, tax.rate),
+                        TaxType::FixedAmount => tax.name.clone(),
+                    };
+                    let label = if tax.category.is_charged() {
+                        label
+                    } else {
+                        format!("{label}, {}", tax.category.label())
+                    };
+                    // Taxes included in the prices are already part of the subtotal
+                    let sign = if tax.included { "incl." } else { "+" };
+                    view! {
+                        <div class="flex justify-between text-sm mb-1">
+                            <span>{label}</span>
+                            <span class="text-yellow-600">{sign} " " {format_amount(tax.amount)}</span>
+                        </div>
+                    }
+                }
+            />
+
             <div class="flex justify-between text-sm mb-1">
                 <span>"Taxes"</span>
-                <span class="text-yellow-600">+ {format!("{:.2}", totals.get().3)}</span>
+                <span class="text-yellow-600">
+                    + {move || {
+                        let totals = totals.get();
+                        format_amount(totals.tax_total - totals.tax_included)
+                    }}
+                </span>
             </div>
 
+            <Show when=move || !totals.get().tax_included.is_zero()>
+                <div class="flex justify-between text-sm mb-1">
+                    <span>"Included taxes"</span>
+                    <span class="text-gray-600">
+                        {move || format_amount(totals.get().tax_included)}
+                    </span>
+                </div>
+            </Show>
+
+            <For
+                each=move || totals.get().exemption_notes()
+                key=|note| note.clone()
+                children=move |note| view! { <p class="text-xs text-gray-500 mb-1">{note}</p> }
+            />
+
             <div class="border-t mt-2 pt-2 flex justify-between font-semibold text-base">
                 <span>"Total"</span>
-                <span>{format!("{:.2}", totals.get().4)}</span>
+                <span>{move || format_amount(totals.get().grand_total)}</span>
+            </div>
+
+            <Show when=move || !totals.get().withholding.is_empty()>
+                <div class="mt-2">
+                    <For
+                        each=move || totals.get().withholding
+                        key=|tax| (tax.tax_id.clone(), tax.amount)
+                        children=move |tax| {
+                            let label = match tax.tax_type {
+                                TaxType::Percentage => format!("{} withheld ({}%)", tax.name, tax.rate),
+                                TaxType::FixedAmount => format!("{} withheld", tax.name),
+                            };
+                            view! {
+                                <div class="flex justify-between text-sm mb-1">
+                                    <span>{label}</span>
+                                    <span class="text-red-600">"- " {format_amount(tax.amount)}</span>
+                                </div>
+                            }
+                        }
+                    />
+                    <div class="flex justify-between font-semibold text-base">
+                        <span>"Net payable"</span>
+                        <span>{move || format_amount(totals.get().net_payable)}</span>
+                    </div>
+                </div>
+            </Show>
+
+            <Show when=move || hsn_rows.with(|rows| !rows.is_empty())>
+                <div class="border-t mt-2 pt-2">
+                    <h3 class="text-sm font-medium mb-1">"GST by HSN/SAC"</h3>
+                    <HsnSummary rows=hsn_rows />
+                </div>
+            </Show>
+
+            <Show when=move || {
+                payments.with(|payments| !payments.is_empty())
+                    || credits.with(|credits| !credits.is_empty())
+            }>
+                <div class="flex justify-between text-sm mt-2 mb-1">
+                    <span>"Amount paid"</span>
+                    <span class="text-green-600">
+                        - {move || payments.with(|payments| format_amount(amount_paid(payments)))}
+                    </span>
+                </div>
+                <Show when=move || credits.with(|credits| !credits.is_empty())>
+                    <div class="flex justify-between text-sm mb-1">
+                        <span>"Credited"</span>
+                        <span class="text-green-600">
+                            - {move || credits.with(|credits| format_amount(amount_credited(credits)))}
+                        </span>
+                    </div>
+                </Show>
+                <div class="flex justify-between font-semibold text-base">
+                    <span>"Balance due"</span>
+                    <span>
+                        {move || {
+                            payments
+                                .with(|payments| {
+                                    credits
+                                        .with(|credits| {
+                                            format_amount(
+                                                balance_due(totals.get().net_payable, payments, credits),
+                                            )
+                                        })
+                                })
+                        }}
+                    </span>
+                </div>
+            </Show>
+
+            <div class="border-t mt-4 pt-4 grid grid-cols-2 gap-4">
+                <div>
+                    <label class="form-label" for="rounding-mode">
+                        "Rounding"
+                    </label>
+                    <select
+                        class="form-select"
+                        id="rounding-mode"
+                        name="rounding-mode"
+                        on:change=handle_mode_change
+                    >
+                        {ROUNDING_MODES
+                            .into_iter()
+                            .map(|mode| {
+                                view! {
+                                    <option
+                                        value=format!("{mode:?}")
+                                        selected=move || rounding.get().mode == mode
+                                    >
+                                        {mode.label()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                </div>
+                <div>
+                    <label class="form-label" for="tax-rounding">
+                        "Round taxes"
+                    </label>
+                    <select
+                        class="form-select"
+                        id="tax-rounding"
+                        name="tax-rounding"
+                        on:change=handle_tax_rounding_change
+                    >
+                        {TAX_ROUNDINGS
+                            .into_iter()
+                            .map(|tax_rounding| {
+                                view! {
+                                    <option
+                                        value=format!("{tax_rounding:?}")
+                                        selected=move || rounding.get().tax_rounding == tax_rounding
+                                    >
+                                        {tax_rounding.label()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                </div>
             </div>
         </div>
     }
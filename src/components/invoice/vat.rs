@@ -0,0 +1,576 @@
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::*;
+
+/// EU member state with the formats of its VAT numbers after the prefix. In a
+/// format `#` is a digit, `@` a letter and `*` either, other characters match
+/// themselves.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EuCountry {
+    pub code: &'static str,
+    pub vat_prefix: &'static str,
+    pub name: &'static str,
+    formats: &'static [&'static str],
+}
+
+pub const EU_COUNTRIES: &[EuCountry] = &[
+    EuCountry {
+        code: "AT",
+        vat_prefix: "AT",
+        name: "Austria",
+        formats: &["U########"],
+    },
+    EuCountry {
+        code: "BE",
+        vat_prefix: "BE",
+        name: "Belgium",
+        formats: &["##########"],
+    },
+    EuCountry {
+        code: "BG",
+        vat_prefix: "BG",
+        name: "Bulgaria",
+        formats: &["#########", "##########"],
+    },
+    EuCountry {
+        code: "CY",
+        vat_prefix: "CY",
+        name: "Cyprus",
+        formats: &["########@"],
+    },
+    EuCountry {
+        code: "CZ",
+        vat_prefix: "CZ",
+        name: "Czechia",
+        formats: &["########", "#########", "##########"],
+    },
+    EuCountry {
+        code: "DE",
+        vat_prefix: "DE",
+        name: "Germany",
+        formats: &["#########"],
+    },
+    EuCountry {
+        code: "DK",
+        vat_prefix: "DK",
+        name: "Denmark",
+        formats: &["########"],
+    },
+    EuCountry {
+        code: "EE",
+        vat_prefix: "EE",
+        name: "Estonia",
+        formats: &["#########"],
+    },
+    EuCountry {
+        code: "ES",
+        vat_prefix: "ES",
+        name: "Spain",
+        formats: &["*#######*"],
+    },
+    EuCountry {
+        code: "FI",
+        vat_prefix: "FI",
+        name: "Finland",
+        formats: &["########"],
+    },
+    EuCountry {
+        code: "FR",
+        vat_prefix: "FR",
+        name: "France",
+        formats: &["**#########"],
+    },
+    EuCountry {
+        code: "GR",
+        vat_prefix: "EL",
+        name: "Greece",
+        formats: &["#########"],
+    },
+    EuCountry {
+        code: "HR",
+        vat_prefix: "HR",
+        name: "Croatia",
+        formats: &["###########"],
+    },
+    EuCountry {
+        code: "HU",
+        vat_prefix: "HU",
+        name: "Hungary",
+        formats: &["########"],
+    },
+    EuCountry {
+        code: "IE",
+        vat_prefix: "IE",
+        name: "Ireland",
+        formats: &["#######@", "#######@@", "#*#####@"],
+    },
+    EuCountry {
+        code: "IT",
+        vat_prefix: "IT",
+        name: "Italy",
+        formats: &["###########"],
+    },
+    EuCountry {
+        code: "LT",
+        vat_prefix: "LT",
+        name: "Lithuania",
+        formats: &["#########", "############"],
+    },
+    EuCountry {
+        code: "LU",
+        vat_prefix: "LU",
+        name: "Luxembourg",
+        formats: &["########"],
+    },
+    EuCountry {
+        code: "LV",
+        vat_prefix: "LV",
+        name: "Latvia",
+        formats: &["###########"],
+    },
+    EuCountry {
+        code: "MT",
+        vat_prefix: "MT",
+        name: "Malta",
+        formats: &["########"],
+    },
+    EuCountry {
+        code: "NL",
+        vat_prefix: "NL",
+        name: "Netherlands",
+        formats: &["#########B##"],
+    },
+    EuCountry {
+        code: "PL",
+        vat_prefix: "PL",
+        name: "Poland",
+        formats: &["##########"],
+    },
+    EuCountry {
+        code: "PT",
+        vat_prefix: "PT",
+        name: "Portugal",
+        formats: &["#########"],
+    },
+    EuCountry {
+        code: "RO",
+        vat_prefix: "RO",
+        name: "Romania",
+        formats: &[
+            "##",
+            "###",
+            "####",
+            "#####",
+            "######",
+            "#######",
+            "########",
+            "#########",
+            "##########",
+        ],
+    },
+    EuCountry {
+        code: "SE",
+        vat_prefix: "SE",
+        name: "Sweden",
+        formats: &["##########01"],
+    },
+    EuCountry {
+        code: "SI",
+        vat_prefix: "SI",
+        name: "Slovenia",
+        formats: &["########"],
+    },
+    EuCountry {
+        code: "SK",
+        vat_prefix: "SK",
+        name: "Slovakia",
+        formats: &["##########"],
+    },
+];
+
+/// Member state by ISO code, VAT prefix or English name
+pub fn eu_country(country: &str) -> Option<&'static EuCountry> {
+    let country = country.trim();
+    EU_COUNTRIES.iter().find(|eu| {
+        eu.code.eq_ignore_ascii_case(country)
+            || eu.vat_prefix.eq_ignore_ascii_case(country)
+            || eu.name.eq_ignore_ascii_case(country)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VatIdError {
+    #[error("A VAT number starts with the code of an EU country")]
+    Country,
+    #[error("Not the format of a VAT number from {0}")]
+    Format(&'static str),
+    #[error("Check digits do not match")]
+    Checksum,
+}
+
+fn matches_format(number: &str, format: &str) -> bool {
+    number.len() == format.len()
+        && number.bytes().zip(format.bytes()).all(|(c, f)| match f {
+            b'#' => c.is_ascii_digit(),
+            b'@' => c.is_ascii_uppercase(),
+            b'*' => c.is_ascii_alphanumeric(),
+            f => c == f,
+        })
+}
+
+fn digits(number: &str) -> Vec<u32> {
+    number.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+fn digit_sum(n: u32) -> u32 {
+    n / 10 + n % 10
+}
+
+fn luhn(digits: &[u32]) -> bool {
+    let sum = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, &d)| if index % 2 == 1 { digit_sum(2 * d) } else { d })
+        .sum::<u32>();
+    sum.is_multiple_of(10)
+}
+
+/// Weighted sum of `digits` with `weights`, the checksum most countries build on
+fn weighted(digits: &[u32], weights: &[u32]) -> u32 {
+    digits.iter().zip(weights).map(|(d, w)| d * w).sum()
+}
+
+/// Whether the check digits of `number` match, for the countries whose algorithm
+/// is published. Numbers of the other countries are only checked for their format.
+fn checksum_matches(country: &str, number: &str) -> bool {
+    let d = digits(number);
+    match country {
+        "AT" => {
+            let sum = d[0]
+                + digit_sum(2 * d[1])
+                + d[2]
+                + digit_sum(2 * d[3])
+                + d[4]
+                + digit_sum(2 * d[5])
+                + d[6];
+            (10 - (sum + 4) % 10) % 10 == d[7]
+        }
+        "BE" => {
+            let base = number[..8].parse::<u32>().unwrap_or_default();
+            d[0] <= 1 && 97 - base % 97 == number[8..].parse::<u32>().unwrap_or_default()
+        }
+        "DE" => {
+            // ISO 7064 MOD 11,10
+            let mut product = 10;
+            for &digit in &d[..8] {
+                let sum = match (digit + product) % 10 {
+                    0 => 10,
+                    sum => sum,
+                };
+                product = 2 * sum % 11;
+            }
+            (11 - product) % 10 == d[8]
+        }
+        "DK" => weighted(&d, &[2, 7, 6, 5, 4, 3, 2, 1]).is_multiple_of(11),
+        "FI" => match weighted(&d, &[7, 9, 10, 5, 8, 4, 2]) % 11 {
+            0 => d[7] == 0,
+            1 => false,
+            rest => 11 - rest == d[7],
+        },
+        // Older numbers have a numeric key, newer ones a letter that is not checked
+        "FR" => match number[..2].parse::<u64>() {
+            Ok(key) => {
+                let siren = number[2..].parse::<u64>().unwrap_or_default();
+                key == (12 + 3 * (siren % 97)) % 97
+            }
+            Err(_) => true,
+        },
+        "IT" => luhn(&d),
+        "LU" => {
+            number[..6].parse::<u32>().unwrap_or_default() % 89
+                == number[6..].parse::<u32>().unwrap_or_default()
+        }
+        // Numbers issued since 2020 use MOD 97-10 on the whole id, older ones an
+        // eleven test on the first nine digits
+        "NL" => {
+            let eleven_test = weighted(&d, &[9, 8, 7, 6, 5, 4, 3, 2]) % 11 == d[8];
+            let remainder = format!("NL{number}")
+                .chars()
+                .filter_map(|c| c.to_digit(36))
+                .fold(0, |rest, value| {
+                    let shift = if value < 10 { 10 } else { 100 };
+                    (rest * shift + value) % 97
+                });
+            eleven_test || remainder == 1
+        }
+        "PL" => weighted(&d, &[6, 5, 7, 2, 3, 4, 5, 6, 7]) % 11 == d[9],
+        "PT" => {
+            let check = 11 - weighted(&d, &[9, 8, 7, 6, 5, 4, 3, 2]) % 11;
+            (if check > 9 { 0 } else { check }) == d[8]
+        }
+        "SE" => luhn(&d[..10]),
+        _ => true,
+    }
+}
+
+/// Checks the country prefix, format and, where published, the check digits of
+/// an EU VAT number. Returns the ISO code of the country that issued it.
+pub fn validate_vat_id(vat_id: &str) -> Result<&'static str, VatIdError> {
+    let vat_id = vat_id
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '.' | '-'))
+        .collect::<String>()
+        .to_ascii_uppercase();
+    let country = EU_COUNTRIES
+        .iter()
+        .find(|eu| vat_id.starts_with(eu.vat_prefix))
+        .ok_or(VatIdError::Country)?;
+    let number = &vat_id[country.vat_prefix.len()..];
+    if !country
+        .formats
+        .iter()
+        .any(|format| matches_format(number, format))
+    {
+        return Err(VatIdError::Format(country.name));
+    }
+    if !checksum_matches(country.code, number) {
+        return Err(VatIdError::Checksum);
+    }
+    Ok(country.code)
+}
+
+/// Whether an invoice sells goods or services, goods to EU businesses are
+/// intra-community supplies and services are reverse charged
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum VatSupply {
+    #[default]
+    Services,
+    Goods,
+}
+
+impl VatSupply {
+    pub const ALL: [VatSupply; 2] = [VatSupply::Services, VatSupply::Goods];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VatSupply::Services => "Services",
+            VatSupply::Goods => "Goods",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|supply| supply.label() == label)
+            .unwrap_or_default()
+    }
+}
+
+/// Country of a biller or client, the ISO code for EU countries. Taken from the
+/// country field and otherwise from the prefix of a valid VAT number.
+pub fn party_country(
+    values: &HashMap<String, FieldItemValue>,
+    country_field_id: &str,
+    tax_id_field_id: &str,
+) -> Option<String> {
+    let country = field_value(values, country_field_id, |label| label.contains("COUNTRY"));
+    if country.is_empty() {
+        validate_vat_id(&tax_id(values, tax_id_field_id))
+            .ok()
+            .map(str::to_string)
+    } else {
+        Some(
+            eu_country(&country)
+                .map(|eu| eu.code.to_string())
+                .unwrap_or_else(|| country.to_uppercase()),
+        )
+    }
+}
+
+/// Category the EU VAT rules give the taxes of an invoice, `None` when its taxes
+/// are charged as entered. That is for clients in the biller's country, for
+/// consumers in other EU countries and whenever a country is not known.
+pub fn vat_category(
+    biller_info: &HashMap<String, FieldItemValue>,
+    client_info: &HashMap<String, FieldItemValue>,
+    supply: VatSupply,
+) -> Option<TaxCategory> {
+    let biller_country =
+        party_country(biller_info, BILLER_COUNTRY_FIELD_ID, BILLER_TAX_ID_FIELD_ID)?;
+    eu_country(&biller_country)?;
+    let client_country =
+        party_country(client_info, CLIENT_COUNTRY_FIELD_ID, CLIENT_TAX_ID_FIELD_ID)?;
+    if client_country == biller_country {
+        return None;
+    }
+    if eu_country(&client_country).is_none() {
+        return Some(TaxCategory::Export);
+    }
+
+    // Only businesses registered for VAT in their own country account for it
+    let vat_country = validate_vat_id(&tax_id(client_info, CLIENT_TAX_ID_FIELD_ID)).ok()?;
+    (vat_country == client_country).then_some(match supply {
+        VatSupply::Services => TaxCategory::ReverseCharge,
+        VatSupply::Goods => TaxCategory::IntraCommunity,
+    })
+}
+
+/// VAT numbers, kind of supply and the category the EU VAT rules choose
+#[component]
+pub fn VatCard(
+    #[prop(into)] biller_info: Signal<HashMap<String, FieldItemValue>>,
+    #[prop(into)] client_info: Signal<HashMap<String, FieldItemValue>>,
+    vat_supply: RwSignal<VatSupply>,
+) -> impl IntoView {
+    let status = move |vat_id: String| match validate_vat_id(&vat_id) {
+        _ if vat_id.is_empty() => "Not entered".to_string(),
+        Ok(country) => format!(
+            "{vat_id}, {}",
+            eu_country(country).map(|eu| eu.name).unwrap_or_default()
+        ),
+        Err(e) => format!("{vat_id}: {e}"),
+    };
+    let biller_vat_id = move || biller_info.with(|info| tax_id(info, BILLER_TAX_ID_FIELD_ID));
+    let client_vat_id = move || client_info.with(|info| tax_id(info, CLIENT_TAX_ID_FIELD_ID));
+    let category = move || {
+        biller_info.with(|biller| {
+            client_info.with(|client| vat_category(biller, client, vat_supply.get()))
+        })
+    };
+
+    view! {
+        <div class="space-y-2">
+            <p class="text-xs text-gray-600">"Biller VAT number: " {move || status(biller_vat_id())}</p>
+            <p class="text-xs text-gray-600">"Client VAT number: " {move || status(client_vat_id())}</p>
+            <div>
+                <label class="form-label" for="vat-supply">
+                    "Supply of"
+                </label>
+                <select
+                    class="form-select"
+                    id="vat-supply"
+                    name="vat-supply"
+                    prop:value=move || vat_supply.get().label()
+                    on:change=move |ev| vat_supply.set(VatSupply::from_label(&event_target_value(&ev)))
+                >
+                    {VatSupply::ALL
+                        .into_iter()
+                        .map(|supply| view! { <option value=supply.label()>{supply.label()}</option> })
+                        .collect_view()}
+                </select>
+            </div>
+            <p class="text-xs text-gray-600">
+                {move || match category() {
+                    Some(category) => {
+                        format!("{}: {}", category.label(), category.default_reason())
+                    }
+                    None => "Taxes are charged as entered".to_string(),
+                }}
+            </p>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::invoice::test_support::field_entry;
+
+    fn values(fields: &[(&str, &str, &str)]) -> HashMap<String, FieldItemValue> {
+        fields
+            .iter()
+            .map(|(id, label, value)| field_entry(id, label, FieldValue::Text(value.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn vat_ids_are_checked_per_country() {
+        for (vat_id, country) in [
+            ("ATU13585627", "AT"),
+            ("BE 0403.170.701", "BE"),
+            ("DE136695976", "DE"),
+            ("DK13585628", "DK"),
+            ("FI20774740", "FI"),
+            ("FR40303265045", "FR"),
+            ("IT00743110157", "IT"),
+            ("LU15027442", "LU"),
+            ("NL004495445B01", "NL"),
+            ("PL5260250274", "PL"),
+            ("PT501964843", "PT"),
+            ("SE556188840401", "SE"),
+            ("EL094259216", "GR"),
+            ("ESB12345678", "ES"),
+        ] {
+            assert_eq!(validate_vat_id(vat_id), Ok(country), "{vat_id}");
+        }
+
+        assert_eq!(validate_vat_id("DE136695977"), Err(VatIdError::Checksum));
+        assert_eq!(validate_vat_id("BE0403170702"), Err(VatIdError::Checksum));
+        assert_eq!(
+            validate_vat_id("DE13669597"),
+            Err(VatIdError::Format("Germany"))
+        );
+        assert_eq!(validate_vat_id("US123456789"), Err(VatIdError::Country));
+    }
+
+    #[test]
+    fn rules_choose_the_category_from_countries_and_vat_ids() {
+        let biller = values(&[(BILLER_TAX_ID_FIELD_ID, "Tax ID", "DE136695976")]);
+        let category =
+            |client: &[(&str, &str, &str)], supply| vat_category(&biller, &values(client), supply);
+
+        let french_business = [(CLIENT_TAX_ID_FIELD_ID, "Client Tax ID", "FR40303265045")];
+        assert_eq!(
+            category(&french_business, VatSupply::Services),
+            Some(TaxCategory::ReverseCharge)
+        );
+        assert_eq!(
+            category(&french_business, VatSupply::Goods),
+            Some(TaxCategory::IntraCommunity)
+        );
+
+        let french_consumer = [(CLIENT_COUNTRY_FIELD_ID, "Client Country", "France")];
+        assert_eq!(category(&french_consumer, VatSupply::Services), None);
+
+        let german_business = [(CLIENT_TAX_ID_FIELD_ID, "Client Tax ID", "DE811569869")];
+        assert_eq!(category(&german_business, VatSupply::Services), None);
+
+        let american = [(CLIENT_COUNTRY_FIELD_ID, "Client Country", "United States")];
+        assert_eq!(
+            category(&american, VatSupply::Goods),
+            Some(TaxCategory::Export)
+        );
+
+        // A VAT number from another country than the client's does not count
+        let mismatched = [
+            (CLIENT_COUNTRY_FIELD_ID, "Client Country", "IT"),
+            (CLIENT_TAX_ID_FIELD_ID, "Client Tax ID", "FR40303265045"),
+        ];
+        assert_eq!(category(&mismatched, VatSupply::Services), None);
+        assert_eq!(category(&[], VatSupply::Services), None);
+    }
+
+    #[test]
+    fn legacy_country_fields_are_picked_by_the_lowest_id() {
+        let client = values(&[
+            ("b", "Country", "Germany"),
+            ("a", "Client country", "France"),
+        ]);
+        assert_eq!(
+            party_country(&client, CLIENT_COUNTRY_FIELD_ID, CLIENT_TAX_ID_FIELD_ID).as_deref(),
+            Some("FR")
+        );
+
+        let client = values(&[
+            (CLIENT_COUNTRY_FIELD_ID, "Country", "Italy"),
+            ("a", "Client country", "France"),
+        ]);
+        assert_eq!(
+            party_country(&client, CLIENT_COUNTRY_FIELD_ID, CLIENT_TAX_ID_FIELD_ID).as_deref(),
+            Some("IT")
+        );
+    }
+}
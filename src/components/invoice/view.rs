@@ -1,7 +1,16 @@
 use leptos::prelude::*;
+use leptos::task::spawn_local;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
 
 use super::*;
 use crate::components::ui::{Tab, TabPanel, TabsProvider};
+use crate::shared::hooks::use_biller_profiles::{
+    delete_biller_profile, save_biller_profile, upsert_biller_profile, use_biller_profiles,
+};
+use crate::shared::hooks::use_clients::{save_client, upsert_client, use_clients};
+use crate::shared::hooks::use_user_templates::use_user_templates;
+use crate::shared::templates::all_templates;
 
 #[component]
 pub fn InternalTab(name: String, label: String) -> impl IntoView {
@@ -35,8 +44,193 @@ pub fn InternalTab(name: String, label: String) -> impl IntoView {
 }
 
 #[component]
-pub fn InvoiceBuilder() -> impl IntoView {
+pub fn InvoiceBuilder(
+    /// Invoice to open, the most recently edited one when not set
+    #[prop(optional_no_strip)]
+    invoice_id: Option<String>,
+    /// Kind of document to start when `invoice_id` was never saved
+    #[prop(optional_no_strip)]
+    document_type: Option<DocumentType>,
+) -> impl IntoView {
     let invoice_builder_state = InvoiceBuilderState::new();
+    if let Some(document_type) = document_type {
+        invoice_builder_state.set_document_type(document_type);
+    }
+    use_invoice_persistence(invoice_builder_state, invoice_id);
+    let snapshot = Memo::new(move |_| invoice_builder_state.snapshot());
+    provide_formula_inputs(invoice_builder_state, snapshot);
+    let user_templates = use_user_templates();
+    let templates = Signal::derive(move || all_templates(&user_templates.get()));
+    let clients = use_clients();
+
+    // Numbers are reserved before the status changes, a failure keeps the draft
+    let finalizing = StoredValue::new(false);
+    let on_finalize = Callback::new(move |status: InvoiceStatus| {
+        if finalizing.get_value() {
+            return;
+        }
+        finalizing.set_value(true);
+        spawn_local(async move {
+            match assign_counter_numbers(invoice_builder_state).await {
+                Ok(()) => {
+                    invoice_builder_state.keep_adjustments();
+                    invoice_builder_state.status.set(status);
+                    invoice_builder_state.needs_review.set(false);
+                }
+                Err(e) => {
+                    log::error!("Failed to assign invoice numbers: {e}");
+                    invoice_builder_state.status.set(InvoiceStatus::Draft);
+                }
+            }
+            finalizing.set_value(false);
+        });
+    });
+
+    let on_quote_status = Callback::new(move |status: QuoteStatus| {
+        if finalizing.get_value() {
+            return;
+        }
+        finalizing.set_value(true);
+        spawn_local(async move {
+            match assign_counter_numbers(invoice_builder_state).await {
+                Ok(()) => {
+                    invoice_builder_state.keep_adjustments();
+                    invoice_builder_state.quote_status.set(status);
+                }
+                Err(e) => {
+                    log::error!("Failed to assign the quote number: {e}");
+                    invoice_builder_state.quote_status.set(QuoteStatus::Draft);
+                }
+            }
+            finalizing.set_value(false);
+        });
+    });
+
+    let on_document_type = Callback::new(move |document_type: DocumentType| {
+        invoice_builder_state.set_document_type(document_type)
+    });
+
+    let on_convert = Callback::new(move |_| {
+        let quote = untrack(|| invoice_builder_state.document());
+        let line_items = invoice_builder_state.line_items.get_untracked();
+        spawn_local(async move {
+            match convert_quote(quote, line_items).await {
+                Ok(quote) => {
+                    invoice_builder_state.quote_status.set(quote.quote_status);
+                    invoice_builder_state
+                        .converted_invoice_id
+                        .set(quote.converted_invoice_id);
+                }
+                Err(e) => log::error!("Failed to convert the quote: {e}"),
+            }
+        });
+    });
+
+    // A credit note reduces what its invoice is owed for as long as it is sent
+    let on_credit_status = Callback::new(move |status: InvoiceStatus| {
+        if finalizing.get_value() {
+            return;
+        }
+        finalizing.set_value(true);
+        let previous = invoice_builder_state.status.get_untracked();
+        spawn_local(async move {
+            let numbered = if status.is_final() {
+                assign_counter_numbers(invoice_builder_state).await
+            } else {
+                Ok(())
+            };
+            if let Err(e) = numbered {
+                log::error!("Failed to assign the credit note number: {e}");
+                invoice_builder_state.status.set(previous);
+                finalizing.set_value(false);
+                return;
+            }
+            invoice_builder_state.status.set(status);
+            if let Err(e) = sync_credit(invoice_builder_state).await {
+                log::error!("Failed to update the credited invoice: {e}");
+            }
+            finalizing.set_value(false);
+        });
+    });
+
+    let created_credit_note = RwSignal::new(None::<String>);
+    let on_credit = Callback::new(move |quantities: HashMap<String, Decimal>| {
+        let invoice = untrack(|| invoice_builder_state.document());
+        let snapshot = snapshot.get_untracked();
+        spawn_local(async move {
+            match create_credit_note(invoice, snapshot, quantities).await {
+                Ok(credit_note_id) => created_credit_note.set(Some(credit_note_id)),
+                Err(e) => log::error!("Failed to create the credit note: {e}"),
+            }
+        });
+    });
+
+    let on_client_select =
+        Callback::new(move |client: Client| invoice_builder_state.apply_client(&client));
+
+    // Stores the client details of this invoice as a new directory entry
+    let on_client_save = Callback::new(move |_| {
+        let client = Client::new(invoice_builder_state.client_info.get_untracked());
+        spawn_local(async move {
+            match save_client(client.clone()).await {
+                Ok(()) => {
+                    invoice_builder_state.client_id.set(Some(client.id.clone()));
+                    upsert_client(clients, client);
+                }
+                Err(e) => log::error!("Failed to save client: {e}"),
+            }
+        });
+    });
+
+    let biller_profiles = use_biller_profiles();
+
+    let on_biller_select = Callback::new(move |profile: BillerProfile| {
+        invoice_builder_state.apply_biller_profile(&profile)
+    });
+
+    let store_biller_profile = move |profile: BillerProfile| {
+        spawn_local(async move {
+            match save_biller_profile(profile.clone()).await {
+                Ok(()) => {
+                    invoice_builder_state
+                        .biller_profile_id
+                        .set(Some(profile.id.clone()));
+                    upsert_biller_profile(biller_profiles, profile);
+                }
+                Err(e) => log::error!("Failed to save biller profile: {e}"),
+            }
+        });
+    };
+
+    let on_biller_create = Callback::new(move |name: String| {
+        let id = uuid::Uuid::new_v4().to_string();
+        store_biller_profile(invoice_builder_state.biller_profile(id, name));
+    });
+
+    let on_biller_update = Callback::new(move |_| {
+        let Some(id) = invoice_builder_state.biller_profile_id.get_untracked() else {
+            return;
+        };
+        let name = biller_profiles
+            .with_untracked(|profiles| profiles.iter().find(|p| p.id == id).map(|p| p.name.clone()))
+            .unwrap_or_default();
+        store_biller_profile(invoice_builder_state.biller_profile(id, name));
+    });
+
+    let on_biller_delete = Callback::new(move |_| {
+        let Some(id) = invoice_builder_state.biller_profile_id.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            match delete_biller_profile(id.clone()).await {
+                Ok(()) => {
+                    invoice_builder_state.biller_profile_id.set(None);
+                    biller_profiles.update(|profiles| profiles.retain(|p| p.id != id));
+                }
+                Err(e) => log::error!("Failed to delete biller profile: {e}"),
+            }
+        });
+    });
 
     view! {
         <div class="lg:col-span-2 space-y-6">
@@ -57,6 +251,10 @@ pub fn InvoiceBuilder() -> impl IntoView {
                                 name="other_charges".to_string()
                                 label="Other Charges".to_string()
                             />
+                            <InternalTab
+                                name="products".to_string()
+                                label="Products".to_string()
+                            />
                             <InternalTab
                                 name="custom_fields".to_string()
                                 label="Data Fields".to_string()
@@ -67,14 +265,63 @@ pub fn InvoiceBuilder() -> impl IntoView {
                     <TabPanel name="builder".to_string()>
                         <Builder
                             line_items=invoice_builder_state.line_items
+                            snapshot=snapshot
                             taxes=invoice_builder_state.taxes.read_only()
                             discounts=invoice_builder_state.discounts.read_only()
                             charges=invoice_builder_state.charges.read_only()
                             custom_fields=invoice_builder_state.custom_fields.read_only()
+                            products=invoice_builder_state.products.read_only()
                             extra_info=invoice_builder_state.extra_info
                             biller_info=invoice_builder_state.biller_info
                             client_info=invoice_builder_state.client_info
                             invoice_info=invoice_builder_state.invoice_info
+                            rounding=invoice_builder_state.rounding
+                            status=invoice_builder_state.status
+                            payments=invoice_builder_state.payments
+                            invoice_id=invoice_builder_state.id
+                            needs_review=invoice_builder_state.needs_review
+                            document_type=invoice_builder_state.document_type.read_only()
+                            on_document_type=on_document_type
+                            quote_status=invoice_builder_state.quote_status
+                            valid_until=invoice_builder_state.valid_until
+                            quote_id=invoice_builder_state.quote_id.read_only()
+                            converted_invoice_id=invoice_builder_state
+                                .converted_invoice_id
+                                .read_only()
+                            on_quote_status=on_quote_status
+                            on_convert=on_convert
+                            credits=invoice_builder_state.credits.read_only()
+                            credited_invoice_id=invoice_builder_state
+                                .credited_invoice_id
+                                .read_only()
+                            credited_invoice_number=invoice_builder_state
+                                .credited_invoice_number
+                                .read_only()
+                            on_credit_status=on_credit_status
+                            created_credit_note=created_credit_note.read_only()
+                            on_credit=on_credit
+                            on_finalize=on_finalize
+                            template_id=invoice_builder_state.template_id
+                            templates=templates
+                            logo=invoice_builder_state.logo
+                            signature=invoice_builder_state.signature
+                            currency=invoice_builder_state.currency
+                            tax_exempt=invoice_builder_state.tax_exempt
+                            tax_exemption_note=invoice_builder_state.tax_exemption_note
+                            prices_include_tax=invoice_builder_state.prices_include_tax
+                            place_of_supply=invoice_builder_state.place_of_supply
+                            vat_rules=invoice_builder_state.vat_rules
+                            vat_supply=invoice_builder_state.vat_supply
+                            clients=clients
+                            on_client_select=on_client_select
+                            on_client_save=on_client_save
+                            biller_profiles=biller_profiles
+                            biller_profile_id=invoice_builder_state.biller_profile_id
+                            number_series=invoice_builder_state.number_series
+                            on_biller_select=on_biller_select
+                            on_biller_create=on_biller_create
+                            on_biller_update=on_biller_update
+                            on_biller_delete=on_biller_delete
                         />
                     </TabPanel>
                     <TabPanel name="taxes".to_string()>
@@ -86,11 +333,20 @@ pub fn InvoiceBuilder() -> impl IntoView {
                     <TabPanel name="other_charges".to_string()>
                         <OtherCharges state=invoice_builder_state.charges />
                     </TabPanel>
+                    <TabPanel name="products".to_string()>
+                        <Products
+                            state=invoice_builder_state.products
+                            taxes=invoice_builder_state.taxes.read_only()
+                            discounts=invoice_builder_state.discounts.read_only()
+                            charges=invoice_builder_state.charges.read_only()
+                            custom_fields=invoice_builder_state.custom_fields.read_only()
+                        />
+                    </TabPanel>
                     <TabPanel name="custom_fields".to_string()>
                         <Fields state=invoice_builder_state.custom_fields />
                     </TabPanel>
                     <TabPanel name="preview".to_string()>
-                        <section class="p-6 text-gray-700">preview</section>
+                        <InvoicePreview state=invoice_builder_state templates=templates />
                     </TabPanel>
                 </div>
             </TabsProvider>
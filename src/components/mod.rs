@@ -1,6 +1,10 @@
 // pub mod invoice_builder;
+pub mod client_directory;
+pub mod diagnostics;
 pub mod editable_grid;
+pub mod font_manager;
 pub mod invoice;
 pub mod layout;
 pub mod pdf_viewer;
+pub mod template_editor;
 pub mod ui;
@@ -1,40 +1,85 @@
 use base64::Engine;
 use base64::engine::general_purpose;
 use leptos::prelude::*;
+use leptos_use::signal_debounced;
 
-use typst::foundations::Bytes;
-use typst::text::Font;
-use typst_pdf::PdfOptions;
+use crate::components::diagnostics::{DiagnosticsList, MissingFonts};
+use crate::shared::fonts::{unknown_font_family, use_font_registry};
+use crate::shared::typst::{InMemoryWorld, VirtualFile, render_pdf};
 
-use crate::shared::fonts::FONTS;
-use crate::shared::typst::InMemoryWorld;
+/// Delay before re-rendering after the template or data changed
+const RENDER_DEBOUNCE_MS: f64 = 400.0;
 
+/// Renders a Typst template to a PDF. Compile errors are listed above the last PDF
+/// that rendered, so a broken template never takes the page down. Fonts the
+/// template asks for are fetched on demand and it re-renders once they arrive.
 #[component]
-fn PdfViewer(content: String) -> impl IntoView {
-    let fonts: Vec<Font> = FONTS
-        .iter()
-        .filter_map(|data| Font::new(Bytes::new(*data), 0))
-        .collect();
+pub fn PdfViewer(
+    /// Typst template source
+    #[prop(into)]
+    source: Signal<String>,
+    /// Data files, images and imported sources the template can read
+    #[prop(into)]
+    files: Signal<Vec<VirtualFile>>,
+) -> impl IntoView {
+    let registry = use_font_registry();
+    let source: Signal<String> = signal_debounced(source, RENDER_DEBOUNCE_MS);
+    let files: Signal<Vec<VirtualFile>> = signal_debounced(files, RENDER_DEBOUNCE_MS);
 
-    let world = InMemoryWorld::new(content, fonts);
+    let rendered = Memo::new(move |_| {
+        let world = InMemoryWorld::new(source.get(), registry.fonts()).with_files(files.get());
+        render_pdf(&world)
+    });
 
-    // Render document
-    let document = typst::compile(&world)
-        .output
-        .expect("Error compiling typst");
+    // Keep showing the previous PDF while the template is broken
+    let pdf_url = RwSignal::new(None::<String>);
+    Effect::new(move |_| {
+        rendered.with(|rendered| {
+            if let Ok(rendered) = rendered {
+                // Encode the PDF's binary data using the standard Base64 engine.
+                let encoded = general_purpose::STANDARD.encode(&rendered.output);
+                pdf_url.set(Some(format!("data:application/pdf;base64,{encoded}")));
+            }
+        })
+    });
 
-    // Output to pdf
-    let pdf_data = typst_pdf::pdf(&document, &PdfOptions::default()).expect("Error exporting PDF");
-    // Encode the PDF's binary data using the standard Base64 engine.
-    let encoded = general_purpose::STANDARD.encode(pdf_data);
+    let all_diagnostics = Memo::new(move |_| match rendered.get() {
+        Ok(rendered) => rendered.warnings,
+        Err(e) => e.diagnostics,
+    });
 
-    // Create the data URL.
-    let data_url = format!("data:application/pdf;base64,{}", encoded);
+    let missing_fonts = RwSignal::new(vec![]);
+    Effect::new(move |_| {
+        missing_fonts
+            .set(all_diagnostics.with(|diagnostics| registry.resolve_missing(diagnostics)));
+    });
+
+    // Unknown fonts are reported by `MissingFonts` instead
+    let diagnostics = Signal::derive(move || {
+        all_diagnostics
+            .get()
+            .into_iter()
+            .filter(|diagnostic| unknown_font_family(diagnostic).is_none())
+            .collect::<Vec<_>>()
+    });
 
-    // In a framework like Leptos, this is the correct way to build the element.
     view! {
-        <div>
-            <iframe src=data_url style="width:100%; height:90vh; border:none;" />
+        <div class="space-y-4">
+            <MissingFonts families=missing_fonts />
+            <DiagnosticsList diagnostics=diagnostics />
+            {move || {
+                pdf_url
+                    .get()
+                    .map(|data_url| {
+                        view! {
+                            <iframe
+                                class=move || if rendered.with(Result::is_err) { "opacity-50" } else { "" }
+                                src=data_url
+                                style="width:100%; height:90vh; border:none;"
+                            />
+                        }
+                    })
+            }}
         </div>
-    };
+    }
 }
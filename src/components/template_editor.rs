@@ -0,0 +1,357 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use typst::foundations::Bytes;
+
+use crate::components::font_manager::FontManager;
+use crate::components::invoice::{TEMPLATE_DATA_PATH, TemplateData};
+use crate::components::pdf_viewer::PdfViewer;
+use crate::shared::dialog::confirm;
+use crate::shared::files::{download_file, read_file, selected_files};
+use crate::shared::hooks::use_user_templates::use_user_templates;
+use crate::shared::storage::{Database, StorageError};
+use crate::shared::templates::{PackageFile, TEMPLATES, TemplatePackage, all_templates};
+use crate::shared::typst::{MAIN_PATH, VirtualFile};
+
+/// Invoice data contract, shown next to the editor
+const DATA_CONTRACT: &str = include_str!("../../templates/README.md");
+
+async fn save_template(template: TemplatePackage) -> Result<(), StorageError> {
+    Database::open().await?.save_template(&template).await
+}
+
+async fn delete_template(template_id: String) -> Result<(), StorageError> {
+    Database::open().await?.delete_template(&template_id).await
+}
+
+/// Inserts or replaces `template` in the list of user templates
+fn upsert(templates: RwSignal<Vec<TemplatePackage>>, template: TemplatePackage) {
+    templates.update(
+        |templates| match templates.iter_mut().find(|t| t.id == template.id) {
+            Some(existing) => *existing = template,
+            None => templates.insert(0, template),
+        },
+    );
+}
+
+/// Editable copy of `template`
+fn copy_of(template: &TemplatePackage) -> TemplatePackage {
+    TemplatePackage {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: format!("Copy of {}", template.name),
+        built_in: false,
+        ..template.clone()
+    }
+}
+
+/// Edit user templates with a live preview against sample invoice data. Built-in
+/// templates are read only, they can be copied into a user template.
+#[component]
+pub fn TemplateEditor() -> impl IntoView {
+    let user_templates = use_user_templates();
+    let templates = Signal::derive(move || all_templates(&user_templates.get()));
+    let draft = RwSignal::new(TEMPLATES[0].package());
+    let error = RwSignal::new(None::<String>);
+
+    let sample_data = TemplateData::sample();
+    let sample_json = serde_json::to_string_pretty(&sample_data).unwrap_or_default();
+    let sample_file = VirtualFile::new(
+        TEMPLATE_DATA_PATH,
+        Bytes::new(sample_data.to_json().into_bytes()),
+    );
+
+    let source = Signal::derive(move || draft.with(|draft| draft.source.clone()));
+    let files = Signal::derive(move || {
+        let mut files = draft.with(TemplatePackage::virtual_files);
+        files.push(sample_file.clone());
+        files
+    });
+    let read_only = move || draft.with(|draft| draft.built_in);
+
+    let on_save = move |_| {
+        let template = draft.get_untracked();
+        spawn_local(async move {
+            match save_template(template.clone()).await {
+                Ok(()) => upsert(user_templates, template),
+                Err(e) => error.set(Some(format!("Failed to save template: {e}"))),
+            }
+        });
+    };
+
+    let on_delete = move |_| {
+        let template = draft.get_untracked();
+        if !confirm(&format!("Delete template \"{}\"?", template.name)) {
+            return;
+        }
+        spawn_local(async move {
+            match delete_template(template.id.clone()).await {
+                Ok(()) => {
+                    user_templates.update(|templates| templates.retain(|t| t.id != template.id));
+                    draft.set(TEMPLATES[0].package());
+                }
+                Err(e) => error.set(Some(format!("Failed to delete template: {e}"))),
+            }
+        });
+    };
+
+    let on_export = move |_| {
+        let template = draft.get_untracked();
+        if let Err(e) = download_file(
+            &template.bundle_file_name(),
+            "application/json",
+            template.to_bundle().as_bytes(),
+        ) {
+            error.set(Some(e));
+        }
+    };
+
+    let on_import = move |ev: web_sys::Event| {
+        let Some(file) = selected_files(&ev).into_iter().next() else {
+            return;
+        };
+        spawn_local(async move {
+            let imported = match read_file(&file).await {
+                Ok(data) => TemplatePackage::from_bundle(&data).map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+            let template = match imported {
+                Ok(template) => template,
+                Err(e) => {
+                    error.set(Some(format!("Failed to import {}: {e}", file.name())));
+                    return;
+                }
+            };
+            match save_template(template.clone()).await {
+                Ok(()) => {
+                    upsert(user_templates, template.clone());
+                    draft.set(template);
+                }
+                Err(e) => error.set(Some(format!("Failed to save template: {e}"))),
+            }
+        });
+    };
+
+    let on_add_files = move |ev: web_sys::Event| {
+        for file in selected_files(&ev) {
+            spawn_local(async move {
+                match read_file(&file).await {
+                    Ok(data) => draft.update(|draft| {
+                        let path = format!("/{}", file.name());
+                        draft.files.retain(|existing| existing.path != path);
+                        draft.files.push(PackageFile { path, data });
+                    }),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    };
+
+    view! {
+        <div class="grid grid-cols-1 xl:grid-cols-[16rem,1fr,1fr] gap-6 p-4">
+            <aside class="space-y-4">
+                <div class="bg-white shadow rounded-lg p-4 space-y-2">
+                    <h3 class="text-sm font-semibold text-gray-900">"Templates"</h3>
+                    <ul class="space-y-1">
+                        {move || {
+                            templates
+                                .get()
+                                .into_iter()
+                                .map(|template| {
+                                    let id = template.id.clone();
+                                    let is_current = move || draft.with(|draft| draft.id == id);
+                                    let selected = template.clone();
+                                    view! {
+                                        <li>
+                                            <button
+                                                type="button"
+                                                class=move || {
+                                                    if is_current() {
+                                                        "w-full text-left px-2 py-1 rounded bg-indigo-50 text-indigo-700 text-sm"
+                                                    } else {
+                                                        "w-full text-left px-2 py-1 rounded hover:bg-gray-50 text-sm"
+                                                    }
+                                                }
+                                                on:click=move |_| draft.set(selected.clone())
+                                            >
+                                                {template.name.clone()}
+                                                {template
+                                                    .built_in
+                                                    .then(|| {
+                                                        view! {
+                                                            <span class="ml-1 text-xs text-gray-400">
+                                                                "built-in"
+                                                            </span>
+                                                        }
+                                                    })}
+                                            </button>
+                                        </li>
+                                    }
+                                })
+                                .collect_view()
+                        }}
+                    </ul>
+                    <button
+                        type="button"
+                        class="w-full rounded-md border border-gray-300 px-3 py-1.5 text-sm hover:bg-gray-50"
+                        on:click=move |_| draft.set(copy_of(&draft.get_untracked()))
+                    >
+                        "Copy as new template"
+                    </button>
+                    <label class="block text-sm text-gray-700" for="template-import">
+                        "Import bundle"
+                    </label>
+                    <input
+                        class="block w-full text-sm text-gray-700"
+                        type="file"
+                        id="template-import"
+                        accept=".json,application/json"
+                        on:change=on_import
+                    />
+                </div>
+
+                <FontManager />
+
+                <details class="bg-white shadow rounded-lg p-4">
+                    <summary class="text-sm font-semibold text-gray-900 cursor-pointer">
+                        "Data contract"
+                    </summary>
+                    <pre class="mt-2 whitespace-pre-wrap text-xs text-gray-700">{DATA_CONTRACT}</pre>
+                    <h4 class="mt-4 text-xs font-semibold text-gray-900">"Sample invoice.json"</h4>
+                    <pre class="mt-1 overflow-x-auto text-xs text-gray-700">{sample_json}</pre>
+                </details>
+            </aside>
+
+            <section class="space-y-4">
+                {move || {
+                    error
+                        .get()
+                        .map(|message| {
+                            view! {
+                                <div class="rounded-md bg-red-50 p-3 text-sm text-red-700">
+                                    {message}
+                                </div>
+                            }
+                        })
+                }}
+                <div class="bg-white shadow rounded-lg p-4 space-y-4">
+                    <div class="grid grid-cols-1 md:grid-cols-2 gap-4">
+                        <div>
+                            <label class="form-label" for="template-name">
+                                "Name"
+                            </label>
+                            <input
+                                class="form-input"
+                                id="template-name"
+                                prop:value=move || draft.with(|draft| draft.name.clone())
+                                disabled=read_only
+                                on:input=move |ev| {
+                                    draft.update(|draft| draft.name = event_target_value(&ev))
+                                }
+                            />
+                        </div>
+                        <div>
+                            <label class="form-label" for="template-description">
+                                "Description"
+                            </label>
+                            <input
+                                class="form-input"
+                                id="template-description"
+                                prop:value=move || draft.with(|draft| draft.description.clone())
+                                disabled=read_only
+                                on:input=move |ev| {
+                                    draft
+                                        .update(|draft| draft.description = event_target_value(&ev))
+                                }
+                            />
+                        </div>
+                    </div>
+
+                    <div>
+                        <label class="form-label" for="template-source">
+                            {MAIN_PATH}
+                        </label>
+                        <textarea
+                            class="form-textarea w-full h-[60vh] font-mono text-xs"
+                            id="template-source"
+                            spellcheck="false"
+                            prop:value=source
+                            readonly=read_only
+                            on:input=move |ev| {
+                                draft.update(|draft| draft.source = event_target_value(&ev))
+                            }
+                        />
+                    </div>
+
+                    <div>
+                        <h4 class="form-label">"Files"</h4>
+                        <ul class="space-y-1 text-sm">
+                            {move || {
+                                draft
+                                    .with(|draft| draft.files.clone())
+                                    .into_iter()
+                                    .map(|file| {
+                                        let path = file.path.clone();
+                                        view! {
+                                            <li class="flex items-center justify-between">
+                                                <span class="font-mono text-xs">{file.path}</span>
+                                                <button
+                                                    type="button"
+                                                    class="text-xs text-red-600 hover:text-red-800 disabled:hidden"
+                                                    disabled=read_only
+                                                    on:click=move |_| {
+                                                        draft
+                                                            .update(|draft| {
+                                                                draft.files.retain(|f| f.path != path)
+                                                            })
+                                                    }
+                                                >
+                                                    "Remove"
+                                                </button>
+                                            </li>
+                                        }
+                                    })
+                                    .collect_view()
+                            }}
+                        </ul>
+                        <input
+                            class="mt-2 block w-full text-sm text-gray-700 disabled:hidden"
+                            type="file"
+                            multiple
+                            disabled=read_only
+                            on:change=on_add_files
+                        />
+                    </div>
+
+                    <div class="flex flex-wrap gap-2">
+                        <button
+                            type="button"
+                            class="rounded-md bg-indigo-600 px-3 py-1.5 text-sm text-white hover:bg-indigo-700 disabled:opacity-50"
+                            disabled=read_only
+                            on:click=on_save
+                        >
+                            "Save"
+                        </button>
+                        <button
+                            type="button"
+                            class="rounded-md border border-gray-300 px-3 py-1.5 text-sm hover:bg-gray-50"
+                            on:click=on_export
+                        >
+                            "Export bundle"
+                        </button>
+                        <button
+                            type="button"
+                            class="rounded-md border border-red-300 px-3 py-1.5 text-sm text-red-700 hover:bg-red-50 disabled:opacity-50"
+                            disabled=read_only
+                            on:click=on_delete
+                        >
+                            "Delete"
+                        </button>
+                    </div>
+                </div>
+            </section>
+
+            <section>
+                <PdfViewer source=source files=files />
+            </section>
+        </div>
+    }
+}
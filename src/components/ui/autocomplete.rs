@@ -237,7 +237,9 @@ where
     });
 
     let select_item = Callback::new(move |item: T| {
+        // A newer copy of an item already selected replaces it
         selected_items.update(|items| {
+            items.retain(|selected| selected.id() != item.id());
             items.insert(item.clone());
         });
         set_query.set(String::new());
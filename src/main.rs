@@ -1,4 +1,9 @@
+use invomodo::components::invoice::provide_recurring_runs;
+use invomodo::routes::clients::Clients;
 use invomodo::routes::invoice_builder::InvoiceBuilder;
+use invomodo::routes::invoices::Invoices;
+use invomodo::routes::templates::Templates;
+use invomodo::shared::fonts::provide_font_registry;
 use leptos::prelude::*;
 use leptos::reactive::spawn_local;
 use leptos_meta::*;
@@ -67,6 +72,8 @@ pub fn App() -> impl IntoView {
     session::load_existing_session(&app_state.session_data, &app_state.session_state);
 
     provide_context(app_state.clone());
+    provide_font_registry();
+    provide_recurring_runs();
 
     // Initialize Firebase authentication
     Effect::new(move |_| {
@@ -98,6 +105,10 @@ pub fn App() -> impl IntoView {
                         <Route path=path!("/docs") view=DocsPage />
                         // <Route path=path!("/") view=HomePage />
                         <Route path=path!("/") view=InvoiceBuilder />
+                        <Route path=path!("/invoices") view=Invoices />
+                        <Route path=path!("/invoices/:id") view=InvoiceBuilder />
+                        <Route path=path!("/templates") view=Templates />
+                        <Route path=path!("/clients") view=Clients />
                         // Protected routes wrapped in <Protected>
                         <Route
                             path=path!("/dashboard")
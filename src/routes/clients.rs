@@ -0,0 +1,40 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+use crate::components::client_directory::ClientDirectory;
+
+#[component]
+pub fn Clients() -> impl IntoView {
+    view! {
+        <div class="min-h-screen bg-gray-50">
+            <header class="bg-white shadow-sm border-b border-gray-200">
+                <div class="px-4 sm:px-6 lg:px-8">
+                    <div class="flex h-16 items-center justify-between">
+                        <div class="flex items-center space-x-8">
+                            <span class="text-lg font-semibold text-gray-800">Clients</span>
+                            <nav class="flex space-x-4">
+                                <A href="/" attr:class="text-sm text-gray-600 hover:text-gray-900">
+                                    "Builder"
+                                </A>
+                                <A
+                                    href="/invoices"
+                                    attr:class="text-sm text-gray-600 hover:text-gray-900"
+                                >
+                                    "Invoices"
+                                </A>
+                                <A
+                                    href="/templates"
+                                    attr:class="text-sm text-gray-600 hover:text-gray-900"
+                                >
+                                    "Templates"
+                                </A>
+                            </nav>
+                        </div>
+                    </div>
+                </div>
+            </header>
+
+            <ClientDirectory />
+        </div>
+    }
+}
@@ -1,9 +1,23 @@
 use leptos::prelude::*;
+use leptos_router::components::A;
+use leptos_router::hooks::{use_params_map, use_query_map};
 
-use crate::components::invoice::InvoiceBuilder as InvoiceBuilderView;
+use crate::components::invoice::{DocumentType, InvoiceBuilder as InvoiceBuilderView};
 
 #[component]
 pub fn InvoiceBuilder() -> impl IntoView {
+    let params = use_params_map();
+    let query = use_query_map();
+    // Rebuild the builder when navigating between invoices
+    let builder = move || {
+        let invoice_id = params.read().get("id");
+        let document_type = query
+            .read()
+            .get("type")
+            .and_then(|key| DocumentType::from_key(&key));
+        view! { <InvoiceBuilderView invoice_id=invoice_id document_type=document_type /> }
+    };
+
     view! {
         <div class="min-h-screen bg-gray-50">
             <header class="bg-white shadow-sm border-b border-gray-200">
@@ -11,6 +25,24 @@ pub fn InvoiceBuilder() -> impl IntoView {
                     <div class="flex h-16 items-center justify-between">
                         <div class="flex items-center space-x-8">
                             <span class="text-lg font-semibold text-gray-800">Invoice Builder</span>
+                            <A
+                                href="/invoices"
+                                attr:class="text-sm text-gray-600 hover:text-gray-900"
+                            >
+                                "Invoices"
+                            </A>
+                            <A
+                                href="/templates"
+                                attr:class="text-sm text-gray-600 hover:text-gray-900"
+                            >
+                                "Templates"
+                            </A>
+                            <A
+                                href="/clients"
+                                attr:class="text-sm text-gray-600 hover:text-gray-900"
+                            >
+                                "Clients"
+                            </A>
                         // <nav class="flex space-x-4">
                         // <a href="#" class="text-sm text-gray-600 hover:text-gray-900">
                         // Dashboard
@@ -34,7 +66,7 @@ pub fn InvoiceBuilder() -> impl IntoView {
             </header>
 
             <div class="lg:col-span-2 space-y-6 p-4">
-                <InvoiceBuilderView />
+                {builder}
             </div>
         </div>
     }
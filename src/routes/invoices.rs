@@ -0,0 +1,42 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+use crate::components::invoice::InvoiceList;
+
+#[component]
+pub fn Invoices() -> impl IntoView {
+    view! {
+        <div class="min-h-screen bg-gray-50">
+            <header class="bg-white shadow-sm border-b border-gray-200">
+                <div class="px-4 sm:px-6 lg:px-8">
+                    <div class="flex h-16 items-center justify-between">
+                        <div class="flex items-center space-x-8">
+                            <span class="text-lg font-semibold text-gray-800">Invoices</span>
+                            <nav class="flex space-x-4">
+                                <A href="/" attr:class="text-sm text-gray-600 hover:text-gray-900">
+                                    "Builder"
+                                </A>
+                                <A
+                                    href="/templates"
+                                    attr:class="text-sm text-gray-600 hover:text-gray-900"
+                                >
+                                    "Templates"
+                                </A>
+                                <A
+                                    href="/clients"
+                                    attr:class="text-sm text-gray-600 hover:text-gray-900"
+                                >
+                                    "Clients"
+                                </A>
+                            </nav>
+                        </div>
+                    </div>
+                </div>
+            </header>
+
+            <div class="space-y-6 p-4">
+                <InvoiceList />
+            </div>
+        </div>
+    }
+}
@@ -1 +1,4 @@
+pub mod clients;
 pub mod invoice_builder;
+pub mod invoices;
+pub mod templates;
@@ -0,0 +1,40 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+use crate::components::template_editor::TemplateEditor;
+
+#[component]
+pub fn Templates() -> impl IntoView {
+    view! {
+        <div class="min-h-screen bg-gray-50">
+            <header class="bg-white shadow-sm border-b border-gray-200">
+                <div class="px-4 sm:px-6 lg:px-8">
+                    <div class="flex h-16 items-center justify-between">
+                        <div class="flex items-center space-x-8">
+                            <span class="text-lg font-semibold text-gray-800">Templates</span>
+                            <nav class="flex space-x-4">
+                                <A href="/" attr:class="text-sm text-gray-600 hover:text-gray-900">
+                                    "Builder"
+                                </A>
+                                <A
+                                    href="/invoices"
+                                    attr:class="text-sm text-gray-600 hover:text-gray-900"
+                                >
+                                    "Invoices"
+                                </A>
+                                <A
+                                    href="/clients"
+                                    attr:class="text-sm text-gray-600 hover:text-gray-900"
+                                >
+                                    "Clients"
+                                </A>
+                            </nav>
+                        </div>
+                    </div>
+                </div>
+            </header>
+
+            <TemplateEditor />
+        </div>
+    }
+}
@@ -0,0 +1,17 @@
+/// Asks the user to confirm a destructive action with the browser's dialog
+pub fn confirm(message: &str) -> bool {
+    web_sys::window()
+        .and_then(|window| window.confirm_with_message(message).ok())
+        .unwrap_or(false)
+}
+
+/// Asks the user for a line of text, `None` when the dialog was cancelled
+pub fn prompt(message: &str, default: &str) -> Option<String> {
+    web_sys::window()
+        .and_then(|window| {
+            window
+                .prompt_with_message_and_default(message, default)
+                .ok()
+        })
+        .flatten()
+}
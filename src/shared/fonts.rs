@@ -1,33 +1,305 @@
-pub static FONTS: &[&[u8]] = &[
-    include_bytes!("../../fonts/DejaVuSansMono-BoldOblique.ttf"),
-    include_bytes!("../../fonts/DejaVuSansMono-Bold.ttf"),
-    include_bytes!("../../fonts/DejaVuSansMono-Oblique.ttf"),
-    include_bytes!("../../fonts/DejaVuSansMono.ttf"),
-    include_bytes!("../../fonts/FoxitDingbats.pfb"),
-    include_bytes!("../../fonts/FoxitFixedBoldItalic.pfb"),
-    include_bytes!("../../fonts/FoxitFixedBold.pfb"),
-    include_bytes!("../../fonts/FoxitFixedItalic.pfb"),
-    include_bytes!("../../fonts/FoxitFixed.pfb"),
-    include_bytes!("../../fonts/FoxitSansBoldItalic.pfb"),
-    include_bytes!("../../fonts/FoxitSansBold.pfb"),
-    include_bytes!("../../fonts/FoxitSansItalic.pfb"),
-    include_bytes!("../../fonts/FoxitSans.pfb"),
-    include_bytes!("../../fonts/FoxitSerifItalic.pfb"),
-    include_bytes!("../../fonts/FoxitSerif.pfb"),
-    include_bytes!("../../fonts/FoxitSerifBoldItalic.pfb"),
-    include_bytes!("../../fonts/FoxitSerifBold.pfb"),
-    include_bytes!("../../fonts/FoxitSymbol.pfb"),
-    include_bytes!("../../fonts/LibertinusSerif-Italic.otf"),
-    include_bytes!("../../fonts/LibertinusSerif-Regular.otf"),
-    include_bytes!("../../fonts/LibertinusSerif-SemiboldItalic.otf"),
-    include_bytes!("../../fonts/LibertinusSerif-BoldItalic.otf"),
-    include_bytes!("../../fonts/LibertinusSerif-Semibold.otf"),
-    include_bytes!("../../fonts/LibertinusSerif-Bold.otf"),
-    include_bytes!("../../fonts/NewCM10-Bold.otf"),
-    include_bytes!("../../fonts/NewCMMath-Regular.otf"),
-    include_bytes!("../../fonts/NewCM10-Italic.otf"),
-    include_bytes!("../../fonts/NewCM10-Regular.otf"),
-    include_bytes!("../../fonts/NewCMMath-Bold.otf"),
-    include_bytes!("../../fonts/NewCM10-BoldItalic.otf"),
-    include_bytes!("../../fonts/NewCMMath-Book.otf"),
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use typst::foundations::Bytes;
+use typst::text::Font;
+
+use crate::shared::files::base64_data;
+use crate::shared::storage::{Database, StorageError};
+use crate::shared::typst::Diagnostic;
+
+/// A font file served from `/fonts`, fetched the first time a template asks for
+/// its family
+pub struct FontAsset {
+    pub family: &'static str,
+    pub file: &'static str,
+}
+
+pub static FONT_ASSETS: &[FontAsset] = &[
+    FontAsset {
+        family: "DejaVu Sans Mono",
+        file: "DejaVuSansMono.ttf",
+    },
+    FontAsset {
+        family: "DejaVu Sans Mono",
+        file: "DejaVuSansMono-Bold.ttf",
+    },
+    FontAsset {
+        family: "DejaVu Sans Mono",
+        file: "DejaVuSansMono-Oblique.ttf",
+    },
+    FontAsset {
+        family: "DejaVu Sans Mono",
+        file: "DejaVuSansMono-BoldOblique.ttf",
+    },
+    FontAsset {
+        family: "Libertinus Serif",
+        file: "LibertinusSerif-Bold.otf",
+    },
+    FontAsset {
+        family: "Libertinus Serif",
+        file: "LibertinusSerif-Italic.otf",
+    },
+    FontAsset {
+        family: "Libertinus Serif",
+        file: "LibertinusSerif-BoldItalic.otf",
+    },
+    FontAsset {
+        family: "Libertinus Serif",
+        file: "LibertinusSerif-Semibold.otf",
+    },
+    FontAsset {
+        family: "Libertinus Serif",
+        file: "LibertinusSerif-SemiboldItalic.otf",
+    },
+    FontAsset {
+        family: "New Computer Modern",
+        file: "NewCM10-Regular.otf",
+    },
+    FontAsset {
+        family: "New Computer Modern",
+        file: "NewCM10-Bold.otf",
+    },
+    FontAsset {
+        family: "New Computer Modern",
+        file: "NewCM10-Italic.otf",
+    },
+    FontAsset {
+        family: "New Computer Modern",
+        file: "NewCM10-BoldItalic.otf",
+    },
+    FontAsset {
+        family: "New Computer Modern Math",
+        file: "NewCMMath-Regular.otf",
+    },
+    FontAsset {
+        family: "New Computer Modern Math",
+        file: "NewCMMath-Book.otf",
+    },
+    FontAsset {
+        family: "New Computer Modern Math",
+        file: "NewCMMath-Bold.otf",
+    },
 ];
+
+/// Typst's default family, embedded so text renders before anything was fetched
+pub const FALLBACK_FAMILY: &str = "Libertinus Serif";
+
+static EMBEDDED_FONTS: &[&[u8]] = &[include_bytes!("../../fonts/LibertinusSerif-Regular.otf")];
+
+fn embedded_fonts() -> Vec<Font> {
+    EMBEDDED_FONTS
+        .iter()
+        .filter_map(|data| Font::new(Bytes::new(*data), 0))
+        .collect()
+}
+
+/// Family named by an "unknown font family" compiler warning
+pub fn unknown_font_family(diagnostic: &Diagnostic) -> Option<&str> {
+    diagnostic.message.strip_prefix("unknown font family: ")
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FontError {
+    #[error("{0} is not a TrueType or OpenType font")]
+    Unsupported(String),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+/// A brand font uploaded by the user
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct UserFont {
+    pub id: String,
+    pub family: String,
+    pub file_name: String,
+    #[serde(with = "base64_data")]
+    pub data: Vec<u8>,
+}
+
+impl UserFont {
+    pub fn from_file(file_name: String, data: Vec<u8>) -> Result<Self, FontError> {
+        let family = Font::iter(Bytes::new(data.clone()))
+            .next()
+            .map(|font| font.info().family.clone())
+            .ok_or_else(|| FontError::Unsupported(file_name.clone()))?;
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            family,
+            file_name,
+            data,
+        })
+    }
+
+    /// Every face in the file, collections hold more than one
+    pub fn faces(&self) -> Vec<Font> {
+        Font::iter(Bytes::new(self.data.clone())).collect()
+    }
+}
+
+/// Fonts available to templates: the embedded fallback, font files fetched on
+/// demand and the user's uploads. Provided once for the whole app.
+#[derive(Clone, Copy)]
+pub struct FontRegistry {
+    embedded: StoredValue<Vec<Font>>,
+    fetched: RwSignal<Vec<Font>>,
+    user_fonts: RwSignal<Vec<UserFont>>,
+    user_faces: RwSignal<Vec<Font>>,
+    /// Files fetched or being fetched, and the ones that failed
+    requested: RwSignal<HashSet<&'static str>>,
+    failed: RwSignal<HashSet<&'static str>>,
+}
+
+impl FontRegistry {
+    fn new() -> Self {
+        let registry = Self {
+            embedded: StoredValue::new(embedded_fonts()),
+            fetched: RwSignal::new(vec![]),
+            user_fonts: RwSignal::new(vec![]),
+            user_faces: RwSignal::new(vec![]),
+            requested: RwSignal::new(HashSet::new()),
+            failed: RwSignal::new(HashSet::new()),
+        };
+
+        // The other faces of the fallback family, used by most templates
+        registry.request_families([FALLBACK_FAMILY]);
+
+        spawn_local(async move {
+            match load_user_fonts().await {
+                Ok(fonts) => registry.set_user_fonts(fonts),
+                Err(e) => log::error!("Failed to load uploaded fonts: {e}"),
+            }
+        });
+
+        registry
+    }
+
+    /// Every loaded font, tracks new arrivals
+    pub fn fonts(&self) -> Vec<Font> {
+        let mut fonts = self.embedded.get_value();
+        fonts.extend(self.fetched.get());
+        fonts.extend(self.user_faces.get());
+        fonts
+    }
+
+    pub fn user_fonts(&self) -> ReadSignal<Vec<UserFont>> {
+        self.user_fonts.read_only()
+    }
+
+    /// Families with at least one fetched or embedded face, for display
+    pub fn is_loaded(&self, family: &str) -> bool {
+        family == FALLBACK_FAMILY
+            || self.fetched.with(|fonts| {
+                fonts
+                    .iter()
+                    .any(|font| font.info().family.eq_ignore_ascii_case(family))
+            })
+    }
+
+    fn set_user_fonts(&self, fonts: Vec<UserFont>) {
+        self.user_faces
+            .set(fonts.iter().flat_map(UserFont::faces).collect());
+        self.user_fonts.set(fonts);
+    }
+
+    /// Starts fetching the files of `families` that were not requested yet and
+    /// returns the families no font can provide
+    pub fn request_families<'a>(&self, families: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        let mut missing = vec![];
+
+        for family in families {
+            let assets = FONT_ASSETS
+                .iter()
+                .filter(|asset| asset.family.eq_ignore_ascii_case(family))
+                .collect::<Vec<_>>();
+            let all_failed = self
+                .failed
+                .with_untracked(|failed| assets.iter().all(|asset| failed.contains(asset.file)));
+            if assets.is_empty() || all_failed {
+                missing.push(family.to_string());
+                continue;
+            }
+
+            for asset in assets {
+                let is_new = self
+                    .requested
+                    .try_update(|requested| requested.insert(asset.file))
+                    .unwrap_or(false);
+                if is_new {
+                    self.fetch(asset.file);
+                }
+            }
+        }
+
+        missing
+    }
+
+    /// Requests the families `diagnostics` report as unknown, returns the ones
+    /// that stay missing so the template falls back to other fonts
+    pub fn resolve_missing(&self, diagnostics: &[Diagnostic]) -> Vec<String> {
+        self.request_families(diagnostics.iter().filter_map(unknown_font_family))
+    }
+
+    fn fetch(&self, file: &'static str) {
+        let registry = *self;
+        spawn_local(async move {
+            match fetch_font(file).await {
+                Ok(data) => registry
+                    .fetched
+                    .update(|fonts| fonts.extend(Font::iter(Bytes::new(data)))),
+                Err(e) => {
+                    log::error!("Failed to fetch font {file}: {e}");
+                    registry.failed.update(|failed| {
+                        failed.insert(file);
+                    });
+                }
+            }
+        });
+    }
+
+    pub async fn add_user_font(&self, file_name: String, data: Vec<u8>) -> Result<(), FontError> {
+        let font = UserFont::from_file(file_name, data)?;
+        Database::open().await?.save_font(&font).await?;
+
+        let mut fonts = self.user_fonts.get_untracked();
+        fonts.push(font);
+        self.set_user_fonts(fonts);
+        Ok(())
+    }
+
+    pub async fn remove_user_font(&self, font_id: String) -> Result<(), FontError> {
+        Database::open().await?.delete_font(&font_id).await?;
+
+        let mut fonts = self.user_fonts.get_untracked();
+        fonts.retain(|font| font.id != font_id);
+        self.set_user_fonts(fonts);
+        Ok(())
+    }
+}
+
+async fn load_user_fonts() -> Result<Vec<UserFont>, StorageError> {
+    Database::open().await?.list_fonts().await
+}
+
+async fn fetch_font(file: &str) -> Result<Vec<u8>, String> {
+    let response = gloo_net::http::Request::get(&format!("/fonts/{file}"))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.ok() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    response.binary().await.map_err(|e| e.to_string())
+}
+
+pub fn provide_font_registry() -> FontRegistry {
+    let registry = FontRegistry::new();
+    provide_context(registry);
+    registry
+}
+
+pub fn use_font_registry() -> FontRegistry {
+    use_context::<FontRegistry>().expect("FontRegistry must be provided")
+}
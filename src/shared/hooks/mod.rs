@@ -1 +1,4 @@
+pub mod use_biller_profiles;
+pub mod use_clients;
 pub mod use_responsive;
+pub mod use_user_templates;
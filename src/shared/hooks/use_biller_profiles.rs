@@ -0,0 +1,44 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::components::invoice::BillerProfile;
+use crate::shared::storage::{Database, StorageError};
+
+/// Saved biller profiles, empty until they are loaded from IndexedDB
+pub fn use_biller_profiles() -> RwSignal<Vec<BillerProfile>> {
+    let profiles = RwSignal::new(vec![]);
+
+    spawn_local(async move {
+        match load_biller_profiles().await {
+            Ok(loaded) => profiles.set(loaded),
+            Err(e) => log::error!("Failed to load biller profiles: {e}"),
+        }
+    });
+
+    profiles
+}
+
+async fn load_biller_profiles() -> Result<Vec<BillerProfile>, StorageError> {
+    Database::open().await?.list_biller_profiles().await
+}
+
+pub async fn save_biller_profile(profile: BillerProfile) -> Result<(), StorageError> {
+    Database::open().await?.save_biller_profile(&profile).await
+}
+
+pub async fn delete_biller_profile(profile_id: String) -> Result<(), StorageError> {
+    Database::open()
+        .await?
+        .delete_biller_profile(&profile_id)
+        .await
+}
+
+/// Inserts or replaces `profile` in the loaded profiles
+pub fn upsert_biller_profile(profiles: RwSignal<Vec<BillerProfile>>, profile: BillerProfile) {
+    profiles.update(
+        |profiles| match profiles.iter_mut().find(|p| p.id == profile.id) {
+            Some(existing) => *existing = profile,
+            None => profiles.push(profile),
+        },
+    );
+}
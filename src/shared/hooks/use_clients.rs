@@ -0,0 +1,37 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::components::invoice::Client;
+use crate::shared::storage::{Database, StorageError};
+
+/// The client directory, empty until it is loaded from IndexedDB
+pub fn use_clients() -> RwSignal<Vec<Client>> {
+    let clients = RwSignal::new(vec![]);
+
+    spawn_local(async move {
+        match load_clients().await {
+            Ok(loaded) => clients.set(loaded),
+            Err(e) => log::error!("Failed to load clients: {e}"),
+        }
+    });
+
+    clients
+}
+
+async fn load_clients() -> Result<Vec<Client>, StorageError> {
+    Database::open().await?.list_clients().await
+}
+
+pub async fn save_client(client: Client) -> Result<(), StorageError> {
+    Database::open().await?.save_client(&client).await
+}
+
+/// Inserts or replaces `client` in the loaded directory
+pub fn upsert_client(clients: RwSignal<Vec<Client>>, client: Client) {
+    clients.update(
+        |clients| match clients.iter_mut().find(|c| c.id == client.id) {
+            Some(existing) => *existing = client,
+            None => clients.push(client),
+        },
+    );
+}
@@ -0,0 +1,23 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use crate::shared::storage::{Database, StorageError};
+use crate::shared::templates::TemplatePackage;
+
+/// Templates the user wrote, empty until they are loaded from IndexedDB
+pub fn use_user_templates() -> RwSignal<Vec<TemplatePackage>> {
+    let templates = RwSignal::new(vec![]);
+
+    spawn_local(async move {
+        match load_user_templates().await {
+            Ok(loaded) => templates.set(loaded),
+            Err(e) => log::error!("Failed to load templates: {e}"),
+        }
+    });
+
+    templates
+}
+
+async fn load_user_templates() -> Result<Vec<TemplatePackage>, StorageError> {
+    Database::open().await?.list_templates().await
+}
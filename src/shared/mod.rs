@@ -1,5 +1,8 @@
 pub mod hooks;
+pub mod storage;
 pub mod types;
 pub mod typst;
+pub mod dialog;
+pub mod files;
 pub mod fonts;
 pub mod templates;
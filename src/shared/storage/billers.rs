@@ -0,0 +1,30 @@
+use super::{Database, StorageError, quote};
+use crate::components::invoice::BillerProfile;
+
+impl Database {
+    /// Biller profiles sorted by name
+    pub async fn list_biller_profiles(&mut self) -> Result<Vec<BillerProfile>, StorageError> {
+        let mut profiles: Vec<BillerProfile> = self
+            .select_documents("SELECT data FROM biller_profiles")
+            .await?;
+        profiles.sort_by_cached_key(|profile| profile.name.to_lowercase());
+        Ok(profiles)
+    }
+
+    pub async fn save_biller_profile(
+        &mut self,
+        profile: &BillerProfile,
+    ) -> Result<(), StorageError> {
+        self.save_document("biller_profiles", &profile.id, profile)
+            .await
+    }
+
+    pub async fn delete_biller_profile(&mut self, profile_id: &str) -> Result<(), StorageError> {
+        self.execute(&format!(
+            "DELETE FROM biller_profiles WHERE id = {}",
+            quote(profile_id)
+        ))
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+use super::{Database, StorageError};
+use crate::components::invoice::Client;
+
+impl Database {
+    /// Every client including archived ones, sorted by name
+    pub async fn list_clients(&mut self) -> Result<Vec<Client>, StorageError> {
+        let mut clients: Vec<Client> = self.select_documents("SELECT data FROM clients").await?;
+        clients.sort_by_cached_key(|client| client.name().to_lowercase());
+        Ok(clients)
+    }
+
+    pub async fn save_client(&mut self, client: &Client) -> Result<(), StorageError> {
+        self.save_document("clients", &client.id, client).await
+    }
+}
@@ -0,0 +1,96 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+use super::{Database, StorageError, quote};
+use crate::components::invoice::{CounterState, counter_id};
+
+#[wasm_bindgen]
+extern "C" {
+    // Web Locks API, shared by every tab of the app
+    #[wasm_bindgen(catch, js_namespace = ["navigator", "locks"], js_name = "request")]
+    fn request_lock(name: &str, callback: &JsValue) -> Result<js_sys::Promise, JsValue>;
+}
+
+/// Web Lock held while a counter value is taken
+const COUNTER_LOCK: &str = "invomodo-counters";
+
+/// Held while a counter value is read and written back. GlueSQL reads and writes
+/// in separate IndexedDB transactions, so taking a value is made exclusive with a
+/// Web Lock instead: two tabs finalizing invoices at once take values one after
+/// the other.
+struct CounterLock {
+    release: js_sys::Function,
+}
+
+impl CounterLock {
+    async fn acquire() -> Result<Self, StorageError> {
+        let lock_error = |error: JsValue| {
+            StorageError::Database(format!("Could not lock the number sequences: {error:?}"))
+        };
+
+        // Promise executors run right away, so both functions are set below
+        let mut release = None;
+        let held = js_sys::Promise::new(&mut |resolve, _| release = Some(resolve));
+        let mut grant = None;
+        let granted = js_sys::Promise::new(&mut |resolve, _| grant = Some(resolve));
+        let (Some(release), Some(grant)) = (release, grant) else {
+            unreachable!("promise executors run synchronously");
+        };
+
+        // The lock stays held until the promise the callback returns settles
+        let callback = Closure::once_into_js(move |_lock: JsValue| {
+            let _ = grant.call0(&JsValue::UNDEFINED);
+            held
+        });
+        let request = request_lock(COUNTER_LOCK, &callback).map_err(lock_error)?;
+        // A request that fails settles before the lock is granted
+        JsFuture::from(js_sys::Promise::race(&js_sys::Array::of2(
+            &granted, &request,
+        )))
+        .await
+        .map_err(lock_error)?;
+
+        Ok(CounterLock { release })
+    }
+}
+
+impl Drop for CounterLock {
+    fn drop(&mut self) {
+        let _ = self.release.call0(&JsValue::UNDEFINED);
+    }
+}
+
+impl Database {
+    async fn load_counter(&mut self, id: &str) -> Result<Option<CounterState>, StorageError> {
+        Ok(self
+            .select_documents::<CounterState>(&format!(
+                "SELECT data FROM counters WHERE id = {}",
+                quote(id)
+            ))
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Reserves the next value of the sequence `key` in `period` and stores the
+    /// counter's new position
+    pub async fn take_counter_value(
+        &mut self,
+        key: &str,
+        period: &str,
+        start_value: i64,
+        increment: i64,
+    ) -> Result<i64, StorageError> {
+        let _lock = CounterLock::acquire().await?;
+
+        let id = counter_id(key, period);
+        let mut state = self
+            .load_counter(&id)
+            .await?
+            .unwrap_or_else(|| CounterState::new(key.to_string(), period.to_string(), start_value));
+        let value = state.take(increment);
+
+        self.save_document("counters", &state.id(), &state).await?;
+        Ok(value)
+    }
+}
@@ -0,0 +1,20 @@
+use super::{Database, StorageError, quote};
+use crate::shared::fonts::UserFont;
+
+impl Database {
+    /// Fonts uploaded by the user, in upload order
+    pub async fn list_fonts(&mut self) -> Result<Vec<UserFont>, StorageError> {
+        self.select_documents("SELECT data FROM fonts ORDER BY updated_at")
+            .await
+    }
+
+    pub async fn save_font(&mut self, font: &UserFont) -> Result<(), StorageError> {
+        self.save_document("fonts", &font.id, font).await
+    }
+
+    pub async fn delete_font(&mut self, font_id: &str) -> Result<(), StorageError> {
+        self.execute(&format!("DELETE FROM fonts WHERE id = {}", quote(font_id)))
+            .await?;
+        Ok(())
+    }
+}
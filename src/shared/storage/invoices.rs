@@ -0,0 +1,177 @@
+use gluesql::prelude::Value;
+use serde::{Serialize, de::DeserializeOwned};
+use std::collections::HashMap;
+
+use super::{Database, StorageError, quote, quote_json, select_rows};
+use crate::components::invoice::{InvoiceDocument, LineItem};
+
+/// Tables holding the reusable taxes, discounts, charges, field definitions and
+/// products
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Catalog {
+    Taxes,
+    Discounts,
+    Charges,
+    Fields,
+    Products,
+}
+
+impl Catalog {
+    fn table(&self) -> &'static str {
+        match self {
+            Catalog::Taxes => "taxes",
+            Catalog::Discounts => "discounts",
+            Catalog::Charges => "charges",
+            Catalog::Fields => "fields",
+            Catalog::Products => "products",
+        }
+    }
+}
+
+impl Database {
+    pub async fn load_catalog<T: DeserializeOwned>(
+        &mut self,
+        catalog: Catalog,
+    ) -> Result<Vec<T>, StorageError> {
+        self.select_documents(&format!(
+            "SELECT data FROM {} ORDER BY position",
+            catalog.table()
+        ))
+        .await
+    }
+
+    /// Replaces the whole catalog with `items`, keeping their order
+    pub async fn save_catalog<T: Serialize>(
+        &mut self,
+        catalog: Catalog,
+        items: &[T],
+        id: impl Fn(&T) -> &str,
+    ) -> Result<(), StorageError> {
+        let table = catalog.table();
+        for (position, item) in items.iter().enumerate() {
+            self.upsert(
+                table,
+                id(item),
+                &[
+                    ("position", position.to_string()),
+                    ("data", quote_json(item)?),
+                ],
+            )
+            .await?;
+        }
+
+        let kept = items.iter().map(id).collect::<Vec<_>>();
+        self.delete_rows_except(table, None, &kept).await
+    }
+
+    /// Most recently saved invoice, if any
+    pub async fn load_latest_invoice(&mut self) -> Result<Option<InvoiceDocument>, StorageError> {
+        let mut invoices = self
+            .select_documents("SELECT data FROM invoices ORDER BY updated_at DESC LIMIT 1")
+            .await?;
+        Ok(invoices.pop())
+    }
+
+    pub async fn load_invoice(
+        &mut self,
+        invoice_id: &str,
+    ) -> Result<Option<InvoiceDocument>, StorageError> {
+        let mut invoices = self
+            .select_documents(&format!(
+                "SELECT data FROM invoices WHERE id = {}",
+                quote(invoice_id)
+            ))
+            .await?;
+        Ok(invoices.pop())
+    }
+
+    /// Every saved invoice, most recently updated first
+    pub async fn list_invoices(&mut self) -> Result<Vec<InvoiceDocument>, StorageError> {
+        self.select_documents("SELECT data FROM invoices ORDER BY updated_at DESC")
+            .await
+    }
+
+    pub async fn delete_invoice(&mut self, invoice_id: &str) -> Result<(), StorageError> {
+        let invoice_id = quote(invoice_id);
+        self.execute(&format!(
+            "DELETE FROM line_items WHERE invoice_id = {invoice_id}"
+        ))
+        .await?;
+        self.execute(&format!("DELETE FROM invoices WHERE id = {invoice_id}"))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn save_invoice(&mut self, invoice: &InvoiceDocument) -> Result<(), StorageError> {
+        self.save_document("invoices", &invoice.id, invoice).await
+    }
+
+    pub async fn load_line_items(
+        &mut self,
+        invoice_id: &str,
+    ) -> Result<Vec<LineItem>, StorageError> {
+        self.select_documents(&format!(
+            "SELECT data FROM line_items WHERE invoice_id = {} ORDER BY position",
+            quote(invoice_id)
+        ))
+        .await
+    }
+
+    /// Line items of every invoice, grouped by invoice id
+    pub async fn load_all_line_items(
+        &mut self,
+    ) -> Result<HashMap<String, Vec<LineItem>>, StorageError> {
+        let rows = select_rows(
+            self.execute("SELECT invoice_id, data FROM line_items ORDER BY position")
+                .await?,
+        )?;
+
+        let mut line_items: HashMap<String, Vec<LineItem>> = HashMap::new();
+        for row in rows {
+            match row.as_slice() {
+                [Value::Str(invoice_id), Value::Str(data)] => line_items
+                    .entry(invoice_id.clone())
+                    .or_default()
+                    .push(serde_json::from_str(data)?),
+                other => {
+                    return Err(StorageError::UnexpectedResult(format!(
+                        "invalid line item row {other:?}"
+                    )));
+                }
+            }
+        }
+        Ok(line_items)
+    }
+
+    /// Replaces the line items of one invoice
+    pub async fn save_line_items(
+        &mut self,
+        invoice_id: &str,
+        items: &[LineItem],
+    ) -> Result<(), StorageError> {
+        let invoice_id = quote(invoice_id);
+        for (position, item) in items.iter().enumerate() {
+            self.upsert(
+                "line_items",
+                &item.id,
+                &[
+                    ("invoice_id", invoice_id.clone()),
+                    ("position", position.to_string()),
+                    ("data", quote_json(item)?),
+                ],
+            )
+            .await?;
+        }
+
+        let kept = items
+            .iter()
+            .map(|item| item.id.as_str())
+            .collect::<Vec<_>>();
+        self.delete_rows_except(
+            "line_items",
+            Some(&format!("invoice_id = {invoice_id}")),
+            &kept,
+        )
+        .await
+    }
+}
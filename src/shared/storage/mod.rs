@@ -0,0 +1,214 @@
+use gluesql::prelude::{Payload, Value};
+use serde::{Serialize, de::DeserializeOwned};
+
+mod billers;
+mod clients;
+mod counters;
+mod fonts;
+mod invoices;
+mod recurring;
+mod schema;
+mod templates;
+
+pub use invoices::*;
+pub use schema::SCHEMA_VERSION;
+
+/// IndexedDB database name used by the app
+#[cfg(target_arch = "wasm32")]
+const NAMESPACE: &str = "invomodo";
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("Database error: {0}")]
+    Database(String),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    #[error("Unexpected result: {0}")]
+    UnexpectedResult(String),
+}
+
+impl From<gluesql::prelude::Error> for StorageError {
+    fn from(error: gluesql::prelude::Error) -> Self {
+        StorageError::Database(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(error: serde_json::Error) -> Self {
+        StorageError::Serialization(error.to_string())
+    }
+}
+
+/// Thin wrapper around a GlueSQL connection to the IndexedDB backed store.
+///
+/// Records are kept as JSON documents in a `data` column next to the few columns
+/// that are needed for lookups and ordering, so adding a field to a struct does not
+/// need a migration as long as it deserializes with a default.
+pub struct Database {
+    #[cfg(target_arch = "wasm32")]
+    glue: gluesql::prelude::Glue<gluesql_idb_storage::IdbStorage>,
+    previous_version: i64,
+}
+
+impl Database {
+    /// Opens the database and brings the schema up to `SCHEMA_VERSION`
+    pub async fn open() -> Result<Self, StorageError> {
+        let mut db = Self::connect().await?;
+        db.previous_version = schema::migrate(&mut db).await?;
+        Ok(db)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn connect() -> Result<Self, StorageError> {
+        let storage = gluesql_idb_storage::IdbStorage::new(Some(NAMESPACE.to_string())).await?;
+        Ok(Self {
+            glue: gluesql::prelude::Glue::new(storage),
+            previous_version: 0,
+        })
+    }
+
+    /// IndexedDB only exists in the browser, native builds like `cargo test` have no
+    /// storage
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn connect() -> Result<Self, StorageError> {
+        Err(StorageError::Database(
+            "IndexedDB is only available in the browser".to_string(),
+        ))
+    }
+
+    /// True when the database did not exist before this connection was opened
+    pub fn is_new(&self) -> bool {
+        self.previous_version == 0
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub async fn execute(&mut self, sql: &str) -> Result<Vec<Payload>, StorageError> {
+        Ok(self.glue.execute(sql).await?)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn execute(&mut self, sql: &str) -> Result<Vec<Payload>, StorageError> {
+        Err(StorageError::Database(format!(
+            "IndexedDB is only available in the browser, cannot run {sql}"
+        )))
+    }
+
+    /// Writes the row `id` of `table` with `columns` (name and SQL literal), updated
+    /// in place when it is already stored. GlueSQL has no transactions over
+    /// IndexedDB, so rows are never deleted to be inserted again where a failed
+    /// insert would lose them.
+    pub(crate) async fn upsert(
+        &mut self,
+        table: &str,
+        id: &str,
+        columns: &[(&str, String)],
+    ) -> Result<(), StorageError> {
+        let id = quote(id);
+        let assignments = columns
+            .iter()
+            .map(|(name, value)| format!("{name} = {value}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let updated = self
+            .execute(&format!("UPDATE {table} SET {assignments} WHERE id = {id}"))
+            .await?;
+
+        if let [Payload::Update(0)] = updated.as_slice() {
+            let names = columns.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+            let values = columns
+                .iter()
+                .map(|(_, value)| value.as_str())
+                .collect::<Vec<_>>();
+            self.execute(&format!(
+                "INSERT INTO {table} (id, {}) VALUES ({id}, {})",
+                names.join(", "),
+                values.join(", ")
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Stores `document` as the row `id` of a table of JSON documents
+    pub(crate) async fn save_document<T: Serialize>(
+        &mut self,
+        table: &str,
+        id: &str,
+        document: &T,
+    ) -> Result<(), StorageError> {
+        self.upsert(
+            table,
+            id,
+            &[
+                ("data", quote_json(document)?),
+                ("updated_at", quote(&now_iso())),
+            ],
+        )
+        .await
+    }
+
+    /// Deletes the rows of `table` matching `scope`, an SQL condition, except `kept`
+    pub(crate) async fn delete_rows_except(
+        &mut self,
+        table: &str,
+        scope: Option<&str>,
+        kept: &[&str],
+    ) -> Result<(), StorageError> {
+        let kept = kept.iter().map(|id| quote(id)).collect::<Vec<_>>();
+        let conditions = scope
+            .map(str::to_string)
+            .into_iter()
+            .chain((!kept.is_empty()).then(|| format!("id NOT IN ({})", kept.join(", "))))
+            .collect::<Vec<_>>();
+        let mut sql = format!("DELETE FROM {table}");
+        if !conditions.is_empty() {
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        self.execute(&sql).await?;
+        Ok(())
+    }
+
+    /// Runs a query selecting a single `data` column and deserializes every row
+    pub async fn select_documents<T: DeserializeOwned>(
+        &mut self,
+        sql: &str,
+    ) -> Result<Vec<T>, StorageError> {
+        let rows = select_rows(self.execute(sql).await?)?;
+
+        rows.into_iter()
+            .map(|row| match row.into_iter().next() {
+                Some(Value::Str(data)) => Ok(serde_json::from_str(&data)?),
+                other => Err(StorageError::UnexpectedResult(format!(
+                    "expected a JSON document, got {other:?}"
+                ))),
+            })
+            .collect()
+    }
+}
+
+/// Extracts the rows of the last `SELECT` in a batch
+pub(crate) fn select_rows(payloads: Vec<Payload>) -> Result<Vec<Vec<Value>>, StorageError> {
+    payloads
+        .into_iter()
+        .rev()
+        .find_map(|payload| match payload {
+            Payload::Select { rows, .. } => Some(rows),
+            _ => None,
+        })
+        .ok_or_else(|| StorageError::UnexpectedResult("query returned no rows".to_string()))
+}
+
+/// Quotes a value as an SQL string literal
+pub(crate) fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Serializes a value and quotes it for use in an `INSERT`
+pub(crate) fn quote_json<T: Serialize>(value: &T) -> Result<String, StorageError> {
+    Ok(quote(&serde_json::to_string(value)?))
+}
+
+/// Current time as an ISO 8601 string
+pub(crate) fn now_iso() -> String {
+    String::from(js_sys::Date::new_0().to_iso_string())
+}
@@ -0,0 +1,38 @@
+use super::{Database, StorageError, quote};
+use crate::components::invoice::RecurringProfile;
+
+impl Database {
+    pub async fn list_recurring_profiles(&mut self) -> Result<Vec<RecurringProfile>, StorageError> {
+        self.select_documents("SELECT data FROM recurring_profiles")
+            .await
+    }
+
+    /// Schedule that repeats the invoice `invoice_id`, if any
+    pub async fn load_recurring_profile_for(
+        &mut self,
+        invoice_id: &str,
+    ) -> Result<Option<RecurringProfile>, StorageError> {
+        Ok(self
+            .list_recurring_profiles()
+            .await?
+            .into_iter()
+            .find(|profile| profile.source_invoice_id == invoice_id))
+    }
+
+    pub async fn save_recurring_profile(
+        &mut self,
+        profile: &RecurringProfile,
+    ) -> Result<(), StorageError> {
+        self.save_document("recurring_profiles", &profile.id, profile)
+            .await
+    }
+
+    pub async fn delete_recurring_profile(&mut self, profile_id: &str) -> Result<(), StorageError> {
+        self.execute(&format!(
+            "DELETE FROM recurring_profiles WHERE id = {}",
+            quote(profile_id)
+        ))
+        .await?;
+        Ok(())
+    }
+}
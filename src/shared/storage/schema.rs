@@ -0,0 +1,142 @@
+use gluesql::prelude::Value;
+
+use super::{Database, StorageError, now_iso, select_rows};
+
+/// Latest schema version, bump it together with a new entry in `MIGRATIONS`
+pub const SCHEMA_VERSION: i64 = 8;
+
+/// Ordered list of migrations, each entry brings the schema to the given version
+const MIGRATIONS: &[(i64, &[&str])] = &[
+    (
+        1,
+        &[
+            "CREATE TABLE IF NOT EXISTS invoices (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            "CREATE TABLE IF NOT EXISTS line_items (
+                id TEXT PRIMARY KEY,
+                invoice_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                data TEXT NOT NULL
+            )",
+            "CREATE TABLE IF NOT EXISTS taxes (
+                id TEXT PRIMARY KEY,
+                position INTEGER NOT NULL,
+                data TEXT NOT NULL
+            )",
+            "CREATE TABLE IF NOT EXISTS discounts (
+                id TEXT PRIMARY KEY,
+                position INTEGER NOT NULL,
+                data TEXT NOT NULL
+            )",
+            "CREATE TABLE IF NOT EXISTS charges (
+                id TEXT PRIMARY KEY,
+                position INTEGER NOT NULL,
+                data TEXT NOT NULL
+            )",
+            "CREATE TABLE IF NOT EXISTS fields (
+                id TEXT PRIMARY KEY,
+                position INTEGER NOT NULL,
+                data TEXT NOT NULL
+            )",
+        ],
+    ),
+    (
+        2,
+        &["CREATE TABLE IF NOT EXISTS templates (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )"],
+    ),
+    (
+        3,
+        &["CREATE TABLE IF NOT EXISTS fonts (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )"],
+    ),
+    (
+        4,
+        &["CREATE TABLE IF NOT EXISTS clients (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )"],
+    ),
+    (
+        5,
+        &["CREATE TABLE IF NOT EXISTS biller_profiles (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )"],
+    ),
+    (
+        6,
+        &["CREATE TABLE IF NOT EXISTS products (
+                id TEXT PRIMARY KEY,
+                position INTEGER NOT NULL,
+                data TEXT NOT NULL
+            )"],
+    ),
+    (
+        7,
+        &["CREATE TABLE IF NOT EXISTS counters (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )"],
+    ),
+    (
+        8,
+        &["CREATE TABLE IF NOT EXISTS recurring_profiles (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )"],
+    ),
+];
+
+/// Applies every migration newer than the stored version and returns the version
+/// the database was at before, `0` for a database that was just created
+pub(super) async fn migrate(db: &mut Database) -> Result<i64, StorageError> {
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+    )
+    .await?;
+
+    let rows = select_rows(
+        db.execute("SELECT MAX(version) FROM schema_version")
+            .await?,
+    )?;
+    let current = match rows.first().and_then(|row| row.first()) {
+        Some(Value::I64(version)) => *version,
+        Some(Value::Null) | None => 0,
+        Some(other) => {
+            return Err(StorageError::UnexpectedResult(format!(
+                "invalid schema version {other:?}"
+            )));
+        }
+    };
+
+    for (version, statements) in MIGRATIONS.iter().filter(|(v, _)| *v > current) {
+        for statement in statements.iter() {
+            db.execute(statement).await?;
+        }
+        db.execute(&format!(
+            "INSERT INTO schema_version VALUES ({version}, '{}')",
+            now_iso()
+        ))
+        .await?;
+        log::info!("Storage schema migrated to version {version}");
+    }
+
+    Ok(current)
+}
@@ -0,0 +1,24 @@
+use super::{Database, StorageError, quote};
+use crate::shared::templates::TemplatePackage;
+
+impl Database {
+    /// Templates written by the user, most recently edited first
+    pub async fn list_templates(&mut self) -> Result<Vec<TemplatePackage>, StorageError> {
+        self.select_documents("SELECT data FROM templates ORDER BY updated_at DESC")
+            .await
+    }
+
+    pub async fn save_template(&mut self, template: &TemplatePackage) -> Result<(), StorageError> {
+        self.save_document("templates", &template.id, template)
+            .await
+    }
+
+    pub async fn delete_template(&mut self, template_id: &str) -> Result<(), StorageError> {
+        self.execute(&format!(
+            "DELETE FROM templates WHERE id = {}",
+            quote(template_id)
+        ))
+        .await?;
+        Ok(())
+    }
+}
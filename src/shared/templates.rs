@@ -1,6 +1,219 @@
-pub static TEMPLATES: &[(&str, &[u8])] = &[
-    (
-        "Basic",
-        include_bytes!("../../templates/basic.typ"),
-    ),
+use serde::{Deserialize, Serialize};
+use typst::foundations::Bytes;
+
+use crate::shared::files::base64_data;
+use crate::shared::typst::VirtualFile;
+
+/// A template shipped with the app, `main` is compiled and may import `files`
+pub struct Template {
+    /// Stable key stored with each invoice
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub main: &'static [u8],
+    pub files: &'static [(&'static str, &'static [u8])],
+}
+
+impl Template {
+    pub fn source(&self) -> String {
+        String::from_utf8_lossy(self.main).into_owned()
+    }
+
+    /// The package's other files, mounted next to the main source
+    pub fn virtual_files(&self) -> Vec<VirtualFile> {
+        self.files
+            .iter()
+            .map(|(path, data)| VirtualFile::new(*path, Bytes::new(*data)))
+            .collect()
+    }
+
+    /// Owned copy, e.g. to start a user template from it
+    pub fn package(&self) -> TemplatePackage {
+        TemplatePackage {
+            id: self.id.to_string(),
+            name: self.name.to_string(),
+            description: self.description.to_string(),
+            source: self.source(),
+            files: self
+                .files
+                .iter()
+                .map(|(path, data)| PackageFile {
+                    path: path.to_string(),
+                    data: data.to_vec(),
+                })
+                .collect(),
+            built_in: true,
+        }
+    }
+}
+
+/// A template as the editor and the preview see it, built in or written by a user
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TemplatePackage {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Main source, compiled as `/main.typ`
+    pub source: String,
+    /// Imported sources and assets, e.g. `/common.typ` or `/images/stamp.png`
+    #[serde(default)]
+    pub files: Vec<PackageFile>,
+    /// Built-in templates are read only
+    #[serde(skip)]
+    pub built_in: bool,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct PackageFile {
+    pub path: String,
+    #[serde(with = "base64_data")]
+    pub data: Vec<u8>,
+}
+
+/// Identifies a template bundle file, bump the version on breaking changes
+const BUNDLE_FORMAT: &str = "invomodo-template";
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateBundleError {
+    #[error("Not a template bundle: {0}")]
+    Invalid(String),
+    #[error("Unsupported template bundle version {0}")]
+    UnsupportedVersion(u32),
+}
+
+/// A template exported to a single JSON file, sources and assets included
+#[derive(Serialize, Deserialize)]
+struct TemplateBundle {
+    format: String,
+    version: u32,
+    template: TemplatePackage,
+}
+
+impl TemplatePackage {
+    pub fn virtual_files(&self) -> Vec<VirtualFile> {
+        self.files
+            .iter()
+            .map(|file| VirtualFile::new(file.path.clone(), Bytes::new(file.data.clone())))
+            .collect()
+    }
+
+    pub fn to_bundle(&self) -> String {
+        let bundle = TemplateBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            template: self.clone(),
+        };
+        // Only strings, lists and maps, serialization cannot fail
+        serde_json::to_string_pretty(&bundle).unwrap_or_default()
+    }
+
+    /// Reads an exported bundle, the template gets a fresh id so it never replaces
+    /// an existing one
+    pub fn from_bundle(data: &[u8]) -> Result<Self, TemplateBundleError> {
+        let bundle: TemplateBundle = serde_json::from_slice(data)
+            .map_err(|e| TemplateBundleError::Invalid(e.to_string()))?;
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(TemplateBundleError::Invalid(format!(
+                "unknown format {:?}",
+                bundle.format
+            )));
+        }
+        if bundle.version > BUNDLE_VERSION {
+            return Err(TemplateBundleError::UnsupportedVersion(bundle.version));
+        }
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            built_in: false,
+            ..bundle.template
+        })
+    }
+
+    /// File name to export the bundle under
+    pub fn bundle_file_name(&self) -> String {
+        let name = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect::<String>();
+        format!("{}.template.json", name.trim_matches('-'))
+    }
+}
+
+/// Helpers shared by the built-in templates
+const COMMON: (&str, &[u8]) = ("/common.typ", include_bytes!("../../templates/common.typ"));
+
+pub const DEFAULT_TEMPLATE_ID: &str = "basic";
+
+pub static TEMPLATES: &[Template] = &[
+    Template {
+        id: DEFAULT_TEMPLATE_ID,
+        name: "Basic",
+        description: "Striped item table with a boxed totals footer",
+        main: include_bytes!("../../templates/basic.typ"),
+        files: &[COMMON],
+    },
+    Template {
+        id: "modern",
+        name: "Modern",
+        description: "Colour header band and card style totals",
+        main: include_bytes!("../../templates/modern.typ"),
+        files: &[COMMON],
+    },
+    Template {
+        id: "classic",
+        name: "Classic",
+        description: "Serif letterhead with a ruled table",
+        main: include_bytes!("../../templates/classic.typ"),
+        files: &[COMMON],
+    },
+    Template {
+        id: "minimal",
+        name: "Minimal",
+        description: "Plain layout with generous whitespace",
+        main: include_bytes!("../../templates/minimal.typ"),
+        files: &[COMMON],
+    },
+    Template {
+        id: "receipt",
+        name: "Compact receipt",
+        description: "80mm thermal printer roll",
+        main: include_bytes!("../../templates/receipt.typ"),
+        files: &[COMMON],
+    },
 ];
+
+/// Template with `id`, the default one when it is unknown
+pub fn find_template(id: &str) -> &'static Template {
+    TEMPLATES
+        .iter()
+        .find(|template| template.id == id)
+        .unwrap_or(&TEMPLATES[0])
+}
+
+/// Built-in templates followed by the user's own
+pub fn all_templates(user_templates: &[TemplatePackage]) -> Vec<TemplatePackage> {
+    TEMPLATES
+        .iter()
+        .map(Template::package)
+        .chain(user_templates.iter().cloned())
+        .collect()
+}
+
+/// Template with `id` among the built-in and user templates, the default one when
+/// it is unknown, e.g. because the user template was deleted
+pub fn resolve_template(id: &str, user_templates: &[TemplatePackage]) -> TemplatePackage {
+    user_templates
+        .iter()
+        .find(|template| template.id == id)
+        .cloned()
+        .unwrap_or_else(|| find_template(id).package())
+}
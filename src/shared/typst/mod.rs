@@ -1,12 +1,38 @@
+mod render;
+
+pub use render::*;
+
+use std::collections::HashMap;
+
 use typst::diag::{FileError, FileResult};
 use typst::foundations::{Bytes, Datetime};
-use typst::syntax::{FileId, Source};
+use typst::syntax::{FileId, Source, VirtualPath};
 use typst::text::{Font, FontBook};
 use typst::utils::LazyHash;
 use typst::{Library, World};
 
+/// Path of the main source, relative imports in it resolve against the root
+pub const MAIN_PATH: &str = "/main.typ";
+
+/// A file the document can read, e.g. `/assets/logo.png` or `/partials/header.typ`
+#[derive(Clone, PartialEq, Debug)]
+pub struct VirtualFile {
+    pub path: String,
+    pub data: Bytes,
+}
+
+impl VirtualFile {
+    pub fn new(path: impl Into<String>, data: Bytes) -> Self {
+        Self {
+            path: path.into(),
+            data,
+        }
+    }
+}
+
 pub struct InMemoryWorld {
     source: Source,
+    files: HashMap<FileId, Bytes>,
     fonts: Vec<Font>,
     book: LazyHash<FontBook>,
     library: LazyHash<Library>,
@@ -14,16 +40,33 @@ pub struct InMemoryWorld {
 
 impl InMemoryWorld {
     pub fn new(text: impl Into<String>, fonts: Vec<Font>) -> Self {
-        let source = Source::detached(text);
+        let source = Source::new(file_id(MAIN_PATH), text.into());
         let book = FontBook::from_fonts(&fonts);
 
         Self {
             source,
+            files: HashMap::new(),
             fonts,
             book: LazyHash::new(book),
             library: LazyHash::new(Library::default()),
         }
     }
+
+    /// Serves `data` at `path`, for images, data files and imported sources
+    pub fn with_file(mut self, path: &str, data: Bytes) -> Self {
+        self.files.insert(file_id(path), data);
+        self
+    }
+
+    pub fn with_files(self, files: impl IntoIterator<Item = VirtualFile>) -> Self {
+        files
+            .into_iter()
+            .fold(self, |world, file| world.with_file(&file.path, file.data))
+    }
+}
+
+fn file_id(path: &str) -> FileId {
+    FileId::new(None, VirtualPath::new(path))
 }
 
 impl World for InMemoryWorld {
@@ -33,14 +76,19 @@ impl World for InMemoryWorld {
 
     fn source(&self, id: FileId) -> FileResult<Source> {
         if id == self.source.id() {
-            Ok(self.source.clone())
-        } else {
-            Err(FileError::AccessDenied)
+            return Ok(self.source.clone());
         }
+
+        let data = self.file(id)?;
+        let text = std::str::from_utf8(&data).map_err(|_| FileError::InvalidUtf8)?;
+        Ok(Source::new(id, text.to_string()))
     }
 
-    fn file(&self, _id: FileId) -> FileResult<Bytes> {
-        Err(FileError::AccessDenied)
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        self.files
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| FileError::NotFound(id.vpath().as_rootless_path().into()))
     }
 
     fn font(&self, index: usize) -> Option<Font> {
@@ -52,13 +100,84 @@ impl World for InMemoryWorld {
     }
 
     fn today(&self, offset: Option<i64>) -> Option<Datetime> {
-        let offset = offset.unwrap_or(0);
-        let offset = time::UtcOffset::from_hms(offset as i8, 0, 0).ok()?;
-        let now = time::OffsetDateTime::now_utc().checked_to_offset(offset)?;
-        Some(Datetime::Date(now.date()))
+        // `time` cannot read the clock on wasm32-unknown-unknown, ask the browser
+        let now = js_sys::Date::new_0();
+        let offset_minutes = match offset {
+            Some(hours) => hours as f64 * 60.0,
+            None => -now.get_timezone_offset(),
+        };
+        let shifted = js_sys::Date::new(&(now.get_time() + offset_minutes * 60_000.0).into());
+        Datetime::from_ymd(
+            shifted.get_utc_full_year() as i32,
+            shifted.get_utc_month() as u8 + 1,
+            shifted.get_utc_date() as u8,
+        )
     }
 
     fn library(&self) -> &LazyHash<Library> {
         &self.library
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(main: &str) -> InMemoryWorld {
+        InMemoryWorld::new(main, Vec::new()).with_files([
+            VirtualFile::new(
+                "/common.typ",
+                Bytes::new(b"#let greeting = [Hello]".to_vec()),
+            ),
+            VirtualFile::new("/assets/logo.png", Bytes::new(vec![0x89, b'P', b'N', b'G'])),
+        ])
+    }
+
+    #[test]
+    fn files_are_looked_up_by_path() {
+        let world = world("");
+
+        assert_eq!(world.main(), file_id(MAIN_PATH));
+        assert_eq!(
+            world.file(file_id("/assets/logo.png")).unwrap().as_slice(),
+            [0x89, b'P', b'N', b'G']
+        );
+        assert_eq!(
+            world.source(file_id("/common.typ")).unwrap().text(),
+            "#let greeting = [Hello]"
+        );
+    }
+
+    #[test]
+    fn missing_file_is_not_found() {
+        let world = world("");
+
+        assert_eq!(
+            world.file(file_id("/assets/missing.png")),
+            Err(FileError::NotFound("assets/missing.png".into()))
+        );
+        assert!(matches!(
+            world.source(file_id("/missing.typ")),
+            Err(FileError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn template_imports_common_source() {
+        // The template fails to compile when the imported value is wrong
+        let world =
+            world("#import \"common.typ\": greeting\n#assert.eq(greeting, [Hello])\n#greeting");
+
+        let rendered = compile(&world).unwrap();
+        assert_eq!(rendered.output.pages.len(), 1);
+    }
+
+    #[test]
+    fn missing_import_fails_with_a_diagnostic() {
+        let world = world("#import \"missing.typ\": greeting");
+
+        let error = compile(&world).unwrap_err();
+        assert!(error.diagnostics[0].message.contains("not found"));
+        assert_eq!(error.diagnostics[0].line, Some(1));
+    }
+}
@@ -0,0 +1,180 @@
+use typst::World;
+use typst::diag::{Severity, SourceDiagnostic, Warned};
+use typst::layout::PagedDocument;
+use typst_pdf::PdfOptions;
+
+use super::InMemoryWorld;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+}
+
+/// A compiler error or warning, resolved to a place in the template sources
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub level: DiagnosticLevel,
+    pub message: String,
+    pub hints: Vec<String>,
+    /// File the diagnostic points into, e.g. `/main.typ`
+    pub path: Option<String>,
+    /// 1-based line and column of the start of the span
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Text of the line the span starts on
+    pub source_line: Option<String>,
+}
+
+impl Diagnostic {
+    fn resolve(world: &InMemoryWorld, diagnostic: &SourceDiagnostic) -> Self {
+        let level = match diagnostic.severity {
+            Severity::Error => DiagnosticLevel::Error,
+            Severity::Warning => DiagnosticLevel::Warning,
+        };
+
+        let mut resolved = Self {
+            level,
+            message: diagnostic.message.to_string(),
+            hints: diagnostic
+                .hints
+                .iter()
+                .map(|hint| hint.to_string())
+                .collect(),
+            path: None,
+            line: None,
+            column: None,
+            source_line: None,
+        };
+
+        let Some(id) = diagnostic.span.id() else {
+            return resolved;
+        };
+        resolved.path = Some(id.vpath().as_rooted_path().display().to_string());
+
+        // Spans in binary files, e.g. a broken image, have no line to show
+        let Ok(source) = world.source(id) else {
+            return resolved;
+        };
+        let Some(start) = source.range(diagnostic.span).map(|range| range.start) else {
+            return resolved;
+        };
+        if let Some(line) = source.byte_to_line(start) {
+            resolved.line = Some(line + 1);
+            resolved.source_line = source.text().lines().nth(line).map(str::to_string);
+        }
+        resolved.column = source.byte_to_column(start).map(|column| column + 1);
+        resolved
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level == DiagnosticLevel::Error
+    }
+
+    /// Where the diagnostic points to, e.g. `/main.typ:12:5`
+    pub fn location(&self) -> Option<String> {
+        let path = self.path.as_ref()?;
+        Some(match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{path}:{line}:{column}"),
+            (Some(line), None) => format!("{path}:{line}"),
+            _ => path.clone(),
+        })
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = match self.level {
+            DiagnosticLevel::Error => "error",
+            DiagnosticLevel::Warning => "warning",
+        };
+        match self.location() {
+            Some(location) => write!(f, "{location}: {level}: {}", self.message),
+            None => write!(f, "{level}: {}", self.message),
+        }
+    }
+}
+
+/// Output of a successful render together with the compiler warnings
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rendered<T> {
+    pub output: T,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// A render that failed, `diagnostics` holds the errors followed by the warnings
+#[derive(Clone, PartialEq, Debug, thiserror::Error)]
+#[error("{}", .diagnostics.first().map(ToString::to_string).unwrap_or_default())]
+pub struct RenderError {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+fn resolve_all(world: &InMemoryWorld, diagnostics: &[SourceDiagnostic]) -> Vec<Diagnostic> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| Diagnostic::resolve(world, diagnostic))
+        .collect()
+}
+
+/// Lays out the document, never panics on broken templates
+pub fn compile(world: &InMemoryWorld) -> Result<Rendered<PagedDocument>, RenderError> {
+    let Warned { output, warnings } = typst::compile::<PagedDocument>(world);
+    let warnings = resolve_all(world, &warnings);
+
+    match output {
+        Ok(document) => Ok(Rendered {
+            output: document,
+            warnings,
+        }),
+        Err(errors) => {
+            let mut diagnostics = resolve_all(world, &errors);
+            diagnostics.extend(warnings);
+            Err(RenderError { diagnostics })
+        }
+    }
+}
+
+/// First page as an SVG image, used for template thumbnails
+pub fn render_svg(world: &InMemoryWorld) -> Result<Rendered<String>, RenderError> {
+    let Rendered { output, warnings } = compile(world)?;
+
+    Ok(Rendered {
+        output: output.pages.first().map(typst_svg::svg).unwrap_or_default(),
+        warnings,
+    })
+}
+
+pub fn render_pdf(world: &InMemoryWorld) -> Result<Rendered<Vec<u8>>, RenderError> {
+    let Rendered { output, warnings } = compile(world)?;
+
+    match typst_pdf::pdf(&output, &PdfOptions::default()) {
+        Ok(pdf) => Ok(Rendered {
+            output: pdf,
+            warnings,
+        }),
+        Err(errors) => {
+            let mut diagnostics = resolve_all(world, &errors);
+            diagnostics.extend(warnings);
+            Err(RenderError { diagnostics })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syntax_error_points_at_its_line_and_column() {
+        let world = InMemoryWorld::new("= Invoice\n#let = 1\n", Vec::new());
+
+        let error = compile(&world).unwrap_err();
+        let diagnostic = &error.diagnostics[0];
+        assert!(diagnostic.is_error());
+        assert_eq!(diagnostic.message, "expected pattern");
+        assert_eq!(diagnostic.path.as_deref(), Some("/main.typ"));
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(2), Some(5)));
+        assert_eq!(diagnostic.source_line.as_deref(), Some("#let = 1"));
+        assert_eq!(error.to_string(), "/main.typ:2:5: error: expected pattern");
+    }
+}
@@ -23,7 +23,8 @@ const NETWORK_FIRST_ROUTES = [
 const CACHE_FIRST_ROUTES = [
   '/static/',
   '/icons/',
-  '/assets/'
+  '/assets/',
+  '/fonts/'
 ];
 
 // Install event - cache static assets
@@ -1,90 +1,31 @@
-// This invoice template is designed to accept all data from a single
-// JSON-like object, making it highly modular and easy to reuse.
-
-// --- 1. CONFIGURATION DATA OBJECT ---
-// All invoice data is now in this single object. Simply update this block
-// to create a new invoice.
-#let invoice-data = (
-  meta: (
-    title: "INVOICE",
-    invoice-number: "SDI-2024-001",
-    date: "2024-08-16",
-    due-date: "2024-08-30",
-  ),
-  sender: (
-    name: "Tech Solutions, LLC",
-    address: "123 Silicon Alley",
-    city: "San Jose, CA 95113",
-    country: "USA",
-    email: "billing@techsolutions.com",
-    phone: "+1 (555) 789-0123",
-  ),
-  client: (
-    name: "Startup Innovations Co.",
-    address: "456 Innovation Drive",
-    city: "Palo Alto, CA 94301",
-    country: "USA",
-    email: "accounts@startupinnovations.com",
-  ),
-  project: (
-    name: "Mobile App Development",
-  ),
-  financial: (
-    currency: "$",
-    tax-rate: 0.08,
-    payment-instructions: "Payment is due upon receipt. We accept bank transfer or PayPal.",
-    payment-details: [
-      Bank: Innovation Bank \
-      Account No.: 555566667777 \
-      Routing No.: 987654321
-    ],
-  ),
-  items: (
-    ("Frontend Development (React)", 45, 120, 5400),
-    ("Backend API Development (Node.js)", 30, 150, 4500),
-    ("Database Design & Integration", 15, 150, 2250),
-    ("Frontend Development (React)", 45, 120, 5400),
-    ("Frontend Development (React)", 45, 120, 5400),
-    ("Frontend Development (React)", 45, 120, 5400),
-    ("Frontend Development (React)", 45, 120, 5400),
-    ("Frontend Development (React)", 45, 120, 5400),
-    ("Frontend Development (React)", 45, 120, 5400),
-    ("Frontend Development (React)", 45, 120, 5400),
-    ("Backend API Development (Node.js)", 30, 150, 4500),
-    ("Database Design & Integration", 15, 150, 2250),
-    ("Backend API Development (Node.js)", 30, 150, 4500),
-    ("Database Design & Integration", 15, 150, 2250),
-    ("Backend API Development (Node.js)", 30, 150, 4500),
-    ("Database Design & Integration", 15, 150, 2250),
-    ("Backend API Development (Node.js)", 30, 150, 4500),
-    ("Database Design & Integration", 15, 150, 2250),
-    ("Backend API Development (Node.js)", 30, 150, 4500),
-    ("Database Design & Integration", 15, 150, 2250),
-    ("Backend API Development (Node.js)", 30, 150, 4500),
-    ("Database Design & Integration", 15, 150, 2250),
-    ("Backend API Development (Node.js)", 30, 150, 4500),
-    ("Database Design & Integration", 15, 150, 2250),
-  ),
-)
+// This invoice template reads all of its data from `/invoice.json`, loaded by
+// the shared helpers in `/common.typ`.
+
+#import "/common.typ": *
+
+// --- 1. LAYOUT & STYLING ---
+#let accent = rgb("#004080")
 
-// --- 2. LAYOUT & STYLING ---
 #set page(
   paper: "a4",
   margin: (top: 1in, bottom: 1in, left: 1in, right: 1in),
 )
 
 #set text(
-  font: "Arial",
+  font: ("Arial", "Libertinus Serif"),
   size: 11pt,
   fill: rgb("#333333")
 )
 
-// --- 3. TEMPLATE STRUCTURE ---
+// --- 2. TEMPLATE STRUCTURE ---
 
 // Header
-#align(right)[
-  #text(30pt, weight: "bold", fill: rgb("#004080"))[#invoice-data.meta.title]
-]
+#grid(
+  columns: (1fr, 1fr),
+  align: (left + horizon, right + horizon),
+  asset(invoice-data.logo, height: 2cm),
+  text(30pt, weight: "bold", fill: accent)[#upper(invoice-data.title)],
+)
 
 #v(1em)
 
@@ -94,43 +35,85 @@
   row-gutter: 1em,
   align: (left, right),
   [
-    #text(12pt, weight: "bold")[#invoice-data.sender.name] \
-    #invoice-data.sender.address \
-    #invoice-data.sender.city \
-    #invoice-data.sender.country \
-    #link("mailto:" + invoice-data.sender.email)[#invoice-data.sender.email] \
-    #invoice-data.sender.phone
+    #text(12pt, weight: "bold")[#party-name(invoice-data.biller)] \
+    #party-details(invoice-data.biller)
   ],
   [
-    #text(weight: "bold")[Invoice No.]: #invoice-data.meta.invoice-number \
-    #text(weight: "bold")[Date]: #invoice-data.meta.date \
-    #text(weight: "bold")[Due Date]: #invoice-data.meta.due-date
+    #text(weight: "bold")[#invoice-data.title No.]: #invoice-data.number \
+    #text(weight: "bold")[Date]: #invoice-data.issue_date \
+    #text(weight: "bold")[#if is-quote [Valid Until] else if is-credit-note [Credits Invoice] else [Due Date]]: #due-date
+    #for field in invoice-data.invoice.filter(field => (
+      field.value != "" and field.id not in ("invoice_number", "issue_date", "due_date")
+    )) [
+      \ #text(weight: "bold")[#field.label]: #field.value
+    ]
   ],
 )
 
 #line(length: 100%, stroke: 1pt + rgb("#CCCCCC"))
 #v(1.5em)
 
-#grid(
-  columns: (1fr, 1fr),
-  column-gutter: 1em,
-  row-gutter: 1em,
-  align: (left, right),
-  [
-    #text(12pt, weight: "bold", fill: rgb("#004080"))[BILL TO] \ 
-    #text(11pt, weight: "bold")[#invoice-data.client.name] \
-    #invoice-data.client.address \
-    #invoice-data.client.city \
-    #invoice-data.client.country \ 
-    #link("mailto:" + invoice-data.client.email)[#invoice-data.client.email]
-  ],
-  [
-    #text(10pt)[Project: #invoice-data.project.name]
-  ],
-)
+#text(12pt, weight: "bold", fill: accent)[BILL TO] \
+#text(11pt, weight: "bold")[#party-name(invoice-data.client)] \
+#party-details(invoice-data.client)
 
-// Define the item data
-#let item-table-data = invoice-data.items
+#v(1.5em)
+
+// Details printed below an item name: description, custom fields, discounts,
+// charges
+#let item-details(item) = {
+  let details = ()
+  if item.description != "" {
+    details.push(item.description)
+  }
+  for field in item.fields.filter(field => field.value != "") {
+    details.push[#field.label: #field.value]
+  }
+  for discount in item.discounts {
+    details.push[Discount #discount.name (#discount.value)]
+  }
+  for charge in item.charges {
+    details.push[#charge.name: #money(charge.value)]
+  }
+  for tax in item.taxes {
+    details.push[#tax.name (#tax.rate#incl(tax)): #money(tax.amount)]
+  }
+  if details.len() > 0 {
+    linebreak()
+    text(8pt, fill: rgb("#666666"), details.join(linebreak()))
+  }
+}
+
+#let stroke = 1.5pt + accent
+
+// Summary rows below the items, label and formatted amount
+#let summary-rows = {
+  let rows = (([Subtotal:], money(invoice-data.totals.subtotal)),)
+  if invoice-data.totals.discount_total != "0.00" {
+    rows.push(([Discounts:], [- #money(invoice-data.totals.discount_total)]))
+  }
+  if invoice-data.totals.charge_total != "0.00" {
+    rows.push(([Charges:], money(invoice-data.totals.charge_total)))
+  }
+  for tax in invoice-data.totals.taxes {
+    rows.push(([#tax.name \(#tax.rate#incl(tax)\):], money(tax.amount)))
+  }
+  rows.push(([*#if is-credit-note [Total Credited:] else [Total Due:]*], [*#money(invoice-data.totals.grand_total)*]))
+  if has-withholding {
+    for tax in invoice-data.totals.withholding {
+      rows.push(([#tax.name withheld \(#tax.rate\):], [- #money(tax.amount)]))
+    }
+    rows.push(([*Net Payable:*], [*#money(invoice-data.totals.net_payable)*]))
+  }
+  if has-payments {
+    rows.push(([Paid:], [- #money(invoice-data.totals.amount_paid)]))
+    if has-credits {
+      rows.push(([Credited:], [- #money(invoice-data.totals.amount_credited)]))
+    }
+    rows.push(([*Balance Due:*], [*#money(invoice-data.totals.balance_due)*]))
+  }
+  rows
+}
 
 // Table with header and styled body
 #table(
@@ -139,95 +122,69 @@
   inset: 10pt,
   fill: (col, row) => if calc.rem(row, 2) == 1 { rgb("F0F8FF") } else { rgb("FFFFFF") },
   stroke: (col, row) => {
-    let stroke = rgb("004080")
-    if col == 0 and row != 0 {
-      return (
-        left: 1.5pt + stroke,
-      )
+    if row == 0 {
+      return stroke
     }
-    if col == item-table-data.first().len() - 1 and row != 0 {
-      return (
-        right: 1.5pt + stroke,
-      )
+    if col == 0 {
+      return (left: stroke)
     }
-    if row == 0 {
-      return 1.5pt + stroke
-    } else {
-      return none
+    if col == 3 {
+      return (right: stroke)
     }
+    none
   },
 
   // Header row
   table.header(
     repeat: true,
     [*Description*],
-    [*Hours*],
-    [*Rate/hr*],
+    [*Qty*],
+    [*Unit price*],
     table.cell(align: right)[*Total*]
   ),
 
   // Body rows
-  ..for (row, (desc, hours, rate, total)) in item-table-data.enumerate() {
-    ([#desc], [#hours], [#rate], [#total])
+  ..for item in invoice-data.items {
+    (
+      [#item.name #item-details(item)],
+      [#item.quantity],
+      money(item.unit_price),
+      money(item.total),
+    )
   },
-  
-  // Totals Section
-  let subtotal = item-table-data.map(row => row.at(3)).sum(),
-  let tax-amount = subtotal * invoice-data.financial.tax-rate,
-  let total = subtotal + tax-amount,
-  let tax-rate-percent = invoice-data.financial.tax-rate * 100,
-
-  let footer_top_stroke = (
-    top: 1.5pt + rgb("004080"),
-  ),
-  let footer_top_left_stroke = (
-    top: 1.5pt + rgb("004080"),
-    left: 1.5pt + rgb("004080"),
-  ),
-  let footer_left_stroke = (
-    left: 1.5pt + rgb("004080"),
-  ),
-  let footer_bottom_stroke = (
-    bottom: 1.5pt + rgb("004080"),
-  ),
-  let footer_bottom_left_stroke = (
-    bottom: 1.5pt + rgb("004080"),
-    left: 1.5pt + rgb("004080"),
-  ),
-  
-  // Footer row
-  table.footer(
-    repeat: true,
 
-    // Subtotal
-    table.cell(stroke: footer_top_stroke)[],
-    table.cell(stroke: footer_top_stroke)[],
-    table.cell(align:right, stroke: footer_top_left_stroke)[*Subtotal:*],
-    table.cell(align:right, stroke: footer_top_stroke)[#invoice-data.financial.currency #subtotal.2],
-
-    // Taxes
-    [],
-    [],
-    table.cell(align:right, stroke: footer_left_stroke)[*Tax \(#tax-rate-percent%\):*],
-    table.cell(align:right)[#invoice-data.financial.currency #tax-amount.2],
-
-    // Total Due
-    table.cell(stroke: footer_bottom_stroke)[],
-    table.cell(stroke: footer_bottom_stroke)[],
-    table.cell(align:right, stroke: footer_bottom_left_stroke)[*Total Due:*],
-    table.cell(align:right, stroke: footer_bottom_stroke)[#invoice-data.financial.currency #total.2],
+  // Totals section
+  table.footer(
+    repeat: false,
+    ..for (index, (label, amount)) in summary-rows.enumerate() {
+      let top = if index == 0 { stroke } else { none }
+      let bottom = if index == summary-rows.len() - 1 { stroke } else { none }
+      (
+        table.cell(colspan: 2, stroke: (top: top, bottom: bottom))[],
+        table.cell(align: right, stroke: (top: top, bottom: bottom, left: stroke))[#label],
+        table.cell(align: right, stroke: (top: top, bottom: bottom, right: stroke))[#amount],
+      )
+    },
   ),
 )
 
 #v(3em)
 
+#if invoice-data.signature != none [
+  #align(right)[
+    #asset(invoice-data.signature, height: 1.5cm) \
+    #text(9pt)[Authorized signature]
+  ]
+]
+
 // Footer
 #line(length: 100%, stroke: 1pt + rgb("#CCCCCC"))
 #v(1em)
 
-#box(align(left)[
-  #text(8pt)[
-    *Payment Instructions:* #invoice-data.financial.payment-instructions #linebreak()
-    #invoice-data.financial.payment-details
-  ]
-])
+#text(8pt, hsn-summary)
+#text(8pt, weight: "bold", tax-exemption)
+#text(8pt, weight: "bold", vat-notes)
+
+#for field in invoice-data.extra.filter(field => field.value != "") [
+  #text(8pt)[*#field.label:* #field.value] \
+]
@@ -0,0 +1,90 @@
+// Classic: serif type, centred letterhead and ruled table.
+
+#import "/common.typ": *
+
+#set page(paper: "a4", margin: 2.5cm)
+#set text(font: ("Libertinus Serif", "New Computer Modern"), size: 11pt)
+
+#align(center)[
+  #asset(invoice-data.logo, height: 1.8cm)
+  #text(18pt, weight: "bold")[#party-name(invoice-data.biller)] \
+  #text(9pt)[#party-details(invoice-data.biller)]
+]
+
+#line(length: 100%, stroke: 1.5pt)
+#v(-0.6em)
+#line(length: 100%, stroke: 0.5pt)
+
+#align(center, text(16pt, tracking: 3pt)[#upper(invoice-data.title)])
+
+#grid(
+  columns: (1fr, 1fr),
+  [
+    _Billed to:_ \
+    *#party-name(invoice-data.client)* \
+    #party-details(invoice-data.client)
+  ],
+  align(right)[
+    #invoice-data.title No. #invoice-data.number \
+    Date: #invoice-data.issue_date \
+    #due-label: #due-date
+  ],
+)
+
+#v(1em)
+
+#table(
+  columns: (1fr, auto, auto, auto),
+  align: (left, right, right, right),
+  stroke: (_, row) => if row == 0 { (y: 0.8pt) } else { (bottom: 0.3pt) },
+  table.header[*Description*][*Quantity*][*Rate*][*Amount*],
+  ..for item in invoice-data.items {
+    (item.name, item.quantity, money(item.unit_price), money(item.total))
+  },
+)
+
+#align(right)[
+  #table(
+    columns: (auto, auto),
+    align: (left, right),
+    stroke: none,
+    [Subtotal], money(invoice-data.totals.subtotal),
+    [Less discounts], money(invoice-data.totals.discount_total),
+    [Charges], money(invoice-data.totals.charge_total),
+    ..for tax in invoice-data.totals.taxes {
+      ([#tax.name (#tax.rate#incl(tax))], money(tax.amount))
+    },
+    table.hline(stroke: 0.8pt),
+    [*#if is-credit-note [Total credited] else [Total due]*], [*#money(invoice-data.totals.grand_total)*],
+    ..if has-withholding {
+      (
+        ..for tax in invoice-data.totals.withholding {
+          ([#tax.name withheld (#tax.rate)], [- #money(tax.amount)])
+        },
+        [*Net payable*], [*#money(invoice-data.totals.net_payable)*],
+      )
+    } else { () },
+    ..if has-payments {
+      (
+        [Paid], money(invoice-data.totals.amount_paid),
+        ..if has-credits { ([Credited], money(invoice-data.totals.amount_credited)) } else { () },
+        [*Balance due*], [*#money(invoice-data.totals.balance_due)*],
+      )
+    } else { () },
+  )
+]
+
+#if invoice-data.signature != none [
+  #v(1em)
+  #asset(invoice-data.signature, height: 1.5cm) \
+  #line(length: 5cm, stroke: 0.5pt)
+  _Authorized signature_
+]
+
+#v(1fr)
+#text(9pt, hsn-summary)
+#text(9pt, style: "italic", tax-exemption)
+#text(9pt, style: "italic", vat-notes)
+#for field in invoice-data.extra.filter(field => field.value != "") [
+  #text(9pt)[_#field.label:_ #field.value] \
+]
@@ -0,0 +1,79 @@
+// Helpers shared by the built-in invoice templates.
+
+// Invoice data written by the app, see `TemplateData` for the shape. Amounts
+// arrive pre-formatted, so templates only lay them out.
+#let invoice-data = json("/invoice.json")
+
+#let money(amount) = [#invoice-data.currency #amount]
+
+// Field values with an empty value are left out
+#let field-lines(fields) = fields
+  .filter(field => field.value != "")
+  .map(field => field.value)
+  .join(linebreak())
+
+// Name of a biller or client, the first filled in field
+#let party-name(fields) = {
+  let named = fields.filter(field => field.value != "")
+  if named.len() > 0 { named.first().value } else { "" }
+}
+
+// Every field after the name
+#let party-details(fields) = field-lines(fields.slice(calc.min(1, fields.len())))
+
+// Why the invoice carries no tax, empty unless it is tax exempt
+#let tax-exemption = {
+  let note = invoice-data.tax_exemption
+  if note == none [] else if note == "" [Tax exempt] else [Tax exempt: #note]
+}
+
+// Why taxes are not charged, e.g. the reverse charge wording, one per line
+#let vat-notes = invoice-data.vat_notes.join(linebreak())
+
+// GST by HSN or SAC code under the place of supply, empty without GST
+#let hsn-summary = if invoice-data.hsn_summary.len() > 0 {
+  let place = invoice-data.place_of_supply
+  [
+    #if place != none [Place of supply: #place]
+    #table(
+      columns: (auto, 1fr, 1fr, 1fr),
+      align: (left, right, right, right),
+      stroke: 0.5pt + luma(200),
+      [*HSN/SAC*], [*Taxable*], [*Tax*], [*Total tax*],
+      ..invoice-data.hsn_summary.map(row => (
+        row.code,
+        money(row.taxable),
+        row.taxes.map(tax => [#tax.name #tax.rate: #tax.amount]).join(linebreak()),
+        money(row.tax_total),
+      )).flatten(),
+    )
+  ]
+}
+
+// Quotes show the date they are valid until where invoices show the due date,
+// credit notes show the number of the invoice they correct
+#let is-quote = invoice-data.document_type == "quote"
+#let is-credit-note = invoice-data.document_type == "credit_note"
+#let due-label = if is-quote [Valid until] else if is-credit-note [Credits invoice] else [Due]
+#let due-date = if is-quote {
+  invoice-data.valid_until
+} else if is-credit-note {
+  invoice-data.credited_invoice_number
+} else {
+  invoice-data.due_date
+}
+
+// Whether payments or credit notes were recorded, templates then show what is
+// left to pay
+#let has-credits = invoice-data.totals.amount_credited != "0.00"
+#let has-payments = invoice-data.totals.amount_paid != "0.00" or has-credits
+
+// Taxes the client withholds are deducted after the total, leaving the net payable
+#let has-withholding = invoice-data.totals.withholding.len() > 0
+
+// Taxes included in the prices are listed but not added to the total again
+#let incl(tax) = if tax.included [ incl.]
+#let has-included-tax = invoice-data.totals.tax_included != "0.00"
+
+// Uploaded image at `path`, nothing when no image was uploaded
+#let asset(path, ..args) = if path != none { image(path, ..args) }
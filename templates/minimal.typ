@@ -0,0 +1,65 @@
+// Minimal: lots of whitespace, no rules or colour.
+
+#import "/common.typ": *
+
+#set page(paper: "a4", margin: (x: 2.5cm, y: 3cm))
+#set text(font: ("Arial", "Libertinus Serif"), size: 10pt, fill: rgb("#222222"))
+
+#grid(
+  columns: (1fr, auto),
+  text(20pt)[#invoice-data.title #invoice-data.number],
+  asset(invoice-data.logo, height: 1.2cm),
+)
+
+#v(2em)
+
+#grid(
+  columns: (1fr, 1fr, 1fr),
+  [#party-name(invoice-data.biller) \ #party-details(invoice-data.biller)],
+  [#party-name(invoice-data.client) \ #party-details(invoice-data.client)],
+  align(right)[Issued #invoice-data.issue_date \ #due-label #due-date],
+)
+
+#v(3em)
+
+#table(
+  columns: (1fr, auto, auto),
+  align: (left, right, right),
+  stroke: none,
+  inset: (x: 0pt, y: 6pt),
+  column-gutter: 2em,
+  ..for item in invoice-data.items {
+    ([#item.name], [#item.quantity × #item.unit_price], money(item.total))
+  },
+)
+
+#v(2em)
+
+#align(right)[
+  #grid(
+    columns: (auto, auto),
+    column-gutter: 2em,
+    row-gutter: 6pt,
+    [Subtotal], money(invoice-data.totals.subtotal),
+    [Tax#if has-included-tax [ (#invoice-data.totals.tax_included incl.)]], money(invoice-data.totals.tax_total),
+    text(14pt)[Total], text(14pt)[#money(invoice-data.totals.grand_total)],
+    ..if has-withholding {
+      for tax in invoice-data.totals.withholding { ([#tax.name withheld], [- #money(tax.amount)]) }
+      ([Net payable], money(invoice-data.totals.net_payable))
+    } else { () },
+    ..if has-payments {
+      ([Paid], money(invoice-data.totals.amount_paid))
+      if has-credits { ([Credited], money(invoice-data.totals.amount_credited)) }
+      ([Balance due], money(invoice-data.totals.balance_due))
+    } else { () },
+  )
+]
+
+#v(1fr)
+#asset(invoice-data.signature, height: 1.2cm)
+#text(8pt, hsn-summary)
+#text(8pt, tax-exemption)
+#text(8pt, vat-notes)
+#for field in invoice-data.extra.filter(field => field.value != "") [
+  #text(8pt)[#field.value] \
+]
@@ -0,0 +1,126 @@
+// Modern: bold colour band header, card style totals.
+
+#import "/common.typ": *
+
+#let accent = rgb("#4f46e5")
+#let muted = rgb("#6b7280")
+
+#set page(paper: "a4", margin: (top: 0pt, bottom: 2cm, x: 0pt))
+#set text(font: ("Arial", "Libertinus Serif"), size: 10pt, fill: rgb("#111827"))
+
+// Header band
+#block(width: 100%, fill: accent, inset: (x: 2cm, y: 1.2cm))[
+  #set text(fill: white)
+  #grid(
+    columns: (1fr, auto),
+    align: (left + horizon, right + horizon),
+    [
+      #asset(invoice-data.logo, height: 1.5cm)
+      #text(24pt, weight: "bold")[#invoice-data.title]
+      #h(0.5em) #text(12pt)[\##invoice-data.number]
+    ],
+    [
+      Issued #invoice-data.issue_date \
+      #due-label #due-date \
+      #text(weight: "bold")[#invoice-data.status]
+    ],
+  )
+]
+
+#pad(x: 2cm, top: 1cm)[
+  #grid(
+    columns: (1fr, 1fr),
+    column-gutter: 1cm,
+    [
+      #text(8pt, fill: muted, tracking: 1pt)[FROM] \
+      #text(weight: "bold")[#party-name(invoice-data.biller)] \
+      #party-details(invoice-data.biller)
+    ],
+    [
+      #text(8pt, fill: muted, tracking: 1pt)[BILL TO] \
+      #text(weight: "bold")[#party-name(invoice-data.client)] \
+      #party-details(invoice-data.client)
+    ],
+  )
+
+  #v(1cm)
+
+  #table(
+    columns: (1fr, auto, auto, auto),
+    align: (left, right, right, right),
+    stroke: none,
+    inset: (x: 6pt, y: 8pt),
+    fill: (_, row) => if row == 0 { rgb("#eef2ff") },
+    table.header(
+      text(fill: accent, weight: "bold")[Item],
+      text(fill: accent, weight: "bold")[Qty],
+      text(fill: accent, weight: "bold")[Price],
+      text(fill: accent, weight: "bold")[Amount],
+    ),
+    ..for item in invoice-data.items {
+      (
+        [
+          #item.name
+          #for tax in item.taxes [
+            \ #text(8pt, fill: muted)[#tax.name #tax.rate#incl(tax): #money(tax.amount)]
+          ]
+        ],
+        item.quantity,
+        money(item.unit_price),
+        money(item.total),
+      )
+    },
+  )
+
+  #line(length: 100%, stroke: 0.5pt + muted)
+
+  #align(right)[
+    #block(width: 7cm, fill: rgb("#f9fafb"), inset: 10pt, radius: 6pt)[
+      #grid(
+        columns: (1fr, auto),
+        row-gutter: 6pt,
+        [Subtotal], money(invoice-data.totals.subtotal),
+        [Discounts], [- #money(invoice-data.totals.discount_total)],
+        [Charges], money(invoice-data.totals.charge_total),
+        ..for tax in invoice-data.totals.taxes {
+          ([#tax.name (#tax.rate#incl(tax))], money(tax.amount))
+        },
+        text(weight: "bold", fill: accent)[Total],
+        text(weight: "bold", fill: accent)[#money(invoice-data.totals.grand_total)],
+        ..if has-withholding {
+          (
+            ..for tax in invoice-data.totals.withholding {
+              ([#tax.name withheld (#tax.rate)], [- #money(tax.amount)])
+            },
+            text(weight: "bold")[Net payable],
+            text(weight: "bold")[#money(invoice-data.totals.net_payable)],
+          )
+        } else { () },
+        ..if has-payments {
+          (
+            [Paid], [- #money(invoice-data.totals.amount_paid)],
+            ..if has-credits {
+              ([Credited], [- #money(invoice-data.totals.amount_credited)])
+            } else { () },
+            text(weight: "bold")[Balance due],
+            text(weight: "bold")[#money(invoice-data.totals.balance_due)],
+          )
+        } else { () },
+      )
+    ]
+  ]
+
+  #if invoice-data.signature != none [
+    #align(right)[#asset(invoice-data.signature, height: 1.5cm)]
+  ]
+
+  #text(8pt, hsn-summary)
+
+  #text(8pt, fill: muted, tax-exemption)
+  #text(8pt, fill: muted, vat-notes)
+
+  #for field in invoice-data.extra.filter(field => field.value != "") [
+    #text(8pt, fill: muted)[*#field.label* \ #field.value]
+    #v(4pt)
+  ]
+]
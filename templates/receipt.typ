@@ -0,0 +1,59 @@
+// Compact receipt: 80mm wide thermal printer roll, page grows with content.
+
+#import "/common.typ": *
+
+#set page(width: 80mm, height: auto, margin: (x: 4mm, y: 6mm))
+#set text(font: ("DejaVu Sans Mono", "Arial"), size: 8pt)
+#set par(spacing: 0.6em)
+
+#let dashed = line(length: 100%, stroke: (thickness: 0.5pt, dash: "dashed"))
+
+#align(center)[
+  #asset(invoice-data.logo, width: 30mm)
+  #text(10pt, weight: "bold")[#party-name(invoice-data.biller)] \
+  #party-details(invoice-data.biller)
+]
+
+#dashed
+#if is-quote [Quote No.] else if is-credit-note [Credit Note No.] else [No.] #invoice-data.number #h(1fr) #invoice-data.issue_date
+#if is-credit-note [\ Credits invoice #invoice-data.credited_invoice_number]
+#dashed
+
+#for item in invoice-data.items [
+  #item.name \
+  #h(2mm) #item.quantity x #item.unit_price #h(1fr) #item.total \
+]
+
+#dashed
+#let row(label, amount) = [#label #h(1fr) #amount \ ]
+#row[Subtotal][#invoice-data.totals.subtotal]
+#if invoice-data.totals.discount_total != "0.00" {
+  row[Discount][-#invoice-data.totals.discount_total]
+}
+#if invoice-data.totals.charge_total != "0.00" {
+  row[Charges][#invoice-data.totals.charge_total]
+}
+#for tax in invoice-data.totals.taxes {
+  row[#tax.name #tax.rate#incl(tax)][#tax.amount]
+}
+#dashed
+#text(10pt, weight: "bold")[#row[TOTAL][#money(invoice-data.totals.grand_total)]]
+#if has-withholding {
+  for tax in invoice-data.totals.withholding { row[#tax.name withheld #tax.rate][-#tax.amount] }
+  text(10pt, weight: "bold")[#row[NET PAYABLE][#money(invoice-data.totals.net_payable)]]
+}
+#if has-payments {
+  row[Paid][-#invoice-data.totals.amount_paid]
+  if has-credits { row[Credited][-#invoice-data.totals.amount_credited] }
+  text(10pt, weight: "bold")[#row[BALANCE DUE][#money(invoice-data.totals.balance_due)]]
+}
+#dashed
+
+#align(center)[
+  #if invoice-data.tax_exemption != none [#tax-exemption \ ]
+  #for note in invoice-data.vat_notes [#note \ ]
+  #for field in invoice-data.extra.filter(field => field.value != "") [
+    #field.value \
+  ]
+  Thank you!
+]